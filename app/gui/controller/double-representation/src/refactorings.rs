@@ -6,5 +6,8 @@
 // ==============
 
 pub mod collapse;
+pub mod inline;
+pub mod rename;
 
 pub use collapse::collapse;
+pub use inline::inline;
@@ -121,6 +121,16 @@ impl GraphHelper {
         found.ok_or_else(|| CannotResolveEndpointNode(id).into())
     }
 
+    /// Get all the nodes in the graph.
+    pub fn nodes(&self) -> &[NodeInfo] {
+        &self.nodes
+    }
+
+    /// Get the refactored definition.
+    pub fn definition(&self) -> &DefinitionInfo {
+        &self.info.source
+    }
+
     /// Get the identifier constituting a connection's endpoint.
     pub fn endpoint_identifier(&self, endpoint: &Endpoint) -> FallibleResult<Identifier> {
         let node = self.lookup_node(endpoint.node)?;
@@ -146,6 +156,8 @@ impl GraphHelper {
                 LineDisposition::Keep => new_lines.push(line),
                 LineDisposition::Remove => {}
                 LineDisposition::Replace(ast) => new_lines.push(BlockLine::new(Some(ast))),
+                LineDisposition::Splice(asts) =>
+                    new_lines.extend(asts.into_iter().map(|ast| BlockLine::new(Some(ast)))),
             }
         }
         updated_definition.set_block_lines(new_lines)?;
@@ -282,6 +294,8 @@ pub enum LineDisposition {
     Keep,
     Remove,
     Replace(Ast),
+    /// Replace the line with a number of lines, used when inlining a node.
+    Splice(Vec<Ast>),
 }
 
 /// Helper type that stores some common data used for collapsing algorithm and implements its logic.
@@ -0,0 +1,526 @@
+//! Module with logic for node inlining, the inverse of [node collapsing](super::collapse).
+//!
+//! See the [`inline`] function for details.
+
+use crate::prelude::*;
+
+use crate::alias_analysis;
+use crate::definition::DefinitionInfo;
+use crate::graph::GraphInfo;
+use crate::identifier::generate_name;
+use crate::node;
+use crate::node::MainLine;
+use crate::node::NodeInfo;
+use crate::refactorings::collapse::GraphHelper;
+use crate::refactorings::collapse::LineDisposition;
+
+use ast::crumbs::Located;
+use ast::BlockLine;
+use parser::Parser;
+
+
+
+// ==================
+// === Inline API ===
+// ==================
+
+// === Entry point ===
+
+/// Run the "inline node" refactoring. Generates output describing how to apply the refactoring.
+///
+/// "Inlining a node" means replacing a node that calls a user-defined method with the nodes of the
+/// method's body. It is the inverse of [collapsing](super::collapse::collapse) nodes.
+///
+/// The call's arguments are substituted for the method's parameters: a parameter is renamed to the
+/// argument if the argument is a variable, otherwise a new node binding the argument to the
+/// parameter is introduced. The method's local variables clashing with the identifiers used in the
+/// refactored graph are renamed. The node yielding the method's result takes the call node's place,
+/// its pattern and its ID.
+pub fn inline(
+    graph: &GraphInfo,
+    call_node: node::Id,
+    method: &DefinitionInfo,
+    parser: &Parser,
+) -> FallibleResult<Inlined> {
+    Inliner::new(graph.clone(), call_node, method, parser.clone_ref())?.inline()
+}
+
+
+// === Inlined ===
+
+/// Result of running node inline algorithm. Describes update to the refactored definition.
+#[derive(Clone, Debug)]
+pub struct Inlined {
+    /// New contents of the refactored definition.
+    pub updated_definition: DefinitionInfo,
+    /// The nodes introduced in place of the call node, in the order of their lines.
+    pub inlined_nodes:      Vec<InlinedNode>,
+    /// Identifier of the inlined node yielding the method's result. It is the call node's ID.
+    pub result_node:        node::Id,
+}
+
+/// A node introduced by inlining.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InlinedNode {
+    /// Identifier of the node in the updated definition.
+    pub id:       node::Id,
+    /// Identifier of the method's node this node was created from. `None` for the nodes binding
+    /// the call's arguments to the method's parameters.
+    pub original: Option<node::Id>,
+}
+
+
+// === Errors ===
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "The node `{}` does not call a method.", _0)]
+pub struct NotAMethodCall(String);
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "The node `{}` does not call the method `{}`.", _0, _1)]
+pub struct NotACallOf(String, String);
+
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Fail)]
+#[fail(display = "The method expects {} arguments, but the call passes {}.", _0, _1)]
+pub struct ArgumentCountMismatch(usize, usize);
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(
+    display = "Currently inlining is supported only for methods with plain variables as \
+parameters. Found parameter `{}`.",
+    _0
+)]
+pub struct UnsupportedParameter(String);
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(
+    display = "Currently inlining is supported only for methods whose body consists of nodes. \
+The method `{}` contains other lines.",
+    _0
+)]
+pub struct UnsupportedMethodBody(String);
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "Internal refactoring error: Cannot generate inlined node from line `{}`.", _0)]
+pub struct CannotConstructInlinedNode(String);
+
+
+
+// ============
+// === Call ===
+// ============
+
+/// Describes a node's expression calling a method, like `Main.method a b`.
+#[derive(Clone, Debug)]
+pub struct Call {
+    /// The target the method is called on, like `Main`. `None` if the method is called by its
+    /// name alone.
+    pub target:    Option<Ast>,
+    /// The name of the called method.
+    pub name:      String,
+    /// The positional arguments of the call.
+    pub arguments: Vec<Ast>,
+}
+
+impl Call {
+    /// Describe the method call in the node's expression.
+    pub fn new(node: &NodeInfo) -> FallibleResult<Self> {
+        let expression = node.expression();
+        let chain = ast::prefix::Chain::from_ast_non_strict(&expression);
+        let arguments = chain.args.iter().map(|arg| arg.sast.wrapped.clone_ref()).collect();
+        let (target, name) = match ast::opr::to_access(&chain.func) {
+            Some(access) => (Some(access.larg.clone_ref()), ast::identifier::as_var(&access.rarg)),
+            None => (None, ast::identifier::as_var(&chain.func)),
+        };
+        let name = name.map(ToOwned::to_owned);
+        let name = name.ok_or_else(|| NotAMethodCall(expression.repr()))?;
+        Ok(Self { target, name, arguments })
+    }
+}
+
+
+
+// ===============
+// === Inliner ===
+// ===============
+
+/// Helper type that stores some common data used for inlining algorithm and implements its logic.
+#[derive(Clone, Debug)]
+pub struct Inliner {
+    /// The graph of definition where the node inlining takes place.
+    graph:        GraphHelper,
+    /// The node calling the inlined method.
+    call_node:    NodeInfo,
+    call:         Call,
+    /// The names of the inlined method's parameters.
+    parameters:   Vec<String>,
+    /// The nodes of the inlined method's body.
+    method_nodes: Vec<NodeInfo>,
+    parser:       Parser,
+}
+
+impl Inliner {
+    /// Does some early pre-processing and gathers common data used in various parts of the
+    /// refactoring algorithm.
+    pub fn new(
+        graph: GraphInfo,
+        call_node: node::Id,
+        method: &DefinitionInfo,
+        parser: Parser,
+    ) -> FallibleResult<Self> {
+        let graph = GraphHelper::new(graph);
+        let call_node = graph.lookup_node(call_node)?.clone();
+        let call = Call::new(&call_node)?;
+        let method_name = &method.name.item.name.item;
+        if call.name != *method_name {
+            let expression = call_node.expression().repr();
+            return Err(NotACallOf(expression, method_name.clone()).into());
+        }
+        let parameters = method.args.iter().map(|arg| {
+            let name = ast::identifier::as_var(&arg.item).map(ToOwned::to_owned);
+            name.ok_or_else(|| UnsupportedParameter(arg.item.repr()))
+        });
+        let parameters = parameters.collect::<Result<Vec<_>, _>>()?;
+        if parameters.len() != call.arguments.len() {
+            return Err(ArgumentCountMismatch(parameters.len(), call.arguments.len()).into());
+        }
+        let method_nodes = GraphInfo::from_definition(method.clone()).nodes();
+        let node_lines: usize =
+            method_nodes.iter().map(|node| 1 + node.documentation.iter().count()).sum();
+        let body_lines = method.block_lines().iter().filter(|line| line.elem.is_some()).count();
+        if method_nodes.is_empty() || node_lines != body_lines {
+            return Err(UnsupportedMethodBody(method_name.clone()).into());
+        }
+        Ok(Inliner { graph, call_node, call, parameters, method_nodes, parser })
+    }
+
+    /// Identifiers introduced or used in the refactored graph.
+    fn caller_names(&self) -> HashSet<String> {
+        let usages = self.graph.nodes().iter().map(|node| alias_analysis::analyze_ast(node.ast()));
+        let used_in_nodes = usages.flat_map(|usage| usage.all_identifiers()).map(|name| name.item);
+        let args = self.graph.definition().args.iter();
+        let parameters =
+            args.filter_map(|arg| ast::identifier::name(&arg.item).map(ToOwned::to_owned));
+        used_in_nodes.chain(parameters).collect()
+    }
+
+    /// If the method returns a local variable by uttering its identifier in the last line, like
+    /// the methods generated by collapsing, get the index of the node introducing the variable and
+    /// its name.
+    ///
+    /// Such a last line is not inlined; the node introducing the variable yields the result
+    /// instead. It is not done if the call node has a pattern other than a single variable.
+    fn returned_local(&self) -> Option<(usize, String)> {
+        let (last, others) = self.method_nodes.split_last()?;
+        let pattern_is_var = |pattern: &Ast| ast::identifier::as_var(pattern).is_some();
+        let call_pattern_supported = self.call_node.pattern().map_or(true, pattern_is_var);
+        if last.pattern().is_some() || !call_pattern_supported {
+            return None;
+        }
+        let name = ast::identifier::as_var(&last.expression())?.to_owned();
+        let introduces = |node: &NodeInfo| {
+            alias_analysis::analyze_ast(node.ast()).introduced.iter().any(|n| n.item == name)
+        };
+        let index = others.iter().position(introduces)?;
+        Some((index, name))
+    }
+
+    /// Generate the inlined node from the method's node, with the identifiers renamed.
+    ///
+    /// The node's line is parsed again, so all its ASTs get new IDs.
+    fn inlined_node(
+        &self,
+        line: &Ast,
+        renames: &HashMap<String, String>,
+    ) -> FallibleResult<NodeInfo> {
+        let renamed = rename_identifiers(line, renames)?.repr();
+        let reparsed = self.parser.parse_line_ast(renamed.as_str())?;
+        NodeInfo::from_main_line_ast(&reparsed)
+            .ok_or_else(|| CannotConstructInlinedNode(renamed).into())
+    }
+
+    /// Assign to a line from refactored definition one of 3 dispositions:
+    /// 1) Lines that are kept intact -- not belonging to the call node;
+    /// 2) The call node's main line, replaced with the inlined lines;
+    /// 3) The call node's documentation line, removed as it is placed in the inlined lines.
+    pub fn rewrite_line(
+        &self,
+        line: &BlockLine<Option<Ast>>,
+        inlined_lines: &[Ast],
+    ) -> FallibleResult<LineDisposition> {
+        let ast = match line.elem.as_ref() {
+            // We leave lines without nodes (blank lines) intact.
+            None => return Ok(LineDisposition::Keep),
+            Some(ast) => ast,
+        };
+        if !self.call_node.contains_line(ast) {
+            Ok(LineDisposition::Keep)
+        } else if MainLine::from_ast(ast).contains_if(|n| n.id() == self.call_node.id()) {
+            Ok(LineDisposition::Splice(inlined_lines.to_vec()))
+        } else {
+            Ok(LineDisposition::Remove)
+        }
+    }
+
+    /// Run the inlining refactoring on this input.
+    pub fn inline(&self) -> FallibleResult<Inlined> {
+        let caller_names = self.caller_names();
+        let method_usages = self
+            .method_nodes
+            .iter()
+            .map(|node| alias_analysis::analyze_ast(node.ast()))
+            .collect_vec();
+        let method_names = method_usages.iter().flat_map(|usage| usage.all_identifiers());
+        let mut unavailable_names = caller_names.clone();
+        unavailable_names.extend(method_names.map(|name| name.item));
+        unavailable_names.extend(self.parameters.iter().cloned());
+        let mut new_name = |base: &str| -> FallibleResult<String> {
+            let names = unavailable_names.iter().map(String::as_str);
+            let name = generate_name(base, names)?.name().to_owned();
+            unavailable_names.insert(name.clone());
+            Ok(name)
+        };
+
+        let mut renames = HashMap::<String, String>::new();
+        let mut argument_lines = Vec::new();
+        for (parameter, argument) in self.parameters.iter().zip(&self.call.arguments) {
+            if let Some(variable) = ast::identifier::as_var(argument) {
+                renames.insert(parameter.clone(), variable.to_owned());
+            } else {
+                let name = if caller_names.contains(parameter) {
+                    new_name(parameter)?
+                } else {
+                    parameter.clone()
+                };
+                let line = format!("{name} = {}", argument.repr());
+                argument_lines.push(self.parser.parse_line_ast(line)?);
+                renames.insert(parameter.clone(), name);
+            }
+        }
+
+        let returned_local = self.returned_local();
+        let mut method_nodes = self.method_nodes.iter().collect_vec();
+        let result_index = match &returned_local {
+            Some((index, name)) => {
+                method_nodes.pop();
+                if let Some(pattern) = self.call_node.pattern() {
+                    let pattern_name = ast::identifier::as_var(pattern).unwrap_or(name);
+                    renames.insert(name.clone(), pattern_name.to_owned());
+                }
+                *index
+            }
+            None => method_nodes.len() - 1,
+        };
+        for usage in &method_usages {
+            for name in &usage.introduced {
+                if caller_names.contains(&name.item) && !renames.contains_key(&name.item) {
+                    renames.insert(name.item.clone(), new_name(&name.item)?);
+                }
+            }
+        }
+
+        let mut inlined_lines = Vec::new();
+        let mut inlined_nodes = Vec::new();
+        for line in argument_lines {
+            let node = NodeInfo::from_main_line_ast(&line);
+            let node = node.ok_or_else(|| CannotConstructInlinedNode(line.repr()))?;
+            inlined_nodes.push(InlinedNode { id: node.id(), original: None });
+            inlined_lines.push(line);
+        }
+        for (index, original) in method_nodes.into_iter().enumerate() {
+            let mut node = self.inlined_node(original.ast(), &renames)?;
+            let mut documentation =
+                original.documentation.as_ref().map(|doc| doc.ast().ast().with_new_id());
+            if index == result_index {
+                node.set_id(self.call_node.id());
+                if let Some(doc) = &self.call_node.documentation {
+                    documentation = Some(doc.ast().into());
+                }
+                if let (None, Some(pattern)) = (&returned_local, self.call_node.pattern()) {
+                    node.set_pattern(pattern.clone_ref());
+                }
+            }
+            inlined_nodes.push(InlinedNode { id: node.id(), original: Some(original.id()) });
+            inlined_lines.extend(documentation);
+            inlined_lines.push(node.ast().clone_ref());
+        }
+
+        let updated_definition =
+            self.graph.rewrite_definition(|line| self.rewrite_line(line, &inlined_lines))?;
+        let result_node = self.call_node.id();
+        Ok(Inlined { updated_definition, inlined_nodes, result_node })
+    }
+}
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Replace the identifiers introduced and used in the line, according to the `renames` map from
+/// the old names to the new ones.
+fn rename_identifiers(line: &Ast, renames: &HashMap<String, String>) -> FallibleResult<Ast> {
+    let usage = alias_analysis::analyze_ast(line);
+    let mut line = line.clone_ref();
+    for Located { crumbs, item } in usage.all_identifiers() {
+        if let Some(new_name) = renames.get(&item) {
+            line = line.set_traversing(&crumbs, Ast::var(new_name.as_str()))?;
+        }
+    }
+    Ok(line)
+}
+
+
+
+// ============
+// === Test ===
+// ============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::definition::DefinitionName;
+    use crate::graph;
+    use crate::module;
+
+    use ast::crumbs::Crumb;
+
+    struct Case {
+        refactored_name:     DefinitionName,
+        inlined_name:        DefinitionName,
+        initial_code:        &'static str,
+        call_line:           usize,
+        expected_refactored: &'static str,
+    }
+
+    impl Case {
+        fn run(&self, parser: &Parser) {
+            let ast = parser.parse_module(self.initial_code, default()).unwrap();
+            ast::test_utils::assert_unique_ids(ast.as_ref());
+            let main = module::locate_child(&ast, &self.refactored_name).unwrap();
+            let method = module::locate_child(&ast, &self.inlined_name).unwrap();
+            let graph = graph::GraphInfo::from_definition(main.item.clone());
+            let call_node = graph.nodes()[self.call_line].id();
+            let inlined = inline(&graph, call_node, &method.item, parser).unwrap();
+            let new_main = &inlined.updated_definition.ast;
+            info!("Updated method:\n{new_main}");
+            let mut module = module::Info { ast: ast.clone_ref() };
+            let main_crumb = Crumb::from(main.crumb());
+            module.ast = module.ast.set(&main_crumb, new_main.ast().clone()).unwrap();
+            ast::test_utils::assert_unique_ids(module.ast.as_ref());
+            assert_eq!(new_main.repr(), self.expected_refactored);
+
+            let new_graph = graph::GraphInfo::from_definition(inlined.updated_definition.clone());
+            let new_ids = new_graph.nodes().iter().map(|node| node.id()).collect_vec();
+            assert!(new_ids.contains(&inlined.result_node));
+            assert_eq!(inlined.result_node, call_node);
+            assert!(inlined.inlined_nodes.iter().all(|node| new_ids.contains(&node.id)));
+        }
+    }
+
+    #[test]
+    fn test_inline() {
+        let parser = Parser::new();
+        let refactored_name = DefinitionName::new_plain("custom_old");
+        let inlined_name = DefinitionName::new_plain("custom_new");
+
+        // Check that inlining reverts the collapsing refactoring: the method's last line returning
+        // a local variable is not inlined, and the node introducing it takes the call's place.
+        let initial_code = r"custom_new a =
+    b = 2
+    c = A + B
+    d = a + b
+    c
+
+custom_old =
+    a = 1
+    c = Main.custom_new a
+    c + 7";
+        let expected_refactored = r"custom_old =
+    a = 1
+    b = 2
+    c = A + B
+    d = a + b
+    c + 7";
+        let mut case =
+            Case { refactored_name, inlined_name, initial_code, call_line: 1, expected_refactored };
+        case.run(&parser);
+
+        // Check that:
+        // 1) parameters are renamed to the variables passed as arguments;
+        // 2) other arguments are bound to the parameters in new nodes, renamed if clashing;
+        // 3) the inline body of the method takes the call node's place with its pattern.
+        case.initial_code = r"custom_new x y = x + y
+
+custom_old =
+    x = 2
+    sum = Main.custom_new 5 x
+    sum";
+        case.expected_refactored = r"custom_old =
+    x = 2
+    x1 = 5
+    sum = x1 + x
+    sum";
+        case.run(&parser);
+
+        // Check that:
+        // 1) local variables clashing with the refactored graph's identifiers are renamed;
+        // 2) the method's last line takes the place of the call without pattern.
+        case.initial_code = r"custom_new a =
+    b = a * 2
+    b + 1
+
+custom_old =
+    b = 10
+    custom_new b";
+        case.expected_refactored = r"custom_old =
+    b = 10
+    b1 = b * 2
+    b1 + 1";
+        case.run(&parser);
+
+        // Check that the result variable is renamed to the call node's pattern.
+        case.initial_code = r"custom_new a =
+    b = a * 2
+    b
+
+custom_old =
+    a = 10
+    result = custom_new a
+    result + 1";
+        case.expected_refactored = r"custom_old =
+    a = 10
+    result = a * 2
+    result + 1";
+        case.run(&parser);
+    }
+
+    #[test]
+    fn inline_unsupported_calls() {
+        let parser = Parser::new();
+        let code = r"custom_new a = a + 1
+
+custom_old =
+    a = 1
+    b = a + 2
+    c = Main.custom_new
+    d = Main.custom_new a";
+        let ast = parser.parse_module(code, default()).unwrap();
+        let main = module::locate_child(&ast, &DefinitionName::new_plain("custom_old")).unwrap();
+        let method = module::locate_child(&ast, &DefinitionName::new_plain("custom_new")).unwrap();
+        let graph = graph::GraphInfo::from_definition(main.item.clone());
+        let nodes = graph.nodes();
+        let inline_node = |index: usize| inline(&graph, nodes[index].id(), &method.item, &parser);
+        assert!(inline_node(1).is_err());
+        assert!(inline_node(2).is_err());
+        assert!(inline_node(3).is_ok());
+    }
+}
@@ -0,0 +1,460 @@
+//! Module with logic for renaming symbols: local variables, methods and types.
+//!
+//! The refactoring works on a single module at a time. The [`occurrences`] of the renamed
+//! [`Symbol`] are located in the module's AST, and the [`conflicts`] function checks whether the
+//! new name would clash with the names already present in the module. Applying the rename to a
+//! whole project is done by calling these functions for every module of the project.
+
+use crate::prelude::*;
+use enso_text::index::*;
+
+use crate::alias_analysis;
+use crate::definition;
+use crate::definition::ChildDefinition;
+use crate::definition::DefinitionInfo;
+use crate::definition::DefinitionProvider;
+use crate::identifier::Identifier;
+use crate::module;
+use crate::name::QualifiedName;
+
+use ast::crumbs::Crumbs;
+use ast::crumbs::InfixCrumb;
+use ast::crumbs::Located;
+use ast::known;
+use ast::Token;
+
+
+// ==============
+// === Errors ===
+// ==============
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "`{}` is not a valid name for the renamed {}.", _0, _1)]
+pub struct InvalidName(String, &'static str);
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "Cannot rename `{}` to `{}`, as the name is already used in: {}.", _0, _1, _2)]
+pub struct NameConflicts(String, String, String);
+
+impl NameConflicts {
+    /// Describe the conflicts of renaming the `symbol` to the `new_name`.
+    pub fn new(symbol: &Symbol, new_name: &str, conflicts: &[Conflict]) -> Self {
+        let modules = conflicts.iter().map(|conflict| conflict.module.to_string()).join(", ");
+        Self(symbol.name().to_owned(), new_name.to_owned(), modules)
+    }
+}
+
+
+
+// ==============
+// === Symbol ===
+// ==============
+
+/// A symbol which can be renamed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Symbol {
+    /// A local variable or a parameter of a definition.
+    Local {
+        /// The module containing the definition.
+        module:     QualifiedName,
+        /// The definition the variable is local to.
+        definition: definition::Id,
+        /// The variable's name.
+        name:       String,
+    },
+    /// A method, possibly defined in the module scope.
+    Method {
+        /// The module the method is defined in.
+        module:           QualifiedName,
+        /// The type the method is defined on. It is the module's name for module methods.
+        self_type:        QualifiedName,
+        /// The method's name.
+        name:             String,
+        /// If set, all the calls with the method's name on any target, like `x.method`, are
+        /// renamed. Otherwise only the calls with the `self_type` as the explicit target are.
+        /// It should be set only if no other method with the same name exists.
+        rename_dot_calls: bool,
+    },
+    /// A type.
+    Type {
+        /// The module the type is defined in.
+        module: QualifiedName,
+        /// The type's name.
+        name:   String,
+    },
+}
+
+impl Symbol {
+    /// The current name of the symbol.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Local { name, .. } | Self::Method { name, .. } | Self::Type { name, .. } => name,
+        }
+    }
+
+    /// The module the symbol is defined in.
+    pub fn module(&self) -> &QualifiedName {
+        match self {
+            Self::Local { module, .. }
+            | Self::Method { module, .. }
+            | Self::Type { module, .. } => module,
+        }
+    }
+
+    /// Check if the symbol can be given the new name: the locals and methods must be named like
+    /// variables, the types like constructors.
+    pub fn validate_new_name(&self, new_name: &str) -> FallibleResult {
+        let kind = match self {
+            Self::Local { .. } => "variable",
+            Self::Method { .. } => "method",
+            Self::Type { .. } => "type",
+        };
+        let is_expected_kind = |identifier: Identifier| match self {
+            Self::Type { .. } => matches!(identifier.shape(), ast::Shape::Cons(_)),
+            _ => matches!(identifier.shape(), ast::Shape::Var(_)),
+        };
+        let identifier = Identifier::from_text(new_name).ok();
+        let is_plain = new_name.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if identifier.map_or(false, is_expected_kind) && is_plain {
+            Ok(())
+        } else {
+            Err(InvalidName(new_name.to_owned(), kind).into())
+        }
+    }
+}
+
+
+// === Conflict ===
+
+/// A name already present in a module which would clash with the renamed symbol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// The module where the name is present.
+    pub module:   QualifiedName,
+    /// The conflicting name.
+    pub new_name: String,
+}
+
+
+
+// ==================
+// === Rename API ===
+// ==================
+
+/// Find all the occurrences of the symbol in the module, which should be replaced when renaming.
+///
+/// The `module_name` is the name of the module that contains `ast`. The returned ranges are sorted
+/// and do not overlap.
+pub fn occurrences(
+    module_name: &QualifiedName,
+    ast: &known::Module,
+    symbol: &Symbol,
+) -> FallibleResult<Vec<enso_text::Range<Byte>>> {
+    let mut crumbs = Vec::new();
+    let mut ranges = Vec::new();
+    match symbol {
+        Symbol::Local { module, definition, name } =>
+            if module == module_name {
+                let definition = module::locate(ast, definition)?;
+                crumbs.extend(local_occurrences(&definition, name));
+            },
+        Symbol::Method { module, self_type, name, rename_dot_calls } => {
+            if module == module_name {
+                crumbs.extend(method_definitions(ast, module, self_type, name));
+            }
+            let is_module_method = module == self_type;
+            let in_defining_module = module == module_name;
+            let bare_calls = is_module_method && in_defining_module;
+            let usage = alias_analysis::analyze_crumbable(ast.shape());
+            for identifier in usage.used.into_iter().filter(|used| used.item == *name) {
+                let should_rename = match access_target(ast, &identifier.crumbs) {
+                    Some(target) => *rename_dot_calls || refers_to(&target, self_type),
+                    None => bare_calls,
+                };
+                if should_rename {
+                    crumbs.push(identifier.crumbs);
+                }
+            }
+        }
+        Symbol::Type { module, name } => {
+            let in_defining_module = module == module_name;
+            let imported = || {
+                let info = module::Info { ast: ast.clone_ref() };
+                let mut imports = info.iter_imports();
+                imports.any(|import| import.qualified_module_name().map_or(false, |m| m == *module))
+            };
+            if in_defining_module || imported() {
+                let usage = alias_analysis::analyze_crumbable(ast.shape());
+                let used = usage.used.into_iter().filter(|used| used.item == *name);
+                crumbs.extend(used.map(|used| used.crumbs));
+                for definition in ast.def_iter() {
+                    crumbs.extend(extended_target_occurrences(&definition, name));
+                }
+            }
+            if in_defining_module {
+                for line in ast.ast().children() {
+                    let line_start = ast.range_of_descendant_at(&line.crumbs)?.start;
+                    ranges.extend(type_header_name(line.item, line_start, name));
+                }
+            }
+        }
+    }
+    for crumbs in crumbs {
+        ranges.push(ast.range_of_descendant_at(&crumbs)?);
+    }
+    ranges.sort_by_key(|range| (range.start, range.end));
+    ranges.dedup();
+    Ok(ranges)
+}
+
+/// Find the names in the module which would clash with the symbol after renaming it to the
+/// `new_name`.
+///
+/// The `module_name` is the name of the module that contains `ast`.
+pub fn conflicts(
+    module_name: &QualifiedName,
+    ast: &known::Module,
+    symbol: &Symbol,
+    new_name: &str,
+) -> Vec<Conflict> {
+    let has_conflict = match symbol {
+        Symbol::Local { module, definition, .. } if module == module_name =>
+            match module::locate(ast, definition) {
+                Ok(definition) => {
+                    let usage = body_usage(&definition);
+                    let mut names = usage.all_identifiers().into_iter().map(|name| name.item);
+                    let mut args = definition.args.iter().filter_map(|arg| arg_name(&arg.item));
+                    names.any(|name| name == new_name) || args.any(|arg| arg == new_name)
+                }
+                Err(_) => false,
+            },
+        Symbol::Method { module, self_type, .. } if module == module_name =>
+            !method_definitions(ast, module, self_type, new_name).is_empty(),
+        Symbol::Type { module, .. } if module == module_name => ast
+            .ast()
+            .children()
+            .any(|line| type_header_name(line.item, default(), new_name).is_some()),
+        _ => false,
+    };
+    let conflict = Conflict { module: module_name.clone(), new_name: new_name.to_owned() };
+    has_conflict.then_some(conflict).into_iter().collect()
+}
+
+/// Create the text changes replacing each of the occurrences with the new name. The changes are
+/// ordered from the last one in the text, so they can be applied one after another.
+pub fn text_changes(
+    occurrences: &[enso_text::Range<Byte>],
+    new_name: &str,
+) -> Vec<enso_text::Change<Byte, String>> {
+    let changes = occurrences.iter().rev();
+    changes.map(|range| enso_text::Change { range: *range, text: new_name.to_owned() }).collect()
+}
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Crumbs of the parameters and local variables of the definition having the given name.
+fn local_occurrences(definition: &ChildDefinition, name: &str) -> Vec<Crumbs> {
+    let is_named = |arg: &&Located<Ast>| arg_name(&arg.item) == Some(name);
+    let args = definition.args.iter().filter(is_named).map(|arg| arg.crumbs.clone());
+    let body = definition.body();
+    let usage = body_usage(definition);
+    let identifiers = usage.all_identifiers().into_iter().filter(|used| used.item == name);
+    let in_body = identifiers.map(|identifier| body.crumbs.iter().chain(&identifier.crumbs));
+    let in_definition = args.chain(in_body.map(|crumbs| crumbs.cloned().collect()));
+    in_definition.map(|crumbs| definition.crumbs.iter().chain(&crumbs).cloned().collect()).collect()
+}
+
+/// The identifiers introduced and used in the definition's body.
+fn body_usage(definition: &DefinitionInfo) -> alias_analysis::IdentifierUsage {
+    let body = definition.body();
+    if matches!(body.shape(), ast::Shape::Block(_)) {
+        alias_analysis::analyze_crumbable(body.item)
+    } else {
+        alias_analysis::analyze_ast(body.item)
+    }
+}
+
+/// Crumbs of the names of the module's definitions of the method.
+fn method_definitions(
+    ast: &known::Module,
+    module: &QualifiedName,
+    self_type: &QualifiedName,
+    name: &str,
+) -> Vec<Crumbs> {
+    let definitions = ast.def_iter().filter(|definition| {
+        let definition_name = &definition.name.item;
+        definition_name.name.item == name
+            && definition_name.method_of(module.name(), self_type.name())
+    });
+    definitions.map(|definition| name_crumbs(&definition, &definition.name.name.crumbs)).collect()
+}
+
+/// Crumbs of the extended target segments of the module's definitions, which have the given name.
+fn extended_target_occurrences(definition: &ChildDefinition, name: &str) -> Vec<Crumbs> {
+    let segments = definition.name.extended_target.iter();
+    let named = segments.filter(|segment| segment.item == name);
+    named.map(|segment| name_crumbs(definition, &segment.crumbs)).collect()
+}
+
+/// Crumbs of the part of the definition's name, located by crumbs relative to the name.
+fn name_crumbs(definition: &ChildDefinition, crumbs_in_name: &[ast::crumbs::Crumb]) -> Crumbs {
+    let prefix = definition.crumbs.iter().chain(&definition.name.crumbs);
+    prefix.chain(crumbs_in_name).cloned().collect()
+}
+
+/// The name of the definition's parameter, if the parameter is a plain variable.
+fn arg_name(arg: &Ast) -> Option<&str> {
+    ast::identifier::as_var(arg)
+}
+
+/// If the identifier is accessed on some target, like in `target.identifier`, return the target's
+/// code.
+fn access_target(ast: &known::Module, crumbs: &[ast::crumbs::Crumb]) -> Option<String> {
+    let (last, parent_crumbs) = crumbs.split_last()?;
+    let is_right_operand = *last == InfixCrumb::RightOperand.into();
+    let parent = ast.get_traversing(parent_crumbs).ok()?;
+    let access = is_right_operand.and_option_from(|| ast::opr::to_access(parent))?;
+    Some(access.larg.repr())
+}
+
+/// Check if the code of the target of a call refers to the given type.
+fn refers_to(target: &str, self_type: &QualifiedName) -> bool {
+    target == self_type.name()
+        || target == self_type.alias_name().as_str()
+        || *target == self_type.to_string()
+}
+
+/// If the line is a type definition with the given name, like `type Name`, return the name's
+/// range, given the position of the line's start.
+fn type_header_name(line: &Ast, line_start: Byte, name: &str) -> Option<enso_text::Range<Byte>> {
+    if !matches!(line.shape(), ast::Shape::Tree(_)) {
+        return None;
+    }
+    let mut tokens = Vec::new();
+    let mut position = line_start;
+    line.shape().feed_to(&mut |token: Token| {
+        let end = position + token.len();
+        if let Token::Str(text) = token {
+            tokens.push((text.to_owned(), enso_text::Range::new(position, end)));
+        }
+        position = end;
+    });
+    match tokens.as_slice() {
+        [(keyword, _), (type_name, range), ..] if keyword == "type" && type_name == name =>
+            Some(*range),
+        _ => None,
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use parser::Parser;
+
+    struct Case {
+        code:     &'static str,
+        symbol:   Symbol,
+        new_name: &'static str,
+        expected: &'static str,
+    }
+
+    impl Case {
+        fn run(&self, parser: &Parser) {
+            let ast = parser.parse_module(self.code, default()).unwrap();
+            let ranges = occurrences(&main_module(), &ast, &self.symbol).unwrap();
+            let mut code = self.code.to_owned();
+            for change in text_changes(&ranges, self.new_name) {
+                let range = change.range.start.value..change.range.end.value;
+                code.replace_range(range, &change.text);
+            }
+            assert_eq!(code, self.expected);
+        }
+    }
+
+    fn main_module() -> QualifiedName {
+        QualifiedName::from_text("local.Project.Main").unwrap()
+    }
+
+    fn local(definition: &str, name: &str) -> Symbol {
+        let definition = definition::Id::new_plain_name(definition);
+        Symbol::Local { module: main_module(), definition, name: name.into() }
+    }
+
+    fn method(self_type: &str, name: &str, rename_dot_calls: bool) -> Symbol {
+        let self_type = QualifiedName::from_text(self_type).unwrap();
+        Symbol::Method { module: main_module(), self_type, name: name.into(), rename_dot_calls }
+    }
+
+    #[test]
+    fn test_rename() {
+        let parser = Parser::new();
+        let cases = [
+            Case {
+                code:     "main =\n    foo = 1\n    bar = foo + 1\n    foo\n\nother foo = foo",
+                symbol:   local("main", "foo"),
+                new_name: "baz",
+                expected: "main =\n    baz = 1\n    bar = baz + 1\n    baz\n\nother foo = foo",
+            },
+            Case {
+                code:     "func foo = foo + 1",
+                symbol:   local("func", "foo"),
+                new_name: "x",
+                expected: "func x = x + 1",
+            },
+            Case {
+                code:
+                    "func x = x + 1\n\nmain =\n    a = func 1\n    b = Main.func a\n    c = a.func",
+                symbol:   method("local.Project.Main", "func", false),
+                new_name: "g",
+                expected: "g x = x + 1\n\nmain =\n    a = g 1\n    b = Main.g a\n    c = a.func",
+            },
+            Case {
+                code:     "func x = x + 1\n\nmain =\n    a = func 1\n    c = a.func",
+                symbol:   method("local.Project.Main", "func", true),
+                new_name: "g",
+                expected: "g x = x + 1\n\nmain =\n    a = g 1\n    c = a.g",
+            },
+        ];
+        for case in cases {
+            case.run(&parser);
+        }
+    }
+
+    #[test]
+    fn rename_conflicts() {
+        let parser = Parser::new();
+        let code = "main =\n    foo = 1\n    bar = foo\n\nfunc x = x";
+        let ast = parser.parse_module(code, default()).unwrap();
+        let module = main_module();
+        assert!(!conflicts(&module, &ast, &local("main", "foo"), "bar").is_empty());
+        assert!(conflicts(&module, &ast, &local("main", "foo"), "baz").is_empty());
+        let func = method("local.Project.Main", "func", false);
+        assert!(!conflicts(&module, &ast, &func, "main").is_empty());
+        assert!(conflicts(&module, &ast, &func, "other").is_empty());
+    }
+
+    #[test]
+    fn validating_new_names() {
+        let func = method("local.Project.Main", "func", false);
+        assert!(func.validate_new_name("other_func").is_ok());
+        assert!(func.validate_new_name("Other").is_err());
+        assert!(func.validate_new_name("a b").is_err());
+        assert!(func.validate_new_name("").is_err());
+        let type_symbol = Symbol::Type { module: main_module(), name: "Foo".into() };
+        assert!(type_symbol.validate_new_name("Bar").is_ok());
+        assert!(type_symbol.validate_new_name("bar").is_err());
+    }
+}
@@ -136,7 +136,9 @@ where
                         Disposition::Ignore => {}
                     }
                 }
-                TransportEvent::Opened => {}
+                TransportEvent::Opened
+                | TransportEvent::Reconnecting
+                | TransportEvent::Reconnected => {}
                 TransportEvent::Closed => self.emit_event(Event::Closed),
             }
         });
@@ -182,6 +182,37 @@ impl SourceFile {
         }
     }
 
+    /// Describe source file contents consisting of the code and the id map and metadata stored
+    /// separately, in the format returned by [`Self::sidecar_content`].
+    ///
+    /// If the sidecar content is not recognized as proper metadata, it is ignored and the
+    /// resulting file consists only of the code.
+    pub fn with_sidecar(code: &str, sidecar: &str) -> Self {
+        let before_tag = "\n".repeat(NEWLINES_BEFORE_TAG);
+        let sidecar = sidecar.trim_end();
+        let file = Self::new(format!("{code}{before_tag}{METADATA_TAG}\n{sidecar}"));
+        if file.has_metadata() {
+            file
+        } else {
+            Self::new_without_metadata(code.into())
+        }
+    }
+
+    /// Check if the file has the metadata section.
+    pub fn has_metadata(&self) -> bool {
+        self.code.end.value < self.content.len()
+    }
+
+    /// Get the description of this file with the id map and metadata sections removed.
+    pub fn without_metadata(&self) -> Self {
+        Self::new_without_metadata(self.code_slice().into())
+    }
+
+    /// Get the id map and metadata sections, in format to be stored apart from the code.
+    pub fn sidecar_content(&self) -> String {
+        format!("{}\n{}", self.id_map_slice(), self.metadata_slice())
+    }
+
     /// Checks if given line might be an ID map.
     pub fn looks_like_idmap(line: &str) -> bool {
         line.is_enclosed('[', ']')
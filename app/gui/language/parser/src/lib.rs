@@ -228,4 +228,23 @@ main =
         let ast = Parser::new().parse_line_ast("-23").unwrap();
         assert_matches!(ast.shape(), ast::Shape::Number(_));
     }
+
+    #[test]
+    fn test_sidecar_metadata() {
+        let code = "main =\n    foo = 2\n    foo + 3\n";
+        let parser = Parser::new();
+        let ast = parser.parse_module(code, default()).unwrap();
+        let source = api::ParsedSourceFile { ast, metadata: serde_json::json!({"foo": 1}) };
+        let file = source.serialize().unwrap();
+        assert!(file.has_metadata());
+
+        let code_only = file.without_metadata();
+        assert!(!code_only.has_metadata());
+        assert_eq!(code_only.content, code);
+        let restored = api::SourceFile::with_sidecar(&code_only.content, &file.sidecar_content());
+        assert_eq!(restored, file);
+
+        let malformed = api::SourceFile::with_sidecar(code, "not a metadata");
+        assert_eq!(malformed, code_only);
+    }
 }
@@ -38,7 +38,9 @@ use span_tree::SpanTree;
 // === Export ===
 // ==============
 
+pub mod clipboard;
 pub mod executed;
+pub mod layout;
 pub mod widget;
 
 pub use double_representation::graph::Id;
@@ -87,6 +89,11 @@ pub struct UnsupportedPatternOnNode;
 #[fail(display = "AST node is missing ID.")]
 pub struct MissingAstId;
 
+/// Error raised when inlining a node calling a method which is not defined in the graph's module.
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "The method `{}` is not defined in this module, so it cannot be inlined.", _0)]
+pub struct NotAModuleMethodCall(String);
+
 
 
 // ====================
@@ -1065,6 +1072,254 @@ impl Handle {
         Ok(collapsed_node)
     }
 
+    /// Inline the node calling a method defined in this module, replacing it with the nodes of the
+    /// method's body. See [`double_representation::refactorings::inline`] for the details.
+    ///
+    /// The inlined nodes keep their positions relative to the node yielding the method's result,
+    /// which is placed where the call node was. If `remove_definition` is set, the method's
+    /// definition is removed from the module, unless it is still used elsewhere.
+    ///
+    /// Returns the IDs of the inlined nodes.
+    pub fn inline(&self, node: node::Id, remove_definition: bool) -> FallibleResult<Vec<node::Id>> {
+        let _transaction_guard = self.get_or_open_transaction("Inline node");
+        analytics::remote_log_event("graph::inline");
+        use double_representation::refactorings::inline::inline;
+        use double_representation::refactorings::inline::Call;
+        use double_representation::refactorings::inline::Inlined;
+        let call_node = self.node(node)?;
+        info!("Inlining {call_node:?}.");
+        let call = Call::new(&call_node.info)?;
+        let module_name = self.module.name();
+        let defined_in_module = call.target.map_or(true, |target| target.repr() == module_name);
+        if !defined_in_module {
+            return Err(NotAModuleMethodCall(call.name).into());
+        }
+        let method_name = definition::DefinitionName::new_plain(call.name.clone());
+        let ast = self.module.ast();
+        let method = module::locate_child(&ast, &method_name)?;
+        let original_positions = method.item.ast.iter_recursive().filter_map(|ast| {
+            let id = ast.id?;
+            Some((id, self.module.node_metadata(id).ok()?.position?))
+        });
+        let original_positions = original_positions.collect::<HashMap<_, _>>();
+        let graph = self.graph_info()?;
+        let Inlined { updated_definition, inlined_nodes, result_node } =
+            inline(&graph, node, &method.item, &self.parser)?;
+
+        let mut module = module::Info { ast };
+        module.update_definition(&self.id, |_| Ok(updated_definition))?;
+        let is_own_definition = self.id == definition::Id::new_single_crumb(method_name.clone());
+        if remove_definition && !is_own_definition {
+            let mut without_method = module.clone();
+            let line_index =
+                module::locate_line_with(&without_method.ast, &method_name)?.line_index;
+            without_method.remove_line(line_index)?;
+            let next_line_blank =
+                without_method.ast.lines.get(line_index).contains_if(|line| line.elem.is_none());
+            if next_line_blank {
+                without_method.remove_line(line_index)?;
+            }
+            let still_used = without_method
+                .ast
+                .iter_recursive()
+                .any(|ast| ast::identifier::as_var(ast) == Some(call.name.as_str()));
+            if still_used {
+                warn!("Not removing the method `{}`, as it is still used.", call.name);
+            } else {
+                module = without_method;
+            }
+        }
+        self.module.update_ast(module.ast)?;
+
+        let result_original = inlined_nodes.iter().find(|inlined| inlined.id == result_node);
+        let result_original = result_original.and_then(|inlined| inlined.original);
+        let anchor = result_original.and_then(|id| original_positions.get(&id).copied());
+        let anchor = anchor
+            .unwrap_or_else(|| model::module::Position::mean(original_positions.values().copied()));
+        if let Some(call_position) = call_node.position() {
+            for inlined in inlined_nodes.iter().filter(|inlined| inlined.id != result_node) {
+                let original = inlined.original.and_then(|id| original_positions.get(&id));
+                if let Some(original) = original {
+                    let offset = original.vector - anchor.vector;
+                    self.set_node_position(inlined.id, call_position.vector + offset)?;
+                }
+            }
+        }
+        self.arrange_unpositioned_nodes(default())?;
+        Ok(inlined_nodes.into_iter().map(|inlined| inlined.id).collect())
+    }
+
+    /// Copy the nodes with given ids, so they can be pasted with [`Self::paste_nodes`].
+    ///
+    /// The nodes are copied in the order of their lines in the graph, along with their
+    /// documentation, positions relative to each other, visualizations, the connections between
+    /// them, and the names of entities they use which need to be imported.
+    pub fn copy_nodes(&self, ids: &[node::Id]) -> FallibleResult<clipboard::CopiedNodes> {
+        let graph = self.graph_info()?;
+        let nodes = graph.nodes().into_iter().filter(|node| ids.contains(&node.id())).collect_vec();
+        let index_of = |id: node::Id| nodes.iter().position(|node| node.id() == id);
+        if let Some(missing) = ids.iter().find(|id| index_of(**id).is_none()) {
+            return Err(NodeNotFound(*missing).into());
+        }
+        info!("Copying nodes {ids:?}.");
+        let metadata = nodes.iter().map(|node| self.module.node_metadata(node.id()).ok());
+        let metadata = metadata.collect_vec();
+        let positions = metadata.iter().filter_map(|md| md.as_ref().and_then(|md| md.position));
+        let origin = model::module::Position::mean(positions);
+        let copied_nodes = nodes.iter().zip(&metadata).map(|(node, metadata)| {
+            let position = metadata.as_ref().and_then(|metadata| metadata.position);
+            clipboard::CopiedNode {
+                code:          node.ast().repr(),
+                documentation: node.documentation_text().map(Into::into),
+                offset:        position.map(|position| (position.vector - origin.vector).into()),
+                visualization: metadata.as_ref().map_or_default(|md| md.visualization.clone()),
+            }
+        });
+        let connections = graph.connections().into_iter().filter_map(|connection| {
+            let source = index_of(connection.source.node)?;
+            let target = index_of(connection.target.node)?;
+            Some(clipboard::CopiedConnection { source, target })
+        });
+        Ok(clipboard::CopiedNodes {
+            nodes:       copied_nodes.collect(),
+            connections: connections.unique().collect(),
+            imports:     self.imports_used_by(&nodes),
+        })
+    }
+
+    /// The qualified names of the entities used by the nodes, which are imported in the module.
+    fn imports_used_by(&self, nodes: &[NodeInfo]) -> Vec<String> {
+        use double_representation::alias_analysis;
+        use double_representation::import::ImportedNames;
+        let usages = nodes.iter().map(|node| alias_analysis::analyze_ast(node.ast())).collect_vec();
+        let introduced = usages.iter().flat_map(|usage| &usage.introduced);
+        let introduced: HashSet<&str> = introduced.map(|name| name.item.as_str()).collect();
+        let used = usages.iter().flat_map(|usage| &usage.used).map(|name| name.item.as_str());
+        let used = used.filter(|name| !introduced.contains(name)).unique().collect_vec();
+        let module = module::Info { ast: self.module.ast() };
+        let imports = module.iter_imports().collect_vec();
+        let imported_entities =
+            used.into_iter().cartesian_product(&imports).filter_map(|(name, import)| {
+                let module_name = import.module.iter().join(".");
+                let is_imported = match &import.imported {
+                    ImportedNames::Module { alias } => {
+                        let module_alias =
+                            alias.as_deref().or(import.module.last().map(ImString::as_str));
+                        return (module_alias == Some(name)).then_some(module_name);
+                    }
+                    ImportedNames::All => true,
+                    ImportedNames::AllExcept { not_imported } => !not_imported.contains(name),
+                    ImportedNames::List { names } => names.contains(name),
+                };
+                is_imported.then(|| format!("{module_name}.{name}"))
+            });
+        let existing = imported_entities
+            .filter(|name| self.suggestion_db.lookup_by_qualified_name_str(name).is_ok());
+        existing.unique().collect()
+    }
+
+    /// Paste the nodes into the graph, placing them around the given position. Returns the ids of
+    /// the pasted nodes.
+    ///
+    /// The nodes' variables already used in the graph are renamed, along with their usages in the
+    /// other pasted nodes. The imports required by the nodes are added to the module. Pasting is a
+    /// single undo-redo transaction.
+    pub fn paste_nodes(
+        &self,
+        payload: &clipboard::Payload,
+        position: model::module::Position,
+    ) -> FallibleResult<Vec<node::Id>> {
+        let _transaction_guard = self.get_or_open_transaction("Paste nodes");
+        let copied = match payload {
+            clipboard::Payload::Nodes(nodes) => Cow::Borrowed(nodes),
+            clipboard::Payload::Text(text) =>
+                Cow::Owned(clipboard::CopiedNodes::from_code(text, &self.parser)?),
+        };
+        info!("Pasting {} nodes.", copied.nodes.len());
+        let imports = copied.imports.iter().filter_map(|name| QualifiedName::from_text(name).ok());
+        self.add_required_imports(imports.map(RequiredImport::Name), ImportType::Permanent)?;
+
+        let mut renamed = HashMap::<usize, (String, String)>::new();
+        let mut pasted = Vec::with_capacity(copied.nodes.len());
+        for (index, copied_node) in copied.nodes.iter().enumerate() {
+            let mut node = clipboard::parse_node_line(&copied_node.code, &self.parser)?;
+            let incoming = copied.connections.iter().filter(|c| c.target == index);
+            let renames = incoming.filter_map(|connection| renamed.get(&connection.source));
+            let renames = renames.cloned().collect();
+            clipboard::rename_used_identifiers(&mut node, &renames)?;
+            let pattern = node.pattern().and_then(ast::identifier::as_var).map(ToOwned::to_owned);
+            if let Some(name) = pattern {
+                let used_names = self.used_names()?;
+                if used_names.iter().any(|used| used.item == name) {
+                    let new_name = self.variable_name_for(&node)?;
+                    renamed.insert(index, (name, new_name.name.clone()));
+                    node.set_pattern(new_name.into());
+                }
+            }
+            node.documentation = copied_node
+                .documentation
+                .as_ref()
+                .and_then(|text| self.documentation_comment_from_pretty_text(text));
+            self.update_definition_ast(|definition| {
+                let mut graph = GraphInfo::from_definition(definition);
+                graph.add_node(&node, LocationHint::End)?;
+                Ok(graph.source)
+            })?;
+            let metadata = NodeMetadata {
+                position: copied_node.offset.map(|offset| position + offset),
+                visualization: copied_node.visualization.clone(),
+                ..default()
+            };
+            self.module.set_node_metadata(node.id(), metadata)?;
+            pasted.push(node.id());
+        }
+        Ok(pasted)
+    }
+
+    /// Arrange the graph's nodes automatically, following the connections between them. The
+    /// `pinned` nodes keep their positions, if they have any. See [`layout`] for the details.
+    ///
+    /// All the nodes are moved in a single undo-redo transaction.
+    pub fn arrange_nodes(
+        &self,
+        pinned: &HashSet<node::Id>,
+        spacing: layout::Spacing,
+    ) -> FallibleResult {
+        let _transaction_guard = self.get_or_open_transaction("Arrange nodes");
+        let graph = self.graph_info()?;
+        let connections = Connections::new(&graph, &span_tree::generate::context::Empty);
+        let mut layout = layout::Layout::new(&graph, &connections);
+        let nodes = self.nodes()?;
+        let positions = nodes.iter().filter_map(|node| Some((node.id(), node.position()?)));
+        let positions = positions.collect_vec();
+        for (id, position) in &positions {
+            if pinned.contains(id) {
+                layout.pin(*id, *position);
+            }
+        }
+        let mean = model::module::Position::mean(positions.iter().map(|(_, position)| *position));
+        let top = positions.iter().map(|(_, position)| position.vector.y).reduce(f32::max);
+        layout.set_origin(model::module::Position::new(mean.vector.x, top.unwrap_or_default()));
+        info!("Arranging nodes in graph {}.", self.id);
+        for (id, position) in layout.compute(spacing) {
+            self.set_node_position(id, position)?;
+        }
+        Ok(())
+    }
+
+    /// Arrange the nodes without positions, e.g. added in the text editor, around the nodes
+    /// having them. See [`Self::arrange_nodes`].
+    pub fn arrange_unpositioned_nodes(&self, spacing: layout::Spacing) -> FallibleResult {
+        let nodes = self.nodes()?;
+        let positioned = nodes.iter().filter(|node| node.has_position()).map(|node| node.id());
+        let positioned = positioned.collect::<HashSet<_>>();
+        if positioned.len() < nodes.len() {
+            self.arrange_nodes(&positioned, spacing)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Updates the given node in the definition.
     ///
     /// The function `F` is called with the information with the state of the node so far and
@@ -1827,6 +2082,89 @@ main =
         }
     }
 
+    #[test]
+    fn graph_controller_copy_and_paste_nodes() {
+        let mut test = Fixture::set_up();
+        test.data.code = r"
+main =
+    foo = 2
+    bar = foo + 1
+    print bar"
+            .into();
+        test.run(|graph| async move {
+            let (foo, bar, _) = graph.nodes().unwrap().expect_tuple();
+            graph.set_node_position(foo.id(), Position::new(0.0, 0.0)).unwrap();
+            graph.set_node_position(bar.id(), Position::new(0.0, -40.0)).unwrap();
+            let copied = graph.copy_nodes(&[bar.id(), foo.id()]).unwrap();
+            let codes = copied.nodes.iter().map(|node| node.code.as_str()).collect_vec();
+            assert_eq!(codes, ["foo = 2", "bar = foo + 1"]);
+            let connection = clipboard::CopiedConnection { source: 0, target: 1 };
+            assert_eq!(copied.connections, [connection]);
+
+            let payload = clipboard::Payload::Nodes(copied);
+            let pasted = graph.paste_nodes(&payload, Position::new(100.0, 100.0)).unwrap();
+            let expected_program = r"
+main =
+    foo = 2
+    bar = foo + 1
+    print bar
+    number1 = 2
+    sum1 = number1 + 1";
+            model::module::test::expect_code(&*graph.module, expected_program);
+            let positions =
+                pasted.iter().map(|id| graph.module.node_metadata(*id).unwrap().position);
+            let expected_positions = [Position::new(100.0, 120.0), Position::new(100.0, 80.0)];
+            assert_eq!(positions.collect_vec(), expected_positions.map(Some));
+
+            let payload = clipboard::Payload::Text("baz = 5\nbaz + 1".into());
+            graph.paste_nodes(&payload, Position::new(0.0, 0.0)).unwrap();
+            let expected_program = format!("{expected_program}\n    baz = 5\n    baz + 1");
+            model::module::test::expect_code(&*graph.module, &expected_program);
+        })
+    }
+
+    #[test]
+    fn graph_controller_inline_node() {
+        let mut test = Fixture::set_up();
+        test.data.code = r"
+custom_new a =
+    b = a + 1
+    d = b * 2
+    d
+
+main =
+    a = 1
+    c = custom_new a
+    c + 7"
+            .into();
+        test.run(|graph| async move {
+            let method_name = definition::DefinitionName::new_plain("custom_new");
+            let method = module::locate_child(&graph.module.ast(), &method_name).unwrap();
+            let method_nodes = GraphInfo::from_definition(method.item).nodes();
+            let set_position = |id, position| {
+                let metadata = NodeMetadata { position: Some(position), ..default() };
+                graph.module.set_node_metadata(id, metadata).unwrap();
+            };
+            set_position(method_nodes[0].id(), Position::new(0.0, 40.0));
+            set_position(method_nodes[1].id(), Position::new(0.0, 0.0));
+            let (_, call, _) = graph.nodes().unwrap().expect_tuple();
+            graph.set_node_position(call.id(), Position::new(100.0, 100.0)).unwrap();
+
+            let inlined = graph.inline(call.id(), true).unwrap();
+            let expected_program = r"
+main =
+    a = 1
+    b = a + 1
+    c = b * 2
+    c + 7";
+            model::module::test::expect_code(&*graph.module, expected_program);
+            assert_eq!(inlined.len(), 2);
+            assert_eq!(inlined[1], call.id());
+            let position = graph.module.node_metadata(inlined[0]).unwrap().position;
+            assert_eq!(position, Some(Position::new(100.0, 140.0)));
+        })
+    }
+
     /// A regression test case for removing arguments. See
     /// https://github.com/enso-org/enso/issues/6228 for the issue's description.
     #[test]
@@ -0,0 +1,236 @@
+//! Copying and pasting graph nodes.
+//!
+//! The nodes copied with [`Handle::copy_nodes`](super::Handle::copy_nodes) are described by
+//! [`CopiedNodes`], serialized to JSON when put in the system clipboard. When the clipboard
+//! contains anything else, its text is pasted as Enso code, each line becoming a separate node.
+
+use crate::prelude::*;
+
+use crate::model::module::Position;
+
+use ast::crumbs::Located;
+use double_representation::alias_analysis;
+use double_representation::node::MainLine;
+use double_representation::node::NodeInfo;
+use parser::Parser;
+use serde::Deserialize;
+use serde::Serialize;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The version of the serialized [`CopiedNodes`] format. Clipboard content in other versions is
+/// pasted as plain text.
+const FORMAT_VERSION: u32 = 1;
+/// The vertical distance between the nodes pasted from plain text lines.
+const PLAIN_TEXT_NODE_SPACING: f32 = 40.0;
+/// The prefix of documentation comment lines in the pasted plain text.
+const DOC_COMMENT_PREFIX: &str = "##";
+
+
+
+// ==============
+// === Errors ===
+// ==============
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "The line `{}` cannot be pasted as a node.", _0)]
+pub struct NotANodeLine(String);
+
+
+
+// ===================
+// === CopiedNodes ===
+// ===================
+
+/// A single copied node.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CopiedNode {
+    /// The code of the node's line, including the pattern and the expression.
+    pub code:          String,
+    /// The text of the node's documentation comment.
+    pub documentation: Option<String>,
+    /// The node's position relative to the mean position of all the copied nodes.
+    pub offset:        Option<Position>,
+    /// The node's visualization metadata.
+    pub visualization: serde_json::Value,
+}
+
+/// A connection between two copied nodes, given by their indices in [`CopiedNodes::nodes`].
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct CopiedConnection {
+    pub source: usize,
+    pub target: usize,
+}
+
+/// The nodes copied from the graph.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct CopiedNodes {
+    /// The copied nodes, in the order of their lines in the graph.
+    pub nodes:       Vec<CopiedNode>,
+    /// The connections between the copied nodes.
+    pub connections: Vec<CopiedConnection>,
+    /// The qualified names of the entities the copied nodes use, which need to be imported.
+    pub imports:     Vec<String>,
+}
+
+/// The [`CopiedNodes`] as serialized in the clipboard.
+#[derive(Debug, Deserialize, Serialize)]
+struct Serialized {
+    #[serde(rename = "enso_copied_nodes_version")]
+    version: u32,
+    #[serde(flatten)]
+    nodes:   CopiedNodes,
+}
+
+impl CopiedNodes {
+    /// Serialize the nodes to the text to be put in the clipboard.
+    pub fn to_clipboard_text(&self) -> String {
+        let serialized = Serialized { version: FORMAT_VERSION, nodes: self.clone() };
+        serde_json::to_string(&serialized).unwrap_or_default()
+    }
+
+    /// Create the nodes from the plain text, each non-empty line becoming a separate node. Lines
+    /// being documentation comments are attached to the following node. The nodes are connected
+    /// when a line uses a variable introduced by one of the previous lines.
+    pub fn from_code(code: &str, parser: &Parser) -> FallibleResult<Self> {
+        let mut nodes = Vec::<CopiedNode>::new();
+        let mut introduced_names = HashMap::<String, usize>::new();
+        let mut connections = Vec::new();
+        let mut documentation = None;
+        for line in code.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(comment) = line.strip_prefix(DOC_COMMENT_PREFIX) {
+                documentation = Some(comment.trim().to_owned());
+                continue;
+            }
+            let node = parse_node_line(line, parser)?;
+            let index = nodes.len();
+            let usage = alias_analysis::analyze_ast(node.ast());
+            let sources = usage.used.iter().filter_map(|name| introduced_names.get(&name.item));
+            let new_connections = sources.map(|&source| CopiedConnection { source, target: index });
+            connections.extend(new_connections.unique());
+            for name in usage.introduced {
+                introduced_names.insert(name.item, index);
+            }
+            let offset = Position::new(0.0, -(index as f32) * PLAIN_TEXT_NODE_SPACING);
+            nodes.push(CopiedNode {
+                code:          line.to_owned(),
+                documentation: documentation.take(),
+                offset:        Some(offset),
+                visualization: default(),
+            });
+        }
+        Ok(Self { nodes, connections, imports: default() })
+    }
+}
+
+
+
+// ===============
+// === Payload ===
+// ===============
+
+/// The content of the clipboard to be pasted into the graph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Payload {
+    /// The nodes copied from a graph.
+    Nodes(CopiedNodes),
+    /// Any other text, pasted as Enso code lines.
+    Text(String),
+}
+
+impl Payload {
+    /// Interpret the text read from the clipboard.
+    pub fn from_clipboard_text(text: &str) -> Self {
+        match serde_json::from_str::<Serialized>(text) {
+            Ok(serialized) if serialized.version == FORMAT_VERSION => Self::Nodes(serialized.nodes),
+            _ => Self::Text(text.to_owned()),
+        }
+    }
+}
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Parse the line of code as a node, with newly generated AST ids.
+pub fn parse_node_line(line: &str, parser: &Parser) -> FallibleResult<NodeInfo> {
+    let line_ast = parser.parse_line_ast(line)?;
+    let node = NodeInfo::from_main_line_ast(&line_ast);
+    node.ok_or_else(|| NotANodeLine(line.to_owned()).into())
+}
+
+/// Replace the usages of the identifiers in the node's line, according to the `renames` map from
+/// the old names to the new ones.
+pub fn rename_used_identifiers(
+    node: &mut NodeInfo,
+    renames: &HashMap<String, String>,
+) -> FallibleResult {
+    if !renames.is_empty() {
+        let usage = alias_analysis::analyze_ast(node.ast());
+        let mut line_ast = node.ast().clone();
+        for Located { crumbs, item } in usage.used {
+            if let Some(new_name) = renames.get(&item) {
+                line_ast =
+                    line_ast.set_traversing(&crumbs, Ast::var(new_name.as_str()).with_new_id())?;
+            }
+        }
+        let main_line = MainLine::from_ast(&line_ast);
+        node.main_line = main_line.ok_or_else(|| NotANodeLine(line_ast.repr()))?;
+    }
+    Ok(())
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_clipboard_text() {
+        let copied = CopiedNodes {
+            nodes:       vec![CopiedNode {
+                code:          "foo = 2 + 2".into(),
+                documentation: Some("Four.".into()),
+                offset:        Some(Position::new(1.0, 2.0)),
+                visualization: default(),
+            }],
+            connections: vec![],
+            imports:     vec!["Standard.Base.Data.Numbers".into()],
+        };
+        let text = copied.to_clipboard_text();
+        assert_eq!(Payload::from_clipboard_text(&text), Payload::Nodes(copied));
+        let code = "foo = 2 + 2";
+        assert_eq!(Payload::from_clipboard_text(code), Payload::Text(code.into()));
+        let version_field = "\"enso_copied_nodes_version\"";
+        let other_version =
+            text.replace(&format!("{version_field}:1"), &format!("{version_field}:0"));
+        assert_eq!(Payload::from_clipboard_text(&other_version), Payload::Text(other_version));
+    }
+
+    #[test]
+    fn nodes_from_code() {
+        let parser = Parser::new();
+        let code = "\n## The input.\nfoo = 2\nbar = foo + 1\n\nfoo + bar\n";
+        let copied = CopiedNodes::from_code(code, &parser).unwrap();
+        let lines = copied.nodes.iter().map(|node| node.code.as_str()).collect_vec();
+        assert_eq!(lines, ["foo = 2", "bar = foo + 1", "foo + bar"]);
+        assert_eq!(copied.nodes[0].documentation.as_deref(), Some("The input."));
+        assert_eq!(copied.nodes[1].documentation, None);
+        let connection = |source, target| CopiedConnection { source, target };
+        let expected_connections = [connection(0, 1), connection(0, 2), connection(1, 2)];
+        assert_eq!(copied.connections, expected_connections);
+    }
+}
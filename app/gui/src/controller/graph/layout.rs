@@ -0,0 +1,247 @@
+//! Automatic layout of the graph nodes.
+//!
+//! The nodes are arranged in layers, in the style of the
+//! [Sugiyama method](https://en.wikipedia.org/wiki/Layered_graph_drawing):
+//! 1. Each node is assigned to the layer right below the lowest of the nodes it is connected from.
+//! 2. The nodes in each layer are ordered to reduce the edge crossings, by sorting them repeatedly
+//!    by the mean order of the nodes they are connected with in the other layers.
+//! 3. The layers are placed one below another, each centered horizontally.
+//!
+//! The pinned nodes keep their positions. The layout of the other nodes is moved so the pinned
+//! nodes are, on average, where the layout would place them, and the nodes overlapping the already
+//! placed ones are moved down.
+
+use crate::prelude::*;
+
+use crate::controller::graph::Connections;
+use crate::model::module::Position;
+
+use double_representation::graph::GraphInfo;
+use double_representation::node;
+use ordered_float::OrderedFloat;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The number of the downward and upward sweeps ordering the nodes in layers.
+const ORDERING_SWEEPS: usize = 4;
+
+
+
+// ===============
+// === Spacing ===
+// ===============
+
+/// The distances between the positions of the neighbouring nodes in the layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spacing {
+    /// The distance between the nodes in a single layer.
+    pub horizontal: f32,
+    /// The distance between the layers.
+    pub vertical:   f32,
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self { horizontal: 240.0, vertical: 60.0 }
+    }
+}
+
+
+
+// ==============
+// === Layout ===
+// ==============
+
+/// The layout of the graph's nodes. See the [module docs](self) for the details.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    /// The ids of the nodes, in the order of their lines in the graph.
+    nodes:   Vec<node::Id>,
+    /// For each node, the indices of the nodes it is connected from.
+    inputs:  Vec<Vec<usize>>,
+    /// For each node, the indices of the nodes it is connected to.
+    outputs: Vec<Vec<usize>>,
+    pinned:  HashMap<usize, Position>,
+    /// The position of the first layer's center, used when there are no pinned nodes.
+    origin:  Position,
+}
+
+impl Layout {
+    /// Create the layout of the graph's nodes and connections, without any nodes pinned.
+    pub fn new(graph: &GraphInfo, connections: &Connections) -> Self {
+        let nodes = graph.nodes().iter().map(|node| node.id()).collect_vec();
+        let index_of = |id: &node::Id| nodes.iter().position(|node| node == id);
+        let edges = connections.connections.iter().filter_map(|connection| {
+            Some((index_of(&connection.source.node)?, index_of(&connection.target.node)?))
+        });
+        let mut inputs = vec![Vec::new(); nodes.len()];
+        let mut outputs = vec![Vec::new(); nodes.len()];
+        // The connection sources are always placed in lines above the targets, which makes the
+        // graph acyclic. Any other edges are ignored to keep it so.
+        for (source, target) in edges.unique().filter(|(source, target)| source < target) {
+            inputs[target].push(source);
+            outputs[source].push(target);
+        }
+        Self { nodes, inputs, outputs, pinned: default(), origin: default() }
+    }
+
+    /// Keep the node at the given position. Does nothing if there is no such node in the graph.
+    pub fn pin(&mut self, id: node::Id, position: Position) {
+        if let Some(index) = self.nodes.iter().position(|node| *node == id) {
+            self.pinned.insert(index, position);
+        }
+    }
+
+    /// Set the position of the first layer's center. It is used only if no node is pinned.
+    pub fn set_origin(&mut self, origin: Position) {
+        self.origin = origin;
+    }
+
+    /// Compute the positions of all the nodes which are not pinned.
+    pub fn compute(&self, spacing: Spacing) -> HashMap<node::Id, Position> {
+        let layers = self.ordered_layers();
+        let mut positions = vec![Position::default(); self.nodes.len()];
+        for (layer_index, layer) in layers.iter().enumerate() {
+            let center = (layer.len() as f32 - 1.0) / 2.0;
+            let y = -(layer_index as f32) * spacing.vertical;
+            for (order, node) in layer.iter().enumerate() {
+                let x = (order as f32 - center) * spacing.horizontal;
+                positions[*node] = Position::new(x, y);
+            }
+        }
+        let offset = if self.pinned.is_empty() {
+            self.origin
+        } else {
+            let pinned_in_layout = self.pinned.keys().map(|node| positions[*node]);
+            let mean_in_layout = Position::mean(pinned_in_layout);
+            let mean_pinned = Position::mean(self.pinned.values().copied());
+            (mean_pinned.vector - mean_in_layout.vector).into()
+        };
+
+        let mut placed = self.pinned.values().copied().collect_vec();
+        let mut result = HashMap::new();
+        for node in layers.iter().flatten().filter(|node| !self.pinned.contains_key(node)) {
+            let mut position = positions[*node] + offset;
+            while placed.iter().any(|other| overlaps(position, *other, spacing)) {
+                position.vector.y -= spacing.vertical;
+            }
+            placed.push(position);
+            result.insert(self.nodes[*node], position);
+        }
+        result
+    }
+
+    /// Assign the nodes to layers and order them within each layer.
+    fn ordered_layers(&self) -> Vec<Vec<usize>> {
+        let mut layer_of = vec![0; self.nodes.len()];
+        for node in 0..self.nodes.len() {
+            let below_inputs = self.inputs[node].iter().map(|input| layer_of[*input] + 1);
+            layer_of[node] = below_inputs.max().unwrap_or_default();
+        }
+        let layer_count = layer_of.iter().max().map_or(0, |max| max + 1);
+        let mut layers = vec![Vec::new(); layer_count];
+        let mut order = vec![0.0; self.nodes.len()];
+        for (node, layer) in layer_of.iter().enumerate() {
+            order[node] = layers[*layer].len() as f32;
+            layers[*layer].push(node);
+        }
+        for _ in 0..ORDERING_SWEEPS {
+            for layer in layers.iter_mut().skip(1) {
+                sort_by_barycenter(layer, &self.inputs, &mut order);
+            }
+            for layer in layers.iter_mut().rev().skip(1) {
+                sort_by_barycenter(layer, &self.outputs, &mut order);
+            }
+        }
+        layers
+    }
+}
+
+/// Sort the nodes of the layer by the mean order of their neighbours, and update the `order` of
+/// the layer's nodes accordingly. Nodes without neighbours keep their order.
+fn sort_by_barycenter(layer: &mut [usize], neighbours: &[Vec<usize>], order: &mut [f32]) {
+    let barycenter = |node: usize| {
+        let neighbours = &neighbours[node];
+        if neighbours.is_empty() {
+            order[node]
+        } else {
+            neighbours.iter().map(|neighbour| order[*neighbour]).sum::<f32>()
+                / neighbours.len() as f32
+        }
+    };
+    let barycenters: HashMap<usize, f32> =
+        layer.iter().map(|node| (*node, barycenter(*node))).collect();
+    layer.sort_by_key(|node| OrderedFloat(barycenters[node]));
+    for (index, node) in layer.iter().enumerate() {
+        order[*node] = index as f32;
+    }
+}
+
+fn overlaps(position: Position, other: Position, spacing: Spacing) -> bool {
+    let distance = position.vector - other.vector;
+    distance.x.abs() < spacing.horizontal && distance.y.abs() < spacing.vertical
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::controller::graph::tests::MockData;
+
+    const PROGRAM: &str = r"
+main =
+    a = 1
+    b = 2
+    c = a + b
+    d = c + 1
+    e = 3";
+    const SPACING: Spacing = Spacing { horizontal: 100.0, vertical: 50.0 };
+
+    fn layout() -> (Layout, Vec<node::Id>) {
+        let graph = MockData { code: PROGRAM.into(), ..MockData::new() }.graph();
+        let graph_info = graph.graph_info().unwrap();
+        let connections = Connections::new(&graph_info, &span_tree::generate::context::Empty);
+        let layout = Layout::new(&graph_info, &connections);
+        let ids = layout.nodes.clone();
+        (layout, ids)
+    }
+
+    fn positions_of(positions: &HashMap<node::Id, Position>, ids: &[node::Id]) -> Vec<(f32, f32)> {
+        ids.iter().map(|id| positions[id].vector).map(|vector| (vector.x, vector.y)).collect()
+    }
+
+    #[test]
+    fn layered_layout() {
+        let (mut layout, ids) = layout();
+        layout.set_origin(Position::new(10.0, 20.0));
+        let positions = layout.compute(SPACING);
+        let expected = [(-90.0, 20.0), (10.0, 20.0), (10.0, -30.0), (10.0, -80.0), (110.0, 20.0)];
+        assert_eq!(positions_of(&positions, &ids), expected);
+    }
+
+    #[test]
+    fn layout_with_pinned_nodes() {
+        let (mut layout, ids) = layout();
+        layout.pin(ids[0], Position::new(500.0, 500.0));
+        let positions = layout.compute(SPACING);
+        assert!(!positions.contains_key(&ids[0]));
+        let expected = [(600.0, 500.0), (600.0, 450.0), (600.0, 400.0), (700.0, 500.0)];
+        assert_eq!(positions_of(&positions, &ids[1..]), expected);
+
+        layout.pin(ids[2], Position::new(500.0, 450.0));
+        let positions = layout.compute(SPACING);
+        let unpinned = [ids[1], ids[3], ids[4]];
+        let expected = [(550.0, 400.0), (550.0, 350.0), (650.0, 500.0)];
+        assert_eq!(positions_of(&positions, &unpinned), expected);
+    }
+}
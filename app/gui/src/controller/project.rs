@@ -4,14 +4,22 @@ use crate::model::traits::*;
 use crate::prelude::*;
 
 use crate::controller::ide::StatusNotificationPublisher;
+use crate::model::module::TextChange;
 
 use double_representation::import;
 use double_representation::name::project;
 use double_representation::name::QualifiedName;
+use double_representation::refactorings::rename;
+use double_representation::text::apply_code_change_to_id_map;
+use engine_protocol::language_server::FileEdit;
+use engine_protocol::language_server::FileEditList;
 use engine_protocol::language_server::MethodPointer;
 use engine_protocol::language_server::Path;
+use engine_protocol::language_server::TextEdit;
+use engine_protocol::types::Sha3_224;
 use enso_frp::web::platform;
 use enso_frp::web::platform::Platform;
+use enso_text::index::Byte;
 use parser::Parser;
 
 
@@ -49,6 +57,17 @@ pub fn main_method_ptr(
 
 
 
+// ==============
+// === Errors ===
+// ==============
+
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Fail)]
+#[fail(display = "No method `{}` in the suggestion database.", _0)]
+pub struct NoSuchMethod(String);
+
+
+
 // =================
 // === Utilities ===
 // =================
@@ -218,6 +237,165 @@ impl Project {
 }
 
 
+// === Renaming Symbols ===
+
+impl Project {
+    /// Describe the method as a symbol which can be [renamed](Self::rename_symbol).
+    ///
+    /// The calls with the method's name on any target are renamed only if there is no other method
+    /// with the same name in the suggestion database.
+    pub fn method_symbol(&self, method: &MethodPointer) -> FallibleResult<rename::Symbol> {
+        use model::suggestion_database::entry::Kind;
+        let suggestion_db = self.model.suggestion_db();
+        let (_, entry) = suggestion_db
+            .lookup_by_method_pointer(method)
+            .ok_or_else(|| NoSuchMethod(method.name.clone()))?;
+        let entries =
+            suggestion_db.keys().into_iter().filter_map(|id| suggestion_db.lookup(id).ok());
+        let mut same_named =
+            entries.filter(|other| other.kind == Kind::Method && other.name == entry.name);
+        let is_unique = same_named.nth(1).is_none();
+        Ok(rename::Symbol::Method {
+            module:           QualifiedName::from_text(&method.module)?,
+            self_type:        QualifiedName::from_text(&method.defined_on_type)?,
+            name:             entry.name.clone(),
+            rename_dot_calls: is_unique,
+        })
+    }
+
+    /// Rename the symbol in all the project's modules it occurs in.
+    ///
+    /// If the new name conflicts with any name already present in the project, the
+    /// [`rename::NameConflicts`] error is returned before any module is modified. The modules are
+    /// updated in a single undoable transaction. Returns the edits made to the modules' files.
+    pub async fn rename_symbol(
+        &self,
+        symbol: &rename::Symbol,
+        new_name: &str,
+    ) -> FallibleResult<FileEditList> {
+        symbol.validate_new_name(new_name)?;
+        let mut conflicts = self.suggestion_db_conflicts(symbol, new_name);
+        let mut renames = Vec::new();
+        for module_name in self.modules_to_rename_in(symbol) {
+            let root_id = self.model.project_content_root_id();
+            let path = model::module::Path::from_name(root_id, &module_name);
+            let module = self.model.module(path).await?;
+            let ast = module.ast();
+            conflicts.extend(rename::conflicts(&module_name, &ast, symbol, new_name));
+            let occurrences = rename::occurrences(&module_name, &ast, symbol)?;
+            if !occurrences.is_empty() {
+                renames.push((module, occurrences));
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(rename::NameConflicts::new(symbol, new_name, &conflicts).into());
+        }
+
+        let _transaction_guard = self.model.urm().get_or_open_transaction("Rename symbol");
+        let parser = self.model.parser();
+        let mut edits = Vec::new();
+        let mut renamed: Vec<(&model::Module, ast::known::Module)> = Vec::new();
+        for (module, occurrences) in &renames {
+            let previous_ast = module.ast();
+            match Self::rename_in_module(module, occurrences, new_name, &parser) {
+                Ok(edit) => {
+                    edits.push(edit);
+                    renamed.push((module, previous_ast));
+                }
+                Err(error) => {
+                    for (module, previous_ast) in renamed {
+                        if let Err(error) = module.update_ast(previous_ast) {
+                            error!(
+                                "Failed to restore module {} after rename: {error}",
+                                module.path()
+                            );
+                        }
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(FileEditList { edits })
+    }
+
+    /// The names of the project's modules the symbol may occur in.
+    fn modules_to_rename_in(&self, symbol: &rename::Symbol) -> Vec<QualifiedName> {
+        if let rename::Symbol::Local { module, .. } = symbol {
+            return vec![module.clone()];
+        }
+        let project = self.model.qualified_name();
+        let suggestion_db = self.model.suggestion_db();
+        let entries =
+            suggestion_db.keys().into_iter().filter_map(|id| suggestion_db.lookup(id).ok());
+        let modules = entries.map(|entry| entry.defined_in.clone());
+        let in_project = modules.filter(|module| *module.project() == project);
+        iter::once(symbol.module().clone()).chain(in_project).unique().collect()
+    }
+
+    /// The conflicts of the new name with the entities from the suggestion database, which might
+    /// be defined outside the modules of the project.
+    fn suggestion_db_conflicts(
+        &self,
+        symbol: &rename::Symbol,
+        new_name: &str,
+    ) -> Vec<rename::Conflict> {
+        use model::suggestion_database::entry::Kind;
+        let suggestion_db = self.model.suggestion_db();
+        let entries =
+            suggestion_db.keys().into_iter().filter_map(|id| suggestion_db.lookup(id).ok());
+        let conflicting = entries.filter(|entry| {
+            entry.name == new_name
+                && match symbol {
+                    rename::Symbol::Local { .. } => false,
+                    rename::Symbol::Method { self_type, .. } =>
+                        entry.kind == Kind::Method && entry.self_type.as_ref() == Some(self_type),
+                    rename::Symbol::Type { module, .. } =>
+                        entry.kind == Kind::Type && entry.defined_in == *module,
+                }
+        });
+        let to_conflict = |entry: Rc<model::suggestion_database::Entry>| rename::Conflict {
+            module:   entry.defined_in.clone(),
+            new_name: new_name.to_owned(),
+        };
+        conflicting.map(to_conflict).collect()
+    }
+
+    /// Replace the occurrences of the renamed symbol in the module with the new name. The module
+    /// is updated with a single code change, spanning from the first to the last occurrence.
+    fn rename_in_module(
+        module: &model::Module,
+        occurrences: &[enso_text::Range<Byte>],
+        new_name: &str,
+        parser: &Parser,
+    ) -> FallibleResult<FileEdit> {
+        let old_content = module.serialized_content()?.content;
+        let ast = module.ast();
+        let old_code = ast.repr();
+        let mut code = old_code.clone();
+        let mut id_map = ast.id_map();
+        for change in rename::text_changes(occurrences, new_name) {
+            apply_code_change_to_id_map(&mut id_map, &change, &code);
+            code.replace_range(change.range.start.value..change.range.end.value, &change.text);
+        }
+        let start = occurrences.first().map_or_default(|range| range.start);
+        let end = occurrences.last().map_or_default(|range| range.end);
+        let new_end = code.len() - (old_code.len() - end.value);
+        let text = code[start.value..new_end].to_owned();
+        let change = TextChange { range: (start..end).into(), text };
+        module.apply_code_change(change, parser, id_map)?;
+
+        let new_content = module.serialized_content()?.content;
+        let edit = TextEdit::from_prefix_postfix_differences(&old_content, &new_content);
+        Ok(FileEdit {
+            path:        module.path().file_path().clone(),
+            edits:       vec![edit],
+            old_version: Sha3_224::new(old_content.as_bytes()),
+            new_version: Sha3_224::new(new_content.as_bytes()),
+        })
+    }
+}
+
+
 // === Project Snapshotting ===
 
 impl Project {
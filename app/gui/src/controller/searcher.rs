@@ -6,6 +6,7 @@ use crate::prelude::*;
 use crate::controller::graph::ImportType;
 use crate::controller::graph::RequiredImport;
 use crate::model::execution_context::GroupQualifiedName;
+use crate::model::local_storage::LocalStorage;
 use crate::model::module::NodeEditStatus;
 use crate::model::suggestion_database;
 use crate::presenter::searcher;
@@ -234,15 +235,18 @@ impl Data {
 #[derive(Debug, Clone, CloneRef, Eq, PartialEq)]
 pub struct Filter {
     /// The part of the input used for filtering.
-    pub pattern: ImString,
+    pub pattern:    ImString,
     /// Additional context. A string representation of the edited accessor chain.
-    pub context: Option<ImString>,
+    pub context:    Option<ImString>,
+    /// The query for components by their argument and return types, if the input is one. Its
+    /// name pattern is the same as [`Self::pattern`].
+    pub type_query: Option<Rc<component::TypeQuery>>,
     /// The name of the currently active module. This is necessary since the module influences what
     /// code to generate. At the time of writing, this is only the case when importing a module
     /// method of a main module: the module is referred to as `Main` from within the same module
     /// or by the project name when referenced elsewhere. See
     /// `enso_suggestion_database::Entry::code_with_static_this` for its usage.
-    module_name: Rc<QualifiedName>,
+    module_name:    Rc<QualifiedName>,
 }
 
 /// Component Browser Controller.
@@ -264,6 +268,7 @@ pub struct Searcher {
     position_in_code: Immutable<Location<Byte>>,
     project:          model::Project,
     node_edit_guard:  Rc<Option<EditGuard>>,
+    usage:            Rc<RefCell<component::usage::Statistics>>,
 }
 
 impl Searcher {
@@ -289,6 +294,8 @@ impl Searcher {
             _ => None,
         });
         let breadcrumbs = Breadcrumbs::new();
+        let usage = LocalStorage::get()
+            .map_or_default(|storage| component::usage::Statistics::load(&storage));
         let ret = Self {
             graph,
             this_arg,
@@ -302,6 +309,7 @@ impl Searcher {
             position_in_code: Immutable(position_in_code),
             project,
             node_edit_guard: node_metadata_guard,
+            usage: Rc::new(RefCell::new(usage)),
         };
         Ok(ret.init())
     }
@@ -390,22 +398,25 @@ impl Searcher {
         let parsed_input = input::Input::parse(self.ide.parser(), new_input, cursor_position);
         let new_context = parsed_input.context().map(|ctx| ctx.into_ast().repr());
         let new_literal = parsed_input.edited_literal().cloned();
+        let is_type_query = parsed_input.type_query().is_some();
         let old_filter = self.filter();
         let old_input = mem::replace(&mut self.data.borrow_mut().input, parsed_input);
         let old_context = old_input.context().map(|ctx| ctx.into_ast().repr());
         let old_literal = old_input.edited_literal();
+        let was_type_query = old_input.type_query().is_some();
 
         self.invalidate_picked_suggestions();
         let context_changed = old_context != new_context;
         let literal_changed = old_literal != new_literal.as_ref();
-        if context_changed || literal_changed {
+        let type_query_toggled = is_type_query != was_type_query;
+        if context_changed || literal_changed || type_query_toggled {
             debug!("Reloading list.");
             self.reload_list();
         } else {
             let filter = self.filter();
             if filter != old_filter {
                 let mut data = self.data.borrow_mut();
-                Rc::make_mut(&mut data.components).update_filtering(filter.clone_ref());
+                self.update_filtering(Rc::make_mut(&mut data.components), filter);
                 executor::global::spawn(self.notifier.publish(Notification::NewComponentList));
             }
         }
@@ -558,6 +569,7 @@ impl Searcher {
         if let Some(this) = self.this_arg.deref().as_ref() {
             this.introduce_pattern(graph.clone_ref())?;
         }
+        self.record_usage();
         // Should go last, as we want to prevent a revert only when the committing process was
         // successful.
         if let Some(guard) = self.node_edit_guard.deref().as_ref() {
@@ -566,6 +578,30 @@ impl Searcher {
         Ok(node_id)
     }
 
+    /// Record the suggestions picked for the committed node in the usage statistics, so they will
+    /// be promoted in the next searches.
+    fn record_usage(&self) {
+        let context = self.usage_context(self.data.borrow().components.this_type.as_ref());
+        let timestamp = component::usage::now();
+        let mut usage = self.usage.borrow_mut();
+        for picked in &self.data.borrow().picked_suggestions {
+            if let component::Suggestion::FromDatabase { entry, .. } = &picked.suggestion {
+                usage.record(entry.qualified_name().to_string(), context.clone(), timestamp);
+            }
+        }
+        if let Some(storage) = LocalStorage::get() {
+            if let Err(error) = usage.store(&storage) {
+                warn!("Cannot store the component usage statistics: {error}");
+            }
+        }
+    }
+
+    fn usage_context(&self, this_type: Option<impl ToString>) -> component::usage::Context {
+        let this_type = this_type.map(|this_type| this_type.to_string());
+        let module = self.module_qualified_name().to_string();
+        component::usage::Context { this_type, module }
+    }
+
     fn get_expression(&self, input: Ast) -> Ast {
         match self.this_var() {
             Some(this_var) => searcher::apply_this_argument(this_var, &input),
@@ -597,9 +633,12 @@ impl Searcher {
     #[profile(Debug)]
     pub fn reload_list(&self) {
         let edited_literal = self.data.borrow().input.edited_literal().cloned();
+        let is_type_query = self.data.borrow().input.type_query().is_some();
         if let Some(literal) = edited_literal {
             let components = component_list_for_literal(&literal, &self.database);
             self.data.borrow_mut().components = Rc::new(components);
+        } else if is_type_query {
+            self.gather_actions_for_type_query();
         } else {
             let this_type = self.this_arg_type_for_next_completion();
             self.gather_actions_from_engine(this_type, None);
@@ -617,19 +656,44 @@ impl Searcher {
             input.edited_name_range().unwrap_or(default_range)
         };
         let is_first_function = replaced_range.start == Byte(0);
+        let this_node_type = is_first_function.then(|| self.this_node_type());
+        async move {
+            match this_node_type {
+                Some(this_node_type) => this_node_type.await,
+                None => None,
+            }
+        }
+    }
+
+    /// Get the typename of the searcher's source node, if there is one. Returns `Future`, as the
+    /// type information might not have came yet from the Language Server.
+    fn this_node_type(&self) -> impl Future<Output = Option<String>> {
         let graph = self.graph.clone_ref();
         let this = self.this_arg.clone_ref();
         async move {
-            if is_first_function {
-                let ThisNode { id, .. } = this.deref().as_ref()?;
-                let opt_type = graph.expression_type(*id).await.map(Into::into);
-                opt_type.map_none(move || error!("Failed to obtain type for this node."))
-            } else {
-                None
-            }
+            let ThisNode { id, .. } = this.deref().as_ref()?;
+            let opt_type = graph.expression_type(*id).await.map(Into::into);
+            opt_type.map_none(move || error!("Failed to obtain type for this node."))
         }
     }
 
+    /// Fill the component list for a [type query](component::TypeQuery).
+    ///
+    /// The query may look for components consuming the source node's value as any argument, not
+    /// only as `self`, so all the entries from the suggestion database are put to the list instead
+    /// of the Language Server's completions.
+    fn gather_actions_for_type_query(&self) {
+        let this_type = self.this_node_type();
+        let this = self.clone_ref();
+        executor::global::spawn(async move {
+            let this_type = this_type.await;
+            info!("Filling the list for a type query. Type of the source node is {this_type:?}.");
+            let new_list = this.make_component_list(this.database.keys(), &this_type);
+            this.data.borrow_mut().components = new_list;
+            this.notifier.publish(Notification::NewComponentList).await;
+        });
+    }
+
     fn gather_actions_from_engine(
         &self,
         this_type: impl Future<Output = Option<String>> + 'static,
@@ -683,10 +747,26 @@ impl Searcher {
 
         builder.add_components_from_db(entry_ids);
         let mut list = builder.build();
-        list.update_filtering(self.filter());
+        let usage_context = self.usage_context(this_type.as_ref());
+        list.set_usage_ranking(
+            self.usage.borrow().ranking(&usage_context, component::usage::now()),
+        );
+        self.update_filtering(&mut list, self.filter());
         Rc::new(list)
     }
 
+    /// Apply the filter to the component list, adding the components whose documentation matches
+    /// the filtering pattern after the ones matched by name.
+    fn update_filtering(&self, list: &mut component::List, filter: Filter) {
+        let search_documentation = filter.type_query.is_none() && !filter.pattern.trim().is_empty();
+        let documentation_matches =
+            search_documentation.then(|| self.database.search_documentation(&filter.pattern));
+        list.update_filtering(filter);
+        if let Some(matches) = documentation_matches {
+            list.add_documentation_matches(&matches);
+        }
+    }
+
     /// Convert a location within a current module (i.e. module being edited) to a location indexed
     /// by UTF-16 code units. This enables Language Server protocol compatibility.
     fn location_to_utf16(
@@ -9,6 +9,7 @@ use crate::controller::searcher::Filter;
 use crate::model::execution_context::GroupQualifiedName;
 use crate::model::suggestion_database;
 
+use double_representation::name::QualifiedName;
 use enso_doc_parser::DocSection;
 use enso_doc_parser::Tag;
 use enso_suggestion_database::entry;
@@ -24,8 +25,11 @@ use superslice::Ext;
 
 pub mod builder;
 pub mod hardcoded;
+pub mod type_query;
+pub mod usage;
 
 pub use builder::Builder;
+pub use type_query::TypeQuery;
 
 
 
@@ -36,6 +40,9 @@ pub use builder::Builder;
 /// A factor to multiply a component's alias match score by. It is intended to reduce the importance
 /// of alias matches compared to label matches.
 const ALIAS_MATCH_ATTENUATION_FACTOR: f32 = 0.75;
+/// The maximum number of components matched by their documentation displayed after the components
+/// matched by name.
+const MAX_DOCUMENTATION_MATCHES: usize = 20;
 
 
 
@@ -70,6 +77,8 @@ pub enum MatchKind {
     Name,
     /// An alias of the entry was matched, contains the specific alias that was matched.
     Alias(ImString),
+    /// The entry's documentation was matched, see [`List::add_documentation_matches`].
+    Documentation,
 }
 
 /// Information how the list entry matches the filtering pattern.
@@ -195,6 +204,8 @@ impl Component {
             MatchInfo::Matches { kind: MatchKind::Alias(alias), .. } => {
                 format!("{alias} ({self})")
             }
+            MatchInfo::Matches { kind: MatchKind::Documentation, .. } =>
+                format!("{self} (in docs)"),
             _ => self.to_string(),
         }
     }
@@ -229,8 +240,9 @@ impl Component {
 
     /// Update matching info.
     ///
-    /// It should be called each time the filtering pattern changes.
-    pub fn update_matching_info(&mut self, filter: Filter) {
+    /// It should be called each time the filtering pattern changes. The `this_type` is the type of
+    /// the searcher's source node, if known; it is used by the [`TypeQuery`] of the filter.
+    pub fn update_matching_info(&mut self, filter: Filter, this_type: Option<&QualifiedName>) {
         // Match the input pattern to the component label.
         let label = self.to_string();
         let label_matches = fuzzly::matches(&label, filter.pattern.as_str());
@@ -289,6 +301,29 @@ impl Component {
                 self.match_info = MatchInfo::DoesNotMatch;
             }
         }
+
+        // Filter out components with types not matching the type query, and rank the rest by how
+        // well the types match.
+        if let Some(type_query) = &filter.type_query {
+            self.apply_type_query(type_query, this_type);
+        }
+    }
+
+    fn apply_type_query(&mut self, type_query: &TypeQuery, this_type: Option<&QualifiedName>) {
+        let type_score = match &self.suggestion {
+            Suggestion::FromDatabase { entry, .. } => type_query.match_entry(entry, this_type),
+            Suggestion::Virtual { .. } => None,
+        };
+        let Some(type_score) = type_score else {
+            self.match_info = MatchInfo::DoesNotMatch;
+            return;
+        };
+        if type_query.name_pattern.trim().is_empty() {
+            let subsequence = fuzzly::Subsequence { score: type_score, indices: default() };
+            self.match_info = MatchInfo::Matches { subsequence, kind: MatchKind::Label };
+        } else if let MatchInfo::Matches { subsequence, .. } = &mut self.match_info {
+            subsequence.score *= type_score;
+        }
     }
 
     /// Check whether the component contains the "PRIVATE" tag.
@@ -358,6 +393,9 @@ pub struct List {
     pub(crate) components:           Vec<Component>,
     pub(crate) displayed_by_default: Vec<Component>,
     pub(crate) groups:               Vec<Group>,
+    /// The type of the searcher's source node, if known.
+    pub(crate) this_type:            Option<QualifiedName>,
+    pub(crate) usage_ranking:        usage::Ranking,
 }
 
 impl List {
@@ -383,16 +421,21 @@ impl List {
 
     /// Update list filtering.
     ///
-    /// If the filtering pattern is not empty, the components will be sorted by match score (best
-    /// match first), and [`Self::displayed`] will return only matched entries. Otherwise
-    /// [`Self::displayed`] will return a "default" view, which depend on the context - see
-    /// [structure docs](List) for details.
+    /// If the filtering pattern is not empty or the filter has a [`TypeQuery`], the components will
+    /// be sorted by match score (best match first, with the score boosted by the
+    /// [usage ranking](Self::set_usage_ranking)), and [`Self::displayed`] will return only
+    /// matched entries. Otherwise [`Self::displayed`] will return a "default" view, which depend on
+    /// the context - see [structure docs](List) for details.
     pub fn update_filtering(&mut self, filter: Filter) {
-        if filter.pattern.trim().is_empty() {
+        if filter.pattern.trim().is_empty() && filter.type_query.is_none() {
             self.filtered_in = None;
         } else {
             for component in &mut self.components {
-                component.update_matching_info(filter.clone_ref());
+                component.update_matching_info(filter.clone_ref(), self.this_type.as_ref());
+                let boost = self.usage_ranking.boost(component);
+                if let MatchInfo::Matches { subsequence, .. } = &mut component.match_info {
+                    subsequence.score *= boost;
+                }
             }
             self.components
                 .sort_by(|lhs, rhs| Self::entry_match_ordering(&lhs.match_info, &rhs.match_info));
@@ -402,6 +445,58 @@ impl List {
         }
     }
 
+    /// Add the components matched by their documentation as a secondary section, displayed after
+    /// the components matched by the filtering pattern. The `matches` should be the result of
+    /// [`suggestion_database::SuggestionDatabase::search_documentation`] for the filtering pattern,
+    /// ordered from the best match.
+    ///
+    /// Does nothing if the list is not filtered. The components already matched by the pattern are
+    /// not repeated.
+    pub fn add_documentation_matches(
+        &mut self,
+        matches: &[enso_suggestion_database::documentation_search::Match],
+    ) {
+        let Some(filtered_in) = self.filtered_in else { return };
+        let not_matching = &mut self.components[filtered_in.end..];
+        let mut documentation_matches = 0;
+        for documentation_match in matches {
+            if documentation_matches == MAX_DOCUMENTATION_MATCHES {
+                break;
+            }
+            let position = not_matching[documentation_matches..]
+                .iter()
+                .position(|component| component.id() == Some(documentation_match.id));
+            if let Some(position) = position {
+                let index = documentation_matches + position;
+                let subsequence =
+                    fuzzly::Subsequence { score: documentation_match.score, indices: default() };
+                let match_info = MatchInfo::Matches { subsequence, kind: MatchKind::Documentation };
+                not_matching[index].match_info = match_info;
+                not_matching[documentation_matches..=index].rotate_right(1);
+                documentation_matches += 1;
+            }
+        }
+        self.filtered_in = Some(..filtered_in.end + documentation_matches);
+    }
+
+    /// Return the components matched by their documentation, displayed after the components
+    /// matched by the filtering pattern. See [`Self::add_documentation_matches`].
+    pub fn documentation_matches(&self) -> &[Component] {
+        let first_documentation_match = self.displayed().iter().position(|component| {
+            matches!(component.match_info, MatchInfo::Matches {
+                kind: MatchKind::Documentation,
+                ..
+            })
+        });
+        first_documentation_match.map_or(&[], |index| &self.displayed()[index..])
+    }
+
+    /// Set the usage statistics of the components, promoting the often used ones when filtering.
+    /// See [`usage::Ranking`].
+    pub fn set_usage_ranking(&mut self, ranking: usage::Ranking) {
+        self.usage_ranking = ranking;
+    }
+
     /// Return the entry match ordering when sorting by match. See [`component::Order::ByMatch`].
     fn entry_match_ordering(lhs: &MatchInfo, rhs: &MatchInfo) -> cmp::Ordering {
         lhs.cmp(rhs).reverse()
@@ -419,6 +514,7 @@ pub(crate) mod tests {
     use super::*;
 
     use double_representation::name::QualifiedName;
+    use enso_suggestion_database::doc_section;
     use enso_suggestion_database::mock_suggestion_database;
 
     pub fn check_displayed_components(list: &List, expected: Vec<&str>) {
@@ -457,6 +553,7 @@ pub(crate) mod tests {
         let make_filter = |pat: &str| Filter {
             pattern:     pat.into(),
             context:     None,
+            type_query:  None,
             module_name: module_name.clone_ref(),
         };
         check_displayed_components(&list, vec!["test.Test.TopModule1"]);
@@ -469,4 +566,119 @@ pub(crate) mod tests {
         list.update_filtering(make_filter(""));
         check_displayed_components(&list, vec!["test.Test.TopModule1"]);
     }
+
+    #[test]
+    fn ranking_by_usage() {
+        let db = mock_suggestion_database! {
+            test.Test {
+                mod TopModule1 {
+                    fn bar() -> Standard.Base.Any;
+
+                    mod SubModule1 {
+                        fn bazz() -> Standard.Base.Any;
+                    }
+                }
+            }
+        };
+        let mut builder = Builder::new_empty(&db);
+        builder.add_components_from_db(db.keys());
+        let mut list = builder.build();
+        let module_name = Rc::new(QualifiedName::from_text("local.New_Project_1").unwrap());
+        let filter = Filter {
+            pattern:     "ba".into(),
+            context:     None,
+            type_query:  None,
+            module_name: module_name.clone_ref(),
+        };
+        list.update_filtering(filter.clone_ref());
+        check_displayed_components(&list, vec!["TopModule1.bar", "SubModule1.bazz"]);
+
+        let context = usage::Context { this_type: None, module: module_name.to_string() };
+        let mut statistics = usage::Statistics::default();
+        for _ in 0..10 {
+            let bazz = "test.Test.TopModule1.SubModule1.bazz";
+            statistics.record(bazz, context.clone(), 0);
+        }
+        list.set_usage_ranking(statistics.ranking(&context, 0));
+        list.update_filtering(filter);
+        check_displayed_components(&list, vec!["SubModule1.bazz", "TopModule1.bar"]);
+    }
+
+    #[test]
+    fn filtering_by_documentation() {
+        let db = mock_suggestion_database! {
+            test.Test {
+                mod TopModule1 {
+                    #[with_doc_section(doc_section!("Reads the file."))]
+                    fn read() -> Standard.Base.Any;
+                    #[with_doc_section(doc_section!("Writes the text to the file."))]
+                    fn write() -> Standard.Base.Any;
+                    fn file_name() -> Standard.Base.Any;
+                }
+            }
+        };
+        let mut builder = Builder::new_empty(&db);
+        builder.add_components_from_db(db.keys());
+        let mut list = builder.build();
+        let filter = Filter {
+            pattern:     "file".into(),
+            context:     None,
+            type_query:  None,
+            module_name: Rc::new(QualifiedName::from_text("local.New_Project_1").unwrap()),
+        };
+        list.update_filtering(filter);
+        check_displayed_components(&list, vec!["TopModule1.file_name"]);
+        list.add_documentation_matches(&db.search_documentation("file"));
+        let expected =
+            vec!["TopModule1.file_name", "TopModule1.read (in docs)", "TopModule1.write (in docs)"];
+        check_displayed_components(&list, expected);
+        let documentation_matches = list.documentation_matches().iter().map(|c| c.name());
+        assert_eq!(documentation_matches.collect_vec(), vec!["read", "write"]);
+    }
+
+    #[test]
+    fn filtering_by_types() {
+        let db = mock_suggestion_database! {
+            test.Test {
+                mod Column {
+                    type Column {
+                        fn to_vector() -> Standard.Base.Data.Vector.Vector;
+                        fn builder() -> Standard.Base.Data.Vector.Vector_Builder;
+                        fn length() -> Standard.Base.Data.Numbers.Integer;
+                    }
+                    static fn from_vector(vector: Standard.Base.Data.Vector.Vector) -> test.Test.Column.Column;
+                    static fn concat(column: test.Test.Column.Column, other: Standard.Base.Any) -> test.Test.Column.Column;
+                }
+            }
+        };
+        let module_name = Rc::new(QualifiedName::from_text("local.New_Project_1").unwrap());
+        let make_filter = |input: &str| {
+            let type_query = TypeQuery::parse(input).map(Rc::new);
+            let pattern =
+                type_query.as_ref().map_or_default(|query| query.name_pattern.clone_ref());
+            Filter { pattern, context: None, type_query, module_name: module_name.clone_ref() }
+        };
+
+        let mut builder = Builder::new_empty(&db);
+        builder.add_components_from_db(db.keys());
+        let mut list = builder.build();
+        list.update_filtering(make_filter("-> Vector"));
+        check_displayed_components(&list, vec!["Column.to_vector", "Column.builder"]);
+        list.update_filtering(make_filter(": -> Int"));
+        check_displayed_components(&list, vec!["Column.length"]);
+        list.update_filtering(make_filter("Vector ->"));
+        check_displayed_components(&list, vec!["Column.from_vector"]);
+        list.update_filtering(make_filter("Column -> Vector"));
+        check_displayed_components(&list, vec!["Column.to_vector"]);
+        list.update_filtering(make_filter("to : Column ->"));
+        check_displayed_components(&list, vec!["Column.to_vector"]);
+        list.update_filtering(make_filter(": _ -> Column"));
+        check_displayed_components(&list, vec![]);
+
+        let mut builder = Builder::new_with_this_type(&db, &[], "test.Test.Column.Column");
+        builder.add_components_from_db(db.keys());
+        let mut list = builder.build();
+        list.update_filtering(make_filter(": _ -> Column"));
+        check_displayed_components(&list, vec!["Column.concat"]);
+    }
 }
@@ -194,6 +194,7 @@ impl<'a> Builder<'a> {
 
     /// Return the built list.
     pub fn build(mut self) -> component::List {
+        self.built_list.this_type = self.this_type;
         self.built_list
             .displayed_by_default
             .sort_by(|lhs, rhs| ComponentOrderingKey::of(lhs).cmp(&ComponentOrderingKey::of(rhs)));
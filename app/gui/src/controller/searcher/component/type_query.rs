@@ -0,0 +1,252 @@
+//! Searching for components by the types of their arguments and return values.
+//!
+//! A type query is written in the searcher input in the style of Enso type signatures:
+//! `[[name] :] [argument type] -> [return type]`, for example:
+//! * `-> Table` finds components returning a table,
+//! * `Column ->` finds components taking a column as any of their arguments (including `self`),
+//! * `Column -> Vector` finds components turning a column into a vector,
+//! * `to : _ -> Vector` finds components named similarly to `to`, which take the value of the
+//!   searcher's source node and return a vector.
+//!
+//! Without the type ascription, the types must be written as type names (capitalized, possibly
+//! qualified) or the `_` placeholder, so expressions containing an arrow, like the lambda
+//! `x -> x + 1`, are not taken for type queries. The ascription with an empty name, like
+//! `: -> table`, allows any type pattern.
+
+use crate::prelude::*;
+
+use crate::model::suggestion_database;
+
+use double_representation::name::QualifiedName;
+use ordered_float::OrderedFloat;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Separates the argument type from the return type in the query.
+const ARROW: &str = "->";
+/// Separates the name pattern from the type signature in the query.
+const TYPE_ASCRIPTION: &str = ":";
+/// The argument type pattern standing for the type of the searcher's source node.
+const SOURCE_NODE_PLACEHOLDER: &str = "_";
+/// The type of any value. Arguments and return values of this type are not considered matching any
+/// pattern, as otherwise they would match every query.
+const ANY_TYPE: &str = "Standard.Base.Any";
+/// The score of a type matched by its full name.
+const EXACT_MATCH_SCORE: f32 = 1.0;
+/// The score of a type whose name starts with the pattern.
+const PREFIX_MATCH_SCORE: f32 = 0.5;
+
+
+
+// ===================
+// === TypePattern ===
+// ===================
+
+/// A pattern for a single type in the [`TypeQuery`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TypePattern {
+    /// The type of the searcher's source node.
+    SourceNode,
+    /// A type name: either its last segment, like `Table`, or a (partially) qualified name.
+    Name(ImString),
+}
+
+impl TypePattern {
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "" => None,
+            SOURCE_NODE_PLACEHOLDER => Some(Self::SourceNode),
+            name => Some(Self::Name(name.into())),
+        }
+    }
+
+    /// Whether the pattern cannot be taken for an expression: it is the source node placeholder
+    /// or a type name, whose every segment is capitalized.
+    fn is_unambiguous(&self) -> bool {
+        match self {
+            Self::SourceNode => true,
+            Self::Name(name) => name.split('.').all(|segment| {
+                let mut chars = segment.chars();
+                let is_capitalized = chars.next().map_or(false, char::is_uppercase);
+                is_capitalized && chars.all(|c| c.is_alphanumeric() || c == '_')
+            }),
+        }
+    }
+
+    /// Score how well the type, as written in the suggestion database, matches the pattern. Each
+    /// alternative of a union type (like `Vector | Array`) is matched separately. Returns [`None`]
+    /// if the type does not match.
+    pub fn match_type(&self, repr_type: &str, source_type: Option<&QualifiedName>) -> Option<f32> {
+        let alternatives = repr_type.split('|').map(str::trim).filter(|tp| *tp != ANY_TYPE);
+        let scores = alternatives.filter_map(|tp| self.match_single_type(tp, source_type));
+        scores.max_by_key(|score| OrderedFloat(*score))
+    }
+
+    fn match_single_type(
+        &self,
+        type_name: &str,
+        source_type: Option<&QualifiedName>,
+    ) -> Option<f32> {
+        match self {
+            Self::SourceNode => {
+                let matches = source_type?.to_string() == type_name;
+                matches.then_some(EXACT_MATCH_SCORE)
+            }
+            Self::Name(pattern) => {
+                let last_segment = type_name.rsplit('.').next().unwrap_or(type_name);
+                let qualified_suffix = type_name.strip_suffix(pattern.as_str());
+                let is_qualified_match = qualified_suffix
+                    .map_or(false, |prefix| prefix.is_empty() || prefix.ends_with('.'));
+                let last_segment_lowercase = last_segment.to_lowercase();
+                let pattern_lowercase = pattern.to_lowercase();
+                if is_qualified_match || last_segment_lowercase == pattern_lowercase {
+                    Some(EXACT_MATCH_SCORE)
+                } else if last_segment_lowercase.starts_with(&pattern_lowercase) {
+                    Some(PREFIX_MATCH_SCORE)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+
+
+// =================
+// === TypeQuery ===
+// =================
+
+/// A query for components by the types of their arguments and return values. See the
+/// [module docs](self) for the syntax.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TypeQuery {
+    /// The pattern the component name is fuzzy-matched against. May be empty.
+    pub name_pattern: ImString,
+    /// The type of any of the component's arguments.
+    pub argument:     Option<TypePattern>,
+    /// The type of the component's return value.
+    pub return_type:  Option<TypePattern>,
+}
+
+impl TypeQuery {
+    /// Parse the searcher input as a type query. Returns [`None`] if the input is not a type query,
+    /// i.e. when it does not contain an arrow, when neither of the types is given, or when the
+    /// input without the type ascription could be an expression, like a lambda.
+    pub fn parse(input: &str) -> Option<Self> {
+        let (name_pattern, signature, has_ascription) = match input.split_once(TYPE_ASCRIPTION) {
+            Some((name_pattern, signature)) => (name_pattern.trim(), signature, true),
+            None => ("", input, false),
+        };
+        let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+        if !name_pattern.chars().all(is_name_char) {
+            return None;
+        }
+        let (argument, return_type) = signature.split_once(ARROW)?;
+        let argument = TypePattern::parse(argument);
+        let return_type = TypePattern::parse(return_type);
+        let mut patterns = argument.iter().chain(&return_type);
+        let is_unambiguous = has_ascription || patterns.all(TypePattern::is_unambiguous);
+        let has_types = argument.is_some() || return_type.is_some();
+        let is_query = has_types && is_unambiguous;
+        is_query.then_some(Self { name_pattern: name_pattern.into(), argument, return_type })
+    }
+
+    /// Check whether the types of the entry's arguments and return value match the query, and
+    /// score how well they do: a type matched by its full name scores better than one matched by a
+    /// prefix of its name. Returns [`None`] if the entry does not match.
+    ///
+    /// The `source_type` is the type of the searcher's source node, used for
+    /// [`TypePattern::SourceNode`] patterns - they never match if it is not known.
+    pub fn match_entry(
+        &self,
+        entry: &suggestion_database::Entry,
+        source_type: Option<&QualifiedName>,
+    ) -> Option<f32> {
+        use suggestion_database::entry::Kind;
+        if entry.kind == Kind::Module {
+            return None;
+        }
+        let argument_score = match &self.argument {
+            Some(pattern) => {
+                let is_method = entry.kind == Kind::Method && !entry.is_static;
+                let self_type = entry.self_type.as_ref().filter(|_| is_method);
+                let self_type = self_type.map(|tp| tp.to_string());
+                let argument_types = entry.arguments.iter().map(|arg| arg.repr_type.clone());
+                let all_types = argument_types.chain(self_type);
+                let scores = all_types.filter_map(|tp| pattern.match_type(&tp, source_type));
+                scores.max_by_key(|score| OrderedFloat(*score))?
+            }
+            None => EXACT_MATCH_SCORE,
+        };
+        let return_score = match &self.return_type {
+            Some(pattern) => pattern.match_type(&entry.return_type.to_string(), source_type)?,
+            None => EXACT_MATCH_SCORE,
+        };
+        Some(argument_score * return_score)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let name = |name: &str| Some(TypePattern::Name(name.into()));
+        let query = |name_pattern: &str, argument, return_type| {
+            Some(TypeQuery { name_pattern: name_pattern.into(), argument, return_type })
+        };
+        assert_eq!(TypeQuery::parse("-> Table"), query("", None, name("Table")));
+        assert_eq!(TypeQuery::parse("Column ->"), query("", name("Column"), None));
+        let column_to_vector = query("", name("Column"), name("Vector"));
+        assert_eq!(TypeQuery::parse("Column -> Vector"), column_to_vector);
+        assert_eq!(TypeQuery::parse(": Column -> Vector"), column_to_vector);
+        let source_node = Some(TypePattern::SourceNode);
+        assert_eq!(TypeQuery::parse("to : _ -> Vector"), query("to", source_node, name("Vector")));
+        let qualified = name("Standard.Table.Data.Table.Table");
+        assert_eq!(
+            TypeQuery::parse("-> Standard.Table.Data.Table.Table"),
+            query("", None, qualified)
+        );
+        assert_eq!(TypeQuery::parse(": -> table"), query("", None, name("table")));
+        assert_eq!(TypeQuery::parse("->"), None);
+        assert_eq!(TypeQuery::parse(": ->"), None);
+        assert_eq!(TypeQuery::parse("foo"), None);
+        assert_eq!(TypeQuery::parse("-> table"), None);
+        assert_eq!(TypeQuery::parse("x -> x + 1"), None);
+        assert_eq!(TypeQuery::parse("_ -> 1"), None);
+        assert_eq!(TypeQuery::parse("(x : Integer) -> x + 1"), None);
+        assert_eq!(TypeQuery::parse("foo : Integer"), None);
+    }
+
+    #[test]
+    fn matching_types() {
+        let pattern = TypePattern::Name("Table".into());
+        let table = "Standard.Table.Data.Table.Table";
+        assert_eq!(pattern.match_type(table, None), Some(EXACT_MATCH_SCORE));
+        assert_eq!(pattern.match_type("Standard.Base.Data.Text.Text", None), None);
+        let union = "Standard.Base.Data.Text.Text | Standard.Table.Data.Table.Table";
+        assert_eq!(pattern.match_type(union, None), Some(EXACT_MATCH_SCORE));
+        let qualified = TypePattern::Name("Table.Table".into());
+        assert_eq!(qualified.match_type(table, None), Some(EXACT_MATCH_SCORE));
+        let prefix = TypePattern::Name("tab".into());
+        assert_eq!(prefix.match_type(table, None), Some(PREFIX_MATCH_SCORE));
+        assert_eq!(prefix.match_type(ANY_TYPE, None), None);
+
+        let source_type = QualifiedName::from_text(table).unwrap();
+        let source_node = TypePattern::SourceNode;
+        assert_eq!(source_node.match_type(table, Some(&source_type)), Some(EXACT_MATCH_SCORE));
+        assert_eq!(source_node.match_type(table, None), None);
+    }
+}
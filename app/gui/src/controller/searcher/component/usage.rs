@@ -0,0 +1,280 @@
+//! The statistics of components picked by the user, promoting the often used components in the
+//! filtered component list.
+//!
+//! Every pick is recorded with its [`Context`] and time. The picks contribute to the component's
+//! usage score with a weight decaying exponentially with the pick's age, and higher when the pick
+//! happened in the same context as the current search. The statistics are kept in the browser's
+//! [local storage](LocalStorage), as they describe the user's habits rather than a project.
+
+use crate::prelude::*;
+
+use crate::controller::searcher::component::Component;
+use crate::controller::searcher::component::Suggestion;
+use crate::model::local_storage::LocalStorage;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The version of the stored statistics' format. Statistics stored in other versions are dropped.
+const FORMAT_VERSION: u32 = 1;
+/// The local storage key of the statistics.
+const STORAGE_KEY: &str = "enso.searcher.component-usage";
+/// The age in milliseconds after which a pick's weight is halved.
+const HALF_LIFE_MS: f64 = 14.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+/// The number of the most recent picks remembered for each component.
+const MAX_PICKS_PER_COMPONENT: usize = 20;
+/// The number of components with remembered picks. When exceeded, the component picked least
+/// recently is forgotten.
+const MAX_COMPONENTS: usize = 500;
+/// The weight multiplier of a pick made with the same type of the source node.
+const SAME_THIS_TYPE_FACTOR: f32 = 2.0;
+/// The weight multiplier of a pick made in the same module.
+const SAME_MODULE_FACTOR: f32 = 1.5;
+/// How much the usage score affects the component's match score. See [`Ranking`].
+const USAGE_WEIGHT: f32 = 0.5;
+
+
+
+// ===============
+// === Context ===
+// ===============
+
+/// The context in which a component was picked or is searched for.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Context {
+    /// The type of the searcher's source node, if any.
+    pub this_type: Option<String>,
+    /// The qualified name of the edited module.
+    pub module:    String,
+}
+
+impl Context {
+    fn weight_factor(&self, current: &Context) -> f32 {
+        let same_this_type = self.this_type.is_some() && self.this_type == current.this_type;
+        let this_type_factor = if same_this_type { SAME_THIS_TYPE_FACTOR } else { 1.0 };
+        let module_factor = if self.module == current.module { SAME_MODULE_FACTOR } else { 1.0 };
+        this_type_factor * module_factor
+    }
+}
+
+
+// === Pick ===
+
+/// A single pick of a component.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct Pick {
+    /// The time of the pick, in milliseconds since the Unix epoch.
+    timestamp: u64,
+    context:   Context,
+}
+
+impl Pick {
+    fn weight(&self, context: &Context, now: u64) -> f32 {
+        let age = now.saturating_sub(self.timestamp) as f64;
+        let decay = 0.5_f64.powf(age / HALF_LIFE_MS) as f32;
+        decay * self.context.weight_factor(context)
+    }
+}
+
+
+
+// ==================
+// === Statistics ===
+// ==================
+
+/// The recorded picks of components, keyed by the components' qualified names.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Statistics {
+    format_version: u32,
+    picks:          BTreeMap<String, Vec<Pick>>,
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Self { format_version: FORMAT_VERSION, picks: default() }
+    }
+}
+
+impl Statistics {
+    /// Load the statistics from the local storage. Returns empty statistics if there are none
+    /// stored, or they cannot be read.
+    pub fn load(storage: &LocalStorage) -> Self {
+        let Some(serialized) = storage.get_item(STORAGE_KEY) else { return default() };
+        match serde_json::from_str::<Self>(&serialized) {
+            Ok(statistics) if statistics.format_version == FORMAT_VERSION => statistics,
+            Ok(statistics) => {
+                let version = statistics.format_version;
+                info!("Dropping the component usage statistics in unsupported format {version}.");
+                default()
+            }
+            Err(error) => {
+                warn!("Cannot read the component usage statistics: {error}");
+                default()
+            }
+        }
+    }
+
+    /// Store the statistics in the local storage.
+    pub fn store(&self, storage: &LocalStorage) -> FallibleResult {
+        storage.set_item(STORAGE_KEY, &serde_json::to_string(self)?)
+    }
+
+    /// Remove all the statistics from the local storage.
+    pub fn reset(storage: &LocalStorage) {
+        storage.remove_item(STORAGE_KEY)
+    }
+
+    /// Forget all the recorded picks.
+    pub fn clear(&mut self) {
+        self.picks.clear()
+    }
+
+    /// Check if there are no recorded picks.
+    pub fn is_empty(&self) -> bool {
+        self.picks.is_empty()
+    }
+
+    /// Record a pick of the component with the given qualified name. The `timestamp` is in
+    /// milliseconds since the Unix epoch, see [`now`].
+    pub fn record(&mut self, component: impl Into<String>, context: Context, timestamp: u64) {
+        let picks = self.picks.entry(component.into()).or_default();
+        picks.push(Pick { timestamp, context });
+        if picks.len() > MAX_PICKS_PER_COMPONENT {
+            picks.drain(..picks.len() - MAX_PICKS_PER_COMPONENT);
+        }
+        if self.picks.len() > MAX_COMPONENTS {
+            let last_pick_time = |picks: &Vec<Pick>| picks.last().map_or(0, |pick| pick.timestamp);
+            let least_recent = self.picks.iter().min_by_key(|(_, picks)| last_pick_time(picks));
+            let least_recent = least_recent.map(|(component, _)| component.clone());
+            if let Some(component) = least_recent {
+                self.picks.remove(&component);
+            }
+        }
+    }
+
+    /// The usage score of the component with the given qualified name: the sum of its picks'
+    /// weights. See the [module docs](self) for details.
+    pub fn score(&self, component: &str, context: &Context, now: u64) -> f32 {
+        let picks = self.picks.get(component).into_iter().flatten();
+        picks.map(|pick| pick.weight(context, now)).sum()
+    }
+
+    /// The ranking of components for a search in the given context.
+    pub fn ranking(&self, context: &Context, now: u64) -> Ranking {
+        let scores = self.picks.keys().map(|component| {
+            let score = self.score(component, context, now);
+            (component.clone(), score)
+        });
+        Ranking { scores: scores.collect() }
+    }
+}
+
+
+// === Time ===
+
+/// The current time in milliseconds since the Unix epoch.
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+/// The current time in milliseconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> u64 {
+    let since_epoch = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    since_epoch.map_or(0, |duration| duration.as_millis() as u64)
+}
+
+
+
+// ===============
+// === Ranking ===
+// ===============
+
+/// The usage scores of components computed for a single search context.
+///
+/// The match score of a filtered component is multiplied by its [boost](Self::boost), so the
+/// often used components are promoted, but the fuzzy match quality still matters the most.
+#[derive(Clone, Debug, Default)]
+pub struct Ranking {
+    scores: HashMap<String, f32>,
+}
+
+impl Ranking {
+    /// The factor the component's match score should be multiplied by. It is `1.0` for components
+    /// never picked, and grows logarithmically with the usage score.
+    pub fn boost(&self, component: &Component) -> f32 {
+        let score = match &component.suggestion {
+            Suggestion::FromDatabase { entry, .. } =>
+                self.scores.get(&entry.qualified_name().to_string()).copied().unwrap_or_default(),
+            Suggestion::Virtual { .. } => 0.0,
+        };
+        1.0 + USAGE_WEIGHT * score.ln_1p()
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn context(this_type: Option<&str>, module: &str) -> Context {
+        Context { this_type: this_type.map(Into::into), module: module.into() }
+    }
+
+    #[test]
+    fn scoring_picks() {
+        let main = context(None, "local.Project.Main");
+        let other_module = context(None, "local.Project.Other");
+        let on_table = context(Some("Standard.Table.Data.Table.Table"), "local.Project.Main");
+        let mut statistics = Statistics::default();
+        statistics.record("Standard.Base.Data.read", main.clone(), 0);
+        statistics.record("Standard.Base.Data.read", main.clone(), 0);
+        statistics.record("Standard.Table.Data.Table.Table.join", on_table.clone(), 0);
+
+        let read_score = statistics.score("Standard.Base.Data.read", &main, 0);
+        assert_eq!(read_score, 2.0 * SAME_MODULE_FACTOR);
+        let read_score_elsewhere = statistics.score("Standard.Base.Data.read", &other_module, 0);
+        assert_eq!(read_score_elsewhere, 2.0);
+        let half_life = HALF_LIFE_MS as u64;
+        let read_score_later =
+            statistics.score("Standard.Base.Data.read", &other_module, half_life);
+        assert_eq!(read_score_later, 1.0);
+        let join_score = statistics.score("Standard.Table.Data.Table.Table.join", &on_table, 0);
+        assert_eq!(join_score, SAME_THIS_TYPE_FACTOR * SAME_MODULE_FACTOR);
+        assert_eq!(statistics.score("Standard.Base.Data.write", &main, 0), 0.0);
+
+        statistics.clear();
+        assert!(statistics.is_empty());
+        assert_eq!(statistics.score("Standard.Base.Data.read", &main, 0), 0.0);
+    }
+
+    #[test]
+    fn limiting_picks() {
+        let context = context(None, "local.Project.Main");
+        let mut statistics = Statistics::default();
+        for day in 0..MAX_PICKS_PER_COMPONENT as u64 * 2 {
+            statistics.record("local.Project.Main.foo", context.clone(), day * DAY_MS);
+        }
+        assert_eq!(statistics.picks["local.Project.Main.foo"].len(), MAX_PICKS_PER_COMPONENT);
+        for index in 0..MAX_COMPONENTS {
+            statistics.record(format!("local.Project.Main.bar{index}"), context.clone(), DAY_MS);
+        }
+        assert_eq!(statistics.picks.len(), MAX_COMPONENTS);
+        assert!(statistics.picks.contains_key("local.Project.Main.foo"));
+        assert!(!statistics.picks.contains_key("local.Project.Main.bar0"));
+    }
+}
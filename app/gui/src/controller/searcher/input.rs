@@ -3,6 +3,7 @@
 use crate::prelude::*;
 
 use crate::controller::searcher::component::Suggestion;
+use crate::controller::searcher::component::TypeQuery;
 use crate::controller::searcher::Filter;
 use crate::controller::searcher::RequiredImport;
 
@@ -311,6 +312,12 @@ impl Input {
 
     /// Return the filtering pattern for the input.
     pub fn filter(&self, module_name: QualifiedName) -> Filter {
+        let module_name = Rc::new(module_name);
+        if let Some(type_query) = self.type_query() {
+            let pattern = type_query.name_pattern.clone_ref();
+            let type_query = Some(Rc::new(type_query));
+            return Filter { pattern, context: None, type_query, module_name };
+        }
         let pattern = if let Some(edited) = &self.edited_ast.edited_name {
             let name = ast::identifier::name(&edited.ast);
             name.map_or_default(|name| name.into())
@@ -320,8 +327,17 @@ impl Input {
             default()
         };
         let context = self.context().map(|c| c.into_ast().repr().to_im_string());
-        let module_name = Rc::new(module_name);
-        Filter { pattern, context, module_name }
+        Filter { pattern, context, type_query: None, module_name }
+    }
+
+    /// The query for components by their argument and return types, if the whole input is one.
+    /// See [`TypeQuery`] for the syntax.
+    pub fn type_query(&self) -> Option<TypeQuery> {
+        if self.edited_literal().is_some() {
+            None
+        } else {
+            TypeQuery::parse(&self.ast.to_string())
+        }
     }
 
     /// Return the accessor chain being the context of the edited name, i.e. the preceding fully
@@ -497,7 +513,11 @@ impl Input {
         let generate_this = !has_this;
         let context = InsertContext { suggestion, context, generate_this };
         let default_range = (self.cursor_position..self.cursor_position).into();
-        let replaced = if context.has_qualified_name() {
+        let replaced = if self.type_query().is_some() {
+            // The type query is not a part of the code, so the whole input is replaced.
+            let input_length = text::Byte(self.ast.to_string().len());
+            (text::Byte(0)..input_length).into()
+        } else if context.has_qualified_name() {
             self.accessor_chain_range().unwrap_or(default_range)
         } else {
             self.edited_name_range()
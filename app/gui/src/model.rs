@@ -25,6 +25,7 @@
 // ==============
 
 pub mod execution_context;
+pub mod local_storage;
 pub mod module;
 pub mod project;
 pub mod registry;
@@ -0,0 +1,73 @@
+//! The browser's local storage, persisting the IDE's caches and usage statistics between sessions.
+
+use crate::prelude::*;
+
+use crate::model::suggestion_database::cache;
+
+
+
+// ====================
+// === LocalStorage ===
+// ====================
+
+/// A handle to the browser's local storage.
+#[derive(Clone, Debug)]
+pub struct LocalStorage {
+    storage: web_sys::Storage,
+}
+
+impl LocalStorage {
+    /// Get the local storage of the current window. Returns [`None`] if the storage is not
+    /// available, e.g. when it is disabled by the browser settings, or when not running in a
+    /// browser.
+    #[cfg(target_arch = "wasm32")]
+    pub fn get() -> Option<Self> {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        Some(Self { storage })
+    }
+
+    /// Get the local storage of the current window. Returns [`None`] if the storage is not
+    /// available, e.g. when it is disabled by the browser settings, or when not running in a
+    /// browser.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn get() -> Option<Self> {
+        None
+    }
+
+    /// The cache of the suggestion database of the project with the given id.
+    pub fn suggestion_database_cache(&self, project_id: Uuid) -> cache::Cache {
+        cache::Cache::new(Rc::new(self.clone()), project_id)
+    }
+
+    /// Get the value stored under the key, if any.
+    pub fn get_item(&self, key: &str) -> Option<String> {
+        self.storage.get_item(key).ok().flatten()
+    }
+
+    /// Store the value under the key. Fails if the storage quota is exceeded.
+    pub fn set_item(&self, key: &str, value: &str) -> FallibleResult {
+        let result = self.storage.set_item(key, value);
+        result.map_err(|error| failure::format_err!("Cannot write to the local storage: {error:?}"))
+    }
+
+    /// Remove the value stored under the key, if any.
+    pub fn remove_item(&self, key: &str) {
+        if let Err(error) = self.storage.remove_item(key) {
+            warn!("Cannot remove {key} from the local storage: {error:?}");
+        }
+    }
+}
+
+impl cache::Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.get_item(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> FallibleResult {
+        self.set_item(key, value)
+    }
+
+    fn remove(&self, key: &str) {
+        self.remove_item(key)
+    }
+}
@@ -25,6 +25,7 @@ use serde::Serialize;
 // === Export ===
 // ==============
 
+pub mod merge;
 pub mod plain;
 pub mod synchronized;
 
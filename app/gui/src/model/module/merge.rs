@@ -0,0 +1,370 @@
+//! Three-way merge of module contents.
+//!
+//! When the module file is modified outside the IDE while the IDE has its own changes, both
+//! versions are merged, using the content last synchronized with the Language Server as their
+//! common base. The code is merged line by line: the external changes are applied to the IDE's
+//! code, keeping the IDs of the unchanged nodes. The metadata are merged by node ID. The
+//! conflicting changes are resolved in favour of the IDE's version and reported as [`Conflict`]s.
+
+use crate::prelude::*;
+use enso_text::index::*;
+
+use crate::model::module::Content;
+use crate::model::module::IdeMetadata;
+use crate::model::module::Metadata;
+use crate::model::module::TextChange;
+
+use double_representation::text::apply_code_change_to_id_map;
+use parser::Parser;
+
+
+
+// ================
+// === Conflict ===
+// ================
+
+/// A change made outside the IDE which conflicts with the IDE's change and has been discarded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Conflict {
+    /// Both versions changed the same lines of code differently, or the external change could not
+    /// be applied because the merged code does not parse.
+    Code {
+        /// The range of the lines in the base version changed outside the IDE.
+        base_lines: Range<usize>,
+        /// The code the lines have been changed to outside the IDE.
+        discarded:  String,
+    },
+    /// Both versions changed the metadata of the same node differently.
+    NodeMetadata(ast::Id),
+    /// Both versions changed the metadata of the same import differently.
+    ImportMetadata(double_representation::import::Id),
+    /// Both versions changed the project metadata differently.
+    ProjectMetadata,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Code { base_lines, .. } =>
+                write!(f, "code lines {}-{}", base_lines.start + 1, base_lines.end),
+            Self::NodeMetadata(id) => write!(f, "metadata of node {id}"),
+            Self::ImportMetadata(id) => write!(f, "metadata of import {id}"),
+            Self::ProjectMetadata => write!(f, "project metadata"),
+        }
+    }
+}
+
+
+
+// ==============
+// === Merged ===
+// ==============
+
+/// The result of merging module contents.
+#[derive(Clone, Debug)]
+pub struct Merged {
+    /// The merged module content.
+    pub content:   Content,
+    /// The external changes discarded because of conflicts with the IDE's changes.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merge the changes made to the module content in the IDE (`ours`) and outside of it (`theirs`),
+/// relative to the `base` content both have been derived from.
+pub fn merge(base: &Content, ours: &Content, theirs: &Content, parser: &Parser) -> Merged {
+    let mut conflicts = Vec::new();
+    let ours_code = ours.ast.repr();
+    let mut id_map = ours.ast.id_map();
+    let (changes, code_conflicts) = merge_code(&base.ast.repr(), &ours_code, &theirs.ast.repr());
+    conflicts.extend(code_conflicts);
+    let mut code = ours_code;
+    for CodeChange { change, .. } in changes.iter().rev() {
+        apply_code_change_to_id_map(&mut id_map, change, &code);
+        code.replace_range(change.range.start.value..change.range.end.value, &change.text);
+    }
+    let ast = match parser.parse_module(code, id_map) {
+        Ok(ast) => ast,
+        Err(error) => {
+            warn!("Cannot parse the merged code, keeping the IDE's version: {error}");
+            conflicts.extend(changes.into_iter().map(CodeChange::discard));
+            ours.ast.clone_ref()
+        }
+    };
+    let metadata = merge_metadata(&base.metadata, &ours.metadata, &theirs.metadata, &mut conflicts);
+    Merged { content: Content { ast, metadata }, conflicts }
+}
+
+
+
+// ==================
+// === Code Merge ===
+// ==================
+
+/// A replacement of a range of base lines by other lines, in one of the merged versions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Hunk<'a> {
+    base:  Range<usize>,
+    lines: Vec<&'a str>,
+}
+
+/// An external change of code to be applied to `ours` code.
+#[derive(Clone, Debug)]
+struct CodeChange {
+    /// The range of the lines in the base version changed outside the IDE.
+    base_lines: Range<usize>,
+    change:     TextChange,
+}
+
+impl CodeChange {
+    /// Report the change as discarded.
+    fn discard(self) -> Conflict {
+        Conflict::Code { base_lines: self.base_lines, discarded: self.change.text }
+    }
+}
+
+/// Compute the changes to be applied to `ours` code, so it includes the changes made in `theirs`.
+/// The changes are ordered by their position in the code.
+fn merge_code(base: &str, ours: &str, theirs: &str) -> (Vec<CodeChange>, Vec<Conflict>) {
+    let base_lines = base.split_inclusive('\n').collect_vec();
+    let ours_lines = ours.split_inclusive('\n').collect_vec();
+    let theirs_lines = theirs.split_inclusive('\n').collect_vec();
+    let ours_hunks = diff_lines(&base_lines, &ours_lines);
+    let theirs_hunks = diff_lines(&base_lines, &theirs_lines);
+
+    let mut changes = Vec::new();
+    let mut conflicts = Vec::new();
+    // The difference between the line indices in `ours` and in `base`, for the lines after the
+    // already processed `ours` hunks.
+    let mut ours_shift = 0_isize;
+    let mut ours_hunks = ours_hunks.into_iter().peekable();
+    let mut theirs_hunks = theirs_hunks.into_iter().peekable();
+    loop {
+        // Gather the overlapping hunks of both versions into a single cluster.
+        let first = match (ours_hunks.peek(), theirs_hunks.peek()) {
+            (Some(ours), Some(theirs)) => ours.base.start.min(theirs.base.start),
+            (Some(ours), None) => ours.base.start,
+            (None, Some(theirs)) => theirs.base.start,
+            (None, None) => break,
+        };
+        let mut cluster = first..first;
+        let mut ours_cluster = Vec::new();
+        let mut theirs_cluster = Vec::new();
+        loop {
+            let (start, end) = (cluster.start, cluster.end);
+            let overlaps = |hunk: &Hunk| hunk.base.start < end || hunk.base.start == start;
+            if let Some(hunk) = ours_hunks.next_if(overlaps) {
+                cluster.end = cluster.end.max(hunk.base.end);
+                ours_cluster.push(hunk);
+            } else if let Some(hunk) = theirs_hunks.next_if(overlaps) {
+                cluster.end = cluster.end.max(hunk.base.end);
+                theirs_cluster.push(hunk);
+            } else {
+                break;
+            }
+        }
+
+        let ours_start = (cluster.start as isize + ours_shift) as usize;
+        let ours_replacement = apply_hunks(&base_lines, cluster.clone(), &ours_cluster);
+        let ours_end = ours_start + ours_replacement.len();
+        ours_shift += ours_replacement.len() as isize - cluster.len() as isize;
+        if theirs_cluster.is_empty() {
+            continue;
+        }
+        let theirs_replacement = apply_hunks(&base_lines, cluster.clone(), &theirs_cluster);
+        if ours_cluster.is_empty() {
+            let start = line_offset(&ours_lines, ours_start);
+            let end = line_offset(&ours_lines, ours_end);
+            let change =
+                TextChange { range: (start..end).into(), text: theirs_replacement.concat() };
+            changes.push(CodeChange { base_lines: cluster, change });
+        } else if ours_replacement != theirs_replacement {
+            conflicts.push(Conflict::Code {
+                base_lines: cluster,
+                discarded:  theirs_replacement.concat(),
+            });
+        }
+    }
+    (changes, conflicts)
+}
+
+/// Get the lines of the `range` of base lines, after applying the hunks located within it.
+fn apply_hunks<'a>(base: &[&'a str], range: Range<usize>, hunks: &[Hunk<'a>]) -> Vec<&'a str> {
+    let mut lines = Vec::new();
+    let mut position = range.start;
+    for hunk in hunks {
+        lines.extend_from_slice(&base[position..hunk.base.start]);
+        lines.extend_from_slice(&hunk.lines);
+        position = hunk.base.end;
+    }
+    lines.extend_from_slice(&base[position..range.end]);
+    lines
+}
+
+/// The byte offset of the line's start in the code split into lines.
+fn line_offset(lines: &[&str], line: usize) -> Byte {
+    Byte::from(lines[..line].iter().map(|line| line.len()).sum::<usize>())
+}
+
+/// Compute the hunks transforming the `base` lines into the `other` lines, using the longest
+/// common subsequence of lines. The hunks are ordered and do not overlap.
+fn diff_lines<'a>(base: &[&'a str], other: &[&'a str]) -> Vec<Hunk<'a>> {
+    // The length of the longest common subsequence of `base[i..]` and `other[j..]`.
+    let mut common = vec![vec![0_usize; other.len() + 1]; base.len() + 1];
+    for i in (0..base.len()).rev() {
+        for j in (0..other.len()).rev() {
+            common[i][j] = if base[i] == other[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut hunks = Vec::new();
+    let mut current: Option<Hunk> = None;
+    let (mut i, mut j) = (0, 0);
+    while i < base.len() || j < other.len() {
+        if i < base.len() && j < other.len() && base[i] == other[j] {
+            hunks.extend(current.take());
+            i += 1;
+            j += 1;
+        } else {
+            let hunk = current.get_or_insert_with(|| Hunk { base: i..i, lines: Vec::new() });
+            if j < other.len() && (i == base.len() || common[i][j + 1] >= common[i + 1][j]) {
+                hunk.lines.push(other[j]);
+                j += 1;
+            } else {
+                hunk.base.end += 1;
+                i += 1;
+            }
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+
+
+// ======================
+// === Metadata Merge ===
+// ======================
+
+fn merge_metadata(
+    base: &Metadata,
+    ours: &Metadata,
+    theirs: &Metadata,
+    conflicts: &mut Vec<Conflict>,
+) -> Metadata {
+    let node = merge_maps(&base.ide.node, &ours.ide.node, &theirs.ide.node, |id| {
+        conflicts.push(Conflict::NodeMetadata(id))
+    });
+    let import = merge_maps(&base.ide.import, &ours.ide.import, &theirs.ide.import, |id| {
+        conflicts.push(Conflict::ImportMetadata(id))
+    });
+    let project = merge_value(&base.ide.project, &ours.ide.project, &theirs.ide.project)
+        .unwrap_or_else(|| {
+            conflicts.push(Conflict::ProjectMetadata);
+            ours.ide.project.clone()
+        });
+    let rest = merge_value(&base.rest, &ours.rest, &theirs.rest);
+    let rest = rest.unwrap_or_else(|| ours.rest.clone());
+    Metadata { ide: IdeMetadata { node, import, project }, rest }
+}
+
+/// Merge the maps entry by entry. The `on_conflict` is called for each key having conflicting
+/// values, for which `ours` value is kept.
+fn merge_maps<K: Copy + Eq + Hash, V: Clone + PartialEq>(
+    base: &HashMap<K, V>,
+    ours: &HashMap<K, V>,
+    theirs: &HashMap<K, V>,
+    mut on_conflict: impl FnMut(K),
+) -> HashMap<K, V> {
+    let keys = ours.keys().chain(theirs.keys()).chain(base.keys()).copied().unique();
+    let merged = keys.filter_map(|key| {
+        let value = |map: &HashMap<K, V>| map.get(&key).cloned();
+        let merged = merge_value(&value(base), &value(ours), &value(theirs));
+        let merged = merged.unwrap_or_else(|| {
+            on_conflict(key);
+            value(ours)
+        });
+        Some((key, merged?))
+    });
+    merged.collect()
+}
+
+/// Merge a single value, returning `None` if both versions changed it differently.
+fn merge_value<T: Clone + PartialEq>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == base || ours == theirs {
+        Some(theirs.clone())
+    } else if theirs == base {
+        Some(ours.clone())
+    } else {
+        None
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::module::NodeMetadata;
+    use crate::model::module::Position;
+
+    fn apply(code: &str, changes: Vec<CodeChange>) -> String {
+        let mut code = code.to_owned();
+        for CodeChange { change, .. } in changes.into_iter().rev() {
+            code.replace_range(change.range.start.value..change.range.end.value, &change.text);
+        }
+        code
+    }
+
+    #[test]
+    fn merging_code() {
+        let base = "main =\n    a = 1\n    b = 2\n    c = 3\n";
+        let ours = "main =\n    a = 10\n    b = 2\n    c = 3\n";
+        let theirs = "main =\n    a = 1\n    b = 2\n    c = 30\n    d = 4\n";
+        let (changes, conflicts) = merge_code(base, ours, theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(apply(ours, changes), "main =\n    a = 10\n    b = 2\n    c = 30\n    d = 4\n");
+
+        let theirs = "main =\n    a = 5\n    b = 2\n    c = 3\n";
+        let (changes, conflicts) = merge_code(base, ours, theirs);
+        assert!(changes.is_empty());
+        let expected = Conflict::Code { base_lines: 1..2, discarded: "    a = 5\n".into() };
+        assert_eq!(conflicts, [expected]);
+
+        let (changes, conflicts) = merge_code(base, ours, ours);
+        assert!(changes.is_empty());
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn merging_metadata_by_node_id() {
+        let parser = Parser::new();
+        let code = "main =\n    a = 1\n    b = 2";
+        let ast = parser.parse_module(code, default()).unwrap();
+        let ids = ast.id_map().vec.iter().map(|(_, id)| *id).take(3).collect_vec();
+        let with_positions = |positions: &[(ast::Id, f32)]| {
+            let mut metadata = Metadata::default();
+            for (id, x) in positions {
+                let position = Some(Position::new(*x, 0.0));
+                metadata.ide.node.insert(*id, NodeMetadata { position, ..default() });
+            }
+            Content { ast: ast.clone_ref(), metadata }
+        };
+        let base = with_positions(&[(ids[0], 0.0), (ids[1], 0.0), (ids[2], 0.0)]);
+        let ours = with_positions(&[(ids[0], 1.0), (ids[1], 1.0), (ids[2], 0.0)]);
+        let theirs = with_positions(&[(ids[0], 0.0), (ids[1], 2.0), (ids[2], 2.0)]);
+        let merged = merge(&base, &ours, &theirs, &parser);
+        let position = |id| merged.content.metadata.ide.node[id].position.unwrap().vector.x;
+        assert_eq!(position(&ids[0]), 1.0);
+        assert_eq!(position(&ids[1]), 1.0);
+        assert_eq!(position(&ids[2]), 2.0);
+        assert_eq!(merged.conflicts, [Conflict::NodeMetadata(ids[1])]);
+    }
+}
@@ -3,6 +3,7 @@
 use crate::prelude::*;
 use enso_text::index::*;
 
+use crate::model::module::merge;
 use crate::model::module::Content;
 use crate::model::module::ImportMetadata;
 use crate::model::module::NodeMetadata;
@@ -126,6 +127,65 @@ enum LanguageServerContent {
 
 
 
+// ========================
+// === Metadata Storage ===
+// ========================
+
+/// The suffix appended to the module's file name to get the name of its metadata sidecar file.
+pub const SIDECAR_FILE_SUFFIX: &str = ".meta";
+
+/// Get the path of the sidecar file storing the metadata of the module with given file path.
+pub fn sidecar_path(file_path: &language_server::Path) -> language_server::Path {
+    let mut sidecar_path = file_path.clone();
+    if let Some(file_name) = sidecar_path.segments.last_mut() {
+        file_name.push_str(SIDECAR_FILE_SUFFIX);
+    }
+    sidecar_path
+}
+
+/// The place where the module's id map and metadata are stored.
+///
+/// The metadata found in the other place when opening the module is migrated automatically.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MetadataStorage {
+    /// The `#### METADATA ####` section at the end of the module's file.
+    #[default]
+    Inline,
+    /// The sidecar file next to the module's file, see [`sidecar_path`].
+    Sidecar,
+}
+
+impl MetadataStorage {
+    /// Get the storage selected in the application's configuration.
+    pub fn from_config() -> Self {
+        if enso_config::ARGS.groups.feature_preview.options.sidecar_metadata.value {
+            Self::Sidecar
+        } else {
+            Self::Inline
+        }
+    }
+
+    /// Get the module's file content as it should be stored by the Language Server.
+    fn file_for_language_server(self, file: &SourceFile) -> SourceFile {
+        match self {
+            Self::Inline => file.clone(),
+            Self::Sidecar => file.without_metadata(),
+        }
+    }
+
+    /// Get the module's full content from the file content stored by the Language Server. In the
+    /// sidecar mode, the metadata are taken from the `model_file`.
+    fn file_from_language_server(self, content: String, model_file: &SourceFile) -> String {
+        match self {
+            Self::Inline => content,
+            Self::Sidecar =>
+                SourceFile::with_sidecar(&content, &model_file.sidecar_content()).content,
+        }
+    }
+}
+
+
+
 // ===========================
 // === Synchronized Module ===
 // ===========================
@@ -139,10 +199,13 @@ enum LanguageServerContent {
 /// [https://github.com/enso-org/enso/blob/develop/docs/language-server/protocol-language-server.md].
 #[derive(Debug)]
 pub struct Module {
-    model:           model::module::Plain,
-    parser:          Parser,
-    ls_content:      Rc<RefCell<LanguageServerContent>>,
-    language_server: Rc<language_server::Connection>,
+    model:            model::module::Plain,
+    parser:           Parser,
+    ls_content:       Rc<RefCell<LanguageServerContent>>,
+    language_server:  Rc<language_server::Connection>,
+    metadata_storage: MetadataStorage,
+    /// The content of the module's sidecar file, if it exists.
+    sidecar:          Rc<RefCell<Option<String>>>,
 }
 
 
@@ -160,12 +223,24 @@ impl Module {
         parser: Parser,
         repository: Rc<model::undo_redo::Repository>,
         read_only: Rc<Cell<bool>>,
+        metadata_storage: MetadataStorage,
     ) -> FallibleResult<Rc<Self>> {
         let file_path = path.file_path().clone();
         info!("Opening module {file_path}");
         let opened = language_server.client.open_text_file(&file_path).await?;
         let content: text::Rope = (&opened.content).into();
         info!("Read content of the module {path}, digest is {:?}", opened.current_version);
+        // The metadata are looked up in the sidecar file only if they are not inlined, so the
+        // inlined ones are preferred during migration to the sidecar file.
+        let sidecar = if SourceFile::new(opened.content.clone()).has_metadata() {
+            None
+        } else {
+            Self::read_sidecar(&language_server, &sidecar_path(&file_path)).await
+        };
+        let full_content = match &sidecar {
+            Some(sidecar) => SourceFile::with_sidecar(&opened.content, sidecar).content,
+            None => opened.content,
+        };
 
         let end_of_file_byte = content.last_line_end_location();
         let ls_content_summary = ContentSummary {
@@ -173,18 +248,27 @@ impl Module {
             end_of_file: content.utf16_code_unit_location_of_location(end_of_file_byte),
         };
 
-        let source = parser.parse_with_metadata(opened.content);
+        let source = parser.parse_with_metadata(full_content);
         // We set ls_content field as default, because it will be replaced anyway upon initial
         // invalidation.
         let ls_content = default();
         let metadata = source.metadata;
         let ast = source.ast;
         let model = model::module::Plain::new(path, ast, metadata, repository, read_only);
-        let this = Rc::new(Module { model, ls_content, parser, language_server });
+        let sidecar = Rc::new(RefCell::new(sidecar));
+        let this = Rc::new(Module {
+            model,
+            ls_content,
+            parser,
+            language_server,
+            metadata_storage,
+            sidecar,
+        });
 
         // The parsed source may introduce changes in metadata (most prominently the missing AST ids
         // are added), so immediately the content in our model differs from Language Server State.
-        // We need to sent an initial invalidation.
+        // We need to sent an initial invalidation. It also moves the metadata to the storage
+        // selected for this module, if they were found in the other one.
         let actual_content = this.model.serialized_content()?;
         let initial_invalidation = this.full_invalidation(ls_content_summary, actual_content);
         let runner = Self::runner(this.clone_ref());
@@ -200,7 +284,9 @@ impl Module {
         let language_server = language_server::Connection::new_mock_rc(client);
         let ls_content = default();
         let parser = Parser::new();
-        Rc::new(Module { model, language_server, ls_content, parser })
+        let metadata_storage = default();
+        let sidecar = default();
+        Rc::new(Module { model, language_server, ls_content, parser, metadata_storage, sidecar })
     }
 
     /// Reopen file in the Language Server.
@@ -217,19 +303,32 @@ impl Module {
 
     /// Reopen file in the Language Server.
     ///
-    /// After reopening we update the model's current content with the LS state.
-    pub async fn reopen_file_and_set_content(&self) -> FallibleResult {
+    /// After reopening we update the model's current content with the LS state, merged with the
+    /// changes not yet synchronized with the LS. Returns the LS changes discarded because of
+    /// conflicts.
+    pub async fn reopen_file_and_set_content(&self) -> FallibleResult<Vec<merge::Conflict>> {
+        let base = self.ls_content.take();
         let opened = self.reopen_file().await?;
         let content = opened.content.into();
-
-        self.set_module_content_from_ls(content).await?;
-
-        Ok(())
+        self.set_module_content_from_ls(base, content).await
     }
 
     /// Apply text changes received from the language server.
-    pub async fn apply_text_change_from_ls(&self, edits: Vec<TextEdit>) -> FallibleResult {
-        let mut content: text::Rope = self.serialized_content()?.content.into();
+    ///
+    /// The changes are merged with the changes not yet synchronized with the LS. Returns the LS
+    /// changes discarded because of conflicts.
+    pub async fn apply_text_change_from_ls(
+        &self,
+        edits: Vec<TextEdit>,
+    ) -> FallibleResult<Vec<merge::Conflict>> {
+        let base = self.ls_content.take();
+        let mut content: text::Rope = match &base {
+            LanguageServerContent::Synchronized(summary) => summary.source.clone(),
+            LanguageServerContent::Unknown => {
+                let file = self.serialized_content()?;
+                self.metadata_storage.file_for_language_server(&file).content.into()
+            }
+        };
         for TextEdit { range, text } in edits {
             let start = content.location_of_utf16_code_unit_location_snapped(range.start.into());
             let end = content.location_of_utf16_code_unit_location_snapped(range.end.into());
@@ -239,11 +338,7 @@ impl Module {
             let change = TextChange { range, text };
             content.apply_change(change);
         }
-        // We don't need to spent our resources of computing what exactly the Language Server state
-        // looks like, because it will be soon updated anyway - see [`set_module_content_from_ls`]
-        // documentation. Just in case, we set it as unknown for now.
-        self.ls_content.replace(LanguageServerContent::Unknown);
-        self.set_module_content_from_ls(content).await
+        self.set_module_content_from_ls(base, content).await
     }
 
     /// Update the module with content received from the language server. This function takes the
@@ -251,21 +346,61 @@ impl Module {
     /// notification. It parses the new file content and updates the module with the parsed content,
     /// sending `NotificationKind::Reloaded` notification.
     ///
-    /// The module content changes during parsing, and the language server is notified of this
-    /// change.
-    async fn set_module_content_from_ls(&self, content: text::Rope) -> FallibleResult {
+    /// If the module has changes not yet synchronized with the language server, they are merged
+    /// with the new content, using the `base` content last synchronized with the language server.
+    /// The new content's changes conflicting with the module's ones are discarded and returned.
+    ///
+    /// The module content changes during parsing and merging, and the language server is notified
+    /// of this change.
+    ///
+    /// When the metadata are stored in the sidecar file, the language server content consists of
+    /// code only, and the module's current metadata are used with both the base and new content.
+    async fn set_module_content_from_ls(
+        &self,
+        base: LanguageServerContent,
+        content: text::Rope,
+    ) -> FallibleResult<Vec<merge::Conflict>> {
         let transaction = self.undo_redo_repository().transaction("Setting module's content");
-        transaction.fill_content(self.id(), self.content().borrow().clone());
-        let parsed_source = self.parser.parse_with_metadata(content.to_string());
+        let current = self.content().borrow().clone();
+        transaction.fill_content(self.id(), current.clone());
+        let current_file = current.serialize()?;
+        let storage = self.metadata_storage;
+        let full_content = storage.file_from_language_server(content.to_string(), &current_file);
+        let parsed_source = self.parser.parse_with_metadata(full_content);
+        let base = match base {
+            LanguageServerContent::Synchronized(summary) => Some(summary.source.to_string()),
+            LanguageServerContent::Unknown => None,
+        };
+        let has_unsynchronized_changes = match &base {
+            Some(base) => storage.file_for_language_server(&current_file).content != *base,
+            None => false,
+        };
+        let (parsed_source, conflicts) = match base {
+            Some(base) if has_unsynchronized_changes => {
+                let base = storage.file_from_language_server(base, &current_file);
+                let base = self.parser.parse_with_metadata(base);
+                let merged = merge::merge(&base, &current, &parsed_source, &self.parser);
+                if !merged.conflicts.is_empty() {
+                    let conflicts = merged.conflicts.iter().join(", ");
+                    warn!(
+                        "Discarded conflicting external changes of {}: {conflicts}.",
+                        self.path()
+                    );
+                }
+                (merged.content, merged.conflicts)
+            }
+            _ => (parsed_source, default()),
+        };
         let source = parsed_source.serialize()?;
         self.content().replace(parsed_source);
         let summary = ContentSummary::new(&content);
-        let change = TextEdit::from_prefix_postfix_differences(&content, &source.content);
+        let ls_source = storage.file_for_language_server(&source);
+        let change = TextEdit::from_prefix_postfix_differences(&content, &ls_source.content);
         let notify_ls = self.notify_language_server(summary.digest, &source, vec![change], true);
         let notification = Notification::new(source, NotificationKind::Reloaded);
         self.notify(notification);
         notify_ls.await;
-        Ok(())
+        Ok(conflicts)
     }
 
     /// Reopen file in the Language Server.
@@ -278,6 +413,27 @@ impl Module {
         let opened = self.language_server.client.open_text_file(file_path).await?;
         Ok(opened)
     }
+
+    /// Read the sidecar file with the module's metadata. Returns [`None`] if the file does not
+    /// exist or cannot be read.
+    async fn read_sidecar(
+        language_server: &language_server::Connection,
+        path: &language_server::Path,
+    ) -> Option<String> {
+        let read = async {
+            let exists = language_server.client.file_exists(path).await?.exists;
+            let contents = if exists {
+                Some(language_server.client.read_file(path).await?.contents)
+            } else {
+                None
+            };
+            FallibleResult::Ok(contents)
+        };
+        read.await.unwrap_or_else(|error| {
+            error!("Error while reading the metadata sidecar file {path}: {error}");
+            None
+        })
+    }
 }
 
 impl API for Module {
@@ -379,7 +535,7 @@ impl API for Module {
     }
 
     fn reopen_externally_changed_file(&self) -> BoxFuture<FallibleResult> {
-        async { self.reopen_file_and_set_content().await }.boxed_local()
+        async { self.reopen_file_and_set_content().await.map(|_| ()) }.boxed_local()
     }
 }
 
@@ -425,6 +581,14 @@ impl Module {
                 }
             }
             LanguageServerContent::Synchronized(summary) => match kind {
+                NotificationKind::MetadataChanged
+                    if self.metadata_storage == MetadataStorage::Sidecar =>
+                {
+                    // The Language Server content is not affected by the metadata stored in the
+                    // sidecar file.
+                    self.ls_content.replace(LanguageServerContent::Synchronized(summary));
+                    profiler::await_!(self.update_sidecar(&new_file), _profiler)
+                }
                 NotificationKind::Invalidate =>
                     profiler::await_!(self.partial_invalidation(summary, new_file), _profiler),
                 NotificationKind::CodeChanged { change, replaced_location } => {
@@ -434,9 +598,10 @@ impl Module {
                         range: replaced_location.map(to_engine_location).into(),
                         text:  change.text,
                     };
+                    let ls_file = self.metadata_storage.file_for_language_server(&new_file);
                     let id_map_change = TextEdit {
                         range: summary.id_map_engine_range().into(),
-                        text:  new_file.id_map_slice().to_string(),
+                        text:  ls_file.id_map_slice().to_string(),
                     };
                     //id_map goes first, because code change may alter its position.
                     let edits = vec![id_map_change, code_change];
@@ -469,7 +634,8 @@ impl Module {
     ) -> impl Future<Output = ()> + 'static {
         debug!("Handling full invalidation: {ls_content:?}.");
         let range = Range::new(Location::default(), ls_content.end_of_file);
-        let edits = vec![TextEdit { range: range.into(), text: new_file.content.clone() }];
+        let ls_file = self.metadata_storage.file_for_language_server(&new_file);
+        let edits = vec![TextEdit { range: range.into(), text: ls_file.content }];
         self.notify_language_server(ls_content.digest, &new_file, edits, true)
     }
 
@@ -528,11 +694,12 @@ impl Module {
         new_file: SourceFile,
     ) -> impl Future<Output = ()> + 'static {
         debug!("Handling partial invalidation: {ls_content:?}.");
+        let ls_file = self.metadata_storage.file_for_language_server(&new_file);
         let edits = vec![
             //id_map and metadata go first, because code change may alter their position.
-            Self::edit_for_idmap(&ls_content, &new_file),
-            Self::edit_for_metadata(&ls_content, &new_file),
-            Self::edit_for_code(&ls_content, &new_file),
+            Self::edit_for_idmap(&ls_content, &ls_file),
+            Self::edit_for_metadata(&ls_content, &ls_file),
+            Self::edit_for_code(&ls_content, &ls_file),
         ]
         .into_iter()
         .flatten()
@@ -544,7 +711,8 @@ impl Module {
     /// Language Server.
     ///
     /// The current value of `Self::ls_content` field s not used in this function, but replaced
-    /// with the new one basing on `new_file` argument.
+    /// with the new one basing on `new_file` argument. Once the update is applied, the sidecar
+    /// file is updated as well.
     #[profile(Debug)]
     fn notify_language_server(
         &self,
@@ -553,21 +721,24 @@ impl Module {
         edits: Vec<TextEdit>,
         execute: bool,
     ) -> impl Future<Output = ()> + 'static {
-        let summary = ParsedContentSummary::from_source(new_file);
+        let ls_file = self.metadata_storage.file_for_language_server(new_file);
+        let summary = ParsedContentSummary::from_source(&ls_file);
         let edit = FileEdit {
             edits,
             path: self.path().file_path().clone(),
             old_version: ls_content_digest,
-            new_version: Sha3_224::new(new_file.content.as_bytes()),
+            new_version: Sha3_224::new(ls_file.content.as_bytes()),
         };
         let ls_future_reply = self.language_server.client.apply_text_file_edit(&edit, &execute);
         let ls_content = self.ls_content.clone_ref();
+        let update_sidecar = self.update_sidecar(new_file);
         async move {
             debug!("Notifying LS with edit: {edit:#?}.");
             match ls_future_reply.await {
                 Ok(()) => {
                     debug!("Updating the LS content digest to: {:?}", summary);
                     ls_content.replace(LanguageServerContent::Synchronized(summary));
+                    update_sidecar.await;
                 }
                 Err(err) => {
                     error!("Error during sending text change to Language Server: {err}");
@@ -576,6 +747,33 @@ impl Module {
             }
         }
     }
+
+    /// Write the metadata of `new_file` to the sidecar file, if they are stored there. Otherwise,
+    /// remove the sidecar file if it exists, as the metadata were migrated to the module's file.
+    fn update_sidecar(&self, new_file: &SourceFile) -> impl Future<Output = ()> + 'static {
+        let new_content = match self.metadata_storage {
+            MetadataStorage::Inline => None,
+            MetadataStorage::Sidecar => Some(new_file.sidecar_content()),
+        };
+        let path = sidecar_path(self.path().file_path());
+        let language_server = self.language_server.clone_ref();
+        let sidecar = self.sidecar.clone_ref();
+        async move {
+            if *sidecar.borrow() == new_content {
+                return;
+            }
+            let result = match &new_content {
+                Some(content) => language_server.client.write_file(&path, content).await,
+                None => language_server.client.delete_file(&path).await,
+            };
+            match result {
+                Ok(()) => {
+                    sidecar.replace(new_content);
+                }
+                Err(err) => error!("Error while updating the metadata sidecar file {path}: {err}"),
+            }
+        }
+    }
 }
 
 impl Drop for Module {
@@ -908,6 +1106,53 @@ pub mod test {
         Runner::run(test);
     }
 
+    #[test]
+    fn storing_metadata_in_sidecar_file() {
+        // The module's file in the language server should contain only the code, while the id map
+        // and metadata go to the sidecar file. Changing metadata should not edit the module's file.
+        let initial_code = "main =\n    println \"Hello World!\"";
+        let mut data = crate::test::mock::Unified::new();
+        data.set_code(initial_code);
+        let sidecar = sidecar_path(data.module_path.file_path());
+        let written_sidecars: Rc<RefCell<Vec<String>>> = default();
+        let edit_handler = LsClientSetup::new_for_mock_data(&data);
+        let mut fixture = data.fixture_customize(|data, client, _| {
+            client.require_all_calls();
+            data.expect_opening_module(client);
+            data.expect_closing_module(client);
+            edit_handler.expect_some_edit(client, |edit| {
+                let (edit,) = edit.edits.iter().expect_tuple();
+                assert!(!SourceFile::new(edit.text.clone()).has_metadata());
+                Ok(())
+            });
+            for _ in 0..2 {
+                let sidecar = sidecar.clone();
+                let written_sidecars = written_sidecars.clone_ref();
+                client.expect.write_file(move |path, contents| {
+                    assert_eq!(path, &sidecar);
+                    written_sidecars.borrow_mut().push(contents.clone());
+                    Ok(())
+                });
+            }
+        });
+
+        let module = fixture.synchronized_module_with_metadata_storage(MetadataStorage::Sidecar);
+        fixture.run_until_stalled();
+        assert_eq!(edit_handler.current_ls_content.get().to_string(), initial_code);
+        let id = ast::Id::new_v4();
+        let position = Some(model::module::Position::new(1.0, 2.0));
+        module.set_node_metadata(id, NodeMetadata { position, ..default() }).unwrap();
+        fixture.run_until_stalled();
+
+        let written_sidecars = written_sidecars.borrow();
+        let (initial_sidecar, updated_sidecar) = written_sidecars.iter().expect_tuple();
+        let serialized = module.serialized_content().unwrap();
+        assert_ne!(initial_sidecar, updated_sidecar);
+        assert_eq!(*updated_sidecar, serialized.sidecar_content());
+        let restored = SourceFile::with_sidecar(initial_code, updated_sidecar);
+        assert_eq!(restored, serialized);
+    }
+
     /// A template for tests checking situation after edit failure due to connectivity issues.
     ///
     /// The test will create model, and - after opening module and performing initialization - will
@@ -184,6 +184,9 @@ pub enum Notification {
     ExecutionComplete,
     /// Indicates failure of the project execution.
     ExecutionFailed,
+    /// Some changes made to the module files outside the IDE conflicted with the IDE's changes,
+    /// and were discarded when merging them.
+    ExternalChangesConflicted,
 }
 
 /// Denotes one of backend connections used by a project.
@@ -6,6 +6,7 @@ use crate::prelude::*;
 use crate::model::execution_context;
 use crate::model::execution_context::synchronized::Notification as ExecutionUpdate;
 use crate::model::execution_context::VisualizationUpdateData;
+use crate::model::local_storage::LocalStorage;
 use crate::model::module;
 use crate::model::SuggestionDatabase;
 use crate::transport::web::WebSocket;
@@ -200,12 +201,14 @@ async fn check_vcs_status_and_notify(
 async fn update_modules_on_file_change(
     changes: FileEditList,
     module_registry: Rc<model::registry::Registry<module::Path, module::Synchronized>>,
+    publisher: notification::Publisher<model::project::Notification>,
 ) -> FallibleResult {
     for file_edit in changes.edits {
         let file_path = file_edit.path.clone();
         let module_path = module::Path::from_file_path(file_path).unwrap();
         if let Some(module) = module_registry.get(&module_path).await? {
-            module.apply_text_change_from_ls(file_edit.edits).await?;
+            let conflicts = module.apply_text_change_from_ls(file_edit.edits).await?;
+            notify_about_merge_conflicts(&conflicts, &publisher);
         }
     }
     Ok(())
@@ -217,14 +220,26 @@ async fn update_modules_on_file_change(
 async fn reload_module_on_file_change(
     modified: TextFileModifiedOnDisk,
     module_registry: Rc<model::registry::Registry<module::Path, module::Synchronized>>,
+    publisher: notification::Publisher<model::project::Notification>,
 ) -> FallibleResult {
     let module_path = module::Path::from_file_path(modified.path)?;
     if let Some(module) = module_registry.get(&module_path).await? {
-        module.reopen_externally_changed_file().await?;
+        let conflicts = module.reopen_file_and_set_content().await?;
+        notify_about_merge_conflicts(&conflicts, &publisher);
     }
     Ok(())
 }
 
+/// Emit a notification if some external changes were discarded when merging them into a module.
+fn notify_about_merge_conflicts(
+    conflicts: &[module::merge::Conflict],
+    publisher: &notification::Publisher<model::project::Notification>,
+) {
+    if !conflicts.is_empty() {
+        publisher.notify(model::project::Notification::ExternalChangesConflicted);
+    }
+}
+
 
 
 // =============
@@ -349,8 +364,14 @@ impl Project {
         let visualization =
             controller::Visualization::new(language_server, embedded_visualizations);
         let language_server = &*language_server_rpc;
-        let suggestion_db = SuggestionDatabase::create_synchronized(language_server);
-        let suggestion_db = Rc::new(suggestion_db.await.map_err(&wrap)?);
+        let suggestion_db = match LocalStorage::get() {
+            Some(storage) => {
+                let cache = storage.suggestion_database_cache(properties.id);
+                SuggestionDatabase::create_synchronized_with_cache(language_server, cache).await
+            }
+            None => SuggestionDatabase::create_synchronized(language_server).await,
+        };
+        let suggestion_db = Rc::new(suggestion_db.map_err(&wrap)?);
         let content_roots = ContentRoots::new_from_connection(language_server);
         let content_roots = Rc::new(content_roots);
         let notifications = notification::Publisher::default();
@@ -550,8 +571,10 @@ impl Project {
                 }
                 Event::Notification(Notification::TextDidChange(changes)) => {
                     if let Some(module_registry) = weak_module_registry.upgrade() {
+                        let publisher = publisher.clone_ref();
                         executor::global::spawn(async move {
-                            let status = update_modules_on_file_change(changes, module_registry);
+                            let status =
+                                update_modules_on_file_change(changes, module_registry, publisher);
                             if let Err(err) = status.await {
                                 error!("Error while applying file changes to modules: {err}");
                             }
@@ -560,8 +583,10 @@ impl Project {
                 }
                 Event::Notification(Notification::TextFileModifiedOnDisk(modified)) => {
                     if let Some(module_registry) = weak_module_registry.upgrade() {
+                        let publisher = publisher.clone_ref();
                         executor::global::spawn(async move {
-                            let status = reload_module_on_file_change(modified, module_registry);
+                            let status =
+                                reload_module_on_file_change(modified, module_registry, publisher);
                             if let Err(err) = status.await {
                                 error!("Error while reloading module on file change: {err}");
                             }
@@ -624,6 +649,12 @@ impl Project {
                 Event::Error(error) => {
                     error!("Error emitted by the JSON-RPC data connection: {error}.");
                 }
+                Event::Reconnecting => {
+                    warn!("Lost JSON-RPC connection with the Language Server, reconnecting.");
+                }
+                Event::Reconnected => {
+                    info!("Restored JSON-RPC connection with the Language Server.");
+                }
             }
             futures::future::ready(())
         }
@@ -666,8 +697,10 @@ impl Project {
         let urm = self.urm();
         let repo = urm.repository.clone_ref();
         let read_only = self.read_only.clone_ref();
+        let storage = module::synchronized::MetadataStorage::from_config();
         async move {
-            let module = module::Synchronized::open(path, ls, parser, repo, read_only).await?;
+            let module =
+                module::Synchronized::open(path, ls, parser, repo, read_only, storage).await?;
             urm.module_opened(module.clone());
             Ok(module)
         }
@@ -927,6 +960,9 @@ mod test {
         let write_capability = Some(write_capability);
         let open_response = response::OpenTextFile { content, current_version, write_capability };
         expect_call!(client.open_text_file(path=path.clone()) => Ok(open_response));
+        let sidecar_path = module::synchronized::sidecar_path(&path);
+        let no_sidecar = response::FileExists { exists: false };
+        expect_call!(client.file_exists(path=sidecar_path) => Ok(no_sidecar));
         client.expect.apply_text_file_edit(|_, _| Ok(()));
         expect_call!(client.close_text_file(path) => Ok(()));
     }
@@ -329,6 +329,30 @@ impl Model {
         );
     }
 
+    fn nodes_copied(&self, copied: &[ViewNodeId]) {
+        self.log_action(
+            || {
+                let ids = copied.iter().filter_map(|node| self.state.ast_node_id_of_view(*node));
+                let copied_nodes = self.controller.graph().copy_nodes(&ids.collect_vec());
+                let clipboard_text = copied_nodes.map(|nodes| nodes.to_clipboard_text());
+                Some(clipboard_text.map(enso_web::clipboard::write_text))
+            },
+            "copy nodes",
+        );
+    }
+
+    fn nodes_pasted(&self, clipboard_text: &str, position: Vector2) {
+        self.log_action(
+            || {
+                let payload =
+                    controller::graph::clipboard::Payload::from_clipboard_text(clipboard_text);
+                let position = model::module::Position { vector: position };
+                Some(self.controller.graph().paste_nodes(&payload, position).map(|_| ()))
+            },
+            "paste nodes",
+        );
+    }
+
     fn log_action<F>(&self, f: F, action: &str)
     where F: FnOnce() -> Option<FallibleResult> {
         debug_span!(
@@ -433,40 +457,31 @@ impl Model {
         }
     }
 
-    /// Look through all graph's nodes in AST and set position where it is missing.
+    /// Look through all graph's nodes in AST and arrange the ones without position around the
+    /// others.
     #[profile(Debug)]
     fn initialize_nodes_positions(&self, default_gap_between_nodes: f32) {
-        match self.controller.graph().nodes() {
+        let graph = self.controller.graph();
+        match graph.nodes() {
             Ok(nodes) => {
                 // We try to avoid spurious updates for nodes that are already positioned.
                 if nodes.iter().any(|n| !n.has_position()) {
-                    use model::module::Position;
-
-                    let base_default_position = default_node_position();
-                    let node_positions =
-                        nodes.iter().filter_map(|node| node.metadata.as_ref()?.position);
-                    let bottommost_pos = node_positions
-                        .min_by(Position::ord_by_y)
-                        .map(|p| p.vector)
-                        .unwrap_or(base_default_position);
-
-                    let offset = default_gap_between_nodes + node_view::HEIGHT;
-                    let mut next_default_position =
-                        Vector2::new(bottommost_pos.x, bottommost_pos.y - offset);
-
                     // As this is not a user-initiated action, we ignore the transaction.
                     let transaction =
                         self.controller.get_or_open_transaction("Setting default positions.");
                     transaction.ignore();
-                    for node in nodes.iter().filter(|n| !n.has_position()) {
-                        if let Err(err) = self
-                            .controller
-                            .graph()
-                            .set_node_position(node.id(), next_default_position)
-                        {
-                            warn!("Failed to initialize position of node {}: {err}", node.id());
+                    // The layout is placed around the positioned nodes, so at least one is needed.
+                    let no_node_positioned = nodes.iter().all(|n| !n.has_position());
+                    if let Some(first) = nodes.first().filter(|_| no_node_positioned) {
+                        let position = default_node_position();
+                        if let Err(err) = graph.set_node_position(first.id(), position) {
+                            warn!("Failed to initialize position of node {}: {err}", first.id());
                         }
-                        next_default_position.y -= offset;
+                    }
+                    let vertical = default_gap_between_nodes + node_view::HEIGHT;
+                    let spacing = controller::graph::layout::Spacing { vertical, ..default() };
+                    if let Err(err) = graph.arrange_unpositioned_nodes(spacing) {
+                        warn!("Failed to initialize nodes positions: {err}");
                     }
                 }
             }
@@ -476,6 +491,28 @@ impl Model {
         }
     }
 
+    fn tidy_up_requested(&self, selected: &[ViewNodeId]) {
+        self.log_action(
+            || {
+                let graph = self.controller.graph();
+                let selected =
+                    selected.iter().filter_map(|node| self.state.ast_node_id_of_view(*node));
+                let selected = selected.collect::<HashSet<_>>();
+                // When some nodes are selected, only they are arranged.
+                let pinned = match graph.all_node_infos() {
+                    Ok(_) if selected.is_empty() => default(),
+                    Ok(nodes) => {
+                        let ids = nodes.iter().map(|node| node.id());
+                        ids.filter(|id| !selected.contains(id)).collect()
+                    }
+                    Err(err) => return Some(Err(err)),
+                };
+                Some(graph.arrange_nodes(&pinned, default()))
+            },
+            "tidy up nodes",
+        );
+    }
+
     fn reopen_file_in_ls(&self) {
         let module = self.controller.graph().module.clone_ref();
         executor::global::spawn(async move {
@@ -754,6 +791,13 @@ impl Graph {
             eval view.node_position_set_batched(((node_id, position)) model.node_position_changed(*node_id, *position));
             eval view.node_removed((node_id) model.node_removed(*node_id));
             eval view.nodes_collapsed(((nodes, _)) model.nodes_collapsed(nodes));
+            eval view.tidy_up_requested((nodes) model.tidy_up_requested(nodes));
+            eval view.nodes_copied((nodes) model.nodes_copied(nodes));
+            eval view.nodes_pasted([model](position) {
+                let model = model.clone_ref();
+                let position = *position;
+                enso_web::clipboard::read_text(move |text| model.nodes_pasted(&text, position));
+            });
             eval view.enabled_visualization_path(((node_id, path)) model.node_visualization_changed(*node_id, path.clone()));
             eval view.node_expression_span_set(((node_id, crumbs, expression)) model.node_expression_span_set(*node_id, crumbs, expression.clone_ref()));
             eval view.connection_made((connection) model.connection_made(connection));
@@ -3,7 +3,9 @@
 
 use crate::prelude::*;
 
+use crate::controller::searcher::component;
 use crate::executor::global::spawn_stream_handler;
+use crate::model::local_storage::LocalStorage;
 use crate::presenter;
 use crate::presenter::searcher::ai::AISearcher;
 use crate::presenter::searcher::SearcherPresenter;
@@ -29,6 +31,11 @@ use model::project::VcsStatus;
 /// indicator for the user. This constant represents a progress percentage that will be displayed.
 const OPEN_PROJECT_SPINNER_PROGRESS: f32 = 0.8;
 
+/// The message displayed when some external changes to the project files were discarded.
+const EXTERNAL_CHANGES_CONFLICTED: &str =
+    "Some changes made to the project files outside the IDE conflicted with the IDE's changes, \
+     and were discarded.";
+
 
 
 // =============
@@ -191,6 +198,13 @@ impl Model {
         self.ide_controller.set_component_browser_private_entries_visibility(!visibility);
     }
 
+    fn reset_component_usage(&self) {
+        if let Some(storage) = LocalStorage::get() {
+            component::usage::Statistics::reset(&storage);
+            info!("Component usage statistics were reset.");
+        }
+    }
+
     /// Toggle the read-only mode, return the new state.
     fn toggle_read_only(&self) -> bool {
         let current_state = self.controller.model.read_only();
@@ -425,6 +439,8 @@ impl Project {
                 model.toggle_component_browser_private_entries_visibility()
             );
 
+            eval_ view.reset_component_usage(model.reset_component_usage());
+
             eval_ view.execution_context_interrupt(model.execution_context_interrupt());
 
             eval_ view.execution_context_restart(model.execution_context_restart());
@@ -496,6 +512,10 @@ impl Project {
                 Notification::ExecutionFailed => {
                     model.execution_failed();
                 }
+                Notification::ExternalChangesConflicted => {
+                    let message = view::status_bar::event::Label::from(EXTERNAL_CHANGES_CONFLICTED);
+                    model.status_bar.add_event(message);
+                }
             };
             std::future::ready(())
         });
@@ -123,6 +123,8 @@ where
 pub mod tests {
     use super::*;
 
+    use crate::executor::test_utils::TestWithVirtualTimeExecutor;
+
     #[test]
     fn successful_operation() {
         let operation = || async { Ok(4) };
@@ -136,6 +138,7 @@ pub mod tests {
 
     #[test]
     fn operation_successful_after_retry() {
+        let mut fixture = TestWithVirtualTimeExecutor::set_up();
         let mut call_index = 0;
         let operation = move || {
             call_index += 1;
@@ -151,15 +154,16 @@ pub mod tests {
         let mut future =
             retry_operation(operation, retry_times, "Test operation failed.").boxed_local();
         future.expect_pending();
-        std::thread::sleep(Duration::from_millis(10));
+        fixture.advance_time(Duration::from_millis(10));
         future.expect_pending();
-        std::thread::sleep(Duration::from_millis(30));
+        fixture.advance_time(Duration::from_millis(30));
         let result = future.expect_ready();
         assert_eq!(result, RetryResult::OkAfterRetries(3, NonEmptyVec::new(1, vec![2])));
     }
 
     #[test]
     fn operation_always_failing() {
+        let mut fixture = TestWithVirtualTimeExecutor::set_up();
         let mut call_index = 0;
         let operation = move || {
             call_index += 1;
@@ -170,15 +174,15 @@ pub mod tests {
             retry_operation(operation, retry_times, "One does not simply walk into Mordor.")
                 .boxed_local();
         future.expect_pending();
-        std::thread::sleep(Duration::from_millis(10));
+        fixture.advance_time(Duration::from_millis(10));
         future.expect_pending();
-        std::thread::sleep(Duration::from_millis(20));
+        fixture.advance_time(Duration::from_millis(20));
         future.expect_pending();
-        std::thread::sleep(Duration::from_millis(30));
+        fixture.advance_time(Duration::from_millis(30));
         future.expect_pending();
-        std::thread::sleep(Duration::from_millis(40));
+        fixture.advance_time(Duration::from_millis(40));
         future.expect_pending();
-        std::thread::sleep(Duration::from_millis(60));
+        fixture.advance_time(Duration::from_millis(60));
         let result: RetryResult<usize, _> = future.expect_ready();
         assert_eq!(result, RetryResult::Err(NonEmptyVec::new(1, vec![2, 3, 4, 5, 6])));
     }
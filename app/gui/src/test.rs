@@ -329,9 +329,18 @@ pub mod mock {
         }
 
         /// Register an expectation that the module described by this mock data will be opened.
+        ///
+        /// If the module's code has no metadata, the synchronized module looks for them in the
+        /// sidecar file, which is expected not to exist.
         pub fn expect_opening_module(&self, client: &mut language_server::MockClient) {
             let content = self.code.clone();
-            self.expect_opening_module_with_content(client, move || Ok(content))
+            let has_metadata = parser::api::SourceFile::new(content.clone()).has_metadata();
+            self.expect_opening_module_with_content(client, move || Ok(content));
+            if !has_metadata {
+                let path = model::module::synchronized::sidecar_path(self.module_path.file_path());
+                let no_sidecar = language_server::response::FileExists { exists: false };
+                expect_call!(client.file_exists(path=path) => Ok(no_sidecar));
+            }
         }
 
         pub fn expect_opening_module_with_content(
@@ -400,12 +409,23 @@ pub mod mock {
         /// language server API. Most likely also closing and initial edit (that adds metadata)
         /// should be expected. See usage for examples.
         pub fn synchronized_module(&self) -> Rc<model::module::Synchronized> {
+            self.synchronized_module_with_metadata_storage(default())
+        }
+
+        /// Create a synchronized module model storing its metadata in the given storage.
+        ///
+        /// Same considerations need to be made as with `[synchronized_module]`.
+        pub fn synchronized_module_with_metadata_storage(
+            &self,
+            storage: model::module::synchronized::MetadataStorage,
+        ) -> Rc<model::module::Synchronized> {
             let parser = self.data.parser.clone();
             let path = self.data.module_path.clone();
             let ls = self.project.json_rpc();
             let repository = self.project.urm().repository.clone_ref();
             let ro = self.read_only.clone_ref();
-            let module_future = model::module::Synchronized::open(path, ls, parser, repository, ro);
+            let module_future =
+                model::module::Synchronized::open(path, ls, parser, repository, ro, storage);
             // We can `expect_ready`, because in fact this is synchronous in test conditions.
             // (there's no real asynchronous connection beneath, just the `MockClient`)
             let module = module_future.boxed_local().expect_ready().unwrap();
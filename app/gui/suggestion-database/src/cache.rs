@@ -0,0 +1,374 @@
+//! A persistent cache of the suggestion database.
+//!
+//! Downloading the whole suggestion database when opening a project is slow for projects importing
+//! large libraries. The cache keeps the last downloaded database of the project together with a log
+//! of the updates applied to it since, so the IDE can skip the download if the version reported by
+//! the Language Server matches the cached one.
+
+use crate::prelude::*;
+
+use engine_protocol::language_server::response::GetSuggestionDatabase;
+use engine_protocol::language_server::SuggestionDatabaseUpdatesEvent;
+use engine_protocol::language_server::SuggestionsDatabaseVersion;
+use serde::Deserialize;
+use serde::Serialize;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The version of the cache format. It must be bumped whenever the format, or any of the serialized
+/// Language Server types, change, so the caches written by older IDE versions are discarded.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The maximum number of updates kept in the log. Once exceeded, the cache is dropped, so the next
+/// time the project is opened the whole database is downloaded and cached again.
+pub const MAX_LOGGED_UPDATES: usize = 1000;
+
+/// The prefix of the keys of all caches in the [`Storage`].
+pub const KEY_PREFIX: &str = "enso.suggestion-database";
+
+
+
+// ==============
+// === Errors ===
+// ==============
+
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Fail)]
+#[fail(
+    display = "Unsupported suggestion database cache format version {}, expected {}.",
+    found, expected
+)]
+pub struct UnsupportedFormat {
+    pub found:    u32,
+    pub expected: u32,
+}
+
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Fail)]
+#[fail(
+    display = "The update log is based on suggestion database version {}, while the cached \
+    database has version {}.",
+    log_base, database
+)]
+pub struct UpdateLogMismatch {
+    pub log_base: SuggestionsDatabaseVersion,
+    pub database: SuggestionsDatabaseVersion,
+}
+
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Fail)]
+#[fail(
+    display = "The update log has {} entries, more than the allowed {}.",
+    _0, MAX_LOGGED_UPDATES
+)]
+pub struct UpdateLogTooLong(pub usize);
+
+
+
+// ===============
+// === Storage ===
+// ===============
+
+/// A key-value storage persisting the caches, e.g. the browser's local storage.
+pub trait Storage: Debug {
+    /// Get the value stored under the key.
+    fn get(&self, key: &str) -> Option<String>;
+    /// Store the value under the key, replacing the previous one.
+    fn set(&self, key: &str, value: &str) -> FallibleResult;
+    /// Remove the value stored under the key, if any.
+    fn remove(&self, key: &str);
+}
+
+/// A [`Storage`] keeping the values in memory, used in tests and where no persistent storage is
+/// available.
+#[derive(Debug, Default)]
+pub struct InMemoryStorage {
+    values: RefCell<HashMap<String, String>>,
+}
+
+impl Storage for InMemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.borrow().get(key).cloned()
+    }
+
+    fn set(&self, key: &str, value: &str) -> FallibleResult {
+        self.values.borrow_mut().insert(key.to_owned(), value.to_owned());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) {
+        self.values.borrow_mut().remove(key);
+    }
+}
+
+
+
+// ======================
+// === CachedDatabase ===
+// ======================
+
+/// The contents of the cache: the last downloaded database and the updates applied to it since.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedDatabase {
+    /// The database, as downloaded from the Language Server.
+    pub database: GetSuggestionDatabase,
+    /// The updates to apply to the database, in order.
+    pub updates:  Vec<SuggestionDatabaseUpdatesEvent>,
+}
+
+impl CachedDatabase {
+    /// The version of the database after applying all updates.
+    pub fn version(&self) -> SuggestionsDatabaseVersion {
+        let last_update = self.updates.last();
+        last_update.map_or(self.database.current_version, |update| update.current_version)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    format_version: u32,
+    database:       GetSuggestionDatabase,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateLog {
+    format_version: u32,
+    base_version:   SuggestionsDatabaseVersion,
+    updates:        Vec<SuggestionDatabaseUpdatesEvent>,
+}
+
+
+
+// =============
+// === Cache ===
+// =============
+
+/// The persistent cache of a single project's suggestion database.
+///
+/// The database and the update log are stored under separate keys, so recording an update does not
+/// require serializing the whole database again.
+#[derive(Debug)]
+pub struct Cache {
+    storage: Rc<dyn Storage>,
+    key:     String,
+    log:     RefCell<Option<UpdateLog>>,
+}
+
+impl Cache {
+    /// Create a cache of the project identified by `project_key` (e.g. the project id), kept in the
+    /// given storage.
+    pub fn new(storage: Rc<dyn Storage>, project_key: impl Display) -> Self {
+        let key = format!("{KEY_PREFIX}.{project_key}");
+        Self { storage, key, log: default() }
+    }
+
+    /// Load the cached database. Returns [`None`] if nothing is cached, or if the cache is
+    /// corrupted or was written in an unsupported format; in the latter cases the cache is cleared.
+    pub fn load(&self) -> Option<CachedDatabase> {
+        match self.try_load() {
+            Ok(cached) => cached,
+            Err(error) => {
+                warn!("Discarding the suggestion database cache: {error}");
+                self.clear();
+                None
+            }
+        }
+    }
+
+    fn try_load(&self) -> FallibleResult<Option<CachedDatabase>> {
+        let Some(snapshot) = self.storage.get(&self.snapshot_key()) else { return Ok(None) };
+        let snapshot: Snapshot = serde_json::from_str(&snapshot)?;
+        check_format_version(snapshot.format_version)?;
+        let log = match self.storage.get(&self.log_key()) {
+            Some(log) => serde_json::from_str(&log)?,
+            None => UpdateLog::new(snapshot.database.current_version),
+        };
+        check_format_version(log.format_version)?;
+        let database = snapshot.database;
+        if log.base_version != database.current_version {
+            let log_base = log.base_version;
+            return Err(UpdateLogMismatch { log_base, database: database.current_version }.into());
+        }
+        if log.updates.len() > MAX_LOGGED_UPDATES {
+            return Err(UpdateLogTooLong(log.updates.len()).into());
+        }
+        let updates = log.updates.clone();
+        *self.log.borrow_mut() = Some(log);
+        Ok(Some(CachedDatabase { database, updates }))
+    }
+
+    /// Replace the cached database with a freshly downloaded one.
+    pub fn store_database(&self, database: &GetSuggestionDatabase) {
+        let snapshot =
+            Snapshot { format_version: FORMAT_VERSION, database: database.clone() };
+        let log = UpdateLog::new(database.current_version);
+        let stored = self
+            .store(&self.snapshot_key(), &snapshot)
+            .and_then(|()| self.store(&self.log_key(), &log));
+        match stored {
+            Ok(()) => *self.log.borrow_mut() = Some(log),
+            Err(error) => {
+                warn!("Failed to store the suggestion database cache: {error}");
+                self.clear();
+            }
+        }
+    }
+
+    /// Record an update applied to the database. Does nothing if no database is cached.
+    pub fn store_update(&self, update: &SuggestionDatabaseUpdatesEvent) {
+        let mut cached_log = self.log.borrow_mut();
+        let Some(log) = cached_log.as_mut() else { return };
+        log.updates.push(update.clone());
+        let too_long = log.updates.len() > MAX_LOGGED_UPDATES;
+        let stored = if too_long {
+            Err(UpdateLogTooLong(log.updates.len()).into())
+        } else {
+            self.store(&self.log_key(), &*log)
+        };
+        if let Err(error) = stored {
+            warn!("Dropping the suggestion database cache: {error}");
+            *cached_log = None;
+            self.storage.remove(&self.snapshot_key());
+            self.storage.remove(&self.log_key());
+        }
+    }
+
+    /// Remove the cached database.
+    pub fn clear(&self) {
+        *self.log.borrow_mut() = None;
+        self.storage.remove(&self.snapshot_key());
+        self.storage.remove(&self.log_key());
+    }
+
+    fn store(&self, key: &str, value: &impl Serialize) -> FallibleResult {
+        self.storage.set(key, &serde_json::to_string(value)?)
+    }
+
+    fn snapshot_key(&self) -> String {
+        format!("{}.database", self.key)
+    }
+
+    fn log_key(&self) -> String {
+        format!("{}.updates", self.key)
+    }
+}
+
+impl UpdateLog {
+    fn new(base_version: SuggestionsDatabaseVersion) -> Self {
+        Self { format_version: FORMAT_VERSION, base_version, updates: default() }
+    }
+}
+
+fn check_format_version(found: u32) -> FallibleResult {
+    if found == FORMAT_VERSION {
+        Ok(())
+    } else {
+        Err(UnsupportedFormat { found, expected: FORMAT_VERSION }.into())
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use engine_protocol::language_server::SuggestionEntry;
+    use engine_protocol::language_server::SuggestionsDatabaseEntry;
+    use engine_protocol::language_server::SuggestionsDatabaseUpdate;
+
+    fn database(version: SuggestionsDatabaseVersion) -> GetSuggestionDatabase {
+        let suggestion = SuggestionEntry::Module {
+            module:        "local.Project.Main".to_owned(),
+            documentation: None,
+            reexport:      None,
+        };
+        let entries = vec![SuggestionsDatabaseEntry { id: 1, suggestion }];
+        GetSuggestionDatabase { entries, current_version: version }
+    }
+
+    fn update(version: SuggestionsDatabaseVersion) -> SuggestionDatabaseUpdatesEvent {
+        let updates = vec![SuggestionsDatabaseUpdate::Remove { id: 1 }];
+        SuggestionDatabaseUpdatesEvent { updates, current_version: version }
+    }
+
+    fn new_cache(storage: &Rc<InMemoryStorage>) -> Cache {
+        Cache::new(storage.clone(), "project")
+    }
+
+    #[test]
+    fn storing_and_loading() {
+        let storage = Rc::new(InMemoryStorage::default());
+        let cache = new_cache(&storage);
+        assert_eq!(cache.load(), None);
+        // Updates are not recorded until there is a database to apply them to.
+        cache.store_update(&update(1));
+        assert_eq!(cache.load(), None);
+
+        cache.store_database(&database(3));
+        cache.store_update(&update(4));
+        cache.store_update(&update(5));
+        let expected =
+            CachedDatabase { database: database(3), updates: vec![update(4), update(5)] };
+        let loaded = new_cache(&storage).load();
+        assert_eq!(loaded, Some(expected));
+        assert_eq!(loaded.unwrap().version(), 5);
+
+        // The loaded cache keeps recording updates.
+        let cache = new_cache(&storage);
+        cache.load();
+        cache.store_update(&update(6));
+        assert_eq!(new_cache(&storage).load().unwrap().version(), 6);
+
+        // A new database replaces the log.
+        cache.store_database(&database(7));
+        let loaded = new_cache(&storage).load().unwrap();
+        assert!(loaded.updates.is_empty());
+        assert_eq!(loaded.version(), 7);
+    }
+
+    #[test]
+    fn discarding_invalid_cache() {
+        let storage = Rc::new(InMemoryStorage::default());
+        let cache = new_cache(&storage);
+        let snapshot_key = cache.snapshot_key();
+        let log_key = cache.log_key();
+        let old_format = UpdateLog { format_version: 0, ..UpdateLog::new(3) };
+        let invalid_caches = [
+            (&snapshot_key, "{ not a json".to_owned()),
+            (&log_key, serde_json::to_string(&UpdateLog::new(2)).unwrap()),
+            (&log_key, serde_json::to_string(&old_format).unwrap()),
+        ];
+        for (key, value) in invalid_caches {
+            cache.store_database(&database(3));
+            assert!(new_cache(&storage).load().is_some());
+            storage.set(key, &value).unwrap();
+            assert_eq!(new_cache(&storage).load(), None, "{key}: {value}");
+            assert_eq!(storage.get(&snapshot_key), None);
+            assert_eq!(storage.get(&log_key), None);
+        }
+    }
+
+    #[test]
+    fn dropping_cache_with_too_long_log() {
+        let storage = Rc::new(InMemoryStorage::default());
+        let cache = new_cache(&storage);
+        cache.store_database(&database(0));
+        for version in 1..=MAX_LOGGED_UPDATES {
+            cache.store_update(&update(version));
+        }
+        assert_eq!(new_cache(&storage).load().unwrap().version(), MAX_LOGGED_UPDATES);
+        cache.store_update(&update(MAX_LOGGED_UPDATES + 1));
+        assert_eq!(new_cache(&storage).load(), None);
+    }
+}
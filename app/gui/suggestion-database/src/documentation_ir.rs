@@ -519,6 +519,56 @@ impl Synopsis {
         let list = sections.into_iter().collect_vec();
         Self { list: Rc::new(list) }
     }
+
+    /// The synopsis of the entry's documentation.
+    pub fn of_entry(entry: &Entry) -> Self {
+        FilteredDocSections::new(entry.documentation.iter()).synopsis
+    }
+
+    /// The text of the synopsis with the HTML markup removed.
+    pub fn plain_text(&self) -> String {
+        let mut html = String::new();
+        for section in self.list.iter() {
+            match section {
+                DocSection::Tag { body, .. } | DocSection::Paragraph { body } =>
+                    html.push_str(body),
+                DocSection::Keyed { key, body } => {
+                    html.push_str(key);
+                    html.push('\n');
+                    html.push_str(body);
+                }
+                DocSection::Marked { header, body, .. } => {
+                    if let Some(header) = header {
+                        html.push_str(header);
+                        html.push('\n');
+                    }
+                    html.push_str(body);
+                }
+            }
+            html.push('\n');
+        }
+        strip_html(&html)
+    }
+}
+
+/// Remove the HTML tags from the text and decode the basic character entities.
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for char in html.chars() {
+        match char {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(char),
+            _ => {}
+        }
+    }
+    const ENTITIES: [(&str, &str); 5] =
+        [("&lt;", "<"), ("&gt;", ">"), ("&quot;", "\""), ("&#39;", "'"), ("&amp;", "&")];
+    ENTITIES.iter().fold(text, |text, (entity, char)| text.replace(entity, char))
 }
 
 // =============
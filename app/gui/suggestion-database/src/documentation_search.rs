@@ -0,0 +1,256 @@
+//! Full-text search over the documentation of the suggestion database entries.
+//!
+//! The [`DocumentationIndex`] is an inverted index of the terms appearing in the entries'
+//! [synopses](Synopsis). The text is split into words, which are lowercased, filtered from the most
+//! common English words, and normalized by stripping a few common suffixes (a very light form of
+//! stemming, so `sorting`, `sorted` and `sorts` are all the same term `sort`). The matches are
+//! scored with the [Okapi BM25](https://en.wikipedia.org/wiki/Okapi_BM25) function.
+
+use crate::prelude::*;
+
+use crate::documentation_ir::Synopsis;
+use crate::entry;
+use crate::Entry;
+
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The BM25 parameter controlling the saturation of the term frequency.
+const K1: f32 = 1.2;
+/// The BM25 parameter controlling the normalization by the document length.
+const B: f32 = 0.75;
+/// The suffixes stripped from words, with their replacements and the shortest stem that may be left
+/// after stripping, checked in order.
+const SUFFIXES: [(&str, &str, usize); 5] =
+    [("ies", "y", 3), ("ing", "", 4), ("ed", "", 3), ("ly", "", 3), ("s", "", 3)];
+/// The words too common to be meaningful in the search.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "if", "in", "into", "is", "it",
+    "its", "of", "on", "or", "that", "the", "this", "to", "will", "with",
+];
+
+
+
+// ====================
+// === Tokenization ===
+// ====================
+
+/// Split the text into normalized terms.
+pub fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    let words = text.split(|char: char| !char.is_alphanumeric()).filter(|word| !word.is_empty());
+    let words = words.map(|word| word.to_lowercase());
+    words.filter(|word| !STOP_WORDS.contains(&word.as_str())).map(|word| normalize(&word))
+}
+
+/// Strip the common suffix of the lowercase word, if the remaining stem is long enough.
+fn normalize(word: &str) -> String {
+    let stripped = SUFFIXES.iter().find_map(|(suffix, replacement, min_stem_length)| {
+        let stem = word.strip_suffix(suffix)?;
+        let is_long_enough = stem.chars().count() >= *min_stem_length;
+        let is_double_s = *suffix == "s" && stem.ends_with('s');
+        (is_long_enough && !is_double_s).then(|| format!("{stem}{replacement}"))
+    });
+    stripped.unwrap_or_else(|| word.to_owned())
+}
+
+
+
+// =============
+// === Match ===
+// =============
+
+/// An entry whose documentation matches the query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Match {
+    /// The id of the matched entry.
+    pub id:    entry::Id,
+    /// The BM25 score of the match. The greater, the better the entry matches.
+    pub score: f32,
+}
+
+
+
+// ==========================
+// === DocumentationIndex ===
+// ==========================
+
+/// An indexed document: the documentation of a single entry.
+#[derive(Clone, Debug, Default)]
+struct Document {
+    /// The number of terms in the document.
+    length: usize,
+    /// The distinct terms of the document.
+    terms:  Vec<String>,
+}
+
+/// The inverted index of the entries' documentation. See the [module docs](self) for details.
+#[derive(Clone, Debug, Default)]
+pub struct DocumentationIndex {
+    /// For each term, the number of its occurrences in each document containing it.
+    postings:     HashMap<String, HashMap<entry::Id, usize>>,
+    documents:    HashMap<entry::Id, Document>,
+    total_length: usize,
+}
+
+impl DocumentationIndex {
+    /// Create an index of the given entries' documentation.
+    pub fn new<'a>(entries: impl IntoIterator<Item = (&'a entry::Id, &'a Rc<Entry>)>) -> Self {
+        let mut index = Self::default();
+        for (id, entry) in entries {
+            index.add(*id, entry);
+        }
+        index
+    }
+
+    /// The number of entries with indexed documentation.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Check if there are no entries with indexed documentation.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Index the documentation of the entry, replacing the previously indexed one.
+    pub fn add(&mut self, id: entry::Id, entry: &Entry) {
+        self.remove(id);
+        let text = Synopsis::of_entry(entry).plain_text();
+        let mut frequencies = HashMap::<String, usize>::new();
+        let mut length = 0;
+        for term in terms(&text) {
+            *frequencies.entry(term).or_default() += 1;
+            length += 1;
+        }
+        if length > 0 {
+            let terms = frequencies.keys().cloned().collect();
+            for (term, frequency) in frequencies {
+                self.postings.entry(term).or_default().insert(id, frequency);
+            }
+            self.documents.insert(id, Document { length, terms });
+            self.total_length += length;
+        }
+    }
+
+    /// Remove the entry's documentation from the index.
+    pub fn remove(&mut self, id: entry::Id) {
+        if let Some(document) = self.documents.remove(&id) {
+            self.total_length -= document.length;
+            for term in document.terms {
+                if let Some(postings) = self.postings.get_mut(&term) {
+                    postings.remove(&id);
+                    if postings.is_empty() {
+                        self.postings.remove(&term);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Find the entries whose documentation matches the query, the best matches first.
+    ///
+    /// An entry matches if its documentation contains any of the query terms. The last term is
+    /// matched as a prefix, unless the query ends with a whitespace, so the results are useful
+    /// while the query is still being typed.
+    pub fn search(&self, query: &str) -> Vec<Match> {
+        let mut query_terms = terms(query).collect_vec();
+        let is_last_term_complete = query.ends_with(char::is_whitespace);
+        let prefix = if is_last_term_complete { None } else { query_terms.pop() };
+        let prefix_terms = prefix.iter().flat_map(|prefix| {
+            self.postings.keys().filter(move |term| term.starts_with(prefix.as_str())).cloned()
+        });
+        let all_terms = query_terms.into_iter().chain(prefix_terms).unique();
+
+        let mut scores = HashMap::<entry::Id, f32>::new();
+        for term in all_terms {
+            if let Some(postings) = self.postings.get(&term) {
+                let idf = self.inverse_document_frequency(postings.len());
+                for (id, frequency) in postings {
+                    *scores.entry(*id).or_default() += idf * self.term_score(*id, *frequency);
+                }
+            }
+        }
+        let matches = scores.into_iter().map(|(id, score)| Match { id, score });
+        matches.sorted_by_key(|m| (Reverse(OrderedFloat(m.score)), m.id)).collect()
+    }
+
+    fn inverse_document_frequency(&self, containing_documents: usize) -> f32 {
+        let documents = self.documents.len() as f32;
+        let containing_documents = containing_documents as f32;
+        (1.0 + (documents - containing_documents + 0.5) / (containing_documents + 0.5)).ln()
+    }
+
+    fn term_score(&self, id: entry::Id, frequency: usize) -> f32 {
+        let length = self.documents.get(&id).map_or(0, |document| document.length) as f32;
+        let average_length = self.total_length as f32 / self.documents.len() as f32;
+        let frequency = frequency as f32;
+        let length_normalization = 1.0 - B + B * length / average_length;
+        frequency * (K1 + 1.0) / (frequency + K1 * length_normalization)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::doc_section;
+    use crate::mock_suggestion_database;
+    use crate::SuggestionDatabase;
+    use double_representation::name::QualifiedName;
+
+    #[test]
+    fn tokenization() {
+        let text = "Sorts the <b>values</b> of a Column, sorting by keys; joined tables & class.";
+        let expected =
+            ["sort", "b", "value", "b", "column", "sort", "key", "join", "table", "class"];
+        assert_eq!(terms(text).collect_vec(), expected);
+    }
+
+    #[test]
+    fn searching() {
+        let db = mock_suggestion_database! {
+            Standard.Base {
+                #[with_doc_section(doc_section!("Reads the file from the given path."))]
+                fn read(path) -> Standard.Base.Any;
+                #[with_doc_section(doc_section!("Writes the text to the file, replacing it."))]
+                fn write(path, text) -> Standard.Base.Any;
+                #[with_doc_section(doc_section!("Sorts the values of the vector."))]
+                fn sort() -> Standard.Base.Any;
+                fn undocumented() -> Standard.Base.Any;
+            }
+        };
+        let search = |query: &str| {
+            let matches = db.search_documentation(query);
+            matches.into_iter().map(|m| db.lookup(m.id).unwrap().name.clone()).collect_vec()
+        };
+        assert_eq!(search("reading files "), ["read", "write"]);
+        assert_eq!(search("sorting"), ["sort"]);
+        assert_eq!(search("repl"), ["write"]);
+        assert_eq!(search("the "), Vec::<String>::new());
+
+        let sort_name = QualifiedName::from_text("Standard.Base.sort").unwrap();
+        let (sort_id, _) = db.lookup_by_qualified_name(&sort_name).unwrap();
+        let mut index = DocumentationIndex::new(db_entries(&db).iter().map(|(id, e)| (id, e)));
+        assert_eq!(index.len(), 3);
+        index.remove(sort_id);
+        assert_eq!(index.len(), 2);
+        assert!(index.search("sort ").is_empty());
+        assert!(!index.postings.contains_key("vector"));
+    }
+
+    fn db_entries(db: &SuggestionDatabase) -> Vec<(entry::Id, Rc<Entry>)> {
+        db.keys().into_iter().map(|id| (id, db.lookup(id).unwrap())).collect()
+    }
+}
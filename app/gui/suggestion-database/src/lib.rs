@@ -55,7 +55,9 @@ use language_server::types::SuggestionsDatabaseVersion;
 // === Export ===
 // ==============
 
+pub mod cache;
 pub mod documentation_ir;
+pub mod documentation_search;
 pub mod entry;
 pub mod example;
 pub mod mock;
@@ -327,9 +329,11 @@ pub struct SuggestionDatabase {
     qualified_name_to_id_map: RefCell<QualifiedNameToIdMap>,
     method_pointer_to_id_map: RefCell<MethodPointerToIdMap>,
     hierarchy_index:          RefCell<HierarchyIndex>,
+    documentation_index:      RefCell<documentation_search::DocumentationIndex>,
     examples:                 RefCell<Vec<Rc<Example>>>,
     version:                  Cell<SuggestionsDatabaseVersion>,
     notifications:            notification::Publisher<Notification>,
+    cache:                    Option<cache::Cache>,
 }
 
 impl SuggestionDatabase {
@@ -348,6 +352,9 @@ impl SuggestionDatabase {
             .inspect(|(id, entry)| ret.method_pointer_to_id_map.borrow_mut().set(entry, **id))
             .map(|(id, entry)| (*id, Rc::new(entry.clone())));
         ret.entries.borrow_mut().extend(entries);
+        let documentation_index =
+            documentation_search::DocumentationIndex::new(&*ret.entries.borrow());
+        ret.documentation_index.replace(documentation_index);
         ret
     }
 
@@ -359,6 +366,38 @@ impl SuggestionDatabase {
         Ok(Self::from_ls_response(response))
     }
 
+    /// Create a new database synchronized with the Language Server, using the cache to avoid
+    /// downloading the whole database when the cached one is up to date.
+    ///
+    /// The cached database is used only if its version matches the one reported by the Language
+    /// Server; otherwise, or if the cache is corrupted, the database is downloaded and the cache
+    /// is replaced. The updates applied to the returned database are recorded in the cache.
+    pub async fn create_synchronized_with_cache(
+        language_server: &language_server::Connection,
+        cache: cache::Cache,
+    ) -> FallibleResult<Self> {
+        let version = language_server.client.get_suggestions_database_version().await?;
+        let version = version.current_version;
+        let cached = cache.load().filter(|cached| cached.version() == version);
+        let mut database = match cached {
+            Some(cached) => {
+                info!("Using the cached suggestion database, version {version}.");
+                let database = Self::from_ls_response(cached.database);
+                for update in cached.updates {
+                    database.apply_update_event(update);
+                }
+                database
+            }
+            None => {
+                let response = language_server.client.get_suggestions_database().await?;
+                cache.store_database(&response);
+                Self::from_ls_response(response)
+            }
+        };
+        database.cache = Some(cache);
+        Ok(database)
+    }
+
     /// Create a new database model from response received from the Language Server.
     fn from_ls_response(response: language_server::response::GetSuggestionDatabase) -> Self {
         let mut entries = HashMap::new();
@@ -375,6 +414,7 @@ impl SuggestionDatabase {
         for (id, entry) in &entries {
             hierarchy_index.add(*id, entry, &qualified_name_to_id_map);
         }
+        let documentation_index = documentation_search::DocumentationIndex::new(&entries);
         //TODO[ao]: This is a temporary solution. Eventually, we should gather examples from the
         //          available modules documentation. (https://github.com/enso-org/ide/issues/1011)
         let examples = example::EXAMPLES.iter().cloned().map(Rc::new).collect_vec();
@@ -383,9 +423,11 @@ impl SuggestionDatabase {
             qualified_name_to_id_map: RefCell::new(qualified_name_to_id_map),
             method_pointer_to_id_map: RefCell::new(method_pointer_to_id_map),
             hierarchy_index:          RefCell::new(hierarchy_index),
+            documentation_index:      RefCell::new(documentation_index),
             examples:                 RefCell::new(examples),
             version:                  Cell::new(response.current_version),
             notifications:            default(),
+            cache:                    default(),
         }
     }
 
@@ -419,17 +461,22 @@ impl SuggestionDatabase {
     /// Apply the update event to the database.
     #[profile(Detail)]
     pub fn apply_update_event(&self, event: SuggestionDatabaseUpdatesEvent) {
+        if let Some(cache) = &self.cache {
+            cache.store_update(&event);
+        }
         for update in event.updates {
             let mut entries = self.entries.borrow_mut();
             let mut qn_to_id_map = self.qualified_name_to_id_map.borrow_mut();
             let mut mp_to_id_map = self.method_pointer_to_id_map.borrow_mut();
             let mut hierarchy_index = self.hierarchy_index.borrow_mut();
+            let mut documentation_index = self.documentation_index.borrow_mut();
             match update {
                 entry::Update::Add { id, suggestion } => {
                     let entry = Entry::from_ls_entry(*suggestion);
                     qn_to_id_map.set_and_warn_if_existed(&Entry::qualified_name(&entry), id);
                     mp_to_id_map.set(&entry, id);
                     hierarchy_index.add(id, &entry, &qn_to_id_map);
+                    documentation_index.add(id, &entry);
                     entries.insert(id, Rc::new(entry));
                 }
                 entry::Update::Remove { id } => {
@@ -439,6 +486,7 @@ impl SuggestionDatabase {
                             qn_to_id_map.remove_and_warn_if_did_not_exist(&entry.qualified_name());
                             mp_to_id_map.remove(&entry);
                             hierarchy_index.remove(id);
+                            documentation_index.remove(id);
                         }
 
                         None => {
@@ -460,6 +508,7 @@ impl SuggestionDatabase {
                         hierarchy_index.add(id, entry, &qn_to_id_map);
                         qn_to_id_map.set_and_warn_if_existed(&entry.qualified_name(), id);
                         mp_to_id_map.set(&*entry, id);
+                        documentation_index.add(id, entry);
                         for error in errors {
                             error!("Error when applying update for entry {id}: {error:?}");
                         }
@@ -542,6 +591,12 @@ impl SuggestionDatabase {
             .map(|(id, entry)| (*id, entry.clone()))
     }
 
+    /// Search the documentation of the entries for the query, see
+    /// [`documentation_search::DocumentationIndex::search`].
+    pub fn search_documentation(&self, query: &str) -> Vec<documentation_search::Match> {
+        self.documentation_index.borrow().search(query)
+    }
+
     /// Search the database for Local or Function entries with given name and visible at given
     /// location in module.
     pub fn lookup_locals_at(&self, name: impl Str, location: &ModuleSpan) -> Vec<Rc<Entry>> {
@@ -607,6 +662,7 @@ impl SuggestionDatabase {
         let mut qn_to_id_map = self.qualified_name_to_id_map.borrow_mut();
         qn_to_id_map.set_and_warn_if_existed(&entry.qualified_name(), id);
         self.hierarchy_index.borrow_mut().add(id, &entry, &qn_to_id_map);
+        self.documentation_index.borrow_mut().add(id, &entry);
         self.entries.borrow_mut().insert(id, Rc::new(entry));
     }
 }
@@ -727,6 +783,61 @@ pub mod test {
         assert_eq!(db.version.get(), 456);
     }
 
+    #[test]
+    fn synchronizing_with_cache() {
+        use engine_protocol::language_server::response::GetSuggestionDatabase;
+        use engine_protocol::language_server::response::GetSuggestionDatabaseVersion;
+        use engine_protocol::language_server::MockClient;
+        use json_rpc::expect_call;
+
+        let mut fixture = TestWithLocalPoolExecutor::set_up();
+        let storage = Rc::new(cache::InMemoryStorage::default());
+        let module_entry = |id: SuggestionId, module: &str| SuggestionsDatabaseEntry {
+            id,
+            suggestion: SuggestionEntry::Module {
+                module:        module.to_owned(),
+                documentation: None,
+                reexport:      None,
+            },
+        };
+        let mut synchronize = |version, database: Option<GetSuggestionDatabase>| {
+            let mut client = MockClient::default();
+            let version = GetSuggestionDatabaseVersion { current_version: version };
+            expect_call!(client.get_suggestions_database_version() => Ok(version));
+            if let Some(database) = database {
+                expect_call!(client.get_suggestions_database() => Ok(database));
+            }
+            let connection = language_server::Connection::new_mock(client);
+            let cache = cache::Cache::new(storage.clone(), "project");
+            let db = SuggestionDatabase::create_synchronized_with_cache(&connection, cache);
+            let db = fixture.expect_completion(db).unwrap();
+            db.keys().into_iter().sorted().collect_vec()
+        };
+
+        // Nothing is cached yet, so the database is downloaded.
+        let entries = vec![module_entry(1, "local.Project.A")];
+        let database = GetSuggestionDatabase { entries, current_version: 1 };
+        assert_eq!(synchronize(1, Some(database.clone())), vec![1]);
+
+        // The cache is up to date, so the database is not downloaded.
+        assert_eq!(synchronize(1, None), vec![1]);
+
+        // The updates are recorded in the cache.
+        let cache = cache::Cache::new(storage.clone(), "project");
+        let mut db = SuggestionDatabase::from_ls_response(cache.load().unwrap().database);
+        db.cache = Some(cache);
+        let suggestion = Box::new(module_entry(2, "local.Project.B").suggestion);
+        let updates = vec![entry::Update::Add { id: 2, suggestion }];
+        db.apply_update_event(SuggestionDatabaseUpdatesEvent { updates, current_version: 2 });
+        assert_eq!(synchronize(2, None), vec![1, 2]);
+
+        // The cache is outdated, so the database is downloaded again.
+        let entries = vec![module_entry(3, "local.Project.C")];
+        let database = GetSuggestionDatabase { entries, current_version: 5 };
+        assert_eq!(synchronize(5, Some(database)), vec![3]);
+        assert_eq!(synchronize(5, None), vec![3]);
+    }
+
     #[test]
     fn applying_update() {
         let mut fixture = TestWithLocalPoolExecutor::set_up();
@@ -797,6 +908,45 @@ pub mod test {
         assert_eq!(db.version.get(), 4);
     }
 
+    #[test]
+    fn searching_documentation_after_updates() {
+        let db = SuggestionDatabase::new_empty();
+        let documented_module = |module: &str, documentation: &str| SuggestionEntry::Module {
+            module:        module.to_owned(),
+            documentation: Some(documentation.to_owned()),
+            reexport:      None,
+        };
+        let search =
+            |query: &str| db.search_documentation(query).iter().map(|m| m.id).collect_vec();
+        let apply = |updates, current_version| {
+            db.apply_update_event(SuggestionDatabaseUpdatesEvent { updates, current_version })
+        };
+
+        let add = |id, suggestion| entry::Update::Add { id, suggestion: Box::new(suggestion) };
+        apply(
+            vec![
+                add(1, documented_module("local.Project.Reading", "Reading the files.")),
+                add(2, documented_module("local.Project.Writing", "Writing the files.")),
+            ],
+            2,
+        );
+        assert_eq!(search("read "), [1]);
+        assert_eq!(search("file "), [1, 2]);
+
+        let modification = SuggestionsDatabaseModification {
+            documentation: Some(FieldUpdate::set("Reading and writing the tables.".to_owned())),
+            ..default()
+        };
+        let modification = Box::new(modification);
+        apply(vec![entry::Update::Modify { id: 1, external_id: None, modification }], 3);
+        assert_eq!(search("file "), [2]);
+        assert_eq!(search("table "), [1]);
+
+        apply(vec![entry::Update::Remove { id: 2 }], 4);
+        assert_eq!(search("writ"), [1]);
+        assert!(search("file ").is_empty());
+    }
+
     /// Looks up an entry at `fully_qualified_name` in the `db` and verifies the name of the
     /// retrieved entry.
     fn lookup_and_verify_result_name(db: &SuggestionDatabase, fully_qualified_name: &str) {
@@ -539,6 +539,12 @@ ensogl::define_endpoints_2! {
         stop_editing(),
         /// Collapse the selected nodes into a new node.
         collapse_selected_nodes(),
+        /// Copy the selected nodes to the clipboard.
+        copy_selected_nodes(),
+        /// Paste the nodes from the clipboard at the mouse cursor position.
+        paste_nodes(),
+        /// Arrange the selected nodes automatically, or all the nodes if none is selected.
+        tidy_up_nodes(),
         /// Indicate whether this node had an error or not.
         set_node_error_status(NodeId,Option<node::error::Error>),
         /// Indicate whether this node has finished execution.
@@ -658,6 +664,9 @@ ensogl::define_endpoints_2! {
         node_added                 (NodeId, Option<NodeSource>, bool),
         node_removed               (NodeId),
         nodes_collapsed            ((Vec<NodeId>, NodeId)),
+        nodes_copied               (Vec<NodeId>),
+        nodes_pasted               (Vector2),
+        tidy_up_requested          (Vec<NodeId>),
         node_hovered               (Switch<NodeId>),
         node_selected              (NodeId),
         node_deselected            (NodeId),
@@ -3000,6 +3009,22 @@ fn init_remaining_graph_editor_frp(
     }
 
 
+    // === Copy and Paste Nodes ===
+
+    frp::extend! { network
+        out.nodes_copied <+ inputs.copy_selected_nodes.map(f_!(model.nodes.all_selected()));
+        paste_position <- cursor.scene_position.sample(&inputs.paste_nodes);
+        out.nodes_pasted <+ paste_position.map(|position| position.xy());
+    }
+
+
+    // === Tidy Up Nodes ===
+
+    frp::extend! { network
+        out.tidy_up_requested <+ inputs.tidy_up_nodes.map(f_!(model.nodes.all_selected()));
+    }
+
+
     // === Set Node SKIP/FREEZE macros and context switch expression ===
 
     frp::extend! { network
@@ -27,6 +27,9 @@ pub const SHORTCUTS: &[(ensogl::application::shortcut::ActionType, &str, &str, &
     ),
     (Press, "has_detached_edge", "escape", "drop_dragged_edge"),
     (Press, "!read_only & !is_fs_visualization_displayed", "cmd g", "collapse_selected_nodes"),
+    (Press, "!node_editing & !is_fs_visualization_displayed", "cmd c", "copy_selected_nodes"),
+    (Press, "!node_editing & !read_only & !is_fs_visualization_displayed", "cmd v", "paste_nodes"),
+    (Press, "!read_only & !is_fs_visualization_displayed", "cmd alt l", "tidy_up_nodes"),
     // === Visualization ===
     (Press, "!node_editing", "space", "press_visualization_visibility"),
     (
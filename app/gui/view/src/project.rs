@@ -139,6 +139,9 @@ ensogl::define_endpoints! {
         debug_push_breadcrumb(),
         /// Pop a breadcrumb without notifying the controller.
         debug_pop_breadcrumb(),
+        /// Forget the usage statistics of components, used for ranking them in the component
+        /// browser.
+        reset_component_usage(),
         /// Started creation of a new node using the AI searcher.
         start_node_creation_with_ai_searcher(),
         /// Started creation of a new node using the Component Browser.
@@ -859,6 +862,7 @@ impl application::View for View {
             (Press, "is_searcher_opened", "enter", "accept_searcher_input"),
             (Press, "debug_mode", "ctrl shift enter", "debug_push_breadcrumb"),
             (Press, "debug_mode", "ctrl shift b", "debug_pop_breadcrumb"),
+            (Press, "debug_mode", "ctrl shift u", "reset_component_usage"),
         ]
         .iter()
         .map(|(a, b, c, d)| Self::self_shortcut_when(*a, *c, *d, *b))
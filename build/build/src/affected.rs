@@ -0,0 +1,283 @@
+//! Computing which build targets are affected by the changes in the repository.
+//!
+//! A changed file affects a target if it belongs to one of the target's Rust crates or their
+//! workspace dependencies, or if it lies under one of the target's non-Rust source paths. A target
+//! is also affected when any of the targets it is built from is. Changes to the build
+//! infrastructure itself affect all the targets.
+
+use crate::prelude::*;
+
+use ide_ci::programs::cargo::metadata::Metadata;
+use ide_ci::programs::git;
+use strum::IntoEnumIterator;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Name of the GitHub Actions step output with the comma-separated list of the affected targets.
+pub const OUTPUT_NAME: &str = "affected";
+
+/// Paths, relative to the repository root, that affect all the targets when changed.
+pub const GLOBAL_PATHS: [&str; 10] = [
+    ".github",
+    "build",
+    "build-config.yaml",
+    "Cargo.lock",
+    "Cargo.toml",
+    "rust-toolchain.toml",
+    "run",
+    "run.cmd",
+    "run.ps1",
+    "package-lock.json",
+];
+
+
+
+// ==============
+// === Target ===
+// ==============
+
+/// The build targets that can be affected by the changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(strum::Display, strum::EnumIter, strum::AsRefStr)]
+pub enum Target {
+    Wasm,
+    Gui,
+    Runtime,
+    Backend,
+    Ide,
+    JavaGen,
+}
+
+impl Target {
+    /// Targets whose outputs are used to build this target.
+    pub fn dependencies(self) -> &'static [Target] {
+        match self {
+            Target::Wasm => &[],
+            Target::Gui => &[Target::Wasm],
+            Target::Runtime => &[Target::JavaGen],
+            Target::Backend => &[Target::Runtime],
+            Target::Ide => &[Target::Gui, Target::Backend],
+            Target::JavaGen => &[],
+        }
+    }
+
+    /// Names of the workspace crates built as a part of this target.
+    pub fn crates(self) -> &'static [&'static str] {
+        match self {
+            Target::Wasm => &["enso-gui"],
+            Target::Runtime => &["enso-parser-jni"],
+            Target::JavaGen => &["enso-parser-generate-java"],
+            Target::Gui | Target::Backend | Target::Ide => &[],
+        }
+    }
+
+    /// Paths of the non-Rust sources of this target, relative to the repository root.
+    pub fn paths(self) -> &'static [&'static str] {
+        match self {
+            Target::Gui => &["app/ide-desktop", "package.json"],
+            Target::Runtime => &["build.sbt", "engine", "lib/scala", "project"],
+            // The `tools` directory contains the servers used by the library tests, the legal
+            // review data and the distribution packaging scripts.
+            Target::Backend => &["distribution", "std-bits", "test", "tools"],
+            Target::Ide => &["integration-test"],
+            Target::Wasm | Target::JavaGen => &[],
+        }
+    }
+}
+
+
+
+// =================
+// === Workspace ===
+// =================
+
+/// The dependency graph of the Rust workspace crates.
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+    /// Directory of each crate.
+    pub directories: BTreeMap<String, PathBuf>,
+    /// Names of the workspace crates that depend on each crate.
+    pub dependents:  BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Workspace {
+    /// Build the dependency graph from the `cargo metadata` output.
+    pub fn from_metadata(metadata: &Metadata) -> Result<Self> {
+        let mut workspace = Self::default();
+        for package in &metadata.packages {
+            let directory = package.directory()?.to_path_buf();
+            workspace.directories.insert(package.name.clone(), directory);
+        }
+        for package in &metadata.packages {
+            let path_dependencies = package.dependencies.iter().filter_map(|d| d.path.as_ref());
+            for path in path_dependencies {
+                if let Some(dependency) = workspace.crate_at(path) {
+                    let dependency = dependency.to_string();
+                    let dependents = workspace.dependents.entry(dependency).or_default();
+                    dependents.insert(package.name.clone());
+                }
+            }
+        }
+        Ok(workspace)
+    }
+
+    /// The crate located exactly at the given directory.
+    pub fn crate_at(&self, directory: &Path) -> Option<&str> {
+        let mut crates = self.directories.iter();
+        crates
+            .find(|(_, crate_directory)| *crate_directory == directory)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The crate containing the given file. Nested crates take precedence over the enclosing ones.
+    pub fn crate_containing(&self, file: &Path) -> Option<&str> {
+        let containing =
+            self.directories.iter().filter(|(_, directory)| file.starts_with(directory));
+        let innermost = containing.max_by_key(|(_, directory)| directory.components().count());
+        innermost.map(|(name, _)| name.as_str())
+    }
+
+    /// The given crates together with all the crates that depend on them, directly or not.
+    pub fn with_dependents<'a>(
+        &'a self,
+        crates: impl IntoIterator<Item = &'a str>,
+    ) -> BTreeSet<&'a str> {
+        let mut result = BTreeSet::new();
+        let mut pending = crates.into_iter().collect_vec();
+        while let Some(name) = pending.pop() {
+            if result.insert(name) {
+                let dependents = self.dependents.get(name).into_iter().flatten();
+                pending.extend(dependents.map(|dependent| dependent.as_str()));
+            }
+        }
+        result
+    }
+}
+
+
+
+// ================
+// === Affected ===
+// ================
+
+/// Compute the targets affected by the given changed files.
+///
+/// The file paths must be absolute, like the crate directories in the workspace description.
+pub fn affected_by(
+    repo_root: &Path,
+    workspace: &Workspace,
+    changed_files: &[PathBuf],
+) -> BTreeSet<Target> {
+    let relative_paths = changed_files.iter().filter_map(|file| file.strip_prefix(repo_root).ok());
+    let relative_paths = relative_paths.collect_vec();
+    let is_changed = |path: &str| relative_paths.iter().any(|file| file.starts_with(path));
+    if GLOBAL_PATHS.iter().any(|path| is_changed(path)) {
+        return Target::iter().collect();
+    }
+
+    let changed_crates = changed_files.iter().filter_map(|file| workspace.crate_containing(file));
+    let affected_crates = workspace.with_dependents(changed_crates);
+    let directly_affected = |target: Target| {
+        target.crates().iter().any(|name| affected_crates.contains(name))
+            || target.paths().iter().any(|path| is_changed(path))
+    };
+    let mut affected: BTreeSet<Target> = Target::iter().filter(|&t| directly_affected(t)).collect();
+    // Propagate the changes to the dependent targets, until a fixed point is reached.
+    loop {
+        let newly_affected = Target::iter()
+            .filter(|target| !affected.contains(target))
+            .filter(|target| target.dependencies().iter().any(|d| affected.contains(d)))
+            .collect_vec();
+        if newly_affected.is_empty() {
+            break affected;
+        }
+        affected.extend(newly_affected);
+    }
+}
+
+/// Compute the targets affected by the changes in the working tree, compared to the given git
+/// reference.
+#[context("Failed to compute the targets affected by changes since {base}.")]
+pub async fn affected_since(repo_root: &Path, base: &str) -> Result<BTreeSet<Target>> {
+    let git = git::Context::new(repo_root).await?;
+    let changed_files = git.diff_against(base).await?;
+    debug!("Files changed since {base}: {changed_files:#?}.");
+    let metadata = ide_ci::programs::cargo::metadata::workspace(repo_root).await?;
+    let workspace = Workspace::from_metadata(&metadata)?;
+    Ok(affected_by(repo_root, &workspace, &changed_files))
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ide_ci::programs::cargo::metadata::Dependency;
+    use ide_ci::programs::cargo::metadata::Package;
+
+    fn package(name: &str, directory: &str, dependencies: &[&str]) -> Package {
+        let root = PathBuf::from("/repo");
+        let dependencies = dependencies
+            .iter()
+            .map(|path| Dependency { name: path.to_string(), path: Some(root.join(path)) });
+        Package {
+            name:          name.into(),
+            manifest_path: root.join(directory).join("Cargo.toml"),
+            dependencies:  dependencies.collect(),
+        }
+    }
+
+    fn affected(workspace: &Workspace, changed_files: &[&str]) -> Vec<Target> {
+        let root = Path::new("/repo");
+        let files = changed_files.iter().map(|file| root.join(file)).collect_vec();
+        affected_by(root, workspace, &files).into_iter().collect()
+    }
+
+    #[test]
+    fn changes_propagate_through_dependencies() -> Result {
+        let metadata = Metadata {
+            workspace_root: "/repo".into(),
+            packages:       vec![
+                package("enso-gui", "app/gui", &["lib/rust/prelude"]),
+                package("enso-prelude", "lib/rust/prelude", &[]),
+                package("enso-parser", "lib/rust/parser", &["lib/rust/prelude"]),
+                package("enso-parser-jni", "lib/rust/parser/jni", &["lib/rust/parser"]),
+                package("enso-parser-generate-java", "lib/rust/parser/generate-java", &[
+                    "lib/rust/parser",
+                ]),
+            ],
+        };
+        let workspace = Workspace::from_metadata(&metadata)?;
+
+        let parser_change = affected(&workspace, &["lib/rust/parser/src/lexer.rs"]);
+        use Target::*;
+        assert_eq!(parser_change, vec![Runtime, Backend, Ide, JavaGen]);
+        let prelude_change = affected(&workspace, &["lib/rust/prelude/src/lib.rs"]);
+        assert_eq!(prelude_change, Target::iter().collect_vec());
+        let gui_change = affected(&workspace, &["app/gui/src/lib.rs"]);
+        assert_eq!(gui_change, vec![Wasm, Gui, Ide]);
+        let library_change =
+            affected(&workspace, &["distribution/lib/Standard/Base/src/Main.enso"]);
+        assert_eq!(library_change, vec![Backend, Ide]);
+        let desktop_change = affected(&workspace, &["app/ide-desktop/lib/client/src/index.ts"]);
+        assert_eq!(desktop_change, vec![Gui, Ide]);
+        let test_change = affected(&workspace, &["test/Tests/src/Data/Vector_Spec.enso"]);
+        assert_eq!(test_change, vec![Backend, Ide]);
+        let tool_change = affected(&workspace, &["tools/simple-httpbin/src/main/java/Main.java"]);
+        assert_eq!(tool_change, vec![Backend, Ide]);
+        let integration_test_change = affected(&workspace, &["integration-test/tests/graph.rs"]);
+        assert_eq!(integration_test_change, vec![Ide]);
+        assert_eq!(affected(&workspace, &["docs/README.md"]), vec![]);
+        assert_eq!(affected(&workspace, &["build/cli/src/lib.rs"]), Target::iter().collect_vec());
+        Ok(())
+    }
+}
@@ -11,6 +11,8 @@ use std::ops::Range;
 // ==============
 
 pub mod check;
+pub mod document;
+pub mod lint;
 
 
 
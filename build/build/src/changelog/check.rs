@@ -64,5 +64,6 @@ pub async fn check(repo_path: RepoRoot, context: ide_ci::actions::Context) -> Re
         ide_ci::actions::workflow::message(MessageLevel::Error, &message);
         bail!(message);
     }
+    crate::changelog::lint::check_file(&changelog)?;
     Ok(())
 }
@@ -0,0 +1,447 @@
+//! The structure of the changelog file: release sections, categories, change entries and link
+//! definitions.
+//!
+//! The changelog is parsed line by line, rather than as a generic Markdown document, so each
+//! element keeps the line number it was defined at. This allows [linting](crate::changelog::lint)
+//! with precise messages.
+
+use crate::prelude::*;
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Prefix of the release section header, e.g. `# Enso 2.0.0-alpha.18 (2021-10-12)`.
+pub const RELEASE_HEADER_PREFIX: &str = "# ";
+
+/// Prefix of the category header, e.g. `#### Visual Environment`.
+pub const CATEGORY_HEADER_PREFIX: &str = "#### ";
+
+/// Prefix of the line with a release tag image, e.g.
+/// `<br/>![Bug Fixes](/docs/assets/tags/bug_fixes.svg)`.
+pub const KIND_TAG_PREFIX: &str = "<br/>![";
+
+/// Indentation of the change entry continuation lines.
+pub const CONTINUATION_INDENT: &str = "  ";
+
+/// Matches a link reference definition, capturing the label and the URL (which might be placed
+/// on the next line).
+static LINK_DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[([^\]]+)\]:\s*(\S*)\s*$").unwrap());
+
+/// Matches a full reference link, like `[text][label]`, capturing the label.
+static FULL_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[[^\]\[]*\]\[([^\]\[]+)\]").unwrap());
+
+/// Matches a potential shortcut reference link, like `[label]`, capturing the label.
+static SHORTCUT_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]\[]+)\](?:[^\[(:]|$)").unwrap());
+
+/// Matches an inline code span.
+static CODE_SPAN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`[^`]*`").unwrap());
+
+/// Normalize the link label, as the Markdown label matching is case-insensitive.
+pub fn normalize_label(label: &str) -> String {
+    label.split_whitespace().join(" ").to_lowercase()
+}
+
+
+
+// ================
+// === Document ===
+// ================
+
+/// The parsed changelog file.
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    /// Release sections, starting with the most recent one.
+    pub releases: Vec<Release>,
+    /// All the link reference definitions in the file.
+    pub links:    Vec<LinkDefinition>,
+}
+
+/// A release section, introduced by a level one header.
+#[derive(Clone, Debug, Default)]
+pub struct Release {
+    /// The header text, without the `#` prefix.
+    pub header:        String,
+    /// Line number of the header.
+    pub line:          usize,
+    /// The version mentioned in the header. The unreleased changes section has none.
+    pub version:       Option<Version>,
+    /// Categorized changes.
+    pub categories:    Vec<Category>,
+    /// Changes placed directly in the release section, outside of any category.
+    pub uncategorized: Vec<Change>,
+}
+
+/// A category of changes within a release, introduced by a level four header.
+#[derive(Clone, Debug, Default)]
+pub struct Category {
+    /// The header text, without the `####` prefix.
+    pub name:    String,
+    /// Line number of the header.
+    pub line:    usize,
+    /// The kind of changes, as given by the preceding tag image, e.g. "Bug Fixes".
+    pub kind:    Option<String>,
+    pub changes: Vec<Change>,
+}
+
+/// A single bullet list entry describing a change.
+#[derive(Clone, Debug, Default)]
+pub struct Change {
+    /// Line number of the first line of the entry.
+    pub line:  usize,
+    /// The bullet list marker.
+    pub mark:  char,
+    /// The entry lines as written in the file, including the indentation of the continuation
+    /// lines. The bullet list marker is not included.
+    pub lines: Vec<String>,
+}
+
+/// A link reference definition, like `[1234]: https://github.com/enso-org/enso/pull/1234`.
+#[derive(Clone, Debug, Default)]
+pub struct LinkDefinition {
+    /// The label, as written in the file.
+    pub label:   String,
+    /// The link destination. Empty if missing.
+    pub url:     String,
+    /// Line number of the definition.
+    pub line:    usize,
+    /// Index of the release section containing the definition.
+    pub release: Option<usize>,
+}
+
+impl Document {
+    /// Parse the changelog file contents.
+    pub fn parse(text: &str) -> Self {
+        let mut parser = Parser::default();
+        for (index, line) in text.lines().enumerate() {
+            parser.line(index + 1, line);
+        }
+        parser.finish()
+    }
+
+    /// Find the release section with the notes for the given version.
+    ///
+    /// If there is no section for this version yet, the top section with the unreleased changes
+    /// is used.
+    pub fn release_for(&self, version: &Version) -> Result<&Release> {
+        let released =
+            self.releases.iter().find(|release| release.version.as_ref() == Some(version));
+        let unreleased = || self.releases.first().filter(|release| release.version.is_none());
+        released
+            .or_else(unreleased)
+            .with_context(|| format!("No changelog section for version {version} was found."))
+    }
+
+    /// Find the definition of the link with the given label. The first definition takes
+    /// precedence, as in Markdown.
+    pub fn link(&self, label: &str) -> Option<&LinkDefinition> {
+        let label = normalize_label(label);
+        self.links.iter().find(|link| normalize_label(&link.label) == label)
+    }
+
+    /// Generate the release notes for the given version.
+    ///
+    /// The notes consist of the release's change entries grouped by their categories, followed by
+    /// the definitions of the links they use. Other contents of the section, like the tag images,
+    /// are skipped, as they use paths relative to the repository.
+    pub fn release_notes(&self, version: &Version) -> Result<String> {
+        let release = self.release_for(version)?;
+        let mut categories: Vec<(&str, Vec<&Change>)> = Vec::new();
+        for category in &release.categories {
+            let changes = category.changes.iter();
+            match categories.iter_mut().find(|(name, _)| *name == category.name) {
+                Some((_, existing)) => existing.extend(changes),
+                None => categories.push((category.name.as_str(), changes.collect())),
+            }
+        }
+        let mut notes = String::new();
+        let mut labels = Vec::new();
+        let uncategorized = release.uncategorized.iter().collect_vec();
+        let sections = once(("", uncategorized)).chain(categories);
+        for (name, changes) in sections.filter(|(_, changes)| !changes.is_empty()) {
+            if !name.is_empty() {
+                notes.push_str(&format!("{CATEGORY_HEADER_PREFIX}{name}\n\n"));
+            }
+            for change in changes {
+                notes.push_str(&format!("{}\n", change.render()));
+                labels.extend(change.references());
+            }
+            notes.push('\n');
+        }
+        let links =
+            labels.iter().unique().filter_map(|label| self.link(label)).unique_by(|l| l.line);
+        for link in links {
+            notes.push_str(&format!("[{}]: {}\n", link.label, link.url));
+        }
+        Ok(notes.trim().to_string())
+    }
+}
+
+impl Release {
+    /// All the changes in this release, including the uncategorized ones.
+    pub fn changes(&self) -> impl Iterator<Item = &Change> {
+        let categorized = self.categories.iter().flat_map(|category| &category.changes);
+        self.uncategorized.iter().chain(categorized)
+    }
+}
+
+impl Change {
+    /// The entry text, with the lines joined.
+    pub fn text(&self) -> String {
+        self.lines.iter().map(|line| line.trim()).join(" ")
+    }
+
+    /// Labels of the links referenced by this entry, in order of appearance.
+    ///
+    /// Potential shortcut references like `[label]` are included as well, so some of the labels
+    /// might not refer to any link.
+    pub fn references(&self) -> Vec<String> {
+        self.full_references().into_iter().chain(self.shortcut_references()).collect()
+    }
+
+    /// Labels of the full reference links, like `[text][label]`. These must refer to a link.
+    pub fn full_references(&self) -> Vec<String> {
+        let text = self.text_without_code();
+        FULL_REFERENCE.captures_iter(&text).map(|captures| captures[1].to_string()).collect()
+    }
+
+    /// Labels of the potential shortcut reference links, like `[label]`.
+    pub fn shortcut_references(&self) -> Vec<String> {
+        let text = self.text_without_code();
+        let text = FULL_REFERENCE.replace_all(&text, "");
+        SHORTCUT_REFERENCE.captures_iter(&text).map(|captures| captures[1].to_string()).collect()
+    }
+
+    /// Render the entry as a bullet list item, keeping the original line breaks.
+    pub fn render(&self) -> String {
+        let lines = self.lines.iter().map(|line| line.trim());
+        let lines = lines.enumerate().map(|(index, line)| {
+            if index == 0 {
+                format!("- {line}")
+            } else {
+                format!("{CONTINUATION_INDENT}{line}")
+            }
+        });
+        lines.join("\n")
+    }
+
+    fn text_without_code(&self) -> String {
+        CODE_SPAN.replace_all(&self.text(), "``").into_owned()
+    }
+}
+
+
+
+// ==============
+// === Parser ===
+// ==============
+
+/// The state of the line-by-line changelog parsing.
+#[derive(Clone, Debug, Default)]
+struct Parser {
+    document:         Document,
+    release:          Option<Release>,
+    category:         Option<Category>,
+    change:           Option<Change>,
+    /// The kind from the last tag image, to be assigned to the next category.
+    kind:             Option<String>,
+    /// Whether we are inside a fenced code block.
+    in_code_block:    bool,
+    /// Whether the last link definition is waiting for its URL on the next line.
+    url_on_next_line: bool,
+}
+
+impl Parser {
+    fn line(&mut self, number: usize, line: &str) {
+        if self.url_on_next_line {
+            self.url_on_next_line = false;
+            if let Some(link) = self.document.links.last_mut() && !line.trim().is_empty() {
+                link.url = line.trim().to_string();
+                return;
+            }
+        }
+        if line.trim_start().starts_with("```") {
+            self.in_code_block = !self.in_code_block;
+        }
+        if self.in_code_block || line.trim_start().starts_with("```") {
+            self.continue_change(line);
+        } else if let Some(header) = line.strip_prefix(RELEASE_HEADER_PREFIX) {
+            self.finish_release();
+            let header = header.trim().to_string();
+            let version = Version::find_in_text(&header).ok();
+            self.release = Some(Release { header, line: number, version, ..default() });
+        } else if let Some(name) = line.strip_prefix(CATEGORY_HEADER_PREFIX) {
+            self.finish_category();
+            let name = name.trim().to_string();
+            let kind = self.kind.take();
+            self.category = Some(Category { name, line: number, kind, ..default() });
+        } else if let Some(tag) = line.strip_prefix(KIND_TAG_PREFIX) {
+            self.finish_change();
+            self.kind = tag.split_once(']').map(|(kind, _)| kind.to_string());
+        } else if let Some(captures) = LINK_DEFINITION.captures(line) {
+            self.finish_change();
+            let label = captures[1].to_string();
+            let url = captures[2].to_string();
+            self.url_on_next_line = url.is_empty();
+            let release = self.document.releases.len();
+            let release = self.release.is_some().then_some(release);
+            self.document.links.push(LinkDefinition { label, url, line: number, release });
+        } else if let Some((mark, rest)) = bullet(line) {
+            self.finish_change();
+            let lines = vec![rest.to_string()];
+            self.change = Some(Change { line: number, mark, lines });
+        } else if line.trim().is_empty() {
+            self.finish_change();
+        } else {
+            self.continue_change(line);
+        }
+    }
+
+    /// Append the line to the current change entry, if any. Other text is not a part of the
+    /// document structure.
+    fn continue_change(&mut self, line: &str) {
+        if let Some(change) = &mut self.change {
+            change.lines.push(line.to_string());
+        }
+    }
+
+    fn finish_change(&mut self) {
+        if let Some(change) = self.change.take() {
+            match (&mut self.category, &mut self.release) {
+                (Some(category), _) => category.changes.push(change),
+                (None, Some(release)) => release.uncategorized.push(change),
+                (None, None) => {}
+            }
+        }
+    }
+
+    fn finish_category(&mut self) {
+        self.finish_change();
+        if let Some(category) = self.category.take() {
+            if let Some(release) = &mut self.release {
+                release.categories.push(category);
+            }
+        }
+    }
+
+    fn finish_release(&mut self) {
+        self.finish_category();
+        self.kind = None;
+        if let Some(release) = self.release.take() {
+            self.document.releases.push(release);
+        }
+    }
+
+    fn finish(mut self) -> Document {
+        self.finish_release();
+        self.document
+    }
+}
+
+/// Split the bullet list item line into the marker and the rest of the line.
+fn bullet(line: &str) -> Option<(char, &str)> {
+    ['-', '*', '+'].into_iter().find_map(|mark| {
+        let rest = line.strip_prefix(mark)?.strip_prefix(' ')?;
+        Some((mark, rest))
+    })
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub const CHANGELOG: &str = r#"# Next Release
+
+#### Visual Environment
+
+- [Nodes can be added to the graph by double-clicking the output ports of
+  existing nodes.][3346]
+- [Debug Mode can be activated using a shortcut.][3264] See [debug-shortcuts].
+
+[3346]: https://github.com/enso-org/enso/pull/3346
+[3264]: https://github.com/enso-org/enso/pull/3264
+[debug-shortcuts]:
+  https://github.com/enso-org/enso/blob/develop/app/gui/docs/product/shortcuts.md#debug
+
+#### Enso Compiler
+
+- [Fixed `[1, 2]` vector literals.][3400]
+
+[3400]: https://github.com/enso-org/enso/pull/3400
+
+# Enso 2.0.0-alpha.18 (2021-10-12)
+
+<br/>![Bug Fixes](/docs/assets/tags/bug_fixes.svg)
+
+#### Visual Environment
+
+- [Fixed freezing after inactivity.][1776]
+
+[1776]: https://github.com/enso-org/ide/pull/1776
+"#;
+
+    #[test]
+    fn parsing_structure() {
+        let document = Document::parse(CHANGELOG);
+        assert_eq!(document.releases.len(), 2);
+        let next = &document.releases[0];
+        assert_eq!(next.header, "Next Release");
+        assert!(next.version.is_none());
+        let names = next.categories.iter().map(|category| category.name.as_str()).collect_vec();
+        assert_eq!(names, vec!["Visual Environment", "Enso Compiler"]);
+        let changes = &next.categories[0].changes;
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].line, 5);
+        assert_eq!(changes[1].references(), vec!["3264", "debug-shortcuts"]);
+        assert_eq!(next.categories[1].changes[0].references(), vec!["3400"]);
+
+        let previous = &document.releases[1];
+        assert_eq!(previous.version, Some(Version::parse("2.0.0-alpha.18").unwrap()));
+        assert_eq!(previous.categories[0].kind.as_deref(), Some("Bug Fixes"));
+
+        let shortcuts = document.link("Debug-Shortcuts").unwrap();
+        assert!(shortcuts.url.ends_with("shortcuts.md#debug"));
+        assert_eq!(shortcuts.release, Some(0));
+        assert_eq!(document.link("1776").unwrap().release, Some(1));
+    }
+
+    #[test]
+    fn generating_release_notes() -> Result {
+        let document = Document::parse(CHANGELOG);
+        let unreleased = Version::parse("2023.1.1")?;
+        let notes = document.release_notes(&unreleased)?;
+        let expected = r#"#### Visual Environment
+
+- [Nodes can be added to the graph by double-clicking the output ports of
+  existing nodes.][3346]
+- [Debug Mode can be activated using a shortcut.][3264] See [debug-shortcuts].
+
+#### Enso Compiler
+
+- [Fixed `[1, 2]` vector literals.][3400]
+
+[3346]: https://github.com/enso-org/enso/pull/3346
+[3264]: https://github.com/enso-org/enso/pull/3264
+[debug-shortcuts]: https://github.com/enso-org/enso/blob/develop/app/gui/docs/product/shortcuts.md#debug
+[3400]: https://github.com/enso-org/enso/pull/3400"#;
+        assert_eq!(notes, expected);
+
+        let released = Version::parse("2.0.0-alpha.18")?;
+        assert!(document.release_notes(&released)?.contains("[1776]: "));
+        Ok(())
+    }
+}
@@ -0,0 +1,369 @@
+//! Offline checks of the changelog contents.
+//!
+//! The checks work on the [parsed document](Document), so they do not need any network access.
+//! They catch the common mistakes: broken or conflicting link definitions, the same pull request
+//! being described in several releases, entries placed outside of the known categories and
+//! formatting inconsistencies.
+
+use crate::prelude::*;
+
+use crate::changelog::document::normalize_label;
+use crate::changelog::document::Change;
+use crate::changelog::document::Document;
+use crate::changelog::document::LinkDefinition;
+use crate::changelog::document::CONTINUATION_INDENT;
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Names of the categories that are allowed in the release sections.
+pub const KNOWN_CATEGORIES: [&str; 5] = [
+    "Visual Environment",
+    "EnsoGL (rendering engine)",
+    "Enso Standard Library",
+    "Enso Compiler",
+    "Anonymous Data Collection",
+];
+
+/// Matches a link to a pull request in one of our repositories, capturing the repository name and
+/// the pull request number.
+static PULL_REQUEST_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^https://github\.com/enso-org/([\w.-]+)/pull/(\d+)/?(?:#.*)?$").unwrap()
+});
+
+
+
+// =============
+// === Issue ===
+// =============
+
+/// A problem found in the changelog.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Issue {
+    /// Line number where the problem is located.
+    pub line:    usize,
+    /// Index of the release section containing the problem, if any.
+    pub release: Option<usize>,
+    pub kind:    IssueKind,
+}
+
+/// The kind of a changelog problem.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// The entry uses a link label that is not defined.
+    UndefinedReference { label: String },
+    /// The link definition has no URL.
+    MissingUrl { label: String },
+    /// The link is defined again with a different URL.
+    ConflictingDefinition { label: String, first_line: usize },
+    /// The link label is a pull request number, but the URL points elsewhere.
+    PullRequestMismatch { label: String, url: String },
+    /// The same pull request is referenced by several releases.
+    DuplicatePullRequest { repository: String, number: u64, first_line: usize },
+    /// The entry is not placed in any category.
+    Uncategorized,
+    /// The category is not one of the [`KNOWN_CATEGORIES`].
+    UnknownCategory { name: String },
+    /// The entry does not reference any link.
+    NoReference,
+    /// The entry uses a bullet list marker other than `-`.
+    BulletMarker { mark: char },
+    /// The entry continuation line is not indented with [`CONTINUATION_INDENT`].
+    ContinuationIndent,
+    /// The line ends with whitespace.
+    TrailingWhitespace,
+}
+
+impl Display for IssueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IssueKind::UndefinedReference { label } =>
+                write!(f, "The link [{label}] is not defined."),
+            IssueKind::MissingUrl { label } => write!(f, "The link [{label}] has no URL."),
+            IssueKind::ConflictingDefinition { label, first_line } => write!(
+                f,
+                "The link [{label}] is defined with a different URL at line {first_line}."
+            ),
+            IssueKind::PullRequestMismatch { label, url } =>
+                write!(f, "The link [{label}] points to {url} instead of the pull request."),
+            IssueKind::DuplicatePullRequest { repository, number, first_line } => write!(
+                f,
+                "The pull request {repository}#{number} is already described at line {first_line}."
+            ),
+            IssueKind::Uncategorized => write!(f, "The entry is not placed in any category."),
+            IssueKind::UnknownCategory { name } => write!(
+                f,
+                "Unknown category \"{name}\". Expected one of: {}.",
+                KNOWN_CATEGORIES.join(", ")
+            ),
+            IssueKind::NoReference => write!(f, "The entry does not link to any pull request."),
+            IssueKind::BulletMarker { mark } =>
+                write!(f, "The entry uses the '{mark}' marker instead of '-'."),
+            IssueKind::ContinuationIndent => write!(
+                f,
+                "The entry continuation is not indented with {} spaces.",
+                CONTINUATION_INDENT.len()
+            ),
+            IssueKind::TrailingWhitespace => write!(f, "The line has trailing whitespace."),
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.kind)
+    }
+}
+
+
+
+// ============
+// === Lint ===
+// ============
+
+/// Check the changelog file contents. The issues are sorted by their line numbers.
+pub fn lint(text: &str) -> Vec<Issue> {
+    let document = Document::parse(text);
+    let mut issues = Vec::new();
+    lint_links(&document, &mut issues);
+    lint_releases(&document, &mut issues);
+    lint_formatting(text, &document, &mut issues);
+    issues.sort();
+    issues.dedup();
+    issues
+}
+
+/// Check the changelog file and report the found issues.
+///
+/// Only the issues in the top release section cause a failure, as the older sections cannot be
+/// fixed by the pull requests anymore. The issues in the older sections are only logged.
+pub fn check_file(path: impl AsRef<Path>) -> Result {
+    let path = path.as_ref();
+    let contents = ide_ci::fs::read_to_string(path)?;
+    let (current, older): (Vec<_>, Vec<_>) =
+        lint(&contents).into_iter().partition(|issue| issue.release == Some(0));
+    for issue in &older {
+        debug!("{}: {issue}", path.display());
+    }
+    if !older.is_empty() {
+        warn!("Found {} issues in the older sections of {}.", older.len(), path.display());
+    }
+    for issue in &current {
+        error!("{}: {issue}", path.display());
+    }
+    ensure!(
+        current.is_empty(),
+        "Found {} issues in the top section of {}.",
+        current.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Check the link definitions and the references to them.
+fn lint_links(document: &Document, issues: &mut Vec<Issue>) {
+    let mut definitions = BTreeMap::<String, &LinkDefinition>::new();
+    for link in &document.links {
+        let issue = |kind| Issue { line: link.line, release: link.release, kind };
+        let label = link.label.clone();
+        if link.url.is_empty() {
+            issues.push(issue(IssueKind::MissingUrl { label }));
+            continue;
+        }
+        if let Some(first) = definitions.get(&normalize_label(&link.label)) {
+            if first.url != link.url {
+                let first_line = first.line;
+                issues.push(issue(IssueKind::ConflictingDefinition { label, first_line }));
+            }
+            continue;
+        }
+        definitions.insert(normalize_label(&link.label), link);
+        let number = link.label.parse::<u64>().ok();
+        let linked_number = pull_request(&link.url).map(|(_, number)| number);
+        if number.is_some() && number != linked_number {
+            let url = link.url.clone();
+            issues.push(issue(IssueKind::PullRequestMismatch { label, url }));
+        }
+    }
+
+    for (index, release) in document.releases.iter().enumerate() {
+        for change in release.changes() {
+            for label in change.full_references() {
+                if !definitions.contains_key(&normalize_label(&label)) {
+                    let kind = IssueKind::UndefinedReference { label };
+                    issues.push(Issue { line: change.line, release: Some(index), kind });
+                }
+            }
+        }
+    }
+}
+
+/// Check the placement of the entries within the release sections.
+fn lint_releases(document: &Document, issues: &mut Vec<Issue>) {
+    let mut pull_requests = BTreeMap::new();
+    for (index, release) in document.releases.iter().enumerate() {
+        let issue = |line, kind| Issue { line, release: Some(index), kind };
+        for category in &release.categories {
+            if !KNOWN_CATEGORIES.contains(&category.name.as_str()) {
+                let name = category.name.clone();
+                issues.push(issue(category.line, IssueKind::UnknownCategory { name }));
+            }
+        }
+        for change in &release.uncategorized {
+            issues.push(issue(change.line, IssueKind::Uncategorized));
+        }
+        for change in release.changes() {
+            let numbers = linked_pull_requests(document, change);
+            if change.references().is_empty() {
+                issues.push(issue(change.line, IssueKind::NoReference));
+            }
+            for (repository, number) in numbers {
+                match pull_requests.get(&(repository, number)) {
+                    Some(&(first_release, first_line)) if first_release != index => {
+                        let repository = repository.to_string();
+                        let kind =
+                            IssueKind::DuplicatePullRequest { repository, number, first_line };
+                        issues.push(issue(change.line, kind));
+                    }
+                    Some(_) => {}
+                    None => {
+                        pull_requests.insert((repository, number), (index, change.line));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Check the whitespace and list formatting of the release sections.
+fn lint_formatting(text: &str, document: &Document, issues: &mut Vec<Issue>) {
+    let release_at = |line: usize| document.releases.iter().rposition(|r| r.line <= line);
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        if line.ends_with(char::is_whitespace) {
+            let kind = IssueKind::TrailingWhitespace;
+            issues.push(Issue { line: number, release: release_at(number), kind });
+        }
+    }
+    for (index, release) in document.releases.iter().enumerate() {
+        for change in release.changes() {
+            let issue = |line, kind| Issue { line, release: Some(index), kind };
+            if change.mark != '-' {
+                issues.push(issue(change.line, IssueKind::BulletMarker { mark: change.mark }));
+            }
+            let continuations = change.lines.iter().enumerate().skip(1);
+            for (offset, line) in continuations {
+                let indent = line.len() - line.trim_start().len();
+                if !line.trim().is_empty() && indent < CONTINUATION_INDENT.len() {
+                    issues.push(issue(change.line + offset, IssueKind::ContinuationIndent));
+                }
+            }
+        }
+    }
+}
+
+/// The repository name and the number of the pull request that the URL points to.
+pub fn pull_request(url: &str) -> Option<(&str, u64)> {
+    let captures = PULL_REQUEST_URL.captures(url)?;
+    let repository = captures.get(1)?.as_str();
+    let number = captures[2].parse().ok()?;
+    Some((repository, number))
+}
+
+/// The pull requests linked from the entry.
+fn linked_pull_requests<'a>(document: &'a Document, change: &Change) -> BTreeSet<(&'a str, u64)> {
+    change
+        .references()
+        .iter()
+        .filter_map(|label| document.link(label))
+        .filter_map(|link| pull_request(&link.url))
+        .collect()
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_changelog_passes() {
+        let text = r#"# Next Release
+
+#### Visual Environment
+
+- [Nodes can be added by double-clicking the output ports of
+  existing nodes.][3346]
+
+[3346]: https://github.com/enso-org/enso/pull/3346
+
+# Enso 2.0.0-alpha.18 (2021-10-12)
+
+#### Enso Compiler
+
+- [Fixed `[1, 2]` vector literals.][1776]
+
+[1776]: https://github.com/enso-org/ide/pull/1776
+"#;
+        assert_eq!(lint(text), vec![]);
+    }
+
+    #[test]
+    fn issues_are_reported() {
+        let text = "# Next Release
+
+- [Uncategorized entry.][3000]
+
+#### Visual Environment
+
+* [Wrong marker.][3001]
+- [Wrong
+indentation.][3002]
+- No reference at all.
+- [Undefined reference.][3003]
+
+[3000]: https://github.com/enso-org/enso/pull/3000
+[3001]: https://github.com/enso-org/enso/pull/3001
+[3002]: https://github.com/enso-org/enso/pull/3003
+[3000]: https://github.com/enso-org/enso/pull/3999
+
+# Enso 2.0.0 (2022-01-01)
+
+#### Unknown Things
+
+- [Released again.][3001]
+
+[3001]: https://github.com/enso-org/enso/pull/3001
+";
+        let issues = lint(text).into_iter().map(|issue| (issue.line, issue.kind)).collect_vec();
+        let expected = vec![
+            (3, IssueKind::Uncategorized),
+            (7, IssueKind::BulletMarker { mark: '*' }),
+            (9, IssueKind::ContinuationIndent),
+            (10, IssueKind::NoReference),
+            (11, IssueKind::UndefinedReference { label: "3003".into() }),
+            (15, IssueKind::PullRequestMismatch {
+                label: "3002".into(),
+                url:   "https://github.com/enso-org/enso/pull/3003".into(),
+            }),
+            (16, IssueKind::ConflictingDefinition { label: "3000".into(), first_line: 13 }),
+            (20, IssueKind::UnknownCategory { name: "Unknown Things".into() }),
+            (22, IssueKind::DuplicatePullRequest {
+                repository: "enso".into(),
+                number:     3001,
+                first_line: 7,
+            }),
+        ];
+        assert_eq!(issues, expected);
+    }
+}
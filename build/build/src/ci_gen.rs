@@ -103,6 +103,22 @@ pub mod secret {
     // === Github Token ===
     /// A token created for the `enso-ci` user.
     pub const CI_PRIVATE_TOKEN: &str = "CI_PRIVATE_TOKEN";
+
+    /// All the secrets above, used to validate the generated workflows.
+    pub const ALL: [&str; 12] = [
+        ARTEFACT_S3_ACCESS_KEY_ID,
+        ARTEFACT_S3_SECRET_ACCESS_KEY,
+        ECR_PUSH_RUNTIME_SECRET_ACCESS_KEY,
+        ECR_PUSH_RUNTIME_ACCESS_KEY_ID,
+        ENSO_ADMIN_TOKEN,
+        APPLE_CODE_SIGNING_CERT,
+        APPLE_CODE_SIGNING_CERT_PASSWORD,
+        APPLE_NOTARIZATION_USERNAME,
+        APPLE_NOTARIZATION_PASSWORD,
+        WINDOWS_CERT_PATH,
+        WINDOWS_CERT_PASSWORD,
+        CI_PRIVATE_TOKEN,
+    ];
 }
 
 pub fn release_concurrency() -> Concurrency {
@@ -0,0 +1,456 @@
+//! Diagnosing the environment of the build script.
+//!
+//! The doctor looks up the external programs used by the build, compares their versions with the
+//! requirements declared in the build configuration and code, and checks the environment variables
+//! read by the build script. It does not need network access and does not install anything.
+
+use crate::prelude::*;
+
+use crate::config::Config;
+use crate::config::RecognizedProgram;
+
+use ide_ci::cache::goodie::binaryen::Binaryen;
+use ide_ci::cache::goodie::graalvm;
+use ide_ci::env::accessor::TypedVariable;
+use ide_ci::program::version::IsVersionPredicate;
+use ide_ci::programs;
+use ide_ci::programs::rustup::Rustup;
+use ide_ci::programs::wasm_opt::WasmOpt;
+use semver::VersionReq;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The file with the Rust toolchain used by the repository, relative to the repository root.
+pub const RUST_TOOLCHAIN_FILE: &str = "rust-toolchain.toml";
+
+/// The SBT build definition, relative to the repository root.
+pub const BUILD_SBT_FILE: &str = "build.sbt";
+
+/// The file with the Node.js version used by the repository, relative to the repository root.
+pub const NODE_VERSION_FILE: &str = ".node-version";
+
+/// Placeholder for the programs without any version requirement.
+const ANY_VERSION: Option<VersionReq> = None;
+
+
+
+// ==============
+// === Status ===
+// ==============
+
+/// Whether the build can proceed without the checked item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Necessity {
+    /// The build fails without it.
+    Required,
+    /// Needed only by some targets, or installed by the build script when missing.
+    Optional,
+}
+
+/// The outcome of checking a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    Ok,
+    Missing,
+    /// The program is present, but its version could not be determined.
+    UnknownVersion,
+    /// The program's version does not match the requirement.
+    WrongVersion,
+}
+
+
+
+// ====================
+// === ProgramCheck ===
+// ====================
+
+/// The result of checking a single program.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProgramCheck {
+    pub name:        String,
+    pub necessity:   Necessity,
+    /// The version requirement, if the build has one.
+    pub requirement: Option<String>,
+    /// Where the program was found.
+    pub path:        Option<PathBuf>,
+    pub version:     Option<String>,
+    pub status:      Status,
+    /// Error encountered when getting the version.
+    pub error:       Option<String>,
+    /// How to fix the problems with this program.
+    pub hint:        String,
+}
+
+impl ProgramCheck {
+    fn new(name: impl Into<String>, necessity: Necessity, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            necessity,
+            requirement: None,
+            path: None,
+            version: None,
+            status: Status::Missing,
+            error: None,
+            hint: hint.into(),
+        }
+    }
+
+    /// Record the found version and whether it matches the requirement.
+    fn record(&mut self, version: Result<(String, bool)>) {
+        match version {
+            Ok((version, matches)) => {
+                self.version = Some(version);
+                self.status = if matches { Status::Ok } else { Status::WrongVersion };
+            }
+            Err(error) => {
+                self.status =
+                    if self.path.is_some() { Status::UnknownVersion } else { Status::Missing };
+                self.error = Some(format!("{error:#}"));
+            }
+        }
+    }
+
+    /// Whether the build will fail because of this program.
+    pub fn is_problem(&self) -> bool {
+        self.necessity == Necessity::Required && self.status != Status::Ok
+    }
+}
+
+/// Check the presence of the program and whether its version matches the requirement.
+async fn check_program<P, R>(
+    program: P,
+    requirement: Option<R>,
+    necessity: Necessity,
+    hint: impl Into<String>,
+) -> ProgramCheck
+where
+    P: Program,
+    R: IsVersionPredicate<Version = P::Version>,
+{
+    let mut check = ProgramCheck::new(program.executable_name(), necessity, hint);
+    check.requirement = requirement.as_ref().map(ToString::to_string);
+    check.path = program.lookup().ok().map(|location| location.executable_path);
+    let version = program.version().await.map(|version| {
+        let matches = requirement.map_or(true, |requirement| requirement.matches(&version));
+        (version.to_string(), matches)
+    });
+    check.record(version);
+    check
+}
+
+/// Check the program required by the `build-config.yaml` file.
+async fn check_configured_program(
+    program: RecognizedProgram,
+    requirement: VersionReq,
+) -> ProgramCheck {
+    let name = program.to_string();
+    let hint = match name.strip_prefix("cargo-") {
+        Some(_) => format!("Run `cargo install {name} --version '{requirement}'`."),
+        None => format!("Install `{name}` in a version matching `{requirement}`."),
+    };
+    let mut check = ProgramCheck::new(&name, Necessity::Required, hint);
+    check.requirement = Some(requirement.to_string());
+    check.path = ide_ci::program::lookup(&name).ok();
+    let version = program.version().await.map(|version| {
+        let matches = requirement.matches(&version);
+        (version.to_string(), matches)
+    });
+    check.record(version);
+    check
+}
+
+/// Check that the GraalVM used by the engine build is the default Java.
+async fn check_graal(graal_version: Version) -> ProgramCheck {
+    let hint = format!(
+        "The build script installs GraalVM CE {graal_version} when building the engine. To use \
+        a system-wide installation instead, put its `bin` directory on `PATH`."
+    );
+    let mut check = ProgramCheck::new("java (GraalVM CE)", Necessity::Optional, hint);
+    let requirement =
+        format!("={}.{}.{}", graal_version.major, graal_version.minor, graal_version.patch);
+    check.requirement = Some(requirement.clone());
+    check.path = programs::Java.lookup().ok().map(|location| location.executable_path);
+    let version = programs::Java.version_string().await.and_then(|text| {
+        let version = graalvm::graal_version_from_version_string(&text)?;
+        let matches = VersionReq::parse(&requirement)?.matches(&version);
+        Ok((version.to_string(), matches))
+    });
+    check.record(version);
+    check
+}
+
+/// Check that the toolchain from `rust-toolchain.toml` is installed.
+async fn check_rust_toolchain(channel: String) -> ProgramCheck {
+    let hint = format!(
+        "Run `rustup show` in the repository root to install the `{channel}` toolchain from \
+        `{RUST_TOOLCHAIN_FILE}`."
+    );
+    let mut check = ProgramCheck::new("rust-toolchain", Necessity::Required, hint);
+    check.requirement = Some(channel.clone());
+    check.path = Rustup.lookup().ok().map(|location| location.executable_path);
+    let toolchains = async { Rustup.cmd()?.args(["toolchain", "list"]).run_stdout().await };
+    let version = toolchains.await.map(|list| {
+        let installed = list.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+        let installed = installed.collect_vec();
+        let matches = installed.iter().any(|toolchain| toolchain.starts_with(&channel));
+        (installed.join(", "), matches)
+    });
+    check.record(version);
+    check
+}
+
+/// Read the toolchain channel from the `rust-toolchain.toml` file.
+pub fn rust_toolchain_channel(repo_root: &Path) -> Result<String> {
+    let path = repo_root.join(RUST_TOOLCHAIN_FILE);
+    let toolchain: toml::Value = ide_ci::fs::read_to_string(&path)?.parse()?;
+    let channel = toolchain.get("toolchain").and_then(|toolchain| toolchain.get("channel"));
+    let channel = channel.and_then(|channel| channel.as_str());
+    Ok(channel.with_context(|| format!("No toolchain channel in {}.", path.display()))?.into())
+}
+
+
+
+// =====================
+// === VariableCheck ===
+// =====================
+
+/// The outcome of checking an environment variable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VariableStatus {
+    Unset,
+    Valid,
+    /// The value cannot be parsed as the type expected by the build script.
+    Invalid,
+}
+
+/// The result of checking an environment variable. The value is not included, as it might be a
+/// secret.
+#[derive(Clone, Debug, Serialize)]
+pub struct VariableCheck {
+    pub name:   String,
+    pub status: VariableStatus,
+    pub error:  Option<String>,
+    /// What the variable is used for.
+    pub hint:   String,
+}
+
+impl VariableCheck {
+    fn new(variable: &impl TypedVariable, hint: impl Into<String>) -> Self {
+        let (status, error) = if !variable.is_set() {
+            (VariableStatus::Unset, None)
+        } else {
+            match variable.get() {
+                Ok(_) => (VariableStatus::Valid, None),
+                Err(error) => (VariableStatus::Invalid, Some(format!("{error:#}"))),
+            }
+        };
+        Self { name: variable.name().into(), status, error, hint: hint.into() }
+    }
+
+    /// Whether the build will fail because of this variable.
+    pub fn is_problem(&self) -> bool {
+        self.status == VariableStatus::Invalid
+    }
+}
+
+
+
+// ==============
+// === Report ===
+// ==============
+
+/// The state of the build environment.
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub os:        String,
+    pub arch:      String,
+    pub programs:  Vec<ProgramCheck>,
+    pub variables: Vec<VariableCheck>,
+}
+
+impl Report {
+    /// Names of the programs and variables that will make the build fail.
+    pub fn problems(&self) -> Vec<&str> {
+        let programs = self.programs.iter().filter(|check| check.is_problem());
+        let variables = self.variables.iter().filter(|check| check.is_problem());
+        let programs = programs.map(|check| check.name.as_str());
+        programs.chain(variables.map(|check| check.name.as_str())).collect()
+    }
+
+    /// Log the hints for all the programs and variables that are not in order.
+    pub fn log_hints(&self) {
+        for check in self.programs.iter().filter(|check| check.status != Status::Ok) {
+            let ProgramCheck { name, status, hint, .. } = check;
+            match check.necessity {
+                Necessity::Required => error!("{name}: {status:?}. {hint}"),
+                Necessity::Optional => warn!("{name}: {status:?}. {hint}"),
+            }
+        }
+        for check in self.variables.iter().filter(|check| check.is_problem()) {
+            let VariableCheck { name, error, hint, .. } = check;
+            error!("{name} has an invalid value: {}. {hint}", error.as_deref().unwrap_or_default());
+        }
+    }
+}
+
+/// Check the programs and environment variables used by the build script.
+#[context("Failed to diagnose the build environment.")]
+pub async fn diagnose(repo_root: &Path, config: &Config) -> Result<Report> {
+    use Necessity::*;
+    let build_sbt = ide_ci::fs::tokio::read_to_string(repo_root.join(BUILD_SBT_FILE)).await?;
+    let graal_version = crate::get_graal_version(&build_sbt)?;
+    let channel = rust_toolchain_channel(repo_root)?;
+    let node_version = ide_ci::fs::tokio::read_to_string(repo_root.join(NODE_VERSION_FILE)).await?;
+    // The configuration requires the same Node.js version, so it is not checked again below.
+    let node_requirement = VersionReq::parse(node_version.trim())?;
+    // The configured wasm-pack requirement is merged with the one of the build script, so the
+    // program is reported once.
+    let mut wasm_pack_requirement =
+        VersionReq::parse(crate::project::wasm::WASM_PACK_VERSION_REQUIREMENT)?;
+    let configured_wasm_pack = RecognizedProgram::Other("wasm-pack".into());
+    if let Some(requirement) = config.required_versions.get(&configured_wasm_pack) {
+        wasm_pack_requirement.comparators.extend(requirement.comparators.iter().cloned());
+    }
+    let flatc_requirement = VersionReq::parse(&format!("={}", crate::engine::FLATC_VERSION))?;
+    let binaryen = Binaryen { version: crate::project::wasm::BINARYEN_VERSION_TO_INSTALL };
+
+    let mut checks = vec![
+        check_program(programs::Git, ANY_VERSION, Required, "Install git.").boxed(),
+        check_program(programs::Cargo, ANY_VERSION, Required, "Install Rust using rustup.").boxed(),
+        check_rust_toolchain(channel).boxed(),
+        check_program(
+            programs::Node,
+            Some(node_requirement),
+            Required,
+            format!("Install the Node.js version given in `{NODE_VERSION_FILE}`."),
+        )
+        .boxed(),
+        check_program(programs::Npm, ANY_VERSION, Required, "Install npm with Node.js.").boxed(),
+        check_program(
+            programs::WasmPack,
+            Some(wasm_pack_requirement),
+            Required,
+            "Run `cargo install wasm-pack`.",
+        )
+        .boxed(),
+        check_program(
+            WasmOpt,
+            Some(binaryen),
+            Optional,
+            "The build script installs binaryen when building the WASM.",
+        )
+        .boxed(),
+        check_program(
+            programs::Sbt,
+            ANY_VERSION,
+            Optional,
+            "The build script installs sbt when building the engine.",
+        )
+        .boxed(),
+        check_graal(graal_version).boxed(),
+        check_program(
+            programs::Flatc,
+            Some(flatc_requirement),
+            Optional,
+            "Install the FlatBuffers compiler, or conda for the build script to install it.",
+        )
+        .boxed(),
+        check_program(
+            programs::Conda,
+            ANY_VERSION,
+            Optional,
+            "Needed only to install the FlatBuffers compiler when it is missing.",
+        )
+        .boxed(),
+    ];
+    // These programs are checked above.
+    let checked = ["node", "wasm-pack"];
+    let mut configured = config.required_versions.iter().collect_vec();
+    configured.retain(|(program, _)| !checked.contains(&program.to_string().as_str()));
+    configured.sort_by_key(|(program, _)| program.to_string());
+    for (program, requirement) in configured {
+        checks.push(check_configured_program(program.clone(), requirement.clone()).boxed());
+    }
+    let programs = futures::future::join_all(checks).await;
+
+    let variables = vec![
+        VariableCheck::new(
+            &ide_ci::github::GITHUB_TOKEN,
+            "Used to access GitHub, e.g. to download CI artifacts. Can also be stored in the \
+            `~/GITHUB_TOKEN` file.",
+        ),
+        VariableCheck::new(
+            &crate::env::ENSO_RELEASE_ID,
+            "Identifies the release being built. Set by the release workflow.",
+        ),
+        VariableCheck::new(
+            &crate::env::ENSO_RUNNER_CONTAINER_NAME,
+            "Set when the build runs in a container.",
+        ),
+        VariableCheck::new(
+            &crate::env::ENSO_NIGHTLY_EDITIONS_LIMIT,
+            "Limits the number of nightly editions kept.",
+        ),
+        VariableCheck::new(
+            &crate::env::ENSO_ADMIN_TOKEN,
+            "Authenticates the admin requests to the cloud. Needed only by the release targets.",
+        ),
+    ];
+
+    Ok(Report { os: TARGET_OS.to_string(), arch: TARGET_ARCH.to_string(), programs, variables })
+}
+
+/// Print the report as JSON to the standard output. Fails if the build cannot proceed in the
+/// current environment.
+pub async fn run(repo_root: &Path, config: &Config) -> Result {
+    let report = diagnose(repo_root, config).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    report.log_hints();
+    let problems = report.problems();
+    ensure!(problems.is_empty(), "The build environment is not ready: {}.", problems.join(", "));
+    Ok(())
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_include_only_required_programs_and_invalid_variables() {
+        let mut required = ProgramCheck::new("git", Necessity::Required, "");
+        required.record(Ok(("2.39.0".into(), true)));
+        let mut optional = ProgramCheck::new("sbt", Necessity::Optional, "");
+        optional.record(Err(anyhow!("Not found.")));
+        let mut outdated = ProgramCheck::new("wasm-pack", Necessity::Required, "");
+        outdated.path = Some("/usr/bin/wasm-pack".into());
+        outdated.record(Ok(("0.9.0".into(), false)));
+        assert_eq!(optional.status, Status::Missing);
+        assert_eq!(outdated.status, Status::WrongVersion);
+        let variable = |status| VariableCheck {
+            name: format!("{status:?}"),
+            status,
+            error: None,
+            hint: default(),
+        };
+        let report = Report {
+            os:        "linux".into(),
+            arch:      "x86_64".into(),
+            programs:  vec![required, optional, outdated],
+            variables: vec![variable(VariableStatus::Unset), variable(VariableStatus::Invalid)],
+        };
+        assert_eq!(report.problems(), vec!["wasm-pack", "Invalid"]);
+    }
+}
@@ -31,7 +31,7 @@ pub use context::RunContext;
 
 
 /// Version of `flatc` (the FlatBuffers compiler) that we require.
-const FLATC_VERSION: Version = Version::new(1, 12, 0);
+pub const FLATC_VERSION: Version = Version::new(1, 12, 0);
 
 /// Whether pure Enso tests should be run in parallel.
 const PARALLEL_ENSO_TESTS: AsyncPolicy = AsyncPolicy::Sequential;
@@ -40,6 +40,10 @@ pub mod test;
 
 pub const BINARYEN_VERSION_TO_INSTALL: u32 = 108;
 
+/// Old `wasm-pack` does not pass trailing `build` command arguments to the Cargo. We want to be
+/// able to pass `--profile` this way.
+pub const WASM_PACK_VERSION_REQUIREMENT: &str = ">=0.10.1";
+
 pub const DEFAULT_INTEGRATION_TESTS_WASM_TIMEOUT: Duration = Duration::from_secs(300);
 
 pub const INTEGRATION_TESTS_CRATE_NAME: &str = "enso-integration-test";
@@ -216,9 +220,9 @@ impl IsTarget for Wasm {
             cargo_opts = ?inner.extra_cargo_options
         );
         async move {
-            // Old wasm-pack does not pass trailing `build` command arguments to the Cargo.
-            // We want to be able to pass --profile this way.
-            WasmPack.require_present_that(VersionReq::parse(">=0.10.1")?).await?;
+            WasmPack
+                .require_present_that(VersionReq::parse(WASM_PACK_VERSION_REQUIREMENT)?)
+                .await?;
 
             let BuildInput {
                 crate_path,
@@ -2,7 +2,7 @@
 
 use crate::prelude::*;
 
-use crate::changelog::Changelog;
+use crate::changelog::document::Document;
 use crate::context::BuildContext;
 use crate::env::ENSO_ADMIN_TOKEN;
 use crate::paths::generated;
@@ -71,8 +71,9 @@ pub fn release_body_placeholders(
 
     // Generate the release notes.
     let changelog_contents = ide_ci::fs::read_to_string(&context.repo_root.changelog_md)?;
-    let latest_changelog_body = Changelog(&changelog_contents).top_release_notes()?;
-    ret.insert("changelog", latest_changelog_body.contents.into());
+    let changelog = Document::parse(&changelog_contents);
+    let release_notes = changelog.release_notes(&context.triple.versions.version)?;
+    ret.insert("changelog", release_notes.into());
     Ok(ret)
 }
 
@@ -9,6 +9,7 @@ use crate::prelude::*;
 use enso_build::paths::generated::RepoRootGithub;
 use enso_build::repo::deduce_repository_path;
 use ide_ci::actions::workflow::definition::WorkflowToWrite;
+use ide_ci::actions::workflow::validation;
 
 
 
@@ -17,6 +18,10 @@ pub mod prelude {
     pub use enso_build_shader_tools::prelude::*;
 }
 
+/// Command line flag that makes the generator only check that the workflow files are up to date,
+/// without writing them.
+const CHECK_FLAG: &str = "--check";
+
 /// Generate the comment that is at the top of each generated workflow file.
 fn preamble(source: &str) -> String {
     format!(
@@ -34,11 +39,39 @@ async fn main() -> Result {
     let mut workflows = enso_build::ci_gen::generate(&workflows_dir)?;
     workflows.push(enso_build_shader_tools::ci::generate_workflow(&workflows_dir.shader_tools_yml));
 
+    let check_only = std::env::args().skip(1).any(|arg| arg == CHECK_FLAG);
+    let mut outdated = vec![];
     for WorkflowToWrite { source, path, workflow } in workflows {
-        let preamble = preamble(&source);
-        let yaml = serde_yaml::to_string(&workflow)?;
-        let contents = format!("{preamble}\n\n{yaml}");
-        ide_ci::fs::tokio::write(path, contents).await?;
+        validation::validate(&workflow, enso_build::ci_gen::secret::ALL)
+            .with_context(|| format!("Generated workflow {} is invalid.", path.display()))?;
+        let differences = validation::compare_with_file(&workflow, &path)?;
+        if check_only {
+            if !differences.is_empty() {
+                error!("Workflow file {} is not up to date:", path.display());
+                for difference in &differences {
+                    error!("  {difference}");
+                }
+                outdated.push(path);
+            }
+        } else {
+            if !differences.is_empty() {
+                info!("Updating {} ({} changes).", path.display(), differences.len());
+            }
+            let preamble = preamble(&source);
+            let yaml = serde_yaml::to_string(&workflow)?;
+            let contents = format!("{preamble}\n\n{yaml}");
+            ide_ci::fs::tokio::write(path, contents).await?;
+        }
+    }
+
+    if check_only {
+        ensure!(
+            outdated.is_empty(),
+            "{} workflow files are outdated. Run `cargo run --package {}` to regenerate them.",
+            outdated.len(),
+            env!("CARGO_PKG_NAME")
+        );
+        return Ok(());
     }
 
     warn!("Remember to run formatter on the generated files!");
@@ -9,6 +9,7 @@ use crate::actions::env_file;
 // ==============
 
 pub mod definition;
+pub mod validation;
 
 
 
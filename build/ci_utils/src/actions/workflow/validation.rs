@@ -0,0 +1,474 @@
+//! Offline validation of the workflow definitions.
+//!
+//! GitHub reports most of the mistakes in the workflow definitions only after the workflow is
+//! pushed, often only when the affected job is about to run. The checks here catch the common ones
+//! before the workflow file is written.
+
+use crate::prelude::*;
+
+use crate::actions::workflow::definition::Job;
+use crate::actions::workflow::definition::RunnerLabel;
+use crate::actions::workflow::definition::Workflow;
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The secret that GitHub provides to every workflow run, so it does not need to be declared.
+pub const GITHUB_TOKEN_SECRET: &str = "GITHUB_TOKEN";
+
+/// Matches the `${{ <expression> }}` syntax, capturing the expression.
+static EXPRESSION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\{\{(.*?)\}\}").unwrap());
+
+/// Matches a reference to a property of the `inputs`, `secrets` or `needs` context within an
+/// expression, capturing the context and property names.
+static CONTEXT_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^\w.])(inputs|secrets|needs)\.([\w-]+)").unwrap());
+
+
+
+// =============
+// === Issue ===
+// =============
+
+/// A single problem found in a workflow definition.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Issue {
+    /// A job needs a job that is not defined in the workflow.
+    UnknownNeededJob { job: String, needed: String },
+    /// Jobs depend on each other in a cycle. The first job is repeated at the end.
+    DependencyCycle(Vec<String>),
+    /// An expression uses outputs of a job that is not among the job's `needs`.
+    UndeclaredJobReference { location: String, job: String },
+    /// An expression uses an input that is not defined by all the triggers providing inputs.
+    UndefinedInput { location: String, input: String },
+    /// An expression uses a secret that is not known to be set up.
+    UnknownSecret { location: String, secret: String },
+    /// A job definition is inconsistent, e.g. has invalid `runs-on` labels.
+    InvalidJob { job: String, reason: String },
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::UnknownNeededJob { job, needed } =>
+                write!(f, "Job `{job}` needs job `{needed}`, which is not defined."),
+            Issue::DependencyCycle(jobs) =>
+                write!(f, "Jobs depend on each other in a cycle: {}.", jobs.join(" -> ")),
+            Issue::UndeclaredJobReference { location, job } =>
+                write!(f, "{location}: uses `needs.{job}`, but `{job}` is not among the needs."),
+            Issue::UndefinedInput { location, input } => write!(
+                f,
+                "{location}: uses `inputs.{input}`, which is not defined by all the triggers \
+                providing inputs."
+            ),
+            Issue::UnknownSecret { location, secret } =>
+                write!(f, "{location}: uses `secrets.{secret}`, which is not a known secret."),
+            Issue::InvalidJob { job, reason } => write!(f, "Job `{job}`: {reason}."),
+        }
+    }
+}
+
+
+
+// ==================
+// === Validation ===
+// ==================
+
+/// Check the workflow and fail with the description of all found issues.
+///
+/// `known_secrets` are the secrets set up in the repository or organization. The
+/// [`GITHUB_TOKEN_SECRET`] and the secrets declared by the `workflow_call` trigger are always
+/// considered known.
+pub fn validate(workflow: &Workflow, known_secrets: impl IntoIterator<Item: AsRef<str>>) -> Result {
+    let issues = issues(workflow, known_secrets)?;
+    ensure!(
+        issues.is_empty(),
+        "Workflow `{}` is invalid:\n{}",
+        workflow.name,
+        issues.iter().map(|issue| format!(" * {issue}")).join("\n")
+    );
+    Ok(())
+}
+
+/// Find all the issues in the workflow. See [`validate`].
+pub fn issues(
+    workflow: &Workflow,
+    known_secrets: impl IntoIterator<Item: AsRef<str>>,
+) -> Result<Vec<Issue>> {
+    let mut known_secrets: BTreeSet<String> =
+        known_secrets.into_iter().map(|secret| secret.as_ref().to_string()).collect();
+    known_secrets.insert(GITHUB_TOKEN_SECRET.into());
+    if let Some(call) = &workflow.on.workflow_call {
+        known_secrets.extend(call.secrets.keys().cloned());
+    }
+    let input_sets = [
+        workflow.on.workflow_dispatch.as_ref().map(|dispatch| input_names(&dispatch.inputs)),
+        workflow.on.workflow_call.as_ref().map(|call| input_names(&call.inputs)),
+    ];
+    let input_sets = input_sets.into_iter().flatten().collect_vec();
+    let is_input_defined = |input: &str| {
+        !input_sets.is_empty() && input_sets.iter().all(|inputs| inputs.contains(input))
+    };
+
+    let mut issues = BTreeSet::new();
+    for (job_id, job) in &workflow.jobs {
+        for needed in &job.needs {
+            if !workflow.jobs.contains_key(needed) {
+                let job = job_id.clone();
+                issues.insert(Issue::UnknownNeededJob { job, needed: needed.clone() });
+            }
+        }
+        issues.extend(job_issues(job_id, job));
+    }
+    issues.extend(dependency_cycles(workflow).into_iter().map(Issue::DependencyCycle));
+
+    let mut references = Vec::new();
+    let mut workflow_value = serde_json::to_value(workflow)?;
+    // Jobs are visited separately, as the `needs` context is valid only within them.
+    let jobs = workflow_value.get_mut("jobs").map(serde_json::Value::take);
+    collect_references(&workflow_value, "workflow".into(), None, &mut references);
+    for (job_id, job) in jobs.iter().flat_map(|jobs| jobs.as_object()).flatten() {
+        let location = format!("jobs.{job_id}");
+        collect_references(job, location, Some(&workflow.jobs[job_id]), &mut references);
+    }
+    for Reference { location, context, name, job } in references {
+        match context.as_str() {
+            "inputs" if !is_input_defined(&name) =>
+                issues.insert(Issue::UndefinedInput { location, input: name }),
+            "secrets" if !known_secrets.contains(&name) =>
+                issues.insert(Issue::UnknownSecret { location, secret: name }),
+            "needs" if !job.is_some_and(|job| job.needs.contains(&name)) =>
+                issues.insert(Issue::UndeclaredJobReference { location, job: name }),
+            _ => false,
+        };
+    }
+    Ok(issues.into_iter().collect())
+}
+
+fn input_names<T>(inputs: &BTreeMap<String, T>) -> BTreeSet<&str> {
+    inputs.keys().map(String::as_str).collect()
+}
+
+/// Check the consistency of a single job definition.
+fn job_issues(job_id: &str, job: &Job) -> Vec<Issue> {
+    let mut reasons = Vec::new();
+    if job.uses.is_some() {
+        if !job.runs_on.is_empty() || !job.steps.is_empty() {
+            reasons.push("a job calling a reusable workflow cannot have `runs-on` or `steps`");
+        }
+    } else {
+        if job.runs_on.is_empty() {
+            reasons.push("no `runs-on` labels");
+        }
+        if job.steps.is_empty() {
+            reasons.push("no steps");
+        }
+    }
+    let github_hosted = job.runs_on.iter().filter(|label| is_github_hosted(**label)).count();
+    if github_hosted > 0 && job.runs_on.len() > 1 {
+        reasons.push("a GitHub-hosted runner label cannot be combined with other labels");
+    }
+    let uses_matrix_os = job.runs_on.iter().any(|label| matches!(label, RunnerLabel::MatrixOs));
+    let has_matrix_os =
+        job.strategy.as_ref().is_some_and(|strategy| strategy.matrix.contains_key("os"));
+    if uses_matrix_os && !has_matrix_os {
+        reasons.push("`runs-on` uses `matrix.os`, but the strategy matrix does not define it");
+    }
+    let job = job_id.to_string();
+    reasons
+        .into_iter()
+        .map(|reason| Issue::InvalidJob { job: job.clone(), reason: reason.into() })
+        .collect()
+}
+
+/// Check if the label denotes a GitHub-hosted runner, which must be requested by a single label.
+fn is_github_hosted(label: RunnerLabel) -> bool {
+    matches!(
+        label,
+        RunnerLabel::MacOSLatest | RunnerLabel::LinuxLatest | RunnerLabel::WindowsLatest
+    )
+}
+
+/// Find the cycles in the job dependency graph. Each cycle starts and ends with the same job.
+fn dependency_cycles(workflow: &Workflow) -> Vec<Vec<String>> {
+    fn visit<'a>(
+        workflow: &'a Workflow,
+        job_id: &'a String,
+        stack: &mut Vec<&'a String>,
+        done: &mut BTreeSet<&'a String>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(position) = stack.iter().position(|id| *id == job_id) {
+            let cycle = stack[position..].iter().chain([&job_id]).map(|id| id.to_string());
+            cycles.push(cycle.collect());
+            return;
+        }
+        if !done.insert(job_id) {
+            return;
+        }
+        stack.push(job_id);
+        let needs = workflow.jobs.get(job_id).into_iter().flat_map(|job| &job.needs);
+        for needed in needs {
+            visit(workflow, needed, stack, done, cycles);
+        }
+        stack.pop();
+    }
+
+    let mut cycles = Vec::new();
+    let mut done = BTreeSet::new();
+    for job_id in workflow.jobs.keys() {
+        visit(workflow, job_id, &mut Vec::new(), &mut done, &mut cycles);
+    }
+    cycles
+}
+
+/// A use of a context property within an expression.
+#[derive(Clone, Debug)]
+struct Reference<'a> {
+    location: String,
+    context:  String,
+    name:     String,
+    /// The job containing the expression, if any.
+    job:      Option<&'a Job>,
+}
+
+/// Find all the context references in the expressions in the serialized workflow part.
+///
+/// The `if` conditions are expressions even without the `${{ }}` syntax.
+fn collect_references<'a>(
+    value: &serde_json::Value,
+    location: String,
+    job: Option<&'a Job>,
+    out: &mut Vec<Reference<'a>>,
+) {
+    match value {
+        serde_json::Value::String(text) => {
+            let expressions = if location.ends_with(".if") && !text.contains("${{") {
+                vec![text.as_str()]
+            } else {
+                EXPRESSION
+                    .captures_iter(text)
+                    .filter_map(|c| c.get(1))
+                    .map(|m| m.as_str())
+                    .collect()
+            };
+            for expression in expressions {
+                for captures in CONTEXT_REFERENCE.captures_iter(expression) {
+                    let location = location.clone();
+                    let context = captures[1].to_string();
+                    let name = captures[2].to_string();
+                    out.push(Reference { location, context, name, job });
+                }
+            }
+        }
+        serde_json::Value::Array(items) =>
+            for (index, item) in items.iter().enumerate() {
+                collect_references(item, format!("{location}[{index}]"), job, out);
+            },
+        serde_json::Value::Object(fields) =>
+            for (key, field) in fields {
+                collect_references(field, format!("{location}.{key}"), job, out);
+            },
+        _ => {}
+    }
+}
+
+
+
+// ==================
+// === Comparison ===
+// ==================
+
+/// A difference between the committed and the freshly generated workflow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference {
+    /// Path to the differing value within the workflow.
+    pub location:  String,
+    /// The committed value, or `None` if it is missing.
+    pub committed: Option<String>,
+    /// The generated value, or `None` if it is missing.
+    pub generated: Option<String>,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let describe = |value: &Option<String>| value.clone().unwrap_or_else(|| "missing".into());
+        let committed = describe(&self.committed);
+        let generated = describe(&self.generated);
+        write!(f, "{}: committed {committed}, generated {generated}", self.location)
+    }
+}
+
+/// Compare the workflow with the one stored in the given YAML file.
+///
+/// The files are compared by their contents, not text, so formatting changes (like the ones
+/// applied by the formatter to the generated files) are ignored. If the file does not exist, the
+/// whole workflow is reported as a difference.
+pub fn compare_with_file(workflow: &Workflow, path: impl AsRef<Path>) -> Result<Vec<Difference>> {
+    let path = path.as_ref();
+    let generated = serde_yaml::to_value(workflow)?;
+    let committed = if path.exists() {
+        let contents = crate::fs::read_to_string(path)?;
+        serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse workflow file {}.", path.display()))?
+    } else {
+        serde_yaml::Value::Null
+    };
+    let mut differences = Vec::new();
+    compare_values("workflow".into(), &committed, &generated, &mut differences);
+    Ok(differences)
+}
+
+fn compare_values(
+    location: String,
+    committed: &serde_yaml::Value,
+    generated: &serde_yaml::Value,
+    out: &mut Vec<Difference>,
+) {
+    use serde_yaml::Value;
+    match (committed, generated) {
+        (Value::Mapping(committed), Value::Mapping(generated)) => {
+            let keys = committed.keys().chain(generated.keys()).unique().collect_vec();
+            for key in keys {
+                let location = format!("{location}.{}", describe_key(key));
+                let committed = committed.get(key).unwrap_or(&Value::Null);
+                let generated = generated.get(key).unwrap_or(&Value::Null);
+                compare_values(location, committed, generated, out);
+            }
+        }
+        (Value::Sequence(committed), Value::Sequence(generated)) => {
+            for index in 0..committed.len().max(generated.len()) {
+                let location = format!("{location}[{index}]");
+                let committed = committed.get(index).unwrap_or(&Value::Null);
+                let generated = generated.get(index).unwrap_or(&Value::Null);
+                compare_values(location, committed, generated, out);
+            }
+        }
+        _ if committed != generated => {
+            let describe = |value: &Value| match value {
+                Value::Null => None,
+                value =>
+                    Some(serde_json::to_string(value).unwrap_or_else(|_| format!("{value:?}"))),
+            };
+            let committed = describe(committed);
+            let generated = describe(generated);
+            out.push(Difference { location, committed, generated });
+        }
+        _ => {}
+    }
+}
+
+fn describe_key(key: &serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(key) => key.clone(),
+        key => serde_json::to_string(key).unwrap_or_else(|_| format!("{key:?}")),
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::workflow::definition::shell;
+    use crate::actions::workflow::definition::wrap_expression;
+    use crate::actions::workflow::definition::WorkflowDispatch;
+    use crate::actions::workflow::definition::WorkflowDispatchInput;
+
+    fn job(name: &str, runs_on: impl IntoIterator<Item = RunnerLabel>) -> Job {
+        let mut job = Job::new(name, runs_on);
+        job.steps.push(shell("echo"));
+        job
+    }
+
+    #[test]
+    fn valid_workflow_passes() -> Result {
+        let mut workflow = Workflow::new("Test");
+        let input = WorkflowDispatchInput::new_string("Version", true, None::<String>);
+        workflow.on.workflow_dispatch(WorkflowDispatch::default().with_input("version", input));
+        workflow.env("VERSION", wrap_expression("inputs.version"));
+        let first = workflow.add_job(job("First", [RunnerLabel::LinuxLatest]));
+        let mut second = job("Second", [RunnerLabel::SelfHosted, RunnerLabel::Linux]);
+        second.expose_secret_as("CI_PRIVATE_TOKEN", "TOKEN");
+        second.r#if = Some(format!("needs.{first}.result == 'success'"));
+        second.needs(first);
+        workflow.add_job(second);
+        validate(&workflow, ["CI_PRIVATE_TOKEN"])
+    }
+
+    #[test]
+    fn issues_are_reported() -> Result {
+        let mut workflow = Workflow::new("Test");
+        let mut first = job("First", [RunnerLabel::LinuxLatest, RunnerLabel::X64]);
+        first.needs("second");
+        first.needs("missing");
+        first.env("VERSION", wrap_expression("inputs.version"));
+        workflow.add_job(first);
+        let mut second = job("Second", [RunnerLabel::MatrixOs]);
+        second.needs("first");
+        second.expose_secret_as("UNKNOWN", "TOKEN");
+        second.env("OUTPUT", wrap_expression("needs.third.outputs.value"));
+        workflow.add_job(second);
+        workflow.add_job(Job { name: "Third".into(), ..default() });
+
+        let issues = issues(&workflow, ["CI_PRIVATE_TOKEN"])?;
+        assert_eq!(issues, vec![
+            Issue::UnknownNeededJob { job: "first".into(), needed: "missing".into() },
+            Issue::DependencyCycle(vec!["first".into(), "second".into(), "first".into()]),
+            Issue::UndeclaredJobReference {
+                location: "jobs.second.env.OUTPUT".into(),
+                job:      "third".into(),
+            },
+            Issue::UndefinedInput {
+                location: "jobs.first.env.VERSION".into(),
+                input:    "version".into(),
+            },
+            Issue::UnknownSecret {
+                location: "jobs.second.env.TOKEN".into(),
+                secret:   "UNKNOWN".into(),
+            },
+            Issue::InvalidJob {
+                job:    "first".into(),
+                reason: "a GitHub-hosted runner label cannot be combined with other labels".into(),
+            },
+            Issue::InvalidJob {
+                job:    "second".into(),
+                reason: "`runs-on` uses `matrix.os`, but the strategy matrix does not define it"
+                    .into(),
+            },
+            Issue::InvalidJob { job: "third".into(), reason: "no `runs-on` labels".into() },
+            Issue::InvalidJob { job: "third".into(), reason: "no steps".into() },
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn comparing_with_file_ignores_formatting() -> Result {
+        let mut workflow = Workflow::new("Test");
+        workflow.add_job(job("First", [RunnerLabel::LinuxLatest]));
+        let temp = tempfile::tempdir()?;
+        let path = temp.path().join("workflow.yml");
+        let yaml = serde_yaml::to_string(&workflow)?;
+        crate::fs::write(&path, format!("# Preamble.\n\n{yaml}\n\n"))?;
+        assert!(compare_with_file(&workflow, &path)?.is_empty());
+
+        workflow.env("NEW_VARIABLE", "value");
+        let differences = compare_with_file(&workflow, &path)?;
+        assert_eq!(differences, vec![Difference {
+            location:  "workflow.env.NEW_VARIABLE".into(),
+            committed: None,
+            generated: Some("\"value\"".into()),
+        }]);
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@ pub mod archive;
 pub mod artifact;
 pub mod asset;
 pub mod download;
+pub mod entry;
 pub mod goodie;
 
 pub use goodie::Goodie;
@@ -74,6 +75,9 @@ pub struct EntryIndexExtended<S: Storable> {
     pub r#type:         Option<String>,
     pub key_type:       Option<String>,
     pub schema_version: Option<u8>,
+    /// When the entry was last retrieved from the cache or generated.
+    #[serde(default)]
+    pub last_used:      Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl<S: Storable> EntryIndexExtended<S> {
@@ -84,6 +88,7 @@ impl<S: Storable> EntryIndexExtended<S> {
             r#type:         Some(std::any::type_name::<S>().into()),
             key_type:       Some(std::any::type_name::<S::Key>().into()),
             schema_version: Some(VERSION),
+            last_used:      Some(chrono::Utc::now()),
         }
     }
 }
@@ -142,7 +147,7 @@ impl Cache {
             let digest = digest(&storable)?;
             tracing::Span::current().record("digest", digest.as_str());
             let entry_dir = this.root.join(&digest);
-            let entry_meta = entry_dir.with_appended_extension("json");
+            let entry_meta = entry::index_path(&entry_dir);
 
             let retrieve = async {
                 let info = entry_meta.read_to_json::<EntryIndexRequired<S>>()?;
@@ -153,6 +158,9 @@ impl Cache {
             match retrieve.await {
                 Ok(out) => {
                     trace!("Found in cache, skipping generation.");
+                    if let Err(e) = entry::mark_used(&entry_meta) {
+                        warn!("Failed to record the use of the cache entry {digest}: {e}");
+                    }
                     Ok(out)
                 }
                 Err(e) => {
@@ -0,0 +1,346 @@
+//! Inspection and maintenance of the cache entries, independent of their
+//! [`Storable`](crate::cache::Storable) types.
+
+use crate::prelude::*;
+
+use crate::cache::Cache;
+use crate::cache::VERSION;
+
+use byte_unit::Byte;
+use chrono::DateTime;
+use chrono::Utc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Name of the entry index field storing the time when the entry was last used.
+pub const LAST_USED_FIELD: &str = "last_used";
+
+/// Extension of the entry index file, which is placed next to the entry directory.
+pub const INDEX_EXTENSION: &str = "json";
+
+/// How long after its last modification an entry without the index file is considered to be still
+/// written by another process, rather than abandoned.
+pub const WRITE_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+
+
+// =================
+// === EntryInfo ===
+// =================
+
+/// The debugging information from the entry index, readable without knowing the entry's
+/// [`Storable`](crate::cache::Storable) type.
+///
+/// See [`crate::cache::EntryIndexExtended`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EntryInfo {
+    pub key:            Option<serde_json::Value>,
+    pub r#type:         Option<String>,
+    pub key_type:       Option<String>,
+    pub schema_version: Option<u8>,
+    pub last_used:      Option<DateTime<Utc>>,
+}
+
+
+
+// ===============
+// === Problem ===
+// ===============
+
+/// Reason why the cache entry is not usable and should be removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// There is an entry directory without the index file.
+    MissingIndex,
+    /// The index file cannot be parsed.
+    InvalidIndex(String),
+    /// There is an index file without the entry directory.
+    MissingDirectory,
+    /// The entry was created with a different hashing scheme than [`VERSION`], so its digest
+    /// will never be requested again.
+    OutdatedSchema(Option<u8>),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingIndex => write!(f, "missing index file"),
+            Problem::InvalidIndex(error) => write!(f, "invalid index file: {error}"),
+            Problem::MissingDirectory => write!(f, "missing entry directory"),
+            Problem::OutdatedSchema(Some(version)) =>
+                write!(f, "schema version {version} differs from the current {VERSION}"),
+            Problem::OutdatedSchema(None) => write!(f, "unknown schema version"),
+        }
+    }
+}
+
+
+
+// =============
+// === Entry ===
+// =============
+
+/// A single cache entry, as found on the disk.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The digest identifying the entry, see [`crate::cache::digest`].
+    pub digest:   String,
+    /// The entry directory. It might not exist.
+    pub path:     PathBuf,
+    /// The entry index contents, or the reason why they cannot be read.
+    pub index:    std::result::Result<EntryInfo, Problem>,
+    /// The total size of the entry directory and the index file.
+    pub size:     Byte,
+    /// The time of the latest modification of the entry directory or any file within it.
+    pub modified: Option<SystemTime>,
+}
+
+impl Entry {
+    /// Read the entry with the given digest from the cache root.
+    pub fn read(root: impl AsRef<Path>, digest: impl Into<String>) -> Result<Self> {
+        let digest = digest.into();
+        let path = root.as_ref().join(&digest);
+        let index_path = index_path(&path);
+        let index = if !index_path.exists() {
+            Err(Problem::MissingIndex)
+        } else {
+            index_path.read_to_json::<EntryInfo>().map_err(|e| Problem::InvalidIndex(e.to_string()))
+        };
+        let size = Byte::from_bytes((size_of(&path)? + size_of(&index_path)?).into());
+        let modified = last_modified(&path)?;
+        Ok(Self { digest, path, index, size, modified })
+    }
+
+    /// Path to the entry index file.
+    pub fn index_path(&self) -> PathBuf {
+        index_path(&self.path)
+    }
+
+    /// The time when the entry was last used, if known.
+    pub fn last_used(&self) -> Option<DateTime<Utc>> {
+        self.index.as_ref().ok().and_then(|info| info.last_used)
+    }
+
+    /// Describe why this entry is not usable. Returns `None` if the entry is valid.
+    pub fn problem(&self) -> Option<Problem> {
+        match &self.index {
+            Err(problem) => Some(problem.clone()),
+            Ok(_) if !self.path.is_dir() => Some(Problem::MissingDirectory),
+            Ok(info) if info.schema_version != Some(VERSION) =>
+                Some(Problem::OutdatedSchema(info.schema_version)),
+            Ok(_) => None,
+        }
+    }
+
+    /// Whether the entry might be still written by another process: its index file has not been
+    /// created yet, and its files were modified within the [`WRITE_GRACE_PERIOD`].
+    pub fn is_being_written(&self) -> bool {
+        let is_recent = |modified: &SystemTime| {
+            modified.elapsed().map_or(true, |elapsed| elapsed < WRITE_GRACE_PERIOD)
+        };
+        matches!(self.index, Err(Problem::MissingIndex))
+            && self.modified.as_ref().map_or(false, is_recent)
+    }
+
+    /// Remove the entry directory and its index file.
+    pub fn remove(&self) -> Result {
+        debug!("Removing cache entry {}.", self.digest);
+        crate::fs::remove_file_if_exists(self.index_path())?;
+        crate::fs::remove_dir_if_exists(&self.path)
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.digest, self.size.get_appropriate_unit(true))?;
+        if let Ok(info) = &self.index {
+            let unknown = || "unknown".to_string();
+            let key = info.key.as_ref().map_or_else(unknown, |key| key.to_string());
+            let r#type = info.r#type.clone().unwrap_or_else(unknown);
+            let key_type = info.key_type.clone().unwrap_or_else(unknown);
+            let schema_version = info.schema_version.map_or_else(unknown, |v| v.to_string());
+            let last_used = info.last_used.map_or_else(|| "never".into(), |t| t.to_rfc3339());
+            write!(f, "\n  type: {type}\n  key: {key}\n  key type: {key_type}")?;
+            write!(f, "\n  schema version: {schema_version}\n  last used: {last_used}")?;
+        }
+        if let Some(problem) = self.problem() {
+            write!(f, "\n  problem: {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Path to the index file of the entry stored in the given directory.
+pub fn index_path(entry_dir: impl AsRef<Path>) -> PathBuf {
+    entry_dir.as_ref().with_appended_extension(INDEX_EXTENSION)
+}
+
+/// Record in the entry index that the entry has been used now.
+///
+/// The other index fields are preserved as they are, so the entry's
+/// [`Storable`](crate::cache::Storable) type is not needed.
+pub fn mark_used(index_path: impl AsRef<Path>) -> Result {
+    let mut index =
+        index_path.as_ref().read_to_json::<serde_json::Map<String, serde_json::Value>>()?;
+    index.insert(LAST_USED_FIELD.into(), serde_json::to_value(Utc::now())?);
+    index_path.as_ref().write_as_json(&index)
+}
+
+/// Total size in bytes of the file or all files in the directory. Returns 0 if the path does not
+/// exist.
+fn size_of(path: impl AsRef<Path>) -> Result<u64> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(0);
+    }
+    walkdir::WalkDir::new(path).into_iter().try_fold(0, |size, entry| -> Result<u64> {
+        let entry = entry?;
+        Ok(if entry.file_type().is_file() { size + entry.metadata()?.len() } else { size })
+    })
+}
+
+/// The latest modification time of the directory or any file within it. Returns `None` if the path
+/// does not exist.
+fn last_modified(path: impl AsRef<Path>) -> Result<Option<SystemTime>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(None);
+    }
+    walkdir::WalkDir::new(path).into_iter().try_fold(None, |latest, entry| -> Result<_> {
+        let modified = entry?.metadata()?.modified()?;
+        Ok(Some(latest.map_or(modified, |latest: SystemTime| latest.max(modified))))
+    })
+}
+
+
+
+// =================
+// === Cache API ===
+// =================
+
+impl Cache {
+    /// Read all the entries in the cache, sorted by their digests.
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let mut digests = BTreeSet::new();
+        for item in crate::fs::read_dir(self.path())? {
+            let path = item?.path();
+            let digest = if path.is_dir() {
+                path.file_name()
+            } else if path.extension().contains(&OsStr::new(INDEX_EXTENSION)) {
+                path.file_stem()
+            } else {
+                None
+            };
+            digests.extend(digest.map(|digest| digest.to_string_lossy().into_owned()));
+        }
+        digests.into_iter().map(|digest| Entry::read(self.path(), digest)).try_collect_vec()
+    }
+
+    /// Remove the unusable entries, then remove the least recently used ones until the total size
+    /// of the cache fits in the `max_size` budget. The entries that might be still written by
+    /// another process are left intact, see [`Entry::is_being_written`].
+    ///
+    /// Returns the removed entries.
+    pub fn gc(&self, max_size: Byte) -> Result<Vec<Entry>> {
+        let (being_written, entries): (Vec<_>, Vec<_>) =
+            self.entries()?.into_iter().partition(|entry| entry.is_being_written());
+        for entry in &being_written {
+            debug!("Skipping cache entry {}, as it might be still written.", entry.digest);
+        }
+        let (invalid, mut valid): (Vec<_>, Vec<_>) =
+            entries.into_iter().partition(|entry| entry.problem().is_some());
+        // Entries never marked as used go first, as they predate the last use tracking.
+        valid.sort_by_key(|entry| entry.last_used());
+        let mut total_size: u128 = valid.iter().map(|entry| entry.size.get_bytes()).sum();
+        let mut removed = invalid;
+        for entry in valid {
+            if total_size <= max_size.get_bytes() {
+                break;
+            }
+            total_size -= entry.size.get_bytes();
+            removed.push(entry);
+        }
+        for entry in &removed {
+            entry.remove()?;
+        }
+        Ok(removed)
+    }
+
+    /// Remove all the entries from the cache.
+    pub fn clear(&self) -> Result {
+        for entry in self.entries()? {
+            entry.remove()?;
+        }
+        Ok(())
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_entry(root: &Path, digest: &str, size: usize, info: &EntryInfo) -> Result {
+        let path = root.join(digest);
+        crate::fs::create_dir_all(&path)?;
+        crate::fs::write(path.join("data"), vec![0u8; size])?;
+        index_path(&path).write_as_json(info)
+    }
+
+    fn used_at(hours_ago: i64) -> EntryInfo {
+        let last_used = Some(Utc::now() - chrono::Duration::hours(hours_ago));
+        EntryInfo { schema_version: Some(VERSION), last_used, ..default() }
+    }
+
+    #[tokio::test]
+    async fn gc_removes_invalid_and_least_recently_used_entries() -> Result {
+        let temp = tempfile::tempdir()?;
+        let cache = Cache::new(temp.path()).await?;
+        write_entry(temp.path(), "old", 1000, &used_at(3))?;
+        write_entry(temp.path(), "recent", 1000, &used_at(1))?;
+        write_entry(temp.path(), "newest", 1000, &used_at(0))?;
+        let outdated = EntryInfo { schema_version: Some(VERSION - 1), ..used_at(0) };
+        write_entry(temp.path(), "outdated", 10, &outdated)?;
+        let orphan = temp.path().join("orphan");
+        crate::fs::create_dir_all(&orphan)?;
+        let abandoned_at = SystemTime::now() - WRITE_GRACE_PERIOD * 2;
+        filetime::set_file_mtime(&orphan, filetime::FileTime::from_system_time(abandoned_at))?;
+        // An entry being written by another process, which has not created the index file yet.
+        let being_written = temp.path().join("being_written");
+        crate::fs::create_dir_all(&being_written)?;
+        crate::fs::write(being_written.join("data"), vec![0u8; 10])?;
+
+        let problems = cache.entries()?.into_iter().filter_map(|e| e.problem()).collect_vec();
+        assert_eq!(problems, vec![
+            Problem::MissingIndex,
+            Problem::MissingIndex,
+            Problem::OutdatedSchema(Some(VERSION - 1))
+        ]);
+
+        let removed = cache.gc(Byte::from_bytes(2500))?;
+        let removed = removed.into_iter().map(|entry| entry.digest).sorted().collect_vec();
+        assert_eq!(removed, vec!["old", "orphan", "outdated"]);
+        let remaining = cache.entries()?.into_iter().map(|entry| entry.digest).collect_vec();
+        assert_eq!(remaining, vec!["being_written", "newest", "recent"]);
+
+        mark_used(index_path(temp.path().join("recent")))?;
+        let recent = Entry::read(temp.path(), "recent")?;
+        assert!(recent.last_used() > Some(Utc::now() - chrono::Duration::minutes(1)));
+        assert!(recent.problem().is_none());
+
+        cache.clear()?;
+        assert!(cache.entries()?.is_empty());
+        Ok(())
+    }
+}
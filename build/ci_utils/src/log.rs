@@ -17,6 +17,13 @@ use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::Registry;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod profile;
+
+
 
 pub fn is_our_module_path(path: impl AsRef<str>) -> bool {
     // true
@@ -74,7 +81,7 @@ pub fn setup_logging() -> Result {
         let progress_bar_writer = IndicatifWriter::new();
 
         tracing::subscriber::set_global_default(
-            Registry::default().with(MyLayer).with(
+            Registry::default().with(MyLayer).with(profile::Layer).with(
                 tracing_subscriber::fmt::layer()
                     .without_time()
                     .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
@@ -0,0 +1,338 @@
+//! Recording of the build step timings in the `enso-profiler` event-log format.
+//!
+//! When enabled with [`set_profile_file`], each tracing span from our modules becomes a profiler
+//! measurement. The written log can be inspected with the tools from the `enso-profiler-data`
+//! crate, like `intervals` or `devtools`.
+//!
+//! The measurements are labeled with the span names. If the span has the [`STEP_FIELD`] field set
+//! when created, its value is appended to the label, so e.g. each external program gets its own
+//! label.
+//!
+//! The profiler format requires the active intervals to form a stack, while the spans of
+//! concurrently running tasks interleave freely. Therefore, only the measurements on the path from
+//! the root to the most recently created or entered span are active, and the other ones are paused
+//! until their spans are entered again. Time spent waiting, e.g. for a child process, is attributed
+//! to the span that was the last one entered.
+
+use crate::prelude::*;
+
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span::Attributes;
+use tracing::Id;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Name of the span field that identifies the build step, e.g. the target or program name.
+pub const STEP_FIELD: &str = "step";
+
+/// Name of the process reported in the profile header.
+pub const PROCESS_NAME: &str = "Build";
+
+
+
+// ==============
+// === Output ===
+// ==============
+
+static PROFILE: LazyLock<Mutex<Option<Profile>>> = LazyLock::new(default);
+
+/// Start recording the spans, to be written to the given file by [`write`]. If `None` is given,
+/// the recording is stopped and the spans recorded so far are discarded.
+pub fn set_profile_file(path: Option<PathBuf>) {
+    let profile = path.map(|path| Profile { path, recorder: Recorder::new() });
+    *PROFILE.lock().unwrap() = profile;
+}
+
+/// Write the spans recorded so far, if the recording is enabled.
+///
+/// Spans that have not been closed yet are written as unfinished measurements.
+pub fn write() -> Result {
+    let guard = PROFILE.lock().unwrap();
+    let Some(profile) = guard.as_ref() else { return Ok(()) };
+    let path = profile.path.clone();
+    let contents = profile.recorder.build_string();
+    // The lock is released before writing, as the file operations might create spans themselves.
+    drop(guard);
+    crate::fs::create_parent_dir_if_missing(&path)?;
+    crate::fs::write(&path, contents)?;
+    info!("Build profile written to {}.", path.display());
+    Ok(())
+}
+
+/// The recorded profile and the file it should be written to.
+#[derive(Debug)]
+struct Profile {
+    path:     PathBuf,
+    recorder: Recorder,
+}
+
+fn with_recorder(f: impl FnOnce(&mut Recorder)) {
+    if let Some(profile) = PROFILE.lock().unwrap().as_mut() {
+        f(&mut profile.recorder);
+    }
+}
+
+
+
+// =============
+// === Layer ===
+// =============
+
+/// The tracing layer that records the spans into the profile, if the recording is enabled.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Layer;
+
+/// The measurement representing the span, stored in the span extensions.
+#[derive(Clone, Copy, Debug)]
+struct SpanMeasurement(usize);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> tracing_subscriber::Layer<S> for Layer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let parent = span
+            .scope()
+            .skip(1)
+            .find_map(|span| span.extensions().get::<SpanMeasurement>().map(|m| m.0));
+        let mut measurement = None;
+        with_recorder(|recorder| measurement = Some(recorder.create(parent, label(attrs))));
+        if let Some(measurement) = measurement {
+            span.extensions_mut().insert(SpanMeasurement(measurement));
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(measurement) = span_measurement(id, &ctx) {
+            with_recorder(|recorder| recorder.focus(measurement));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(measurement) = span_measurement(&id, &ctx) {
+            with_recorder(|recorder| recorder.end(measurement));
+        }
+    }
+}
+
+fn span_measurement<S: Subscriber + for<'a> LookupSpan<'a>>(
+    id: &Id,
+    ctx: &Context<'_, S>,
+) -> Option<usize> {
+    ctx.span(id)?.extensions().get::<SpanMeasurement>().map(|m| m.0)
+}
+
+/// Describe the span in the format expected by the `enso-profiler-data` label parser:
+/// `name (file:line)`.
+fn label(attrs: &Attributes<'_>) -> String {
+    let metadata = attrs.metadata();
+    let mut step = StepVisitor::default();
+    attrs.record(&mut step);
+    let name = match step.0 {
+        Some(step) => format!("{} [{step}]", metadata.name()),
+        None => metadata.name().to_string(),
+    };
+    let file = metadata.file().unwrap_or("?");
+    let line = metadata.line().unwrap_or_default();
+    format!("{name} ({file}:{line})")
+}
+
+/// Extracts the value of the [`STEP_FIELD`] field.
+#[derive(Clone, Debug, Default)]
+struct StepVisitor(Option<String>);
+
+impl Visit for StepVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == STEP_FIELD {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == STEP_FIELD {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+
+
+// ================
+// === Recorder ===
+// ================
+
+/// A single recorded event, with time in milliseconds since the recording started.
+#[derive(Clone, Debug)]
+enum Event {
+    Create { parent: Option<usize>, label: String, time: f64 },
+    Start { id: usize, time: f64 },
+    Pause { id: usize, time: f64 },
+    End { id: usize, time: f64 },
+}
+
+/// Records the measurements, keeping the active ones on a stack as the profiler format requires.
+#[derive(Debug)]
+struct Recorder {
+    origin:      Instant,
+    /// Time of the recording start, in milliseconds since the Unix epoch.
+    time_offset: f64,
+    events:      Vec<Event>,
+    /// Parent of each measurement, indexed by the measurement id.
+    parents:     Vec<Option<usize>>,
+    ended:       Vec<bool>,
+    /// The active measurements, forming a path from the root.
+    active:      Vec<usize>,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        Self {
+            origin:      Instant::now(),
+            time_offset: chrono::Utc::now().timestamp_millis() as f64,
+            events:      default(),
+            parents:     default(),
+            ended:       default(),
+            active:      default(),
+        }
+    }
+
+    fn now(&self) -> f64 {
+        self.origin.elapsed().as_secs_f64() * 1000.0
+    }
+
+    /// Create a new measurement and make it active.
+    fn create(&mut self, parent: Option<usize>, label: String) -> usize {
+        let id = self.parents.len();
+        let time = self.now();
+        self.events.push(Event::Create { parent, label, time });
+        self.parents.push(parent);
+        self.ended.push(false);
+        self.focus(id);
+        id
+    }
+
+    /// Make the measurement and its ancestors active, pausing all the other active measurements.
+    fn focus(&mut self, id: usize) {
+        if self.ended[id] || self.active.last() == Some(&id) {
+            return;
+        }
+        let path = self.path_to(id);
+        let common = self.active.iter().zip(&path).take_while(|(a, b)| a == b).count();
+        let time = self.now();
+        while self.active.len() > common {
+            let id = self.active.pop().unwrap();
+            self.events.push(Event::Pause { id, time });
+        }
+        for &id in &path[common..] {
+            self.active.push(id);
+            self.events.push(Event::Start { id, time });
+        }
+    }
+
+    /// End the measurement. Its parent stays active.
+    fn end(&mut self, id: usize) {
+        if self.ended[id] {
+            return;
+        }
+        self.focus(id);
+        self.active.pop();
+        self.ended[id] = true;
+        let time = self.now();
+        self.events.push(Event::End { id, time });
+    }
+
+    /// The measurement and its not yet ended ancestors, starting from the root.
+    fn path_to(&self, id: usize) -> Vec<usize> {
+        let ancestors = std::iter::successors(self.parents[id], |&parent| self.parents[parent]);
+        let mut path = ancestors.filter(|&parent| !self.ended[parent]).collect_vec();
+        path.reverse();
+        path.push(id);
+        path
+    }
+
+    /// Render the recorded events in the `enso-profiler` JSON event-log format. The format is
+    /// written directly, so the build scripts do not depend on the profiler crates.
+    fn build_string(&self) -> String {
+        let ticks = |ms: f64| (ms * 1000.0).round() as u64;
+        let mut log = vec![
+            serde_json::json!({ "X": { "t": 0, "d": { "$TimeOffset": ticks(self.time_offset) } } }),
+            serde_json::json!({ "X": { "t": 0, "d": { "$Process": PROCESS_NAME } } }),
+        ];
+        let mut labels = HashMap::<&str, usize>::new();
+        for event in &self.events {
+            let event = match event {
+                Event::Create { parent, label, time } => {
+                    let new_label_id = labels.len();
+                    let label_id = *labels.entry(label).or_insert_with(|| {
+                        log.push(serde_json::json!({ "L": { "l": label } }));
+                        new_label_id
+                    });
+                    // The top-level measurements have the special parent `-1`.
+                    let parent = parent.map_or(-1, |parent| parent as i64);
+                    serde_json::json!({ "C": { "p": parent, "t": ticks(*time), "l": label_id } })
+                }
+                Event::Start { id, time } =>
+                    serde_json::json!({ "S": { "i": id, "t": ticks(*time) } }),
+                Event::Pause { id, time } =>
+                    serde_json::json!({ "P": { "i": id, "t": ticks(*time) } }),
+                Event::End { id, time } =>
+                    serde_json::json!({ "E": { "i": id, "t": ticks(*time) } }),
+            };
+            log.push(event);
+        }
+        serde_json::Value::Array(log).to_string()
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleaved_measurements_form_valid_profile() {
+        let mut recorder = Recorder::new();
+        let target = recorder.create(None, "Running target. [Ide] (cli/src/lib.rs:1)".into());
+        let download = recorder.create(Some(target), "Downloading a file. (download.rs:2)".into());
+        // A concurrent task starts while the download is still running.
+        let process =
+            recorder.create(Some(target), "Running process. [cargo] (command.rs:3)".into());
+        recorder.focus(download);
+        recorder.end(process);
+        recorder.end(download);
+        recorder.end(target);
+
+        let log: Vec<serde_json::Value> = serde_json::from_str(&recorder.build_string()).unwrap();
+        let events_of = |kind: &str| log.iter().filter_map(|event| event.get(kind)).collect_vec();
+        let labels = events_of("L").iter().map(|label| label["l"].clone()).collect_vec();
+        assert_eq!(labels, [
+            "Running target. [Ide] (cli/src/lib.rs:1)",
+            "Downloading a file. (download.rs:2)",
+            "Running process. [cargo] (command.rs:3)"
+        ]);
+        let parents = events_of("C").iter().map(|create| create["p"].clone()).collect_vec();
+        assert_eq!(parents, [-1, 0, 0]);
+        let count =
+            |kind: &str, id: usize| events_of(kind).iter().filter(|event| event["i"] == id).count();
+        for id in [target, download, process] {
+            assert_eq!(count("E", id), 1);
+            assert_eq!(count("S", id), count("P", id) + 1);
+        }
+        // The download is paused whenever the concurrent process is active.
+        assert_eq!(count("S", download), 3);
+    }
+}
@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
 use crate::env::accessor::TypedVariable;
+use crate::ok_ready_boxed;
+use crate::program::command::invocation::Invocation;
 
 use anyhow::Context;
 use std::borrow::BorrowMut;
@@ -21,6 +23,7 @@ use tracing::field;
 // === Export ===
 // ==============
 
+pub mod invocation;
 pub mod provider;
 
 
@@ -328,8 +331,15 @@ impl Command {
 
     pub fn run_ok(&mut self) -> BoxFuture<'static, Result<()>> {
         let pretty = self.describe();
+        let invocation = Invocation::new(self.as_std());
+        if invocation.dry_run {
+            info!("Dry run, not executing. {pretty}");
+            invocation.finish(None);
+            return ok_ready_boxed(());
+        }
         let span = info_span!(
             "Running process.",
+            step = invocation.program.as_str(),
             status = tracing::field::Empty,
             pid = tracing::field::Empty,
             command = tracing::field::Empty,
@@ -338,13 +348,10 @@ impl Command {
         let child = self.spawn_intercepting();
         let status_checker = self.status_checker.clone();
         async move {
-            let mut child = child?;
-            let status = child
-                .wait()
-                .inspect_ok(|exit_status| {
-                    tracing::Span::current().record("status", exit_status.code());
-                })
-                .await?;
+            let status = async { child?.wait().await.anyhow_err() }.await;
+            invocation.finish(status.as_ref().ok().copied());
+            let status = status?;
+            tracing::Span::current().record("status", status.code());
             status_checker(status).context(format!("Command failed: {pretty}"))
         }
         .instrument(span.exit())
@@ -353,8 +360,16 @@ impl Command {
 
     pub fn output_ok(&mut self) -> BoxFuture<'static, Result<Output>> {
         let pretty = self.describe();
+        let invocation = Invocation::new(self.as_std());
+        if invocation.dry_run {
+            info!("Dry run, not executing, the output will be empty. {pretty}");
+            invocation.finish(None);
+            let status = invocation::success_status();
+            return ok_ready_boxed(Output { status, stdout: default(), stderr: default() });
+        }
         let span = info_span!(
             "Running process for the output.",
+            step = invocation.program.as_str(),
             status = tracing::field::Empty,
             pid = tracing::field::Empty,
             command = tracing::field::Empty,
@@ -366,9 +381,12 @@ impl Command {
         let child = self.spawn();
         let status_checker = self.status_checker.clone();
         async move {
-            let child = child?;
-            let output =
-                child.wait_with_output().await.context("Failed while waiting for output.")?;
+            let output = async {
+                child?.wait_with_output().await.context("Failed while waiting for output.")
+            }
+            .await;
+            invocation.finish(output.as_ref().ok().map(|output| output.status));
+            let output = output?;
             tracing::Span::current().record("status", output.status.code());
             status_checker(output.status).with_context(|| {
                 format!(
@@ -0,0 +1,176 @@
+//! Recording of the external program invocations, used by the dry-run and trace modes.
+//!
+//! In the dry-run mode, [`Command::run_ok`](crate::program::Command::run_ok) and
+//! [`Command::output_ok`](crate::program::Command::output_ok) do not execute the program, but only
+//! report what would be executed. Commands run for their output yield empty output in this mode.
+//!
+//! In the trace mode, each invocation is appended to a JSON file, together with its duration and
+//! exit status.
+
+use crate::prelude::*;
+
+use chrono::DateTime;
+use chrono::Utc;
+use std::process::ExitStatus;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::Instant;
+
+
+
+// =============
+// === Modes ===
+// =============
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+static TRACE: LazyLock<Mutex<Option<Trace>>> = LazyLock::new(default);
+
+/// Enable or disable the dry-run mode.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::SeqCst);
+}
+
+/// Check if the dry-run mode is enabled.
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::SeqCst)
+}
+
+/// Start recording the invocations to the given JSON file, or stop recording if `None` is given.
+///
+/// The file is created (or truncated) immediately and rewritten after each recorded invocation,
+/// so it is complete even if the build is interrupted.
+pub fn set_trace_file(path: Option<PathBuf>) -> Result {
+    let trace = path.map(|path| Trace { path, invocations: default() });
+    if let Some(trace) = &trace {
+        trace.write()?;
+    }
+    *TRACE.lock().unwrap() = trace;
+    Ok(())
+}
+
+/// The trace file with the invocations recorded so far.
+#[derive(Clone, Debug)]
+struct Trace {
+    path:        PathBuf,
+    invocations: Vec<Invocation>,
+}
+
+impl Trace {
+    fn write(&self) -> Result {
+        crate::fs::create_parent_dir_if_missing(&self.path)?;
+        self.path.write_as_json(&self.invocations)
+    }
+}
+
+
+
+// ==================
+// === Invocation ===
+// ==================
+
+/// Description of a single external program invocation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Invocation {
+    pub program:     String,
+    pub args:        Vec<String>,
+    pub current_dir: Option<PathBuf>,
+    /// Environment variables that differ from the ones of the build script process. Removed
+    /// variables are mapped to `None`.
+    pub env:         BTreeMap<String, Option<String>>,
+    pub started_at:  DateTime<Utc>,
+    /// Whether the program was not actually executed because of the dry-run mode.
+    pub dry_run:     bool,
+    /// Duration of the program execution in seconds. Missing if the program was not executed or
+    /// failed to start.
+    pub duration:    Option<f64>,
+    /// Exit code of the program. Missing if the program did not finish normally.
+    pub exit_code:   Option<i32>,
+    pub success:     Option<bool>,
+    #[serde(skip, default = "Instant::now")]
+    started:         Instant,
+}
+
+impl Invocation {
+    /// Describe the invocation of the given command, starting now.
+    pub fn new(command: &std::process::Command) -> Self {
+        let to_string = |s: &OsStr| s.to_string_lossy().into_owned();
+        let env = command
+            .get_envs()
+            .filter(|(name, value)| std::env::var_os(name).as_deref() != *value)
+            .map(|(name, value)| (to_string(name), value.map(to_string)))
+            .collect();
+        Self {
+            program: to_string(command.get_program()),
+            args: command.get_args().map(to_string).collect(),
+            current_dir: command.get_current_dir().map(Into::into),
+            env,
+            started_at: Utc::now(),
+            dry_run: is_dry_run(),
+            duration: None,
+            exit_code: None,
+            success: None,
+            started: Instant::now(),
+        }
+    }
+
+    /// Record the finished invocation in the trace file, if the trace mode is enabled.
+    ///
+    /// `status` is `None` if the program could not be started or waited for.
+    pub fn finish(mut self, status: Option<ExitStatus>) {
+        if !self.dry_run {
+            self.duration = Some(self.started.elapsed().as_secs_f64());
+            self.exit_code = status.and_then(|status| status.code());
+            self.success = Some(status.is_some_and(|status| status.success()));
+        }
+        self.record();
+    }
+
+    fn record(self) {
+        let mut trace = TRACE.lock().unwrap();
+        if let Some(trace) = trace.as_mut() {
+            trace.invocations.push(self);
+            if let Err(e) = trace.write() {
+                warn!("Failed to write the command trace to {}: {e}", trace.path.display());
+            }
+        }
+    }
+}
+
+/// Exit status of a successfully finished process, reported for commands skipped in the dry-run
+/// mode.
+pub fn success_status() -> ExitStatus {
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt;
+    #[cfg(windows)]
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(0)
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describing_invocation() {
+        let path = std::env::var("PATH").unwrap_or_default();
+        let mut command = std::process::Command::new("cargo");
+        command.args(["build", "--release"]).current_dir("target");
+        command.env("ENSO_INVOCATION_TEST", "1").env("PATH", &path).env_remove("ENSO_UNSET_TEST");
+        let invocation = Invocation::new(&command);
+        assert_eq!(invocation.program, "cargo");
+        assert_eq!(invocation.args, vec!["build", "--release"]);
+        assert_eq!(invocation.current_dir, Some(PathBuf::from("target")));
+        // Unchanged `PATH` and the removal of a variable that is not set are not part of the diff.
+        let expected_env = [("ENSO_INVOCATION_TEST".to_string(), Some("1".to_string()))];
+        assert_eq!(invocation.env, BTreeMap::from(expected_env));
+    }
+}
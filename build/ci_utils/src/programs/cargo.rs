@@ -11,6 +11,7 @@ use crate::program::command::Manipulator;
 pub mod build_env;
 pub mod clippy;
 pub mod fmt;
+pub mod metadata;
 
 
 
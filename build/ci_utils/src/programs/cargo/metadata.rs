@@ -0,0 +1,63 @@
+//! Reading the workspace structure with `cargo metadata`.
+//!
+//! Only the subset of the output format that describes the workspace members is modelled. The
+//! dependencies are not resolved, so the registry is never accessed.
+//!
+//! See: <https://doc.rust-lang.org/cargo/commands/cargo-metadata.html#output-format>
+
+use crate::prelude::*;
+
+use crate::programs::Cargo;
+
+
+
+/// Version of the `cargo metadata` output format that we support.
+pub const FORMAT_VERSION: &str = "1";
+
+/// The workspace description, as reported by `cargo metadata --no-deps`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Metadata {
+    /// The workspace member packages.
+    pub packages:       Vec<Package>,
+    pub workspace_root: PathBuf,
+}
+
+/// A workspace member package.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Package {
+    pub name:          String,
+    /// Path to the package's `Cargo.toml`.
+    pub manifest_path: PathBuf,
+    /// The declared dependencies, of all kinds.
+    pub dependencies:  Vec<Dependency>,
+}
+
+/// A dependency declared in the package manifest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    /// The dependency directory, if it is a path dependency.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+impl Package {
+    /// The directory containing the package's `Cargo.toml`.
+    pub fn directory(&self) -> Result<&Path> {
+        self.manifest_path.parent().with_context(|| {
+            format!("Manifest path {} has no parent.", self.manifest_path.display())
+        })
+    }
+}
+
+/// Describe the workspace containing the given directory.
+#[context("Failed to read the Cargo metadata of the workspace at {}.", path.as_ref().display())]
+pub async fn workspace(path: impl AsRef<Path>) -> Result<Metadata> {
+    let stdout = Cargo
+        .cmd()?
+        .with_args(["metadata", "--no-deps", "--format-version", FORMAT_VERSION])
+        .with_current_dir(&path)
+        .run_stdout()
+        .await?;
+    Ok(serde_json::from_str(&stdout)?)
+}
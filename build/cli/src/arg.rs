@@ -7,7 +7,6 @@ use clap::Parser;
 use clap::Subcommand;
 use derivative::Derivative;
 use enso_build_base::extensions::path::display_fmt;
-use ide_ci::cache;
 use ide_ci::github::Repo;
 use octocrab::models::RunId;
 
@@ -16,7 +15,9 @@ use octocrab::models::RunId;
 // === Export ===
 // ==============
 
+pub mod affected;
 pub mod backend;
+pub mod cache;
 pub mod engine;
 pub mod git_clean;
 pub mod gui;
@@ -45,7 +46,7 @@ pub fn default_repo_remote() -> Repo {
 }
 
 pub fn default_cache_path() -> Option<PathBuf> {
-    cache::default_path().ok()
+    ide_ci::cache::default_path().ok()
 }
 
 /// Extensions to the `clap::Arg`, intended to be used as argument attributes.
@@ -107,7 +108,7 @@ macro_rules! source_args_hlp {
 }
 
 #[allow(clippy::large_enum_variant)]
-#[derive(Subcommand, Clone, Debug)]
+#[derive(Subcommand, Clone, Debug, strum::AsRefStr)]
 pub enum Target {
     /// Build/Test the Rust part of the GUI.
     Wasm(wasm::Target),
@@ -126,6 +127,8 @@ pub enum Target {
     /// Clean the repository. Keeps the IntelliJ's .idea directory intact. WARNING: This removes
     /// files that are not under version control in the repository subtree.
     GitClean(git_clean::Options),
+    /// Inspect and clean up the build script's cache.
+    Cache(cache::Target),
     /// Lint the codebase.
     Lint,
     /// Apply automatic formatters on the repository.
@@ -137,6 +140,13 @@ pub enum Target {
     JavaGen(java_gen::Target),
     /// Check if the changelog has been updated. Requires CI environment.
     ChangelogCheck,
+    /// Check the changelog for broken links, misplaced entries and formatting issues.
+    ChangelogLint,
+    /// List the targets affected by the changes since the given git reference.
+    Affected(affected::Target),
+    /// Check the programs and environment variables needed by the build script and print a JSON
+    /// report. Does not need network access.
+    Doctor,
 }
 
 /// Build, test and package Enso Engine.
@@ -172,6 +182,22 @@ pub struct Cli {
     #[clap(long, global = true, hide = !ide_ci::actions::workflow::is_in_env(), parse(try_from_str), default_value_t = true, enso_env())]
     pub upload_artifacts: bool,
 
+    /// Do not run the external programs, only log what would be run. Programs run for their
+    /// output yield empty output in this mode, so some targets might fail.
+    #[clap(long, global = true, enso_env())]
+    pub dry_run: bool,
+
+    /// Record each external program invocation, with its duration and exit status, to the given
+    /// JSON file.
+    #[clap(long, global = true, parse(try_from_str=normalize_path), enso_env())]
+    pub trace_file: Option<PathBuf>,
+
+    /// Record the timings of the build steps to the given file, in the format of the
+    /// `enso-profiler` event log. It can be inspected with the `enso-profiler-data` tools, like
+    /// `intervals` or `devtools`.
+    #[clap(long, global = true, parse(try_from_str=normalize_path), enso_env())]
+    pub profile_file: Option<PathBuf>,
+
     #[clap(subcommand)]
     pub target: Target,
 }
@@ -0,0 +1,13 @@
+use crate::prelude::*;
+
+use clap::Args;
+
+
+
+/// Structure that represents `affected` subcommand arguments.
+#[derive(Args, Clone, Debug)]
+pub struct Target {
+    /// The git reference to compare the working tree against, e.g. `origin/develop`.
+    #[clap(long, enso_env())]
+    pub base: String,
+}
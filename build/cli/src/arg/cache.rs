@@ -0,0 +1,34 @@
+use crate::prelude::*;
+
+use clap::Args;
+use clap::Subcommand;
+
+
+
+/// Structure that represents `gc` subcommand arguments.
+#[derive(Args, Clone, Copy, Debug)]
+pub struct Gc {
+    /// The size that the cache should fit in after removing the least recently used entries.
+    /// Supports format like "20GiB".
+    #[clap(long, default_value = "20GiB", enso_env())]
+    pub max_size: byte_unit::Byte,
+}
+
+#[derive(Subcommand, Clone, Copy, Debug)]
+pub enum Action {
+    /// List the cache entries with their keys, types, schema versions, sizes and last use times.
+    List,
+    /// Check the cache entries and fail if any of them is broken or has an outdated schema.
+    Verify,
+    /// Remove the broken and outdated entries, and the least recently used ones exceeding the
+    /// size budget.
+    Gc(Gc),
+    /// Remove all the cache entries.
+    Clear,
+}
+
+#[derive(Args, Clone, Copy, Debug)]
+pub struct Target {
+    #[clap(subcommand)]
+    pub action: Action,
+}
@@ -771,6 +771,13 @@ pub async fn main_internal(config: Option<enso_build::config::Config>) -> Result
     let cli = Cli::parse();
 
     debug!("Parsed CLI arguments: {cli:#?}");
+    ide_ci::program::command::invocation::set_trace_file(cli.trace_file.clone())?;
+    ide_ci::log::profile::set_profile_file(cli.profile_file.clone());
+
+    if let Target::Doctor = cli.target {
+        // The build context is not created, as it might need network access.
+        return enso_build::doctor::run(&cli.repo_path.absolutize()?, &config).await;
+    }
 
     if !cli.skip_version_check {
         // Let's be helpful!
@@ -780,6 +787,9 @@ pub async fn main_internal(config: Option<enso_build::config::Config>) -> Result
         config.check_programs().await.context(error_message)?;
     }
 
+    // Enabled after checking the program versions, as the check needs the actual program output.
+    ide_ci::program::command::invocation::set_dry_run(cli.dry_run);
+
     // TRANSITION: Previous Engine CI job used to clone these both repositories side-by-side.
     // This collides with GraalVM native image build location.
     if is_in_env() {
@@ -788,7 +798,16 @@ pub async fn main_internal(config: Option<enso_build::config::Config>) -> Result
     }
 
     let ctx: Processor = Processor::new(&cli).instrument(info_span!("Building context.")).await?;
-    match cli.target {
+    let target_span = info_span!("Running target.", step = cli.target.as_ref());
+    run_target(ctx, cli.target).instrument(target_span).await?;
+    info!("Completed main job.");
+    global::complete_tasks().await?;
+    Ok(())
+}
+
+/// Run the build target selected in the command line.
+async fn run_target(ctx: Processor, target: Target) -> Result {
+    match target {
         Target::Wasm(wasm) => ctx.handle_wasm(wasm).await?,
         Target::Gui(gui) => ctx.handle_gui(gui).await?,
         Target::Runtime(runtime) => ctx.handle_runtime(runtime).await?,
@@ -814,6 +833,37 @@ pub async fn main_internal(config: Option<enso_build::config::Config>) -> Result
             };
             try_join(git_clean, clean_cache).await?;
         }
+        Target::Cache(cache) => match cache.action {
+            crate::arg::cache::Action::List =>
+                for entry in ctx.cache.entries()? {
+                    println!("{entry}");
+                },
+            crate::arg::cache::Action::Verify => {
+                let entries = ctx.cache.entries()?;
+                let problems = entries
+                    .iter()
+                    .filter_map(|entry| entry.problem().map(|problem| (&entry.digest, problem)))
+                    .collect_vec();
+                for (digest, problem) in &problems {
+                    error!("Cache entry {digest} is not usable: {problem}.");
+                }
+                ensure!(
+                    problems.is_empty(),
+                    "{} of {} cache entries are not usable. Use the `cache gc` command to remove \
+                    them.",
+                    problems.len(),
+                    entries.len()
+                );
+            }
+            crate::arg::cache::Action::Gc(args) => {
+                let crate::arg::cache::Gc { max_size } = args;
+                let removed = ctx.cache.gc(max_size)?;
+                let freed = removed.iter().map(|entry| entry.size.get_bytes()).sum::<u128>();
+                let freed = byte_unit::Byte::from_bytes(freed).get_appropriate_unit(true);
+                info!("Removed {} cache entries, freeing {freed}.", removed.len());
+            }
+            crate::arg::cache::Action::Clear => ctx.cache.clear()?,
+        },
         Target::Lint => {
             Cargo
                 .cmd()?
@@ -835,6 +885,7 @@ pub async fn main_internal(config: Option<enso_build::config::Config>) -> Result
                 .args(["--", "--check"])
                 .run_ok()
                 .await?;
+            enso_formatter::process_path(&ctx.repo_root, enso_formatter::Action::Check).await?;
 
             ensogl_pack::build_ts_sources_only().await?;
             prettier::check(&ctx.repo_root).await?;
@@ -896,9 +947,22 @@ pub async fn main_internal(config: Option<enso_build::config::Config>) -> Result
             let ci_context = ide_ci::actions::context::Context::from_env()?;
             enso_build::changelog::check::check(ctx.repo_root.clone(), ci_context).await?;
         }
+        Target::Doctor => unreachable!("The doctor runs before the build context is created."),
+        Target::ChangelogLint => {
+            enso_build::changelog::lint::check_file(&ctx.repo_root.changelog_md)?;
+        }
+        Target::Affected(args) => {
+            let affected = enso_build::affected::affected_since(&ctx.repo_root, &args.base).await?;
+            for target in &affected {
+                println!("{target}");
+            }
+            if is_in_env() {
+                let affected = affected.iter().join(",");
+                ide_ci::actions::workflow::set_output(enso_build::affected::OUTPUT_NAME, &affected)
+                    .await?;
+            }
+        }
     };
-    info!("Completed main job.");
-    global::complete_tasks().await?;
     Ok(())
 }
 
@@ -906,7 +970,16 @@ pub fn lib_main(config: Option<enso_build::config::Config>) -> Result {
     trace!("Starting the tokio runtime.");
     let rt = tokio::runtime::Runtime::new()?;
     trace!("Entering main.");
-    rt.block_on(async { main_internal(config).await })?;
+    let result = rt.block_on(async { main_internal(config).await });
+    // The profile is written even if the build failed, as it might help to investigate the failure.
+    // In such case, the build error is the one reported.
+    if let Err(error) = ide_ci::log::profile::write() {
+        if result.is_ok() {
+            return Err(error);
+        }
+        error!("Failed to write the profile: {error:?}");
+    }
+    result?;
     rt.shutdown_timeout(Duration::from_secs(60 * 30));
     info!("Successfully ending.");
     Ok(())
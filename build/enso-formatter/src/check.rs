@@ -0,0 +1,387 @@
+//! Code style rules that are checked rather than applied on every formatting run.
+//!
+//! Each [`Rule`] reports [`Diagnostic`]s pointing to the offending lines. Some of the rules can
+//! also be fixed automatically with [`fix`], one rule at a time, so the resulting changes can be
+//! reviewed separately.
+
+use ide_ci::prelude::*;
+
+use crate::STD_LINTER_ATTRIBS;
+
+use regex::Regex;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Number of empty lines expected before a top-level section header.
+const SECTION_SPACING: usize = 3;
+
+/// Number of empty lines expected before the `Export` section header. It is lower than
+/// [`SECTION_SPACING`], as the section directly follows the imports.
+const EXPORT_SECTION_SPACING: usize = 2;
+
+/// Final segments of the paths that are allowed to be imported with a star import.
+const STAR_IMPORT_ALLOWED: &[&str] = &["prelude", "traits", "super"];
+
+lazy_static! {
+    static ref SECTION_BAR: Regex = Regex::new(r"^// =+$").unwrap();
+    static ref SECTION_TITLE: Regex = Regex::new(r"^// === (.*) ===$").unwrap();
+    static ref STAR_IMPORT: Regex = Regex::new(r"^use +([\w:]+?) *:: *\*;").unwrap();
+}
+
+
+
+// ============
+// === Rule ===
+// ============
+
+/// A code style rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rule {
+    /// Top-level section headers are preceded by the standard number of empty lines and their
+    /// bars match the title length.
+    SectionSpacing,
+    /// Star imports are used only for the `prelude`, `traits` and `super` modules.
+    StarImport,
+    /// The `lib.rs` and `main.rs` files contain the standard linter configuration.
+    LinterConfig,
+    /// The `lib.rs` files start with the module documentation.
+    ModuleDoc,
+}
+
+/// How serious the rule violation is. Only the errors cause the check to fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Rule {
+    /// All the rules, in the order they are checked in.
+    pub const ALL: [Rule; 4] =
+        [Rule::SectionSpacing, Rule::StarImport, Rule::LinterConfig, Rule::ModuleDoc];
+
+    /// The rule name, as used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::SectionSpacing => "section-spacing",
+            Rule::StarImport => "star-import",
+            Rule::LinterConfig => "linter-config",
+            Rule::ModuleDoc => "module-doc",
+        }
+    }
+
+    /// The severity of the rule violations.
+    ///
+    /// Rules that the existing code does not follow yet are reported as warnings.
+    pub fn severity(self) -> Severity {
+        match self {
+            Rule::LinterConfig => Severity::Error,
+            Rule::SectionSpacing | Rule::StarImport | Rule::ModuleDoc => Severity::Warning,
+        }
+    }
+
+    /// Whether the rule violations can be fixed automatically with [`fix`].
+    pub fn is_fixable(self) -> bool {
+        match self {
+            Rule::SectionSpacing | Rule::LinterConfig => true,
+            Rule::StarImport | Rule::ModuleDoc => false,
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == s).with_context(|| {
+            let names = Rule::ALL.map(Rule::name).join(", ");
+            format!("Unknown rule '{s}'. Available rules: {names}.")
+        })
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+
+
+// ==================
+// === Diagnostic ===
+// ==================
+
+/// A rule violation found in a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Line number, starting from 1.
+    pub line:    usize,
+    pub rule:    Rule,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Constructor.
+    pub fn new(line: usize, rule: Rule, message: impl Into<String>) -> Self {
+        Self { line, rule, message: message.into() }
+    }
+
+    /// Describe the diagnostic in the `file:line: severity[rule]: message` format.
+    pub fn display_in(&self, path: &Path) -> String {
+        let severity = self.rule.severity();
+        format!("{}:{}: {severity}[{}]: {}", path.display(), self.line, self.rule, self.message)
+    }
+}
+
+
+
+// =============
+// === Check ===
+// =============
+
+/// Check the file contents against all the rules. The diagnostics are sorted by line.
+///
+/// The `is_main_file` flag has the same meaning as in [`crate::process_file_content`].
+pub fn check(path: &Path, input: &str, is_main_file: bool) -> Vec<Diagnostic> {
+    let lines = input.lines().collect_vec();
+    let is_lib_file = path.file_name().contains(&"lib.rs");
+    let mut diagnostics = section_spacing(&lines);
+    diagnostics.extend(star_imports(&lines));
+    if is_main_file {
+        diagnostics.extend(linter_config(&lines));
+    }
+    if is_lib_file {
+        diagnostics.extend(module_doc(&lines));
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    diagnostics
+}
+
+/// A top-level section header, spanning three lines: a bar, the title and another bar.
+#[derive(Clone, Copy, Debug)]
+struct SectionHeader<'a> {
+    /// Index of the first line of the header.
+    index:   usize,
+    title:   &'a str,
+    /// Number of empty lines directly preceding the header, if it is preceded by any code.
+    spacing: Option<usize>,
+}
+
+impl SectionHeader<'_> {
+    fn expected_spacing(&self) -> usize {
+        if self.title == "Export" {
+            EXPORT_SECTION_SPACING
+        } else {
+            SECTION_SPACING
+        }
+    }
+
+    fn bar(&self) -> String {
+        format!("// ===={}====", "=".repeat(self.title.len()))
+    }
+}
+
+fn section_headers<'a>(lines: &[&'a str]) -> Vec<SectionHeader<'a>> {
+    let windows = lines.windows(3).enumerate();
+    let headers = windows.filter_map(|(index, window)| {
+        let is_bar = |line: &str| SECTION_BAR.is_match(line);
+        let title = SECTION_TITLE.captures(window[1])?.get(1)?.as_str();
+        (is_bar(window[0]) && is_bar(window[2])).then_some((index, title))
+    });
+    let headers = headers.map(|(index, title)| {
+        let preceding = lines[..index].iter().rev();
+        let spacing = preceding.clone().take_while(|line| line.trim().is_empty()).count();
+        let previous = preceding.find(|line| !line.trim().is_empty());
+        // Sections opening a block are not separated from it.
+        let is_nested = previous.map_or(true, |line| line.trim_end().ends_with('{'));
+        let spacing = (!is_nested).then_some(spacing);
+        SectionHeader { index, title, spacing }
+    });
+    headers.collect()
+}
+
+fn section_spacing(lines: &[&str]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for header in section_headers(lines) {
+        let line = header.index + 1;
+        let expected = header.expected_spacing();
+        if let Some(spacing) = header.spacing.filter(|&spacing| spacing != expected) {
+            let message = format!(
+                "Section '{}' is preceded by {spacing} empty lines instead of {expected}.",
+                header.title
+            );
+            diagnostics.push(Diagnostic::new(line, Rule::SectionSpacing, message));
+        }
+        let bar = header.bar();
+        if lines[header.index] != bar || lines[header.index + 2] != bar {
+            let message = format!("Bars of section '{}' do not match its title.", header.title);
+            diagnostics.push(Diagnostic::new(line, Rule::SectionSpacing, message));
+        }
+    }
+    diagnostics
+}
+
+fn star_imports(lines: &[&str]) -> Vec<Diagnostic> {
+    let imports = lines.iter().enumerate().filter_map(|(index, line)| {
+        let path = STAR_IMPORT.captures(line)?.get(1)?.as_str();
+        let last_segment = path.rsplit("::").next().unwrap_or(path).trim();
+        (!STAR_IMPORT_ALLOWED.contains(&last_segment)).then_some((index, path))
+    });
+    let message = |path: &str| {
+        let allowed = STAR_IMPORT_ALLOWED.join(", ");
+        format!("Star import of '{path}'. Star imports are allowed only for: {allowed}.")
+    };
+    imports
+        .map(|(index, path)| Diagnostic::new(index + 1, Rule::StarImport, message(path)))
+        .collect()
+}
+
+fn linter_config(lines: &[&str]) -> Vec<Diagnostic> {
+    let attributes = lines.iter().map(|line| line.trim()).collect::<HashSet<_>>();
+    let missing = STD_LINTER_ATTRIBS.iter().filter(|attrib| {
+        let attribute = format!("#![{attrib}]");
+        !attributes.contains(attribute.as_str())
+    });
+    let missing = missing.map(|attrib| format!("#![{attrib}]")).collect_vec();
+    if missing.is_empty() {
+        vec![]
+    } else {
+        let message = format!("Missing standard linter configuration: {}.", missing.join(", "));
+        vec![Diagnostic::new(1, Rule::LinterConfig, message)]
+    }
+}
+
+fn module_doc(lines: &[&str]) -> Vec<Diagnostic> {
+    let first = lines.iter().find(|line| !line.trim().is_empty());
+    if first.map_or(false, |line| line.starts_with("//!")) {
+        vec![]
+    } else {
+        vec![Diagnostic::new(1, Rule::ModuleDoc, "Missing module documentation.")]
+    }
+}
+
+
+
+// ===========
+// === Fix ===
+// ===========
+
+/// Fix the violations of the given rule in the file contents.
+///
+/// The linter configuration is fixed by formatting the whole file header, as
+/// [`crate::process_file_content`] does.
+pub fn fix(input: &str, rule: Rule, is_main_file: bool) -> Result<String> {
+    match rule {
+        Rule::SectionSpacing => Ok(fix_section_spacing(input)),
+        Rule::LinterConfig if is_main_file => crate::process_file_content(input.into(), true),
+        Rule::LinterConfig => Ok(input.into()),
+        Rule::StarImport | Rule::ModuleDoc =>
+            bail!("The rule '{rule}' cannot be fixed automatically."),
+    }
+}
+
+fn fix_section_spacing(input: &str) -> String {
+    let lines = input.lines().collect_vec();
+    let headers = section_headers(&lines);
+    let mut out = Vec::with_capacity(lines.len());
+    let mut headers = headers.iter().peekable();
+    for (index, line) in lines.iter().enumerate() {
+        match headers.peek() {
+            Some(header) if header.index == index => {
+                if let Some(spacing) = header.spacing {
+                    out.truncate(out.len() - spacing);
+                    out.extend(std::iter::repeat(String::new()).take(header.expected_spacing()));
+                }
+                out.push(header.bar());
+            }
+            Some(header) if header.index + 2 == index => {
+                out.push(header.bar());
+                headers.next();
+            }
+            _ => out.push(line.to_string()),
+        }
+    }
+    let trailing_newline = if input.ends_with('\n') { "\n" } else { "" };
+    out.join("\n") + trailing_newline
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#"//! Module documentation.
+
+// === Standard Linter Configuration ===
+#![deny(non_ascii_idents)]
+#![warn(unsafe_code)]
+#![allow(clippy::bool_to_int_with_if)]
+#![allow(clippy::let_and_return)]
+
+use crate::prelude::*;
+use crate::model::*;
+
+
+// ============
+// === Export ===
+// ============
+
+pub mod model;
+
+// ============
+// === Main ===
+// ============
+
+mod tests {
+    // =============
+    // === Tests ===
+    // =============
+}
+"#;
+
+    #[test]
+    fn violations_are_reported() {
+        let diagnostics = check(Path::new("lib.rs"), INPUT, true);
+        let found = diagnostics.iter().map(|d| (d.line, d.rule)).collect_vec();
+        let expected =
+            vec![(10, Rule::StarImport), (13, Rule::SectionSpacing), (19, Rule::SectionSpacing)];
+        assert_eq!(found, expected);
+        let missing_config = check(Path::new("main.rs"), "//! Docs.\n", true);
+        assert_eq!(missing_config.iter().map(|d| d.rule).collect_vec(), vec![Rule::LinterConfig]);
+        let missing_doc = check(Path::new("lib.rs"), "// Not a doc.\n", false);
+        assert_eq!(missing_doc.iter().map(|d| d.rule).collect_vec(), vec![Rule::ModuleDoc]);
+    }
+
+    #[test]
+    fn section_spacing_is_fixed() -> Result {
+        let fixed = fix(INPUT, Rule::SectionSpacing, true)?;
+        let expected = INPUT
+            .replace("// ============\n// === Export", "// ==============\n// === Export")
+            .replace("// === Export ===\n// ============", "// === Export ===\n// ==============")
+            .replace("pub mod model;\n\n", "pub mod model;\n\n\n\n");
+        assert_eq!(fixed, expected);
+        assert!(check(Path::new("lib.rs"), &fixed, true)
+            .iter()
+            .all(|d| d.rule == Rule::StarImport));
+        Ok(())
+    }
+}
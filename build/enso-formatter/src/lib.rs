@@ -3,11 +3,9 @@
 //! - Sorting imports into groups (e.g. local imports, pub imports, etc.).
 //! - Sorting module attributes into groups.
 //! - Adding standard lint configuration to `lib.rs` and `main.rs` files.
-//! - (Currently disabled) Emitting warnings about star imports that are not ending with `traits::*`
-//!   nor `prelude::*`.
 //!
-//! Possible extensions, not implemented yet:
-//! - Sections are automatically keeping spacing.
+//! Additionally, the [`check`] module implements rules that are only reported, and can be fixed
+//! on demand, like the section spacing or the star import policy.
 
 // === Features ===
 #![feature(exit_status_error)]
@@ -51,6 +49,13 @@ use std::hash::Hasher;
 use tokio as _;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod check;
+
+
 
 // =================
 // === Constants ===
@@ -338,6 +343,11 @@ pub enum Action {
     Format,
     DryRun,
     FormatAndCheck,
+    /// Report the [`check::Rule`] violations without modifying the files. Fails if any of them is
+    /// an error.
+    Check,
+    /// Fix the violations of the given [`check::Rule`], without applying other formatting.
+    Fix(check::Rule),
 }
 
 
@@ -370,6 +380,11 @@ pub struct RustSourcePath {
 #[context("Enso Formatter: failed to process root path '{}'.", path.as_ref().display())]
 pub async fn process_path(path: impl AsRef<Path> + Copy, action: Action) -> Result {
     let paths = discover_paths(path)?;
+    match action {
+        Action::Check => return check_paths(&paths).await,
+        Action::Fix(rule) => return fix_paths(&paths, rule).await,
+        _ => {}
+    }
     let total = paths.len();
     let mut hash_map = HashMap::<PathBuf, u64>::new();
     for (i, sub_path) in paths.iter().enumerate() {
@@ -396,6 +411,39 @@ pub async fn process_path(path: impl AsRef<Path> + Copy, action: Action) -> Resu
     Ok(())
 }
 
+/// Check all the given files against the [`check::Rule`]s and print the found violations.
+pub async fn check_paths(paths: &[RustSourcePath]) -> Result {
+    let mut errors = 0;
+    let mut warnings = 0;
+    for sub_path in paths {
+        let input = fs::read_to_string(&sub_path.path).await?;
+        for diagnostic in check::check(&sub_path.path, &input, sub_path.is_main) {
+            println!("{}", diagnostic.display_in(&sub_path.path));
+            match diagnostic.rule.severity() {
+                check::Severity::Error => errors += 1,
+                check::Severity::Warning => warnings += 1,
+            }
+        }
+    }
+    info!("Checked {} files: {errors} errors, {warnings} warnings.", paths.len());
+    ensure!(errors == 0, "{errors} code style errors found.");
+    Ok(())
+}
+
+/// Fix the violations of the given rule in all the given files.
+pub async fn fix_paths(paths: &[RustSourcePath], rule: check::Rule) -> Result {
+    ensure!(rule.is_fixable(), "The rule '{rule}' cannot be fixed automatically.");
+    for sub_path in paths {
+        let input = fs::read_to_string(&sub_path.path).await?;
+        let output = check::fix(&input, rule, sub_path.is_main)?;
+        if output != input {
+            info!("Fixing {} in {}.", rule, sub_path.path.display());
+            fs::write(&sub_path.path, output).await?;
+        }
+    }
+    Ok(())
+}
+
 /// Discover all paths containing Rust sources, recursively.
 #[context("Discovering Rust paths failed for '{}' failed.", path.as_ref().display())]
 pub fn discover_paths(path: impl AsRef<Path>) -> Result<Vec<RustSourcePath>> {
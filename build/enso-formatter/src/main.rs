@@ -6,11 +6,34 @@
 
 use ide_ci::prelude::*;
 
+use enso_formatter::Action;
 
 
+
+/// Command line flag that makes the formatter only report the code style violations.
+const CHECK_FLAG: &str = "--check";
+
+/// Command line flag that makes the formatter fix the violations of the rule given as the next
+/// argument.
+const FIX_FLAG: &str = "--fix";
+
+fn parse_action(mut args: impl Iterator<Item = String>) -> Result<Action> {
+    match args.next().as_deref() {
+        None => Ok(Action::Format),
+        Some(CHECK_FLAG) => Ok(Action::Check),
+        Some(FIX_FLAG) => {
+            let rule =
+                args.next().with_context(|| format!("Missing rule name after {FIX_FLAG}."))?;
+            Ok(Action::Fix(rule.parse()?))
+        }
+        Some(other) => bail!("Unknown argument '{other}'. Expected {CHECK_FLAG} or {FIX_FLAG}."),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result {
     setup_logging()?;
+    let action = parse_action(std::env::args().skip(1))?;
     info!("Enso Formatter running in {}", ide_ci::env::current_dir()?.display());
-    enso_formatter::process_path(".", enso_formatter::Action::Format).await
+    enso_formatter::process_path(".", action).await
 }
@@ -8,6 +8,15 @@ use crate::global::spawn;
 use futures::executor;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod virtual_time;
+
+pub use virtual_time::TestWithVirtualTimeExecutor;
+
+
 
 /// A fixture for tests which makes able to run part of tests as asynchronous tasks in
 /// LocalPoolExecutor. All spawned task will be run before dropping this structure - if some
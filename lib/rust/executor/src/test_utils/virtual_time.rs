@@ -0,0 +1,558 @@
+//! A deterministic test executor with a virtual clock.
+//!
+//! [`TestWithVirtualTimeExecutor`] runs spawned tasks in a single thread, in the order they were
+//! woken, or in a pseudo-random order derived from a seed (see
+//! [`TestWithVirtualTimeExecutor::set_up_randomized`]). Time does not flow on its own: the test
+//! moves the [`VirtualClock`] forward explicitly, and all timers created by [`enso_web::sleep`]
+//! in the meantime complete as if the time has passed.
+
+use crate::prelude::*;
+
+use crate::global::set_spawner;
+
+use futures::future::LocalBoxFuture;
+use futures::task::waker;
+use futures::task::ArcWake;
+use futures::task::LocalFutureObj;
+use futures::task::LocalSpawn;
+use futures::task::SpawnError;
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::panic::Location;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use std::time::Duration;
+
+
+
+// ====================
+// === VirtualClock ===
+// ====================
+
+#[derive(Debug, Default)]
+struct ClockData {
+    now:           Duration,
+    next_timer_id: u64,
+    /// Wakers of the pending [`Sleep`] futures, ordered by their deadlines.
+    timers:        BTreeMap<(Duration, u64), Waker>,
+}
+
+/// A clock whose time is moved forward only explicitly, by [`VirtualClock::set_now`].
+///
+/// The time is measured from the clock's creation.
+#[derive(Clone, CloneRef, Debug, Default)]
+pub struct VirtualClock {
+    data: Rc<RefCell<ClockData>>,
+}
+
+impl VirtualClock {
+    /// The current virtual time.
+    pub fn now(&self) -> Duration {
+        self.data.borrow().now
+    }
+
+    /// A future completing once the clock reaches the current time plus the `duration`.
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        let mut data = self.data.borrow_mut();
+        let id = data.next_timer_id;
+        data.next_timer_id += 1;
+        Sleep { clock: self.clone_ref(), deadline: data.now + duration, id }
+    }
+
+    /// The earliest deadline of the pending timers.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        self.data.borrow().timers.keys().next().map(|(deadline, _)| *deadline)
+    }
+
+    /// Number of the pending timers.
+    pub fn pending_timers(&self) -> usize {
+        self.data.borrow().timers.len()
+    }
+
+    /// Move the clock to the given time, waking the timers which deadlines have passed.
+    ///
+    /// Panics if the time is earlier than [`Self::now`].
+    pub fn set_now(&self, now: Duration) {
+        let expired = {
+            let mut data = self.data.borrow_mut();
+            assert!(now >= data.now, "The virtual clock cannot go back in time.");
+            data.now = now;
+            let pending = data.timers.split_off(&(now, u64::MAX));
+            mem::replace(&mut data.timers, pending)
+        };
+        for waker in expired.into_values() {
+            waker.wake();
+        }
+    }
+}
+
+
+// === Sleep ===
+
+/// A future returned by [`VirtualClock::sleep`].
+#[derive(Debug)]
+pub struct Sleep {
+    clock:    VirtualClock,
+    deadline: Duration,
+    id:       u64,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut data = self.clock.data.borrow_mut();
+        if data.now >= self.deadline {
+            data.timers.remove(&(self.deadline, self.id));
+            Poll::Ready(())
+        } else {
+            data.timers.insert((self.deadline, self.id), cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        // Dropped timers (e.g. timeouts of completed requests) should not keep the clock busy.
+        self.clock.data.borrow_mut().timers.remove(&(self.deadline, self.id));
+    }
+}
+
+
+
+// ==================
+// === Scheduling ===
+// ==================
+
+/// The policy of choosing which of the woken tasks is polled next.
+#[derive(Clone, Copy, Debug)]
+pub enum Scheduling {
+    /// The tasks are polled in the order they were woken.
+    Fifo,
+    /// The tasks are polled in a pseudo-random order, fully determined by the seed.
+    Randomized {
+        /// The seed of the pseudo-random sequence, reported on test failures.
+        seed: u64,
+    },
+}
+
+/// A [SplitMix64](https://prng.di.unimi.it/splitmix64.c) generator. It's good enough for
+/// shuffling tasks and keeps this crate free of dependencies.
+#[derive(Clone, Copy, Debug)]
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+
+
+// ============
+// === Task ===
+// ============
+
+/// An identifier of a task spawned in the [`TestWithVirtualTimeExecutor`].
+pub type TaskId = usize;
+
+struct Task {
+    name:     String,
+    location: Option<&'static Location<'static>>,
+    polls:    usize,
+    future:   LocalBoxFuture<'static, ()>,
+}
+
+impl Debug for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Task")
+            .field("name", &self.name)
+            .field("location", &self.location)
+            .field("polls", &self.polls)
+            .finish()
+    }
+}
+
+/// Information about a task which has not finished yet.
+#[derive(Clone, Debug)]
+pub struct PendingTask {
+    /// The task identifier, assigned in the spawning order.
+    pub id:       TaskId,
+    /// The task's name; tasks spawned by the global spawner are named after their id.
+    pub name:     String,
+    /// Where the task was spawned, if known.
+    pub location: Option<&'static Location<'static>>,
+    /// How many times the task has been polled.
+    pub polls:    usize,
+}
+
+impl Display for PendingTask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{} `{}` (polled {} times", self.id, self.name, self.polls)?;
+        if let Some(location) = self.location {
+            write!(f, ", spawned at {location}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+
+// === ReadyQueue ===
+
+/// Ids of the woken tasks. Shared with the wakers, which are required to be thread-safe.
+type ReadyQueue = Arc<Mutex<VecDeque<TaskId>>>;
+
+struct TaskWaker {
+    id:    TaskId,
+    ready: ReadyQueue,
+}
+
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        let mut ready = arc_self.ready.lock().unwrap();
+        if !ready.contains(&arc_self.id) {
+            ready.push_back(arc_self.id);
+        }
+    }
+}
+
+
+// === Spawner ===
+
+/// A handle for spawning tasks in the [`TestWithVirtualTimeExecutor`]. It is also set as the
+/// global spawner (see [`crate::global`]).
+#[derive(Clone, CloneRef, Debug, Default)]
+pub struct Spawner {
+    spawned: Rc<RefCell<Vec<Task>>>,
+}
+
+impl Spawner {
+    fn spawn(
+        &self,
+        name: String,
+        location: Option<&'static Location<'static>>,
+        future: LocalBoxFuture<'static, ()>,
+    ) {
+        self.spawned.borrow_mut().push(Task { name, location, polls: 0, future });
+    }
+}
+
+impl LocalSpawn for Spawner {
+    fn spawn_local_obj(&self, future: LocalFutureObj<'static, ()>) -> Result<(), SpawnError> {
+        self.spawn(default(), None, future.boxed_local());
+        Ok(())
+    }
+}
+
+
+
+// ===================================
+// === TestWithVirtualTimeExecutor ===
+// ===================================
+
+/// A fixture for tests running asynchronous tasks which await timers. See the
+/// [module docs](self).
+///
+/// Like [`super::TestWithLocalPoolExecutor`], it is set as the global spawner, and all spawned
+/// tasks must be finished before the fixture is dropped, or a panic is raised, listing the
+/// pending tasks.
+#[derive(Debug)]
+pub struct TestWithVirtualTimeExecutor {
+    tasks:      BTreeMap<TaskId, Task>,
+    next_id:    TaskId,
+    ready:      ReadyQueue,
+    spawner:    Spawner,
+    clock:      VirtualClock,
+    scheduling: Scheduling,
+    rng:        Rng,
+}
+
+impl TestWithVirtualTimeExecutor {
+    /// Set up the test fixture, polling tasks in the order they were woken.
+    pub fn set_up() -> Self {
+        Self::new(Scheduling::Fifo)
+    }
+
+    /// Set up the test fixture, polling woken tasks in a pseudo-random order determined by the
+    /// seed. Running a test with many seeds helps finding bugs depending on the tasks' order.
+    pub fn set_up_randomized(seed: u64) -> Self {
+        Self::new(Scheduling::Randomized { seed })
+    }
+
+    fn new(scheduling: Scheduling) -> Self {
+        let seed = match scheduling {
+            Scheduling::Fifo => 0,
+            Scheduling::Randomized { seed } => seed,
+        };
+        let spawner = Spawner::default();
+        let clock = VirtualClock::default();
+        set_spawner(spawner.clone_ref());
+        let timer = clock.clone_ref();
+        let timer = move |duration| Box::pin(timer.sleep(duration)) as enso_web::SleepFuture;
+        enso_web::set_timer_override(Some(Rc::new(timer)));
+        let tasks = default();
+        let ready = default();
+        let rng = Rng { state: seed };
+        Self { tasks, next_id: 0, ready, spawner, clock, scheduling, rng }
+    }
+
+    /// The executor's clock, which is also used by [`enso_web::sleep`] while the fixture exists.
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// The current virtual time.
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// A handle for spawning tasks in this executor.
+    pub fn spawner(&self) -> Spawner {
+        self.spawner.clone_ref()
+    }
+
+    /// Spawn a new task, named after the place it was spawned in.
+    #[track_caller]
+    pub fn run_task<Task>(&mut self, task: Task)
+    where Task: Future<Output = ()> + 'static {
+        let location = Location::caller();
+        self.spawner.spawn(location.to_string(), Some(location), task.boxed_local());
+    }
+
+    /// Spawn a new task with a name, used in reports of the pending tasks.
+    #[track_caller]
+    pub fn run_named_task<Task>(&mut self, name: impl Into<String>, task: Task)
+    where Task: Future<Output = ()> + 'static {
+        let location = Location::caller();
+        self.spawner.spawn(name.into(), Some(location), task.boxed_local());
+    }
+
+    /// Check if there are any uncompleted tasks.
+    pub fn has_ongoing_task(&self) -> bool {
+        !self.tasks.is_empty() || !self.spawner.spawned.borrow().is_empty()
+    }
+
+    /// The tasks which have not finished yet, in the spawning order.
+    pub fn pending_tasks(&self) -> Vec<PendingTask> {
+        let pending = self.tasks.iter().map(|(&id, task)| PendingTask {
+            id,
+            name: task.name.clone(),
+            location: task.location,
+            polls: task.polls,
+        });
+        pending.collect()
+    }
+
+    /// Runs the woken tasks until no more progress can be made without moving the clock forward.
+    pub fn run_until_stalled(&mut self) {
+        loop {
+            self.accept_spawned();
+            match self.next_ready() {
+                Some(id) => self.poll_task(id),
+                None => break,
+            }
+        }
+    }
+
+    /// Move the clock forward by the `duration`, running the tasks woken by each timer before
+    /// firing the next one. Timers created meanwhile are also fired, if their deadlines fall
+    /// before the new time.
+    pub fn advance_time(&mut self, duration: Duration) {
+        let target = self.now() + duration;
+        self.run_until_stalled();
+        while let Some(deadline) = self.clock.next_deadline().filter(|d| *d <= target) {
+            self.clock.set_now(deadline);
+            self.run_until_stalled();
+        }
+        self.clock.set_now(target);
+        self.run_until_stalled();
+    }
+
+    /// Run the tasks, moving the clock to the subsequent timers' deadlines, until there are no
+    /// pending timers. Note that it never returns if some task sets timers in an infinite loop.
+    pub fn run_until_idle(&mut self) {
+        self.run_until_stalled();
+        while let Some(deadline) = self.clock.next_deadline() {
+            self.clock.set_now(deadline);
+            self.run_until_stalled();
+        }
+    }
+
+    /// Run all tasks until executor is stalled, and run callback then.
+    ///
+    /// See [`super::TestWithLocalPoolExecutor::when_stalled`].
+    pub fn when_stalled<Callback>(&mut self, callback: Callback)
+    where Callback: FnOnce() {
+        self.run_until_stalled();
+        if self.has_ongoing_task() {
+            callback();
+        }
+    }
+
+    /// Runs all tasks until stalled. Panics, if some tasks remains then unfinished, listing them.
+    pub fn expect_finished(&mut self) {
+        self.run_until_stalled();
+        if self.has_ongoing_task() {
+            let pending = self.pending_tasks().iter().map(|task| format!("\n  {task}")).join("");
+            panic!(
+                "The tasks are not complete at {:?} ({:?}, {} pending timers):{pending}",
+                self.now(),
+                self.scheduling,
+                self.clock.pending_timers()
+            );
+        }
+    }
+
+    /// Runs all tasks until stalled and tries retrieving value from the future.
+    /// If the future cannot complete, panics.
+    pub fn expect_completion<R>(&mut self, fut: impl Future<Output = R>) -> R {
+        self.run_until_stalled();
+        fut.boxed_local().expect_ready()
+    }
+
+    /// Run all tasks until stalled and try retrieving value from the future.
+    /// Panics if the future is able to complete then.
+    pub fn expect_pending<R>(&mut self, fut: impl Future<Output = R>) {
+        self.run_until_stalled();
+        fut.boxed_local().expect_pending()
+    }
+
+    /// Move the tasks spawned since the last call to the ready queue.
+    fn accept_spawned(&mut self) {
+        let spawned = mem::take(&mut *self.spawner.spawned.borrow_mut());
+        for mut task in spawned {
+            let id = self.next_id;
+            self.next_id += 1;
+            if task.name.is_empty() {
+                task.name = format!("task #{id}");
+            }
+            self.tasks.insert(id, task);
+            self.ready.lock().unwrap().push_back(id);
+        }
+    }
+
+    fn next_ready(&mut self) -> Option<TaskId> {
+        let mut ready = self.ready.lock().unwrap();
+        match self.scheduling {
+            Scheduling::Fifo => ready.pop_front(),
+            Scheduling::Randomized { .. } if ready.is_empty() => None,
+            Scheduling::Randomized { .. } => ready.remove(self.rng.below(ready.len())),
+        }
+    }
+
+    fn poll_task(&mut self, id: TaskId) {
+        let ready = self.ready.clone();
+        let finished = self.tasks.get_mut(&id).map_or(false, |task| {
+            task.polls += 1;
+            trace!("Polling task {id} ({}), poll {}.", task.name, task.polls);
+            let waker = waker(Arc::new(TaskWaker { id, ready }));
+            let mut context = Context::from_waker(&waker);
+            task.future.as_mut().poll(&mut context).is_ready()
+        });
+        if finished {
+            let task = self.tasks.remove(&id);
+            trace!("Task {id} ({}) finished.", task.map(|task| task.name).unwrap_or_default());
+        }
+    }
+}
+
+impl Drop for TestWithVirtualTimeExecutor {
+    fn drop(&mut self) {
+        enso_web::set_timer_override(None);
+        // We should be able to finish test.
+        if !std::thread::panicking() {
+            self.expect_finished();
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::global::spawn;
+
+    #[test]
+    fn timers_fire_in_virtual_time() {
+        let mut fixture = TestWithVirtualTimeExecutor::set_up();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        for (name, delay) in [("slow", 30), ("fast", 10)] {
+            let log = log.clone_ref();
+            fixture.run_named_task(name, async move {
+                enso_web::sleep(Duration::from_millis(delay)).await;
+                log.borrow_mut().push(name);
+            });
+        }
+        fixture.advance_time(Duration::from_millis(20));
+        assert_eq!(*log.borrow(), ["fast"]);
+        assert_eq!(fixture.pending_tasks().iter().map(|task| &task.name).collect_vec(), ["slow"]);
+        fixture.run_until_idle();
+        assert_eq!(*log.borrow(), ["fast", "slow"]);
+        assert_eq!(fixture.now(), Duration::from_millis(30));
+    }
+
+    #[test]
+    fn timers_set_while_advancing_are_fired() {
+        let mut fixture = TestWithVirtualTimeExecutor::set_up();
+        let finished_at = Rc::new(Cell::new(None));
+        let clock = fixture.clock().clone_ref();
+        let result = finished_at.clone_ref();
+        spawn(async move {
+            for _ in 0..3 {
+                enso_web::sleep(Duration::from_millis(10)).await;
+            }
+            result.set(Some(clock.now()));
+        });
+        fixture.advance_time(Duration::from_millis(35));
+        assert_eq!(finished_at.get(), Some(Duration::from_millis(30)));
+        assert_eq!(fixture.now(), Duration::from_millis(35));
+    }
+
+    #[test]
+    fn randomized_scheduling_is_reproducible() {
+        let order_for_seed = |seed| {
+            let mut fixture = TestWithVirtualTimeExecutor::set_up_randomized(seed);
+            let order = Rc::new(RefCell::new(Vec::new()));
+            for index in 0..8 {
+                let order = order.clone_ref();
+                fixture.run_task(async move { order.borrow_mut().push(index) });
+            }
+            fixture.expect_finished();
+            let order = order.borrow().clone();
+            order
+        };
+        assert_eq!(order_for_seed(7), order_for_seed(7));
+        let mut order = order_for_seed(7);
+        order.sort();
+        assert_eq!(order, (0..8).collect_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "`blocked`")]
+    fn reporting_pending_tasks() {
+        let mut fixture = TestWithVirtualTimeExecutor::set_up();
+        fixture.run_named_task("blocked", futures::future::pending());
+        fixture.expect_finished();
+    }
+}
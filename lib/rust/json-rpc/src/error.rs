@@ -89,4 +89,9 @@ pub enum HandlingError {
     /// decode it.
     #[fail(display = "Failed to decode a notification: {}.", _0)]
     InvalidNotification(#[cause] serde_json::Error),
+
+    /// Server sent a request for a method that has no handler registered. The request has been
+    /// replied with the "method not found" error.
+    #[fail(display = "Server called a method with no registered handler: {}.", _0)]
+    UnhandledRequest(String),
 }
@@ -10,6 +10,7 @@ use crate::ensogl::Duration;
 use crate::error::HandlingError;
 use crate::error::RpcError;
 use crate::messages;
+use crate::messages::error_code;
 use crate::messages::Id;
 use crate::transport::Transport;
 use crate::transport::TransportEvent;
@@ -23,6 +24,8 @@ use futures::FutureExt;
 use futures::Stream;
 use futures::StreamExt;
 use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::value::RawValue;
 use std::future::Future;
 
 
@@ -101,6 +104,10 @@ pub enum Event<N> {
     Error(HandlingError),
     /// Notification received.
     Notification(N),
+    /// Connection has been lost, and the transport is trying to restore it.
+    Reconnecting,
+    /// Connection has been restored.
+    Reconnected,
 }
 
 
@@ -115,6 +122,43 @@ pub enum Event<N> {
 pub type OngoingCalls = HashMap<Id, oneshot::Sender<ReplyMessage>>;
 
 
+// === RequestHandlers ===
+
+/// Handler of a request made by the peer. Decodes the call parameters and computes the result to
+/// be sent back.
+pub type RequestHandler = Rc<dyn Fn(&RawValue) -> messages::Result<serde_json::Value>>;
+
+/// Container that stores handlers of requests made by the peer, by method name.
+#[derive(Clone, Default)]
+pub struct RequestHandlers(HashMap<String, RequestHandler>);
+
+impl Debug for RequestHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+
+// === CancellationHook ===
+
+/// Callback invoked when a request is cancelled by the client, i.e. when its future is dropped or
+/// times out before the reply arrives. It receives the id of the cancelled request and can be used
+/// to inform the peer that it may stop processing it.
+pub struct CancellationHook<Notification>(Rc<dyn Fn(&Handler<Notification>, Id)>);
+
+impl<Notification> Clone for CancellationHook<Notification> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<Notification> Debug for CancellationHook<Notification> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CancellationHook")
+    }
+}
+
+
 
 // ===============
 // === Handler ===
@@ -139,15 +183,19 @@ shared! { Handler
 #[derive(Debug)]
 pub struct HandlerData<Notification> {
     /// Timeout for futures.
-    timeout         : Duration,
+    timeout           : Duration,
     /// Ongoing calls.
-    ongoing_calls   : OngoingCalls,
+    ongoing_calls     : OngoingCalls,
+    /// Handlers of calls made by the peer.
+    request_handlers  : RequestHandlers,
+    /// Called when an ongoing call is cancelled by the client.
+    cancellation_hook : Option<CancellationHook<Notification>>,
     /// Handle to send outgoing events.
-    outgoing_events : Option<UnboundedSender<Event<Notification>>>,
+    outgoing_events   : Option<UnboundedSender<Event<Notification>>>,
     /// Provides identifiers for requests.
-    id_generator    : IdGenerator,
+    id_generator      : IdGenerator,
     /// Transports text messages between this handler and the peer.
-    transport       : Box<dyn Transport>,
+    transport         : Box<dyn Transport>,
 }
 
 
@@ -236,11 +284,13 @@ impl<Notification> Handler<Notification> {
     /// `Transport` must be functional (e.g. not in the process of opening).
     pub fn new(transport: impl Transport + 'static) -> Handler<Notification> {
         let data = HandlerData {
-            timeout:         crate::constants::TIMEOUT,
-            ongoing_calls:   default(),
-            id_generator:    IdGenerator::new(),
-            transport:       Box::new(transport),
-            outgoing_events: None,
+            timeout:           crate::constants::TIMEOUT,
+            ongoing_calls:     default(),
+            request_handlers:  default(),
+            cancellation_hook: None,
+            id_generator:      IdGenerator::new(),
+            transport:         Box::new(transport),
+            outgoing_events:   None,
         };
         Handler { rc: Rc::new(RefCell::new(data)) }
     }
@@ -290,18 +340,32 @@ impl<Notification> Handler<Notification> {
         &self,
         id: Id,
         message_json: &str,
+    ) -> impl Future<Output = Result<Returned>> {
+        let ret = self.register_ongoing_request(id);
+        if self.send_text_message(message_json).is_err() {
+            // If message cannot be send, future ret must be cancelled.
+            self.remove_ongoing_request(id);
+        }
+        ret
+    }
+
+    /// Registers an ongoing request and returns a `Future` that shall yield its reply.
+    ///
+    /// The future fails if the reply does not arrive before the timeout. If the future is dropped
+    /// (or times out) before the reply arrives, the request is cancelled, see
+    /// [`Self::cancel_request`].
+    fn register_ongoing_request<Returned: DeserializeOwned>(
+        &self,
+        id: Id,
     ) -> impl Future<Output = Result<Returned>> {
         let (sender, receiver) = oneshot::channel::<ReplyMessage>();
-        let ret = receiver.map(|result_or_cancel| {
+        let cancel_guard = CancelOnDrop::new(self, id);
+        let ret = receiver.map(move |result_or_cancel| {
+            cancel_guard.disarm();
             let result = result_or_cancel?;
             decode_result(result)
         });
-
         self.insert_ongoing_request(id, sender);
-        if self.send_text_message(message_json).is_err() {
-            // If message cannot be send, future ret must be cancelled.
-            self.remove_ongoing_request(id);
-        }
 
         let millis = self.timeout().as_millis();
         future::select(ret, sleep(self.timeout()).boxed_local()).map(move |either| match either {
@@ -310,6 +374,65 @@ impl<Notification> Handler<Notification> {
         })
     }
 
+    /// Creates a new, empty batch of requests. See [`Batch`].
+    pub fn batch(&self) -> Batch<Notification> {
+        Batch::new(self)
+    }
+
+    /// Cancels an ongoing request: its reply will not be awaited anymore, and the cancellation
+    /// hook is called. Does nothing if the request is not ongoing.
+    ///
+    /// This is called automatically when a future returned by [`Self::open_request`] (or similar)
+    /// is dropped or times out before receiving a reply.
+    pub fn cancel_request(&self, id: Id) {
+        if self.remove_ongoing_request(id).is_some() {
+            self.rc.borrow_mut().transport.request_cancelled(id);
+            let hook = self.rc.borrow().cancellation_hook.clone();
+            if let Some(CancellationHook(hook)) = hook {
+                hook(self, id);
+            }
+        }
+    }
+
+    /// Sets a hook called whenever a request is cancelled by the client. See
+    /// [`CancellationHook`].
+    pub fn set_cancellation_hook(&self, hook: impl Fn(&Handler<Notification>, Id) + 'static) {
+        self.rc.borrow_mut().cancellation_hook = Some(CancellationHook(Rc::new(hook)));
+    }
+
+    /// Registers a handler of requests made by the peer, calling the method named `In::NAME`.
+    ///
+    /// The handler gets the decoded call parameters, and its result is sent back to the peer as
+    /// the reply. If the handler returns [`RpcError::RemoteError`], its contents are replied as
+    /// the error; any other error is replied as an internal error. Replaces any handler
+    /// previously registered for the same method.
+    ///
+    /// Requests for methods with no registered handler are replied with the "method not found"
+    /// error, and reported as [`HandlingError::UnhandledRequest`].
+    pub fn on_request<In>(&self, handler: impl Fn(In) -> Result<In::Returned> + 'static)
+    where
+        In: api::RemoteMethodCall + DeserializeOwned,
+        In::Returned: Serialize, {
+        let handler = move |params: &RawValue| match serde_json::from_str::<In>(params.get()) {
+            Ok(input) => match handler(input)
+                .and_then(|ret| serde_json::to_value(ret).map_err(RpcError::from))
+            {
+                Ok(ret) => messages::Result::new_success(ret),
+                Err(RpcError::RemoteError(error)) => messages::Result::Error { error },
+                Err(error) => {
+                    let code = error_code::INTERNAL_ERROR;
+                    messages::Result::new_error_simple(code, error.to_string())
+                }
+            },
+            Err(error) => {
+                let code = error_code::INVALID_PARAMS;
+                messages::Result::new_error_simple(code, error.to_string())
+            }
+        };
+        let handlers = &mut self.rc.borrow_mut().request_handlers;
+        handlers.0.insert(In::NAME.into(), Rc::new(handler));
+    }
+
     /// Deal with `Response` message from the peer.
     ///
     /// It shall be either matched with an open request or yield an error.
@@ -347,17 +470,50 @@ impl<Notification> Handler<Notification> {
         }
     }
 
+    /// Deal with `Request` message from the peer.
+    ///
+    /// The request is passed to the handler registered for its method (see [`Self::on_request`])
+    /// and the result is sent back to the peer.
+    #[profile(Debug)]
+    pub fn process_request(&self, message: messages::Request<messages::MethodCall<Box<RawValue>>>) {
+        let messages::Request { id, call } = message;
+        let handler = self.rc.borrow().request_handlers.0.get(&call.method).cloned();
+        let result = match handler {
+            Some(handler) => handler(&*call.params),
+            None => {
+                let code = error_code::METHOD_NOT_FOUND;
+                let message = format!("Method not found: {}.", call.method);
+                self.error_occurred(HandlingError::UnhandledRequest(call.method));
+                messages::Result::new_error_simple(code, message)
+            }
+        };
+        let response = messages::Message::new(messages::Response { id, result });
+        let serialized_response = serde_json::to_string(&response).unwrap();
+        if let Err(error) = self.send_text_message(&serialized_response) {
+            warn!("Failed to send the reply to the request {id}: {error}");
+        }
+    }
+
     /// Deal with incoming text message from the peer.
     ///
-    /// The message must conform either to the `Response` or to the
-    /// `Notification` JSON-serialized format. Otherwise, an error is raised.
+    /// The message must conform either to the `Response`, `Request` or
+    /// `Notification` JSON-serialized format, or be a batch of such messages.
+    /// Otherwise, an error is raised.
     #[profile(Debug)]
     pub fn process_incoming_message(&self, message: String)
     where Notification: DeserializeOwned {
-        match messages::decode_incoming_message(&message) {
-            Ok(messages::IncomingMessage::Response(response)) => self.process_response(response),
-            Ok(messages::IncomingMessage::Notification(notification)) =>
-                self.process_notification(notification),
+        match messages::decode_incoming_messages(&message) {
+            Ok(messages) =>
+                for message in messages {
+                    match message {
+                        messages::IncomingMessage::Response(response) =>
+                            self.process_response(response),
+                        messages::IncomingMessage::Notification(notification) =>
+                            self.process_notification(notification),
+                        messages::IncomingMessage::Request(request) =>
+                            self.process_request(request),
+                    }
+                },
             Err(err) => self.error_occurred(HandlingError::InvalidMessage(err)),
         }
     }
@@ -383,6 +539,9 @@ impl<Notification> Handler<Notification> {
                 self.clear_ongoing_requests();
                 self.emit_event(Event::Closed);
             }
+            // Ongoing calls are kept, as the transport either replays or fails them.
+            TransportEvent::Reconnecting => self.emit_event(Event::Reconnecting),
+            TransportEvent::Reconnected => self.emit_event(Event::Reconnected),
         }
     }
 
@@ -412,3 +571,133 @@ impl<Notification> Handler<Notification> {
         })
     }
 }
+
+
+
+// ===================
+// === CancelOnDrop ===
+// ===================
+
+/// Cancels an ongoing request when dropped, unless disarmed first.
+///
+/// It is owned by the future awaiting the request's reply, so dropping that future cancels the
+/// request.
+#[derive(Debug)]
+struct CancelOnDrop<Notification> {
+    handler: WeakHandler<Notification>,
+    id:      Id,
+    armed:   bool,
+}
+
+impl<Notification> CancelOnDrop<Notification> {
+    fn new(handler: &Handler<Notification>, id: Id) -> Self {
+        Self { handler: handler.downgrade(), id, armed: true }
+    }
+
+    /// Consume the guard without cancelling the request.
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl<Notification> Drop for CancelOnDrop<Notification> {
+    fn drop(&mut self) {
+        if self.armed {
+            if let Some(handler) = self.handler.upgrade() {
+                handler.cancel_request(self.id);
+            }
+        }
+    }
+}
+
+
+
+// =============
+// === Batch ===
+// =============
+
+/// A set of requests sent to the peer together, as a single JSON-RPC batch message.
+///
+/// The futures yielding the replies are returned when the requests are added to the batch; they
+/// shall complete once the batch is sent and the peer replies. If the batch is dropped without
+/// being sent, its requests fail with [`RpcError::LostConnection`].
+#[derive(Debug)]
+pub struct Batch<Notification> {
+    handler:  Handler<Notification>,
+    ids:      Vec<Id>,
+    messages: Vec<String>,
+}
+
+impl<Notification> Batch<Notification> {
+    /// Create a new, empty batch of requests to be sent by the given handler.
+    pub fn new(handler: &Handler<Notification>) -> Self {
+        Self { handler: handler.clone(), ids: default(), messages: default() }
+    }
+
+    /// Adds a request to the batch and returns a `Future` that shall yield its reply, see
+    /// [`Handler::open_request`].
+    pub fn open_request<In: api::RemoteMethodCall>(
+        &mut self,
+        input: In,
+    ) -> impl Future<Output = Result<In::Returned>> {
+        let id = self.handler.generate_new_id();
+        let message = api::into_request_message(input, id);
+        let serialized_message = serde_json::to_string(&message).unwrap();
+        self.add_message(id, serialized_message)
+    }
+
+    /// Adds a request to the batch and returns a `Future` that shall yield its reply, see
+    /// [`Handler::open_request_with_json`].
+    pub fn open_request_with_json<Returned: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        input: &RawValue,
+    ) -> impl Future<Output = Result<Returned>> {
+        let id = self.handler.generate_new_id();
+        let message = messages::Message::new_request(id, method_name, input);
+        let serialized_message = serde_json::to_string(&message).unwrap();
+        self.add_message(id, serialized_message)
+    }
+
+    fn add_message<Returned: DeserializeOwned>(
+        &mut self,
+        id: Id,
+        message_json: String,
+    ) -> impl Future<Output = Result<Returned>> {
+        self.ids.push(id);
+        self.messages.push(message_json);
+        self.handler.register_ongoing_request(id)
+    }
+
+    /// Number of requests in the batch.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Checks if there are no requests in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Sends all the requests in a single message. Sending an empty batch does nothing.
+    ///
+    /// If the message cannot be sent, all the requests fail with [`RpcError::LostConnection`].
+    pub fn send(mut self) -> std::result::Result<(), failure::Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let message_json = format!("[{}]", self.messages.join(","));
+        self.handler.send_text_message(&message_json)?;
+        // The requests are sent, so they shall not be removed when the batch is dropped.
+        self.ids.clear();
+        Ok(())
+    }
+}
+
+impl<Notification> Drop for Batch<Notification> {
+    fn drop(&mut self) {
+        for id in mem::take(&mut self.ids) {
+            self.handler.remove_ongoing_request(id);
+        }
+    }
+}
@@ -22,6 +22,9 @@
 ///     fn expect_call_me_please
 ///     (&mut self, my_number_is:String,result:json_rpc::api::Result<()>) { /* impl */ }
 /// ```
+///
+/// Additionally, a `Batch` struct is generated, with the same methods as `API`. It is created by
+/// `Client::batch` and sends all the calls made on it in a single JSON-RPC batch message.
 #[macro_export]
 macro_rules! make_rpc_methods {
     (
@@ -83,6 +86,18 @@ macro_rules! make_rpc_methods {
             pub fn set_timeout(&mut self, timeout:std::time::Duration) {
                 self.handler.borrow().set_timeout(timeout);
             }
+
+            /// The JSON-RPC protocol handler used by this client. It may be used e.g. to register
+            /// handlers of requests made by the server.
+            pub fn handler(&self) -> Handler<Notification> {
+                self.handler.borrow().clone()
+            }
+
+            /// Create a new, empty batch of calls, to be sent to the server in a single message.
+            pub fn batch(&self) -> Batch {
+                let batch = self.handler.borrow().batch();
+                Batch { batch }
+            }
         }
 
         impl API for Client {
@@ -121,6 +136,36 @@ macro_rules! make_rpc_methods {
             }
         }
 
+        /// A batch of calls sent to the server in a single JSON-RPC batch message.
+        ///
+        /// Each method adds a call to the batch and returns a future yielding its result. The
+        /// futures complete after the batch is sent with [`Batch::send`] and the server replies.
+        #[derive(Debug)]
+        pub struct Batch {
+            batch : json_rpc::handler::Batch<Notification>,
+        }
+
+        impl Batch {
+            $(
+                $(#[doc = $doc])+
+                #[allow(clippy::ptr_arg)]
+                pub fn $method(&mut self $(,$param_name:&$param_ty)*)
+                -> std::pin::Pin<Box<dyn Future<Output=Result<$result>>>> {
+                    json_rpc::log::rpc_request(stringify!($method));
+                    let phantom    = ZST();
+                    let input      = $method_input { phantom, $($param_name),* };
+                    let input_json = serde_json::value::to_raw_value(&input).unwrap();
+                    let name       = $method_input::NAME;
+                    Box::pin(self.batch.open_request_with_json(name, &input_json))
+                }
+            )*
+
+            /// Send all the calls in the batch to the server.
+            pub fn send(self) -> std::result::Result<(), failure::Error> {
+                self.batch.send()
+            }
+        }
+
         $(
             /// Structure transporting method arguments.
             #[derive(Serialize,Debug,PartialEq, Eq)]
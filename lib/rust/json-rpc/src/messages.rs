@@ -189,20 +189,47 @@ pub struct Error<Payload = serde_json::Value> {
     pub data:    Option<Payload>,
 }
 
-/// A message that can come from Server to Client — either a response or
-/// notification.
+/// Standard error codes, as defined by the JSON-RPC 2.0 specification.
+pub mod error_code {
+    /// The method does not exist or is not available.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// Invalid method parameters.
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// Internal JSON-RPC error.
+    pub const INTERNAL_ERROR: i64 = -32603;
+}
+
+/// A message that can come from Server to Client — either a response, a
+/// notification or a request.
 #[derive(Debug)]
 pub enum IncomingMessage {
     /// A response to a call made by client.
     Response(Response<Box<serde_json::value::RawValue>>),
     /// A notification call (initiated by the server).
     Notification(Notification<Box<serde_json::value::RawValue>>),
+    /// A call initiated by the server, expecting a response from the client.
+    Request(Request<MethodCall<Box<serde_json::value::RawValue>>>),
+}
+
+/// Partially decodes incoming text, which may be either a single message or a batch (a JSON
+/// array of messages).
+///
+/// Fails if any message of a batch cannot be decoded.
+#[profile(Debug)]
+pub fn decode_incoming_messages(text: &str) -> serde_json::Result<Vec<IncomingMessage>> {
+    let is_batch = text.trim_start().starts_with('[');
+    if is_batch {
+        let messages: Vec<Box<serde_json::value::RawValue>> = serde_json::from_str(text)?;
+        messages.iter().map(|message| decode_incoming_message(message.get())).collect()
+    } else {
+        decode_incoming_message(text).map(|message| vec![message])
+    }
 }
 
 /// Partially decodes incoming message.
 ///
 /// This checks if has `jsonrpc` version string, and whether it is a
-/// response or a notification.
+/// response, a request or a notification.
 #[profile(Debug)]
 pub fn decode_incoming_message(message: &str) -> serde_json::Result<IncomingMessage> {
     type Payload = serde_json::value::RawValue;
@@ -220,6 +247,10 @@ pub fn decode_incoming_message(message: &str) -> serde_json::Result<IncomingMess
         result:  Option<Option<Box<Payload>>>,
         #[serde(default)]
         error:   Option<Error>,
+        #[serde(default)]
+        method:  Option<String>,
+        #[serde(default)]
+        params:  Option<Box<Payload>>,
     }
     fn deserialize_some<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
     where
@@ -228,13 +259,20 @@ pub fn decode_incoming_message(message: &str) -> serde_json::Result<IncomingMess
         Deserialize::deserialize(deserializer).map(Some)
     }
     let raw: RawMessage = serde_json::from_str(message)?;
-    Ok(match (raw.id, raw.result, raw.error) {
-        (Some(id), Some(result), None) => {
+    Ok(match (raw.id, raw.result, raw.error, raw.method) {
+        (Some(id), Some(result), None, _) => {
             let result = result.unwrap_or_default();
             IncomingMessage::Response(Response { id, result: Result::Success(Success { result }) })
         }
-        (Some(id), None, Some(error)) =>
+        (Some(id), None, Some(error), _) =>
             IncomingMessage::Response(Response { id, result: Result::Error { error } }),
+        (Some(id), None, None, Some(method)) => {
+            let params = match raw.params {
+                Some(params) => params,
+                None => serde_json::value::to_raw_value(&serde_json::Value::Null)?,
+            };
+            IncomingMessage::Request(Request::new(id, MethodCall { method, params }))
+        }
         _ => {
             let payload: Box<serde_json::value::RawValue> = serde_json::from_str(message)?;
             IncomingMessage::Notification(Notification(payload))
@@ -378,6 +416,33 @@ mod tests {
         assert_eq!(got_value, Version::V2);
     }
 
+    #[test]
+    fn decode_incoming_request() {
+        let text = r#"{"jsonrpc":"2.0","id":3,"method":"ping","params":{"n":1}}"#;
+        match decode_incoming_message(text) {
+            Ok(IncomingMessage::Request(request)) => {
+                assert_eq!(request.id, Id(3));
+                assert_eq!(request.call.method, "ping");
+                assert_eq!(request.call.params.get(), r#"{"n":1}"#);
+            }
+            other => panic!("Invalid decoding result of {text}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn decode_incoming_batch() {
+        let text = r#"[
+            {"jsonrpc":"2.0","id":0,"result":null},
+            {"jsonrpc":"2.0","method":"ping","params":{}}
+        ]"#;
+        let messages = decode_incoming_messages(text).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0], IncomingMessage::Response(Response { id: Id(0), .. })));
+        assert!(matches!(messages[1], IncomingMessage::Notification(_)));
+        let single = r#"{"jsonrpc":"2.0","method":"ping","params":{}}"#;
+        assert_eq!(decode_incoming_messages(single).unwrap().len(), 1);
+    }
+
     #[test]
     fn decode_incoming_error_message_text() {
         let text = r#"{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"Service error"}}"#;
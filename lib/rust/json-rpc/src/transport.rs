@@ -2,12 +2,21 @@
 
 use crate::prelude::*;
 
+use crate::messages::Id;
+
 use failure::Error;
 use futures::channel::mpsc::unbounded;
 use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::mpsc::UnboundedSender;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod reconnecting;
+
+
 
 /// A transport that facilitate JSON-RPC protocol.
 ///
@@ -26,6 +35,11 @@ pub trait Transport: Debug {
     /// Set up a channel which shall be used to receive events from the `Transport`.
     fn set_event_transmitter(&mut self, transmitter: UnboundedSender<TransportEvent>);
 
+    /// Informs that the reply to the request with the given id is no longer awaited, because the
+    /// request was cancelled or timed out. Transports keeping track of the sent requests should
+    /// forget it.
+    fn request_cancelled(&mut self, _id: Id) {}
+
     /// Sets up a stream's receiver yielding `TransportEvent`s.
     fn establish_event_stream(&mut self) -> UnboundedReceiver<TransportEvent> {
         let (event_transmitter, event_receiver) = unbounded();
@@ -46,4 +60,9 @@ pub enum TransportEvent {
     /// A socket has been closed by the peer.
     /// This event may be also emitted when reconnecting has failed.
     Closed,
+    /// The connection has been lost and the transport is trying to restore it. Messages sent in
+    /// the meantime will be delivered after reconnecting.
+    Reconnecting,
+    /// The connection has been restored after being lost.
+    Reconnected,
 }
@@ -0,0 +1,747 @@
+//! A [`Transport`] wrapper that restores the connection when it is lost.
+//!
+//! When the underlying transport is closed, [`ReconnectingTransport`] establishes a new one using a
+//! [`Connector`], retrying with exponential backoff. Each new connection is first initialized with
+//! the configured handshake requests (e.g. `session/initProtocolConnection`); then the requests
+//! that were awaiting a reply when the connection was lost are sent again, provided that their
+//! methods are marked as idempotent. Other lost requests are replied with an error
+//! ([`REQUEST_LOST_ERROR_CODE`]), as we cannot know if the peer has processed them.
+//!
+//! Messages sent while reconnecting are queued and delivered once the connection is restored.
+//! Requests cancelled by the user (see [`Transport::request_cancelled`]) are neither sent again
+//! nor replied with an error.
+
+use crate::prelude::*;
+
+use crate::constants;
+use crate::ensogl::sleep;
+use crate::messages;
+use crate::messages::Id;
+use crate::messages::IncomingMessage;
+use crate::messages::Message;
+use crate::transport::Transport;
+use crate::transport::TransportEvent;
+
+use failure::Error;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::mpsc::UnboundedSender;
+use futures::future;
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use futures::StreamExt;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::Duration;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The code of the error replied to requests that were lost together with the connection and
+/// that were not sent again, because their methods are not idempotent.
+///
+/// This is one of the codes reserved by JSON-RPC for implementation-defined errors.
+pub const REQUEST_LOST_ERROR_CODE: i64 = -32099;
+
+
+
+// ==============
+// === Errors ===
+// ==============
+
+/// Errors emitted by the `ReconnectingTransport`.
+#[derive(Clone, Debug, Fail)]
+pub enum ReconnectingError {
+    /// The connection is lost and there will be no more attempts to restore it.
+    #[fail(display = "Cannot send message, because the connection is closed.")]
+    Closed,
+    /// Binary messages cannot be queued while reconnecting.
+    #[fail(display = "Cannot send binary message while reconnecting.")]
+    Reconnecting,
+    /// The new connection was closed before completing the handshake.
+    #[fail(display = "Connection closed during the handshake.")]
+    ClosedDuringHandshake,
+    /// The peer did not reply to a handshake request in time.
+    #[fail(display = "Handshake request {} timed out.", _0)]
+    HandshakeTimeout(String),
+    /// The peer replied to a handshake request with an error.
+    #[fail(display = "Handshake request {} failed: {}.", method, message)]
+    #[allow(missing_docs)]
+    HandshakeRejected { method: String, message: String },
+}
+
+
+
+// ==============
+// === Config ===
+// ==============
+
+/// Delays between subsequent reconnection attempts, growing exponentially.
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    /// Delay before the first attempt.
+    pub initial:    Duration,
+    /// Upper bound of the delay.
+    pub max:        Duration,
+    /// Factor by which the delay grows with each failed attempt.
+    pub multiplier: f64,
+}
+
+impl Backoff {
+    /// The delay before the attempt with the given index (counting from 0).
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let factor = self.multiplier.powi(attempt.min(i32::MAX as usize) as i32);
+        let delay = self.initial.as_secs_f64() * factor;
+        Duration::from_secs_f64(delay.min(self.max.as_secs_f64()))
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial:    Duration::from_millis(200),
+            max:        Duration::from_secs(10),
+            multiplier: 2.0,
+        }
+    }
+}
+
+
+// === HandshakeRequest ===
+
+/// A request initializing a new connection, sent before any other message.
+#[derive(Clone, Debug)]
+pub struct HandshakeRequest {
+    /// Name of the remote method.
+    pub method: String,
+    /// Method arguments.
+    pub params: serde_json::Value,
+}
+
+impl HandshakeRequest {
+    /// Constructor.
+    pub fn new(method: impl Into<String>, params: serde_json::Value) -> Self {
+        Self { method: method.into(), params }
+    }
+}
+
+
+// === Config ===
+
+/// Configuration of the [`ReconnectingTransport`].
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Delays between reconnection attempts.
+    pub backoff:            Backoff,
+    /// After this many failed attempts in a row, the connection is considered closed for good.
+    /// If `None`, the transport tries to reconnect indefinitely.
+    pub max_attempts:       Option<usize>,
+    /// Requests sent on each new connection, in order. Each must succeed before the next one is
+    /// sent; if any fails, the attempt to reconnect is considered failed.
+    pub handshake:          Vec<HandshakeRequest>,
+    /// How long to wait for the reply to each handshake request.
+    pub handshake_timeout:  Duration,
+    /// Methods whose requests can be safely sent again after reconnecting.
+    pub idempotent_methods: HashSet<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            backoff:            default(),
+            max_attempts:       None,
+            handshake:          default(),
+            handshake_timeout:  constants::TIMEOUT,
+            idempotent_methods: default(),
+        }
+    }
+}
+
+
+
+// =================
+// === Connector ===
+// =================
+
+/// Establishes a new connection, returning its transport. The transport must be functional (e.g.
+/// not in the process of opening).
+#[derive(Clone)]
+pub struct Connector(Rc<dyn Fn() -> LocalBoxFuture<'static, Result<Box<dyn Transport>, Error>>>);
+
+impl Connector {
+    /// Create a connector from a function establishing a new connection.
+    pub fn new<F, T>(f: impl Fn() -> F + 'static) -> Self
+    where
+        F: Future<Output = Result<T, Error>> + 'static,
+        T: Transport + 'static, {
+        Self(Rc::new(move || {
+            f().map(|transport| transport.map(|t| Box::new(t) as Box<dyn Transport>)).boxed_local()
+        }))
+    }
+
+    fn connect(&self) -> LocalBoxFuture<'static, Result<Box<dyn Transport>, Error>> {
+        (self.0)()
+    }
+}
+
+impl Debug for Connector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Connector")
+    }
+}
+
+
+
+// =============
+// === State ===
+// =============
+
+/// State of the connection of the [`ReconnectingTransport`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// Messages are sent to the peer.
+    Connected,
+    /// The connection has been lost; messages are queued until it is restored.
+    Reconnecting,
+    /// The connection has been lost for good.
+    Closed,
+}
+
+
+
+// =============
+// === Model ===
+// =============
+
+/// A request sent over the current connection, and not replied yet.
+#[derive(Clone, Debug)]
+struct SentRequest {
+    method:  String,
+    message: String,
+}
+
+/// Fields of a JSON-RPC message needed to identify requests and responses.
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id:     Option<Id>,
+    #[serde(default)]
+    method: Option<String>,
+}
+
+/// Parse the text message, which may be a batch, into its messages' envelopes and texts.
+fn envelopes(text: &str) -> Vec<(Envelope, String)> {
+    let parse = |text: &str| serde_json::from_str::<Envelope>(text).ok();
+    if text.trim_start().starts_with('[') {
+        let messages: Vec<Box<serde_json::value::RawValue>> =
+            serde_json::from_str(text).unwrap_or_default();
+        let messages = messages.iter().map(|message| message.get());
+        messages
+            .filter_map(|text| parse(text).map(|envelope| (envelope, text.to_owned())))
+            .collect()
+    } else {
+        parse(text).map(|envelope| (envelope, text.to_owned())).into_iter().collect()
+    }
+}
+
+#[derive(Debug)]
+struct Model {
+    config:            Config,
+    connector:         Connector,
+    state:             State,
+    /// The transport of the current connection; `None` while reconnecting.
+    transport:         Option<Box<dyn Transport>>,
+    /// Events of the initial connection, until the runner takes them.
+    initial_events:    Option<UnboundedReceiver<TransportEvent>>,
+    /// Events sink of the transport's user.
+    event_transmitter: Option<UnboundedSender<TransportEvent>>,
+    /// Requests sent over the current connection and not replied yet.
+    ongoing:           BTreeMap<Id, SentRequest>,
+    /// Text messages sent while reconnecting.
+    queued:            Vec<String>,
+}
+
+impl Model {
+    fn emit(&self, event: TransportEvent) {
+        if let Some(transmitter) = self.event_transmitter.as_ref() {
+            channel::emit(transmitter, event);
+        }
+    }
+
+    /// Remember requests sent to the peer, so they can be replayed after reconnecting.
+    fn track_sent(&mut self, text: &str) {
+        for (envelope, message) in envelopes(text) {
+            if let (Some(id), Some(method)) = (envelope.id, envelope.method) {
+                self.ongoing.insert(id, SentRequest { method, message });
+            }
+        }
+    }
+
+    /// Forget the request whose reply is no longer awaited. If it is still queued, it is not sent
+    /// at all, unless it is batched with other messages.
+    fn forget(&mut self, id: Id) {
+        self.ongoing.remove(&id);
+        self.queued.retain(|text| match envelopes(text).as_slice() {
+            [(Envelope { id: Some(queued_id), .. }, _)] => *queued_id != id,
+            _ => true,
+        });
+    }
+
+    /// Forget requests replied by the peer.
+    fn track_received(&mut self, text: &str) {
+        for (envelope, _) in envelopes(text) {
+            if let (Some(id), None) = (envelope.id, envelope.method) {
+                self.ongoing.remove(&id);
+            }
+        }
+    }
+
+    fn send_text(&mut self, text: &str) -> Result<(), Error> {
+        match self.state {
+            State::Connected => {
+                if let Some(transport) = self.transport.as_mut() {
+                    transport.send_text(text)?;
+                }
+                self.track_sent(text);
+                Ok(())
+            }
+            State::Reconnecting => {
+                self.queued.push(text.to_owned());
+                Ok(())
+            }
+            State::Closed => Err(ReconnectingError::Closed.into()),
+        }
+    }
+
+    fn send_binary(&mut self, data: &[u8]) -> Result<(), Error> {
+        match (self.state, self.transport.as_mut()) {
+            (State::Connected, Some(transport)) => transport.send_binary(data),
+            (State::Closed, _) => Err(ReconnectingError::Closed.into()),
+            _ => Err(ReconnectingError::Reconnecting.into()),
+        }
+    }
+
+    /// Pass an event of the current connection to the user.
+    fn forward(&mut self, event: TransportEvent) {
+        if let TransportEvent::TextMessage(text) = &event {
+            self.track_received(text);
+        }
+        self.emit(event);
+    }
+
+    /// Start reconnecting. Lost requests that cannot be replayed are replied with an error.
+    fn connection_lost(&mut self) {
+        warn!("Connection lost, reconnecting.");
+        self.state = State::Reconnecting;
+        self.transport = None;
+        self.emit(TransportEvent::Reconnecting);
+        let idempotent = &self.config.idempotent_methods;
+        let ongoing = mem::take(&mut self.ongoing).into_iter();
+        let (replayed, lost): (BTreeMap<_, _>, Vec<_>) =
+            ongoing.partition(|(_, request)| idempotent.contains(&request.method));
+        self.ongoing = replayed;
+        for (id, request) in lost {
+            let code = REQUEST_LOST_ERROR_CODE;
+            let text = format!("Connection lost before receiving a reply to {}.", request.method);
+            let error: messages::ResponseMessage<()> = Message::new_error(id, code, text, None);
+            let error = serde_json::to_string(&error).unwrap();
+            self.emit(TransportEvent::TextMessage(error));
+        }
+    }
+
+    /// Use the new connection, sending the replayed and queued messages.
+    fn restore(&mut self, transport: Box<dyn Transport>) {
+        info!("Connection restored.");
+        self.state = State::Connected;
+        self.transport = Some(transport);
+        let replayed = self.ongoing.values().map(|request| request.message.clone()).collect_vec();
+        let queued = mem::take(&mut self.queued);
+        for message in replayed.into_iter().chain(queued) {
+            if let Err(error) = self.send_text(&message) {
+                warn!("Failed to send message after reconnecting: {error}");
+            }
+        }
+        self.emit(TransportEvent::Reconnected);
+    }
+
+    /// Give up reconnecting.
+    fn close(&mut self) {
+        error!("Failed to restore the connection.");
+        self.state = State::Closed;
+        self.transport = None;
+        self.ongoing.clear();
+        self.queued.clear();
+        self.emit(TransportEvent::Closed);
+    }
+}
+
+
+
+// =============================
+// === ReconnectingTransport ===
+// =============================
+
+/// A [`Transport`] which restores the connection when it is lost. See the [module docs](self).
+///
+/// The future returned by [`Self::runner`] performs the reconnecting and must be run for the
+/// transport to work.
+#[derive(Clone, CloneRef, Debug)]
+pub struct ReconnectingTransport {
+    model: Rc<RefCell<Model>>,
+}
+
+impl ReconnectingTransport {
+    /// Wrap a functional transport. When its connection is lost, a new one will be established by
+    /// the `connector`.
+    pub fn new(transport: impl Transport + 'static, connector: Connector, config: Config) -> Self {
+        let mut transport: Box<dyn Transport> = Box::new(transport);
+        let initial_events = Some(transport.establish_event_stream());
+        let model = Model {
+            config,
+            connector,
+            state: State::Connected,
+            transport: Some(transport),
+            initial_events,
+            event_transmitter: None,
+            ongoing: default(),
+            queued: default(),
+        };
+        Self { model: Rc::new(RefCell::new(model)) }
+    }
+
+    /// The current state of the connection.
+    pub fn state(&self) -> State {
+        self.model.borrow().state
+    }
+
+    /// Returns a `Future` that passes the events of the current connection to the user and
+    /// restores the connection when it is lost. It finishes when the connection is closed for
+    /// good, or when the transport is dropped.
+    ///
+    /// Should be called once; subsequent calls return futures that finish immediately.
+    pub fn runner(&self) -> impl Future<Output = ()> {
+        let initial_events = self.model.borrow_mut().initial_events.take();
+        let model = Rc::downgrade(&self.model);
+        async move {
+            let mut events = match initial_events {
+                Some(events) => events,
+                None => return,
+            };
+            loop {
+                while let Some(event) = events.next().await {
+                    let Some(model) = model.upgrade() else { return };
+                    if let TransportEvent::Closed = event {
+                        break;
+                    }
+                    model.borrow_mut().forward(event);
+                }
+                let Some(strong_model) = model.upgrade() else { return };
+                strong_model.borrow_mut().connection_lost();
+                drop(strong_model);
+                match Self::reconnect(&model).await {
+                    Some(new_events) => events = new_events,
+                    None => return,
+                }
+            }
+        }
+    }
+
+    /// Try to establish a new connection until succeeded or the attempts are exhausted.
+    ///
+    /// Returns the events of the new connection.
+    async fn reconnect(model: &Weak<RefCell<Model>>) -> Option<UnboundedReceiver<TransportEvent>> {
+        for attempt in 0.. {
+            let (connector, delay) = {
+                let model = model.upgrade()?;
+                let mut model = model.borrow_mut();
+                if model.config.max_attempts.map_or(false, |max| attempt >= max) {
+                    model.close();
+                    return None;
+                }
+                (model.connector.clone(), model.config.backoff.delay(attempt))
+            };
+            if !delay.is_zero() {
+                sleep(delay).await;
+            }
+            info!("Reconnecting, attempt {}.", attempt + 1);
+            let result = match connector.connect().await {
+                Ok(mut transport) => {
+                    let mut events = transport.establish_event_stream();
+                    let handshake = Self::handshake(model, transport.as_mut(), &mut events).await;
+                    handshake.map(|()| (transport, events))
+                }
+                Err(error) => Err(error),
+            };
+            match result {
+                Ok((transport, events)) => {
+                    model.upgrade()?.borrow_mut().restore(transport);
+                    return Some(events);
+                }
+                Err(error) => warn!("Reconnecting attempt {} failed: {error}", attempt + 1),
+            }
+        }
+        None
+    }
+
+    /// Send the handshake requests over the new connection, one by one, awaiting the replies.
+    async fn handshake(
+        model: &Weak<RefCell<Model>>,
+        transport: &mut dyn Transport,
+        events: &mut UnboundedReceiver<TransportEvent>,
+    ) -> Result<(), Error> {
+        let (requests, timeout) = match model.upgrade() {
+            Some(model) => {
+                let model = model.borrow();
+                (model.config.handshake.clone(), model.config.handshake_timeout)
+            }
+            None => return Ok(()),
+        };
+        for (index, request) in requests.into_iter().enumerate() {
+            // Negative ids never collide with the ids of the requests made by `Handler`.
+            let id = Id(-1 - index as i64);
+            let message = Message::new_request(id, &request.method, &request.params);
+            transport.send_text(&serde_json::to_string(&message)?)?;
+            let reply = Self::handshake_reply(model, events, id, request.method.clone());
+            match future::select(reply.boxed_local(), sleep(timeout).boxed_local()).await {
+                future::Either::Left((result, _)) => result?,
+                future::Either::Right(_) =>
+                    return Err(ReconnectingError::HandshakeTimeout(request.method).into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for the reply to the handshake request with the given id. Other events are passed to
+    /// the user.
+    async fn handshake_reply(
+        model: &Weak<RefCell<Model>>,
+        events: &mut UnboundedReceiver<TransportEvent>,
+        id: Id,
+        method: String,
+    ) -> Result<(), Error> {
+        while let Some(event) = events.next().await {
+            let reply = match &event {
+                TransportEvent::TextMessage(text) =>
+                    match messages::decode_incoming_message(text) {
+                        Ok(IncomingMessage::Response(response)) if response.id == id =>
+                            Some(response),
+                        _ => None,
+                    },
+                TransportEvent::Closed => break,
+                _ => None,
+            };
+            match reply.map(|response| response.result) {
+                Some(messages::Result::Success(_)) => return Ok(()),
+                Some(messages::Result::Error { error }) => {
+                    let message = error.message;
+                    return Err(ReconnectingError::HandshakeRejected { method, message }.into());
+                }
+                None =>
+                    if let Some(model) = model.upgrade() {
+                        model.borrow_mut().forward(event);
+                    },
+            }
+        }
+        Err(ReconnectingError::ClosedDuringHandshake.into())
+    }
+}
+
+impl Transport for ReconnectingTransport {
+    fn send_text(&mut self, message: &str) -> Result<(), Error> {
+        self.model.borrow_mut().send_text(message)
+    }
+
+    fn send_binary(&mut self, message: &[u8]) -> Result<(), Error> {
+        self.model.borrow_mut().send_binary(message)
+    }
+
+    fn set_event_transmitter(&mut self, transmitter: UnboundedSender<TransportEvent>) {
+        self.model.borrow_mut().event_transmitter = Some(transmitter);
+    }
+
+    fn request_cancelled(&mut self, id: Id) {
+        self.model.borrow_mut().forget(id);
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RpcError;
+    use crate::test_util::transport::mock::MockTransport;
+    use crate::Handler;
+
+    use futures::executor::LocalPool;
+    use futures::task::LocalSpawnExt;
+
+    const HANDSHAKE_METHOD: &str = "session/initProtocolConnection";
+
+    struct Fixture {
+        first:     MockTransport,
+        next:      Rc<RefCell<Vec<MockTransport>>>,
+        transport: ReconnectingTransport,
+        events:    UnboundedReceiver<TransportEvent>,
+        pool:      LocalPool,
+    }
+
+    impl Fixture {
+        fn new(config: Config) -> Self {
+            let first = MockTransport::new();
+            let next = Rc::new(RefCell::new(Vec::<MockTransport>::new()));
+            let available = next.clone();
+            let connector = Connector::new(move || {
+                future::ready(
+                    available.borrow_mut().pop().ok_or_else(|| failure::err_msg("refused")),
+                )
+            });
+            let mut transport = ReconnectingTransport::new(first.clone(), connector, config);
+            let events = transport.establish_event_stream();
+            let pool = LocalPool::new();
+            pool.spawner().spawn_local(transport.runner()).unwrap();
+            Self { first, next, transport, events, pool }
+        }
+
+        fn config() -> Config {
+            let backoff = Backoff { initial: Duration::ZERO, ..default() };
+            let handshake = vec![HandshakeRequest::new(HANDSHAKE_METHOD, serde_json::json!({}))];
+            let idempotent_methods = ["file/read".to_owned()].into();
+            Config { backoff, handshake, idempotent_methods, ..default() }
+        }
+
+        fn expect_text_event(&mut self) -> String {
+            match self.events.expect_next() {
+                TransportEvent::TextMessage(text) => text,
+                event => panic!("Expected a text message, got {event:?}."),
+            }
+        }
+    }
+
+    fn request(id: i64, method: &str) -> String {
+        let message = Message::new_request(Id(id), method, serde_json::json!({}));
+        serde_json::to_string(&message).unwrap()
+    }
+
+    #[test]
+    fn replaying_requests_after_reconnecting() {
+        let mut fixture = Fixture::new(Fixture::config());
+        let mut second = MockTransport::new();
+        fixture.next.borrow_mut().push(second.clone());
+        let read = request(0, "file/read");
+        let write = request(1, "file/write");
+        fixture.transport.send_text(&read).unwrap();
+        fixture.transport.send_text(&write).unwrap();
+        assert_eq!(fixture.first.expect_text_message(), read);
+        assert_eq!(fixture.first.expect_text_message(), write);
+
+        fixture.first.mock_connection_closed();
+        fixture.pool.run_until_stalled();
+        assert!(matches!(fixture.events.expect_next(), TransportEvent::Reconnecting));
+        let error = fixture.expect_text_event();
+        match messages::decode_incoming_message(&error).unwrap() {
+            IncomingMessage::Response(messages::Response {
+                id,
+                result: messages::Result::Error { error },
+            }) => {
+                assert_eq!(id, Id(1));
+                assert_eq!(error.code, REQUEST_LOST_ERROR_CODE);
+            }
+            message => panic!("Expected an error response, got {message:?}."),
+        }
+        assert_eq!(fixture.transport.state(), State::Reconnecting);
+
+        // Messages sent while reconnecting are queued.
+        let queued = request(2, "file/write");
+        fixture.transport.send_text(&queued).unwrap();
+
+        let handshake: messages::RequestMessage<serde_json::Value> = second.expect_json_message();
+        assert_eq!(handshake.method, HANDSHAKE_METHOD);
+        fixture.events.expect_pending();
+        second.mock_peer_json_message(Message::new_success(handshake.id, ()));
+        fixture.pool.run_until_stalled();
+
+        assert_eq!(fixture.transport.state(), State::Connected);
+        assert!(matches!(fixture.events.expect_next(), TransportEvent::Reconnected));
+        assert_eq!(second.expect_text_message(), read);
+        assert_eq!(second.expect_text_message(), queued);
+
+        // Messages of the new connection are passed to the user.
+        let reply = r#"{"jsonrpc":"2.0","id":0,"result":null}"#;
+        second.mock_peer_text_message(reply);
+        fixture.pool.run_until_stalled();
+        assert_eq!(fixture.expect_text_event(), reply);
+    }
+
+    #[test]
+    fn forgetting_timed_out_requests() {
+        let mut fixture = Fixture::new(Fixture::config());
+        let mut second = MockTransport::new();
+        fixture.next.borrow_mut().push(second.clone());
+        let handler = Handler::<()>::new(fixture.transport.clone_ref());
+        handler.set_timeout(Duration::from_millis(10));
+        let params = serde_json::value::RawValue::from_string("{}".into()).unwrap();
+        let read = handler.open_request_with_json::<serde_json::Value>("file/read", &params);
+        let mut read = Box::pin(read);
+        read.expect_pending();
+        let read_request = fixture.first.expect_text_message();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(matches!(read.expect_err(), RpcError::TimeoutError { .. }));
+        assert!(fixture.transport.model.borrow().ongoing.is_empty());
+
+        fixture.first.mock_connection_closed();
+        fixture.pool.run_until_stalled();
+        let handshake: messages::RequestMessage<serde_json::Value> = second.expect_json_message();
+        second.mock_peer_json_message(Message::new_success(handshake.id, ()));
+        fixture.pool.run_until_stalled();
+        assert_eq!(fixture.transport.state(), State::Connected);
+        let sent = second.with_mut_data(|data| data.sent_text_msgs.clone());
+        assert!(!sent.contains(&read_request), "Timed out request was sent again: {sent:?}");
+    }
+
+    #[test]
+    fn retrying_failed_handshake() {
+        let mut fixture = Fixture::new(Fixture::config());
+        let mut second = MockTransport::new();
+        let mut third = MockTransport::new();
+        fixture.next.borrow_mut().extend([third.clone(), second.clone()]);
+
+        fixture.first.mock_connection_closed();
+        fixture.pool.run_until_stalled();
+        assert!(matches!(fixture.events.expect_next(), TransportEvent::Reconnecting));
+
+        let handshake: messages::RequestMessage<serde_json::Value> = second.expect_json_message();
+        let error: messages::ResponseMessage<()> =
+            Message::new_error(handshake.id, 1, "Not now".into(), None);
+        second.mock_peer_json_message(error);
+        fixture.pool.run_until_stalled();
+        assert_eq!(fixture.transport.state(), State::Reconnecting);
+
+        let handshake: messages::RequestMessage<serde_json::Value> = third.expect_json_message();
+        third.mock_peer_json_message(Message::new_success(handshake.id, ()));
+        fixture.pool.run_until_stalled();
+        assert_eq!(fixture.transport.state(), State::Connected);
+        assert!(matches!(fixture.events.expect_next(), TransportEvent::Reconnected));
+    }
+
+    #[test]
+    fn closing_after_exhausting_attempts() {
+        let config = Config { max_attempts: Some(3), ..Fixture::config() };
+        let mut fixture = Fixture::new(config);
+        fixture.first.mock_connection_closed();
+        fixture.pool.run_until_stalled();
+
+        assert!(matches!(fixture.events.expect_next(), TransportEvent::Reconnecting));
+        assert!(matches!(fixture.events.expect_next(), TransportEvent::Closed));
+        assert_eq!(fixture.transport.state(), State::Closed);
+        assert!(fixture.transport.send_text(&request(0, "file/read")).is_err());
+    }
+}
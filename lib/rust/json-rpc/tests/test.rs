@@ -304,3 +304,104 @@ fn test_handling_invalid_notification() {
         panic!("expected InvalidNotification error");
     }
 }
+
+#[test]
+fn test_batch_call() {
+    let mut fixture = Fixture::new();
+    let mut batch = fixture.client.handler.batch();
+    let mut fut1 = Box::pin(batch.open_request(MockRequest { i: 2 }));
+    let mut fut2 = Box::pin(batch.open_request(MockRequest { i: 3 }));
+    assert_eq!(batch.len(), 2);
+    batch.send().unwrap();
+
+    // validate a single batch message sent
+    let requests = fixture.transport.expect_json_message::<Vec<MockRequestMessage>>();
+    assert_eq!(requests.len(), 2);
+    fut1.expect_pending();
+    fut2.expect_pending();
+
+    // replies in a batch may come in any order
+    let replies: Vec<_> = requests.into_iter().rev().map(pow_impl).collect();
+    fixture.transport.mock_peer_json_message(replies);
+    fixture.pool.run_until_stalled();
+
+    assert_eq!(fut1.expect_ok().result, 4);
+    assert_eq!(fut2.expect_ok().result, 9);
+}
+
+#[test]
+fn test_dropping_unsent_batch() {
+    let mut fixture = Fixture::new();
+    let mut batch = fixture.client.handler.batch();
+    let mut fut = Box::pin(batch.open_request(MockRequest { i: 2 }));
+    drop(batch);
+
+    fixture.transport.with_mut_data(|data| assert!(data.sent_text_msgs.is_empty()));
+    if let RpcError::LostConnection = fut.expect_err() {
+    } else {
+        panic!("Expected an error to be LostConnection");
+    }
+}
+
+#[test]
+fn test_cancelling_dropped_request() {
+    let mut fixture = Fixture::new();
+    let cancelled = Rc::new(RefCell::new(Vec::new()));
+    let cancelled_by_hook = cancelled.clone();
+    let hook = move |_: &Handler<MockNotification>, id| cancelled_by_hook.borrow_mut().push(id);
+    fixture.client.handler.set_cancellation_hook(hook);
+    let mut fut = Box::pin(fixture.client.pow(8));
+    fut.expect_pending();
+    let req_msg = fixture.transport.expect_json_message::<MockRequestMessage>();
+
+    drop(fut);
+    assert_eq!(*cancelled.borrow(), vec![req_msg.id]);
+
+    // the late reply cannot be matched with any ongoing request
+    fixture.transport.mock_peer_json_message(pow_impl(req_msg));
+    fixture.pool.run_until_stalled();
+    let internal_error = fixture.client.expect_handling_error();
+    if let HandlingError::UnexpectedResponse(_) = internal_error {
+    } else {
+        panic!("Expected an error to be UnexpectedResponse");
+    }
+}
+
+#[test]
+fn test_handling_server_request() {
+    let mut fixture = Fixture::new();
+    fixture.client.handler.on_request(|input: MockRequest| {
+        let result = input.i * input.i;
+        Ok(MockResponse { result })
+    });
+    let request_id = Id(7);
+    let request = Message::new_request(request_id, MockRequest::NAME, MockRequest { i: 5 });
+    fixture.transport.mock_peer_json_message(request);
+    fixture.pool.run_until_stalled();
+
+    let reply = fixture.transport.expect_json_message::<MockResponseMessage>();
+    assert_eq!(reply.id, request_id);
+    assert_eq!(reply.result, messages::Result::new_success(MockResponse { result: 25 }));
+    fixture.client.expect_no_notification_yet();
+}
+
+#[test]
+fn test_handling_unknown_server_request() {
+    let mut fixture = Fixture::new();
+    let request = Message::new_request(Id(7), MockRequest::NAME, MockRequest { i: 5 });
+    fixture.transport.mock_peer_json_message(request);
+    fixture.pool.run_until_stalled();
+
+    let reply = fixture.transport.expect_json_message::<MockResponseMessage>();
+    if let messages::Result::Error { error } = &reply.result {
+        assert_eq!(error.code, messages::error_code::METHOD_NOT_FOUND);
+    } else {
+        panic!("Expected an error reply");
+    }
+    let internal_error = fixture.client.expect_handling_error();
+    if let HandlingError::UnhandledRequest(method) = internal_error {
+        assert_eq!(method, MockRequest::NAME);
+    } else {
+        panic!("Expected an error to be UnhandledRequest");
+    }
+}
@@ -0,0 +1,48 @@
+//! Tool that generates [Firefox Profiler](https://profiler.firefox.com/) files from profiling data.
+//!
+//! # Usage
+//!
+//! The tool reads a
+//! [JSON-formatted event log](https://github.com/enso-org/design/blob/main/epics/profiling/implementation.md#file-format)
+//! from stdin, and writes a report to stdout. The input may contain profiles of multiple processes;
+//! each is exported separately, with timestamps aligned to a common time origin.
+//!
+//! For example:
+//!
+//! ```console
+//! ~/git/enso/data $ cargo run --bin firefox < profile.json > firefox.json
+//! ```
+
+// === Standard Linter Configuration ===
+#![deny(non_ascii_idents)]
+#![warn(unsafe_code)]
+#![allow(clippy::bool_to_int_with_if)]
+#![allow(clippy::let_and_return)]
+// === Non-Standard Linter Configuration ===
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_import_braces)]
+
+use enso_profiler::format::AnyMetadata;
+use enso_profiler_data as data;
+
+
+
+// ============
+// === main ===
+// ============
+
+fn main() {
+    use std::io::Read;
+    let mut log = String::new();
+    std::io::stdin().read_to_string(&mut log).unwrap();
+    let profiles: Vec<data::Profile<AnyMetadata>> =
+        data::parse_multiprocess_profile(&log).collect::<Result<_, _>>().unwrap();
+    let profiles: Vec<_> = profiles.iter().collect();
+    let file = data::export::firefox::File::from_profiles(&profiles);
+    serde_json::to_writer(std::io::stdout(), &file).unwrap();
+}
@@ -0,0 +1,93 @@
+//! Conversion of profile data to the formats of external profile viewers.
+
+use crate::IntervalId;
+use crate::Profile;
+
+
+// ==============
+// === Export ===
+// ==============
+
+pub mod firefox;
+pub mod speedscope;
+
+
+
+// ============
+// === Edge ===
+// ============
+
+/// A boundary of an interval, for formats that represent intervals as sequences of open and close
+/// events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Edge {
+    /// The interval begins.
+    Open,
+    /// The interval ends.
+    Close,
+}
+
+/// An [`Edge`] of a particular interval, at a time given in milliseconds from the process's time
+/// origin.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct IntervalEdge {
+    pub edge:     Edge,
+    pub interval: IntervalId,
+    pub time_ms:  f64,
+}
+
+/// Produce the edges of all closed intervals in the profile, ordered so that each interval is
+/// opened after its parent and closed before it, and so that times never decrease.
+///
+/// The root interval is skipped, as it is not a real measurement. Open intervals are also skipped
+/// (as they are by Chrome DevTools), but their closed children are included.
+pub(crate) fn interval_edges<M>(profile: &Profile<M>) -> Vec<IntervalEdge> {
+    let mut edges = Vec::new();
+    let mut now = 0.0;
+    for &child in &profile.root_interval().children {
+        visit_interval(profile, child, &mut now, &mut edges);
+    }
+    edges
+}
+
+fn visit_interval<M>(
+    profile: &Profile<M>,
+    id: IntervalId,
+    now: &mut f64,
+    edges: &mut Vec<IntervalEdge>,
+) {
+    let active = &profile[id];
+    let mut children: Vec<_> = active.children.clone();
+    children.sort_by_key(|&child| profile[child].interval.start);
+    let end = active.interval.end;
+    if end.is_some() {
+        // Clamp to the latest emitted time, in case a child was logged as starting before its
+        // parent; consumers of this data require nesting to be consistent with time order.
+        *now = f64::max(*now, active.interval.start.into_ms());
+        edges.push(IntervalEdge { edge: Edge::Open, interval: id, time_ms: *now });
+    }
+    for child in children {
+        visit_interval(profile, child, now, edges);
+    }
+    if let Some(end) = end {
+        *now = f64::max(*now, end.into_ms());
+        edges.push(IntervalEdge { edge: Edge::Close, interval: id, time_ms: *now });
+    }
+}
+
+/// The offset of the profile's time origin from the Unix Epoch, in milliseconds; zero if the
+/// profile doesn't contain the information.
+pub(crate) fn time_offset_ms<M>(profile: &Profile<M>) -> f64 {
+    profile.headers.time_offset.map(|offset| offset.into_ms()).unwrap_or_default()
+}
+
+/// The earliest time origin of the given profiles, in milliseconds from the Unix Epoch. Times from
+/// different processes are made comparable by translating them to offsets from this value.
+pub(crate) fn common_time_origin_ms<M>(profiles: &[&Profile<M>]) -> f64 {
+    profiles.iter().map(|profile| time_offset_ms(profile)).reduce(f64::min).unwrap_or_default()
+}
+
+/// A human-readable name for the process that produced the profile.
+pub(crate) fn process_name<M>(profile: &Profile<M>, index: usize) -> String {
+    profile.headers.process.clone().unwrap_or_else(|| format!("Process {index}"))
+}
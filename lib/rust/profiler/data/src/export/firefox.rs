@@ -0,0 +1,612 @@
+//! Support for the processed profile format of the [Firefox Profiler](https://profiler.firefox.com/).
+//!
+//! Each process is exported as a thread. Since our data is instrumented rather than sampled, a
+//! sample is emitted whenever the active stack changes, weighted by the time until the next
+//! change (the `tracing-ms` weight type). Metadata is exported as instant markers.
+//!
+//! The format is described here:
+//! https://github.com/firefox-devtools/profiler/blob/main/docs-developer/CHANGELOG-formats.md
+//! The profiler upgrades older versions of the processed format on load; we write the version
+//! identified by [`PROCESSED_PROFILE_VERSION`].
+
+use crate::export;
+use crate::export::Edge;
+use crate::Label;
+use crate::Profile;
+
+use std::collections::HashMap;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The version of the processed profile format implemented by this module.
+pub const PROCESSED_PROFILE_VERSION: u32 = 44;
+
+/// The version of the Gecko profile format the processed format was derived from.
+const GECKO_PROFILE_VERSION: u32 = 27;
+
+/// Name of the marker schema used for metadata.
+const METADATA_MARKER_TYPE: &str = "EnsoMetadata";
+
+/// Index of the only category we use.
+const CATEGORY: usize = 0;
+
+/// Index of the only subcategory of [`CATEGORY`].
+const SUBCATEGORY: usize = 0;
+
+/// Marker phase of an instant marker.
+const PHASE_INSTANT: u8 = 0;
+
+
+
+// ============
+// === File ===
+// ============
+
+/// A profile document in the processed format.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct File {
+    /// Information about the profile as a whole.
+    pub meta:    Meta,
+    /// Shared libraries; not applicable to our data.
+    pub libs:    Vec<()>,
+    /// Web pages; not applicable to our data.
+    pub pages:   Vec<()>,
+    /// One thread per process.
+    pub threads: Vec<Thread>,
+}
+
+impl File {
+    /// Convert the given profiles, each captured by a different process.
+    ///
+    /// Timestamps of all profiles are translated to a common time origin, so that events from
+    /// different processes can be compared. Metadata is serialized to JSON to be displayed in
+    /// marker details.
+    pub fn from_profiles<M: serde::Serialize>(profiles: &[&Profile<M>]) -> Self {
+        let origin = export::common_time_origin_ms(profiles);
+        let threads = profiles
+            .iter()
+            .enumerate()
+            .map(|(i, profile)| {
+                let name = export::process_name(profile, i);
+                let offset = export::time_offset_ms(profile) - origin;
+                Thread::new(profile, name, i, offset)
+            })
+            .collect();
+        let meta = Meta::new(origin);
+        Self { meta, libs: Vec::new(), pages: Vec::new(), threads }
+    }
+}
+
+
+// === Meta ===
+
+/// Information about the profile as a whole.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Meta {
+    /// Nominal sampling interval, in milliseconds.
+    pub interval: f64,
+    /// Time origin of all threads, in milliseconds from the Unix Epoch.
+    pub start_time: f64,
+    /// Type of the main process.
+    pub process_type: u32,
+    /// Name of the profiled application.
+    pub product: String,
+    /// Whether stack walking was enabled.
+    pub stackwalk: u32,
+    /// Whether the application was a debug build.
+    pub debug: bool,
+    /// Version of the Gecko format.
+    pub version: u32,
+    /// Version of the processed format.
+    pub preprocessed_profile_version: u32,
+    /// Whether frame addresses have been resolved to symbols.
+    pub symbolicated: bool,
+    /// Categories frames can be assigned to.
+    pub categories: Vec<Category>,
+    /// Definitions of the marker types used.
+    pub marker_schema: Vec<MarkerSchema>,
+}
+
+impl Meta {
+    fn new(start_time: f64) -> Self {
+        let categories = vec![Category {
+            name:          "Other".to_owned(),
+            color:         "grey".to_owned(),
+            subcategories: vec!["Other".to_owned()],
+        }];
+        let marker_schema = vec![MarkerSchema {
+            name:          METADATA_MARKER_TYPE.to_owned(),
+            display:       vec!["marker-chart".to_owned(), "marker-table".to_owned()],
+            data:          vec![MarkerSchemaField {
+                key:    "value".to_owned(),
+                label:  "Value".to_owned(),
+                format: "string".to_owned(),
+            }],
+            tooltip_label: Some("{marker.name}".to_owned()),
+        }];
+        Self {
+            interval: 1.0,
+            start_time,
+            process_type: 0,
+            product: "Enso".to_owned(),
+            stackwalk: 0,
+            debug: false,
+            version: GECKO_PROFILE_VERSION,
+            preprocessed_profile_version: PROCESSED_PROFILE_VERSION,
+            symbolicated: true,
+            categories,
+            marker_schema,
+        }
+    }
+}
+
+/// A category frames can be assigned to.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Category {
+    /// Displayed name.
+    pub name:          String,
+    /// Color used to draw frames of the category.
+    pub color:         String,
+    /// Names of the subcategories.
+    pub subcategories: Vec<String>,
+}
+
+/// Definition of a type of marker.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkerSchema {
+    /// Value of the `type` field of marker data of this type.
+    pub name:          String,
+    /// Where markers of this type are displayed.
+    pub display:       Vec<String>,
+    /// Fields of the marker data.
+    pub data:          Vec<MarkerSchemaField>,
+    /// Template for the marker tooltip.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip_label: Option<String>,
+}
+
+/// A field of marker data.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MarkerSchemaField {
+    /// Key of the field in the marker data.
+    pub key:    String,
+    /// Displayed name of the field.
+    pub label:  String,
+    /// How the field is displayed.
+    pub format: String,
+}
+
+
+
+// ==============
+// === Thread ===
+// ==============
+
+/// Data captured by one thread. We export each process as one thread.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thread {
+    /// Gecko process type.
+    pub process_type:          String,
+    /// When the process started, relative to [`Meta::start_time`].
+    pub process_startup_time:  f64,
+    /// When the process ended, if known.
+    pub process_shutdown_time: Option<f64>,
+    /// When the thread was registered.
+    pub register_time:         f64,
+    /// When the thread was unregistered, if known.
+    pub unregister_time:       Option<f64>,
+    /// Ranges of time profiling was paused.
+    pub paused_ranges:         Vec<()>,
+    /// Name of the thread.
+    pub name:                  String,
+    /// Whether this is the main thread of its process.
+    pub is_main_thread:        bool,
+    /// Name of the process.
+    pub process_name:          String,
+    /// Identifies the process.
+    pub pid:                   String,
+    /// Identifies the thread.
+    pub tid:                   usize,
+    /// Stack samples.
+    pub samples:               SamplesTable,
+    /// Markers.
+    pub markers:               RawMarkerTable,
+    /// Stacks referenced by samples.
+    pub stack_table:           StackTable,
+    /// Frames referenced by stacks.
+    pub frame_table:           FrameTable,
+    /// Functions referenced by frames.
+    pub func_table:            FuncTable,
+    /// Resources referenced by functions; not applicable to our data.
+    pub resource_table:        ResourceTable,
+    /// Native symbols referenced by frames; not applicable to our data.
+    pub native_symbols:        NativeSymbolTable,
+    /// Strings referenced by index from the other tables.
+    pub string_array:          Vec<String>,
+}
+
+impl Thread {
+    fn new<M: serde::Serialize>(
+        profile: &Profile<M>,
+        name: String,
+        index: usize,
+        offset_ms: f64,
+    ) -> Self {
+        let mut builder = ThreadBuilder::default();
+        builder.add_samples(profile, offset_ms);
+        builder.add_metadata_markers(profile, offset_ms);
+        let ThreadBuilder { strings, stacks, frames, funcs, samples, markers, .. } = builder;
+        let end_time = samples.time.last().copied();
+        Self {
+            process_type: "default".to_owned(),
+            process_startup_time: offset_ms,
+            process_shutdown_time: end_time,
+            register_time: offset_ms,
+            unregister_time: end_time,
+            paused_ranges: Vec::new(),
+            name: name.clone(),
+            is_main_thread: true,
+            process_name: name,
+            pid: (index + 1).to_string(),
+            tid: index + 1,
+            samples,
+            markers,
+            stack_table: stacks,
+            frame_table: frames,
+            func_table: funcs,
+            resource_table: Default::default(),
+            native_symbols: Default::default(),
+            string_array: strings.strings,
+        }
+    }
+}
+
+
+// === Tables ===
+
+/// Stack samples.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplesTable {
+    /// Interpretation of [`Self::weight`].
+    pub weight_type: &'static str,
+    /// Duration each sample represents, in milliseconds.
+    pub weight:      Vec<f64>,
+    /// Index into [`StackTable`]; `None` if no measurement is active.
+    pub stack:       Vec<Option<usize>>,
+    /// Time of the sample.
+    pub time:        Vec<f64>,
+    /// Number of rows.
+    pub length:      usize,
+}
+
+/// Markers.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawMarkerTable {
+    /// Marker payload, interpreted according to the [`MarkerSchema`] named by its `type` field.
+    pub data:       Vec<Option<serde_json::Value>>,
+    /// Index into the string array.
+    pub name:       Vec<usize>,
+    /// Time of the marker.
+    pub start_time: Vec<Option<f64>>,
+    /// End time of the marker, for interval markers.
+    pub end_time:   Vec<Option<f64>>,
+    /// Type of marker: instant, interval, interval start or interval end.
+    pub phase:      Vec<u8>,
+    /// Index into [`Meta::categories`].
+    pub category:   Vec<usize>,
+    /// Number of rows.
+    pub length:     usize,
+}
+
+/// Call stacks, represented as a tree.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct StackTable {
+    /// Index into [`FrameTable`] of the innermost frame.
+    pub frame:       Vec<usize>,
+    /// Index of the stack of the caller.
+    pub prefix:      Vec<Option<usize>>,
+    /// Index into [`Meta::categories`].
+    pub category:    Vec<usize>,
+    /// Index of the subcategory.
+    pub subcategory: Vec<usize>,
+    /// Number of rows.
+    pub length:      usize,
+}
+
+/// Stack frames.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameTable {
+    /// Code address; unknown for our frames.
+    pub address:         Vec<i64>,
+    /// Depth of the inlined call.
+    pub inline_depth:    Vec<u32>,
+    /// Index into [`Meta::categories`].
+    pub category:        Vec<Option<usize>>,
+    /// Index of the subcategory.
+    pub subcategory:     Vec<Option<usize>>,
+    /// Index into [`FuncTable`].
+    pub func:            Vec<usize>,
+    /// Index into [`NativeSymbolTable`].
+    pub native_symbol:   Vec<Option<usize>>,
+    /// Identifies the web page of the frame.
+    #[serde(rename = "innerWindowID")]
+    pub inner_window_id: Vec<Option<u64>>,
+    /// JS implementation tier.
+    pub implementation:  Vec<Option<usize>>,
+    /// Line number.
+    pub line:            Vec<Option<u32>>,
+    /// Column number.
+    pub column:          Vec<Option<u32>>,
+    /// Number of rows.
+    pub length:          usize,
+}
+
+/// Functions.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuncTable {
+    /// Index into the string array.
+    pub name:            Vec<usize>,
+    /// Whether the function is JavaScript.
+    #[serde(rename = "isJS")]
+    pub is_js:           Vec<bool>,
+    /// Whether the function is relevant when filtering for JavaScript.
+    #[serde(rename = "relevantForJS")]
+    pub relevant_for_js: Vec<bool>,
+    /// Index into [`ResourceTable`], or -1.
+    pub resource:        Vec<i64>,
+    /// Index into the string array.
+    pub file_name:       Vec<Option<usize>>,
+    /// Line number.
+    pub line_number:     Vec<Option<u32>>,
+    /// Column number.
+    pub column_number:   Vec<Option<u32>>,
+    /// Number of rows.
+    pub length:          usize,
+}
+
+/// Resources, such as libraries or URLs. Always empty.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct ResourceTable {
+    /// Index of the library.
+    pub lib:           Vec<usize>,
+    /// Index into the string array.
+    pub name:          Vec<usize>,
+    /// Index into the string array.
+    pub host:          Vec<usize>,
+    /// Type of resource.
+    #[serde(rename = "type")]
+    pub resource_type: Vec<u32>,
+    /// Number of rows.
+    pub length:        usize,
+}
+
+/// Native symbols. Always empty.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeSymbolTable {
+    /// Index of the library.
+    pub lib_index:     Vec<usize>,
+    /// Address of the symbol.
+    pub address:       Vec<u64>,
+    /// Index into the string array.
+    pub name:          Vec<usize>,
+    /// Size of the function.
+    pub function_size: Vec<Option<u64>>,
+    /// Number of rows.
+    pub length:        usize,
+}
+
+
+
+// =====================
+// === ThreadBuilder ===
+// =====================
+
+/// Accumulates the tables of a [`Thread`].
+#[derive(Debug, Default)]
+struct ThreadBuilder {
+    strings:      Strings,
+    stacks:       StackTable,
+    frames:       FrameTable,
+    funcs:        FuncTable,
+    samples:      SamplesTable,
+    markers:      RawMarkerTable,
+    func_indices: HashMap<String, usize>,
+    stack_ids:    HashMap<(Option<usize>, usize), usize>,
+}
+
+impl ThreadBuilder {
+    /// Emit a sample at each time the active stack changes.
+    fn add_samples<M>(&mut self, profile: &Profile<M>, offset_ms: f64) {
+        self.samples.weight_type = "tracing-ms";
+        let mut stack: Vec<Option<usize>> = vec![None];
+        for edge in export::interval_edges(profile) {
+            match edge.edge {
+                Edge::Open => {
+                    let measurement = &profile[profile[edge.interval].measurement];
+                    let frame = self.frame(&measurement.label);
+                    let prefix = *stack.last().unwrap();
+                    let id = self.stack(prefix, frame);
+                    stack.push(Some(id));
+                }
+                Edge::Close => {
+                    stack.pop();
+                }
+            }
+            self.sample(offset_ms + edge.time_ms, *stack.last().unwrap_or(&None));
+        }
+    }
+
+    fn sample(&mut self, time: f64, stack: Option<usize>) {
+        let samples = &mut self.samples;
+        if let Some(&last_time) = samples.time.last() {
+            *samples.weight.last_mut().unwrap() = time - last_time;
+            if time == last_time {
+                // Multiple changes at the same moment; only the final state is observable.
+                *samples.stack.last_mut().unwrap() = stack;
+                return;
+            }
+        }
+        samples.weight.push(0.0);
+        samples.stack.push(stack);
+        samples.time.push(time);
+        samples.length += 1;
+    }
+
+    /// Emit an instant marker for each metadata entry.
+    fn add_metadata_markers<M: serde::Serialize>(&mut self, profile: &Profile<M>, offset_ms: f64) {
+        let mut metadata: Vec<_> = profile.metadata().collect();
+        metadata.sort_by_key(|metadata| metadata.time);
+        for metadata in metadata {
+            let value = serde_json::to_value(&metadata.data).unwrap_or_default();
+            let (name, value) = match value {
+                // Metadata types are enums; use the variant name as the marker name.
+                serde_json::Value::Object(map) if map.len() == 1 => map.into_iter().next().unwrap(),
+                value => ("Metadata".to_owned(), value),
+            };
+            let data = serde_json::json!({
+                "type": METADATA_MARKER_TYPE,
+                "value": value.to_string(),
+            });
+            let name = self.strings.index_of(&name);
+            let markers = &mut self.markers;
+            markers.data.push(Some(data));
+            markers.name.push(name);
+            markers.start_time.push(Some(offset_ms + metadata.time.into_ms()));
+            markers.end_time.push(None);
+            markers.phase.push(PHASE_INSTANT);
+            markers.category.push(CATEGORY);
+            markers.length += 1;
+        }
+    }
+
+    /// Get or create the frame for a label. Each label has one function and one frame.
+    fn frame(&mut self, label: &Label) -> usize {
+        if let Some(&index) = self.func_indices.get(&label.to_string()) {
+            return index;
+        }
+        let name = self.strings.index_of(&label.name);
+        let file_name = label.pos.as_ref().map(|pos| self.strings.index_of(&pos.file));
+        let line = label.pos.as_ref().map(|pos| pos.line);
+        let funcs = &mut self.funcs;
+        let func = funcs.length;
+        funcs.name.push(name);
+        funcs.is_js.push(false);
+        funcs.relevant_for_js.push(false);
+        funcs.resource.push(-1);
+        funcs.file_name.push(file_name);
+        funcs.line_number.push(line);
+        funcs.column_number.push(None);
+        funcs.length += 1;
+        let frames = &mut self.frames;
+        let frame = frames.length;
+        frames.address.push(-1);
+        frames.inline_depth.push(0);
+        frames.category.push(Some(CATEGORY));
+        frames.subcategory.push(Some(SUBCATEGORY));
+        frames.func.push(func);
+        frames.native_symbol.push(None);
+        frames.inner_window_id.push(None);
+        frames.implementation.push(None);
+        frames.line.push(line);
+        frames.column.push(None);
+        frames.length += 1;
+        self.func_indices.insert(label.to_string(), frame);
+        frame
+    }
+
+    /// Get or create the stack consisting of the given frame called from the given stack.
+    fn stack(&mut self, prefix: Option<usize>, frame: usize) -> usize {
+        let stacks = &mut self.stacks;
+        *self.stack_ids.entry((prefix, frame)).or_insert_with(|| {
+            stacks.frame.push(frame);
+            stacks.prefix.push(prefix);
+            stacks.category.push(CATEGORY);
+            stacks.subcategory.push(SUBCATEGORY);
+            stacks.length += 1;
+            stacks.length - 1
+        })
+    }
+}
+
+
+// === Strings ===
+
+/// Deduplicating string table.
+#[derive(Debug, Default)]
+struct Strings {
+    strings: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl Strings {
+    fn index_of(&mut self, s: &str) -> usize {
+        if let Some(&index) = self.indices.get(s) {
+            return index;
+        }
+        let index = self.strings.len();
+        self.strings.push(s.to_owned());
+        self.indices.insert(s.to_owned(), index);
+        index
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OpaqueMetadata;
+    use enso_profiler::format;
+
+    fn ms(ms: f64) -> format::Timestamp {
+        format::Timestamp::from_ms(ms)
+    }
+
+    #[test]
+    fn samples_and_markers() {
+        let mut log = format::Builder::new();
+        log.process("Ide");
+        let parent = log.create(Some(ms(0.0)), format::Parent::root(), "parent");
+        log.start(ms(1.0), parent);
+        let child = log.create(Some(ms(2.0)), parent.into(), "child");
+        log.start(ms(2.0), child);
+        log.metadata(ms(2.5), "RpcEvent", "file/write");
+        log.end(ms(3.0), child);
+        log.end(ms(4.0), parent);
+        let profile: Profile<OpaqueMetadata> = log.build_string().parse().unwrap();
+        let file = File::from_profiles(&[&profile]);
+        let thread = &file.threads[0];
+        assert_eq!(thread.name, "Ide");
+        let strings = &thread.string_array;
+        let func_names: Vec<_> =
+            thread.func_table.name.iter().map(|&i| strings[i].as_str()).collect();
+        assert_eq!(func_names, ["parent", "child"]);
+        assert_eq!(thread.stack_table.prefix, [None, Some(0)]);
+        let samples = &thread.samples;
+        assert_eq!(samples.time, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(samples.stack, [Some(0), Some(1), Some(0), None]);
+        assert_eq!(samples.weight, [1.0, 1.0, 1.0, 0.0]);
+        let markers = &thread.markers;
+        assert_eq!(markers.length, 1);
+        assert_eq!(strings[markers.name[0]], "RpcEvent");
+        assert_eq!(markers.start_time[0], Some(2.5));
+        let value = &markers.data[0].as_ref().unwrap()["value"];
+        assert_eq!(value, "\"file/write\"");
+    }
+}
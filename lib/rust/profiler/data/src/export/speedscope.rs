@@ -0,0 +1,276 @@
+//! Support for the [Speedscope](https://www.speedscope.app/) file format.
+//!
+//! Each process is exported as an *evented* profile: a sequence of frame open/close events.
+//! The schema is documented here:
+//! https://github.com/jlfwong/speedscope/blob/main/src/lib/file-format-spec.ts
+
+use crate::export;
+use crate::export::Edge;
+use crate::Label;
+use crate::Profile;
+
+use std::collections::HashMap;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The identifier of the schema implemented by this module.
+pub const SCHEMA: &str = "https://www.speedscope.app/file-format-schema.json";
+
+
+
+// ============
+// === File ===
+// ============
+
+/// A Speedscope document.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct File {
+    /// Identifies the file format; always [`SCHEMA`].
+    #[serde(rename = "$schema")]
+    pub schema:               &'static str,
+    /// Data shared by all profiles in the file.
+    pub shared:               Shared,
+    /// One profile per process.
+    pub profiles:             Vec<EventedProfile>,
+    /// Name of the document.
+    pub name:                 String,
+    /// Index of the profile to show when the file is opened.
+    pub active_profile_index: usize,
+    /// Name of the program that produced the file.
+    pub exporter:             String,
+}
+
+impl File {
+    /// Convert the given profiles, each captured by a different process.
+    ///
+    /// Timestamps of all profiles are translated to a common time origin, so that events from
+    /// different processes can be compared.
+    pub fn from_profiles<M>(profiles: &[&Profile<M>]) -> Self {
+        let origin = export::common_time_origin_ms(profiles);
+        let mut frames = Frames::default();
+        let profiles = profiles
+            .iter()
+            .enumerate()
+            .map(|(i, profile)| {
+                let name = export::process_name(profile, i);
+                let offset = export::time_offset_ms(profile) - origin;
+                EventedProfile::new(profile, name, offset, &mut frames)
+            })
+            .collect();
+        let shared = Shared { frames: frames.frames };
+        let name = "Enso profile".to_owned();
+        let exporter = concat!(env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION")).to_owned();
+        Self { schema: SCHEMA, shared, profiles, name, active_profile_index: 0, exporter }
+    }
+}
+
+
+// === Shared ===
+
+/// Data shared by all profiles in a file.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Shared {
+    /// Frames referenced by index from profile events.
+    pub frames: Vec<Frame>,
+}
+
+
+// === Frame ===
+
+/// A stack frame; corresponds to a measurement label.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Frame {
+    /// The name of the measurement.
+    pub name: String,
+    /// The source file the measurement originated in, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// The line the measurement originated at, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+}
+
+impl From<&Label> for Frame {
+    fn from(label: &Label) -> Self {
+        let name = label.name.clone();
+        let file = label.pos.as_ref().map(|pos| pos.file.clone());
+        let line = label.pos.as_ref().map(|pos| pos.line);
+        Self { name, file, line }
+    }
+}
+
+/// Deduplicating table of [`Frame`]s.
+#[derive(Debug, Default)]
+struct Frames {
+    frames:   Vec<Frame>,
+    by_label: HashMap<String, usize>,
+}
+
+impl Frames {
+    fn index_of(&mut self, label: &Label) -> usize {
+        let frames = &mut self.frames;
+        *self.by_label.entry(label.to_string()).or_insert_with(|| {
+            frames.push(label.into());
+            frames.len() - 1
+        })
+    }
+}
+
+
+
+// ======================
+// === EventedProfile ===
+// ======================
+
+/// A profile represented as a sequence of frame open/close events.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventedProfile {
+    /// Identifies the representation of the profile.
+    #[serde(rename = "type")]
+    pub profile_type: ProfileType,
+    /// Name of the process the profile was captured by.
+    pub name:         String,
+    /// Unit of [`Self::start_value`], [`Self::end_value`] and the event times.
+    pub unit:         ValueUnit,
+    /// Time the profile begins.
+    pub start_value:  f64,
+    /// Time the profile ends.
+    pub end_value:    f64,
+    /// Frame open and close events, ordered by time.
+    pub events:       Vec<Event>,
+}
+
+impl EventedProfile {
+    fn new<M>(profile: &Profile<M>, name: String, offset_ms: f64, frames: &mut Frames) -> Self {
+        let events: Vec<_> = export::interval_edges(profile)
+            .into_iter()
+            .map(|edge| {
+                let measurement = &profile[profile[edge.interval].measurement];
+                let frame = frames.index_of(&measurement.label);
+                let event_type = match edge.edge {
+                    Edge::Open => EventType::Open,
+                    Edge::Close => EventType::Close,
+                };
+                Event { event_type, frame, at: offset_ms + edge.time_ms }
+            })
+            .collect();
+        let start_value = offset_ms;
+        let end_value = events.last().map_or(start_value, |event| event.at);
+        let profile_type = ProfileType::Evented;
+        let unit = ValueUnit::Milliseconds;
+        Self { profile_type, name, unit, start_value, end_value, events }
+    }
+}
+
+/// Type of a profile. Only evented profiles are supported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileType {
+    /// A sequence of open and close events.
+    Evented,
+}
+
+/// Unit of the values in a profile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueUnit {
+    /// Milliseconds.
+    Milliseconds,
+}
+
+
+// === Event ===
+
+/// A frame being entered or exited.
+#[derive(Copy, Clone, Debug, serde::Serialize)]
+pub struct Event {
+    /// Whether the frame is entered or exited.
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    /// Index into [`Shared::frames`].
+    pub frame:      usize,
+    /// Time of the event.
+    pub at:         f64,
+}
+
+/// Distinguishes opening from closing a frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub enum EventType {
+    /// The frame is entered.
+    #[serde(rename = "O")]
+    Open,
+    /// The frame is exited.
+    #[serde(rename = "C")]
+    Close,
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OpaqueMetadata;
+    use enso_profiler::format;
+
+    fn ms(ms: f64) -> Option<format::Timestamp> {
+        Some(format::Timestamp::from_ms(ms))
+    }
+
+    #[test]
+    fn nested_events() {
+        let mut log = format::Builder::new();
+        log.process("Ide");
+        log.time_offset(format::Timestamp::from_ms(100.0));
+        let parent = log.create(ms(0.0), format::Parent::root(), "parent");
+        log.start(ms(1.0).unwrap(), parent);
+        let child = log.create(ms(2.0), parent.into(), "child");
+        log.start(ms(2.0).unwrap(), child);
+        log.end(ms(3.0).unwrap(), child);
+        log.end(ms(4.0).unwrap(), parent);
+        let profile: Profile<OpaqueMetadata> = log.build_string().parse().unwrap();
+        let file = File::from_profiles(&[&profile]);
+        let frame_names: Vec<_> = file.shared.frames.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(frame_names, ["parent", "child"]);
+        assert_eq!(file.profiles.len(), 1);
+        let profile = &file.profiles[0];
+        assert_eq!(profile.name, "Ide");
+        let events: Vec<_> = profile.events.iter().map(|e| (e.event_type, e.frame, e.at)).collect();
+        let expected = [
+            (EventType::Open, 0, 1.0),
+            (EventType::Open, 1, 2.0),
+            (EventType::Close, 1, 3.0),
+            (EventType::Close, 0, 4.0),
+        ];
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn processes_share_time_origin() {
+        let mut profiles = vec![];
+        for (process, offset) in [("Ide", 100.0), ("Backend", 50.0)] {
+            let mut log = format::Builder::new();
+            log.process(process);
+            log.time_offset(format::Timestamp::from_ms(offset));
+            let id = log.create(ms(0.0), format::Parent::root(), "work");
+            log.start(ms(10.0).unwrap(), id);
+            log.end(ms(20.0).unwrap(), id);
+            let profile: Profile<OpaqueMetadata> = log.build_string().parse().unwrap();
+            profiles.push(profile);
+        }
+        let profiles: Vec<_> = profiles.iter().collect();
+        let file = File::from_profiles(&profiles);
+        assert_eq!(file.shared.frames.len(), 1);
+        assert_eq!(file.profiles[0].events[0].at, 60.0);
+        assert_eq!(file.profiles[1].events[0].at, 10.0);
+    }
+}
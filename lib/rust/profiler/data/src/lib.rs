@@ -98,6 +98,7 @@ use std::rc::Rc;
 // ==============
 
 pub mod aggregate;
+pub mod export;
 pub mod parse;
 
 
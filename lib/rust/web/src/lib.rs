@@ -1035,8 +1035,40 @@ pub async fn sleep(duration: Duration) {
     TimeoutFuture::new(duration.as_millis() as u32).await
 }
 
+/// A future returned by [`sleep`].
 #[cfg(not(target_arch = "wasm32"))]
-pub use async_std::task::sleep;
+pub type SleepFuture = std::pin::Pin<Box<dyn std::future::Future<Output = ()>>>;
+
+/// A timer used by [`sleep`] instead of the system clock. See [`set_timer_override`].
+#[cfg(not(target_arch = "wasm32"))]
+pub type TimerOverride = Rc<dyn Fn(Duration) -> SleepFuture>;
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static TIMER_OVERRIDE: RefCell<Option<TimerOverride>> = RefCell::new(None);
+}
+
+/// Make [`sleep`] calls in the current thread use the given timer instead of the system clock,
+/// or restore the system clock if `None` is passed.
+///
+/// Meant for test executors with virtual time, so the tests don't have to wait for real delays.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_timer_override(timer: Option<TimerOverride>) {
+    TIMER_OVERRIDE.with(|current| *current.borrow_mut() = timer);
+}
+
+/// Sleeps for the specified amount of time.
+///
+/// This function might sleep for slightly longer than the specified duration but never less. Its
+/// timer starts just after the function call. If a timer was set with [`set_timer_override`], it
+/// is used instead of the system clock.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn sleep(duration: Duration) -> SleepFuture {
+    match TIMER_OVERRIDE.with(|timer| timer.borrow().clone()) {
+        Some(timer) => timer(duration),
+        None => Box::pin(async_std::task::sleep(duration)),
+    }
+}
 
 
 
@@ -15,6 +15,10 @@
 #![warn(missing_copy_implementations)]
 #![warn(missing_debug_implementations)]
 
+
+
+mod rules;
+
 use enso_prelude::*;
 
 use clap::Parser;
@@ -49,18 +53,28 @@ struct Args {
 
     /// Specification file.
     #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
-    spec: PathBuf,
+    #[clap(required_unless_present = "rules", conflicts_with = "rules")]
+    spec: Option<PathBuf>,
 
-    /// Wstest log file.
+    /// Rules file describing the spans to measure. See the "Rules" section of the README for the
+    /// format.
     #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    rules: Option<PathBuf>,
+
+    /// Write the spans found with the rules as an `enso_profiler` event log.
+    #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath, requires = "rules")]
+    profile: Option<PathBuf>,
+
+    /// Wstest log file.
+    #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath, conflicts_with = "rules")]
     wstest_log: Option<PathBuf>,
 
     /// Number of iterations to skip.
-    #[clap(long, default_value = "0")]
+    #[clap(long, default_value = "0", conflicts_with = "rules")]
     skip_iterations: usize,
 
     /// Calculate median instead of mean.
-    #[clap(long)]
+    #[clap(long, conflicts_with = "rules")]
     median: bool,
 }
 
@@ -236,6 +250,34 @@ async fn read_logfile(path: &PathBuf, spec: &Spec) -> Result<Vec<Iteration>> {
     Ok(iterations)
 }
 
+/// Read the timestamped messages of all the valid log lines.
+async fn read_entries(path: &PathBuf) -> Result<Vec<(OffsetDateTime, String)>> {
+    let file = File::open(path).await?;
+    let mut lines = BufReader::new(file).lines();
+
+    let mut entries = vec![];
+    while let Some(line) = lines.next_line().await? {
+        if let Some(cap) = RE_LOGLINE.captures(line.as_str()) {
+            let groups = (
+                cap.get(RE_LOGLINE_TIMESTAMP_CAPTURE_GROUP),
+                cap.get(RE_LOGLINE_MESSAGE_CAPTURE_GROUP),
+            );
+            match groups {
+                (Some(timestamp), Some(message)) =>
+                    match OffsetDateTime::parse(timestamp.as_str(), &Rfc3339) {
+                        Ok(timestamp) => entries.push((timestamp, message.as_str().to_string())),
+                        Err(_) => eprintln!("[ERR] Invalid timestamp in log line [{line}]"),
+                    },
+                _ => {
+                    eprintln!("[ERR] Invalid log line [{line}]");
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
 /// Calcualte median of values.
 fn median<I>(durations_iter: I) -> Duration
 where I: Iterator<Item = Duration> {
@@ -324,10 +366,39 @@ fn iterations_average(ops: &[Iteration], stats: &[Stats]) -> Stats {
 // === Main ===
 // ============
 
+/// Measure the spans described by the rules file.
+async fn analyze_spans(args: &Args, rules_path: &PathBuf) -> Result<()> {
+    let rules = rules::read_rules(rules_path).await?;
+    let entries = read_entries(&args.log).await?;
+    let messages = entries.iter().map(|(timestamp, message)| (*timestamp, message.as_str()));
+    let extraction = rules::extract(&rules, messages);
+
+    println!("avg [min..max] (of {} spans)", extraction.spans.len());
+    for s in rules::group_stats(&extraction.spans) {
+        println!("{s}");
+    }
+    if extraction.unmatched_starts > 0 || extraction.unmatched_ends > 0 {
+        eprintln!(
+            "[WARN] Unmatched span starts: [{}], ends: [{}]",
+            extraction.unmatched_starts, extraction.unmatched_ends
+        );
+    }
+    if let Some(path) = &args.profile {
+        tokio::fs::write(path, rules::profile(&extraction.spans)).await?;
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    let spec = read_specs(&args.spec).await?;
+    let spec_path = match (&args.spec, &args.rules) {
+        (Some(spec_path), _) => spec_path,
+        (None, Some(rules_path)) => return analyze_spans(&args, rules_path).await,
+        (None, None) => unreachable!("Clap requires either the spec or the rules."),
+    };
+    let spec = read_specs(spec_path).await?;
 
     let log_iterations = read_logfile(&args.log, &spec).await?;
 
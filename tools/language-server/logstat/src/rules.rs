@@ -0,0 +1,388 @@
+//! Rules extracting the timed spans from any log file.
+//!
+//! A rules file is a YAML file like the following:
+//!
+//! ```yaml
+//! rules:
+//!   - name: request
+//!     start: 'Received text message: .*"id": ?(?P<key>\d+), "method": "(?P<method>[\w/]+)"'
+//!     end: 'Sending text message: .*"id": ?(?P<key>\d+)'
+//!     labels: [method]
+//!   - name: job
+//!     start: 'Executing job (?P<job>\w+)'
+//!     end: 'Job (?P<job>\w+) finished'
+//!     labels: [job]
+//! ```
+//!
+//! Each rule describes a span, starting at a log line matching the `start` regex and ending at the
+//! first following line matching the `end` regex. If the regexes have a capture group named `key`,
+//! the start and end lines are paired only if their keys are equal, which allows measuring
+//! overlapping spans, like concurrent requests. The spans with equal keys (or without any) are
+//! paired as nested ones: an end line closes the most recently started span.
+//!
+//! The spans of each rule are grouped by the values of the `labels`, which are named capture groups
+//! of either regex. A span is nested in the innermost span, of any rule, that contains it.
+
+use enso_prelude::*;
+
+use enso_profiler::format;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io;
+use std::io::Result;
+use std::path::Path;
+use time::Duration;
+use time::OffsetDateTime;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Name of the capture group pairing the start and end lines.
+const KEY_GROUP: &str = "key";
+
+/// Name of the process in the generated profile.
+const PROFILE_PROCESS: &str = "LanguageServer";
+
+
+
+// =============
+// === Rules ===
+// =============
+
+/// Contents of a rules file.
+#[derive(Clone, Debug, Deserialize)]
+struct RulesFile {
+    rules: Vec<RuleDefinition>,
+}
+
+/// A rule, as written in the rules file.
+#[derive(Clone, Debug, Deserialize)]
+struct RuleDefinition {
+    name:   String,
+    start:  String,
+    end:    String,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+/// A rule describing how to find the start and the end of a span.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    /// Name of the span.
+    pub name:   String,
+    /// Regex matching the message starting the span.
+    pub start:  Regex,
+    /// Regex matching the message ending the span.
+    pub end:    Regex,
+    /// Names of the capture groups used to group the spans.
+    pub labels: Vec<String>,
+}
+
+impl Rule {
+    fn new(definition: RuleDefinition) -> Result<Self> {
+        let RuleDefinition { name, start, end, labels } = definition;
+        let start = Regex::new(&start).map_err(invalid_data)?;
+        let end = Regex::new(&end).map_err(invalid_data)?;
+        let has_group =
+            |regex: &Regex, group: &str| regex.capture_names().flatten().any(|n| n == group);
+        if has_group(&start, KEY_GROUP) != has_group(&end, KEY_GROUP) {
+            let message = format!("Only one of the regexes of rule '{name}' captures the key.");
+            return Err(invalid_data(message));
+        }
+        if let Some(label) = labels.iter().find(|l| !has_group(&start, l) && !has_group(&end, l)) {
+            let message = format!("Rule '{name}' does not capture the label '{label}'.");
+            return Err(invalid_data(message));
+        }
+        Ok(Self { name, start, end, labels })
+    }
+}
+
+/// Read the rules from a YAML file.
+pub async fn read_rules(path: &Path) -> Result<Vec<Rule>> {
+    let contents = tokio::fs::read_to_string(path).await?;
+    let file: RulesFile = serde_yaml::from_str(&contents).map_err(invalid_data)?;
+    if file.rules.is_empty() {
+        return Err(invalid_data(format!("No rules in {}.", path.display())));
+    }
+    file.rules.into_iter().map(Rule::new).collect()
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+
+
+// ============
+// === Span ===
+// ============
+
+/// A span of the log, delimited by the lines matching a rule.
+#[derive(Clone, Debug)]
+pub struct Span {
+    /// The rule name, followed by the label values.
+    pub group:  String,
+    pub start:  OffsetDateTime,
+    pub end:    OffsetDateTime,
+    /// Index of the innermost span containing this one.
+    pub parent: Option<usize>,
+}
+
+impl Span {
+    /// Time between the start and the end of the span.
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    fn contains(&self, other: &Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// A span whose end line was not found yet.
+#[derive(Clone, Debug)]
+struct OpenSpan {
+    start:  OffsetDateTime,
+    labels: BTreeMap<String, String>,
+}
+
+/// The spans found in the log.
+#[derive(Clone, Debug, Default)]
+pub struct Extraction {
+    /// The spans, ordered by their start time. Spans starting at the same time are ordered from
+    /// the outermost.
+    pub spans:            Vec<Span>,
+    /// Number of the start lines without a matching end line.
+    pub unmatched_starts: usize,
+    /// Number of the end lines without a matching start line.
+    pub unmatched_ends:   usize,
+}
+
+/// Find the spans described by the rules in the log entries, given as timestamped messages.
+///
+/// A message matching both the end and the start regex of a rule first ends a span, and then starts
+/// a new one.
+pub fn extract<'a>(
+    rules: &[Rule],
+    entries: impl IntoIterator<Item = (OffsetDateTime, &'a str)>,
+) -> Extraction {
+    let mut extraction = Extraction::default();
+    let mut open: HashMap<(usize, String), Vec<OpenSpan>> = HashMap::new();
+    for (timestamp, message) in entries {
+        for (index, rule) in rules.iter().enumerate() {
+            if let Some(captures) = rule.end.captures(message) {
+                let key = captured_key(&captures);
+                match open.get_mut(&(index, key)).and_then(|spans| spans.pop()) {
+                    Some(OpenSpan { start, mut labels }) => {
+                        for (label, value) in captured_labels(rule, &captures) {
+                            labels.entry(label).or_insert(value);
+                        }
+                        let group = group_name(rule, &labels);
+                        let span = Span { group, start, end: timestamp, parent: None };
+                        extraction.spans.push(span);
+                    }
+                    None => extraction.unmatched_ends += 1,
+                }
+            }
+            if let Some(captures) = rule.start.captures(message) {
+                let key = captured_key(&captures);
+                let labels = captured_labels(rule, &captures).collect();
+                let span = OpenSpan { start: timestamp, labels };
+                open.entry((index, key)).or_default().push(span);
+            }
+        }
+    }
+    extraction.unmatched_starts = open.values().map(|spans| spans.len()).sum();
+    let spans = &mut extraction.spans;
+    spans.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    // The ancestors of the current span form a chain of the spans containing each other.
+    let mut ancestors: Vec<usize> = Vec::new();
+    for index in 0..spans.len() {
+        while let Some(&ancestor) = ancestors.last() {
+            if spans[ancestor].contains(&spans[index]) {
+                break;
+            }
+            ancestors.pop();
+        }
+        spans[index].parent = ancestors.last().copied();
+        ancestors.push(index);
+    }
+    extraction
+}
+
+fn captured_key(captures: &regex::Captures) -> String {
+    captures.name(KEY_GROUP).map(|key| key.as_str().to_string()).unwrap_or_default()
+}
+
+fn captured_labels<'a>(
+    rule: &'a Rule,
+    captures: &'a regex::Captures,
+) -> impl Iterator<Item = (String, String)> + 'a {
+    let values = rule.labels.iter().filter_map(|l| Some((l, captures.name(l)?)));
+    values.map(|(label, value)| (label.clone(), value.as_str().to_string()))
+}
+
+fn group_name(rule: &Rule, labels: &BTreeMap<String, String>) -> String {
+    if labels.is_empty() {
+        rule.name.clone()
+    } else {
+        let labels = labels.iter().map(|(label, value)| format!("{label}={value}")).join(",");
+        format!("{}{{{labels}}}", rule.name)
+    }
+}
+
+
+
+// ==================
+// === Statistics ===
+// ==================
+
+/// Statistics of the durations of the spans in a group.
+#[derive(Clone, Debug)]
+pub struct GroupStats {
+    pub group:  String,
+    pub count:  usize,
+    pub total:  Duration,
+    pub min:    Duration,
+    pub avg:    Duration,
+    pub median: Duration,
+    pub max:    Duration,
+}
+
+impl Display for GroupStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self { group, count, total, min, avg, median, max } = self;
+        let [total, min, avg, median, max] =
+            [total, min, avg, median, max].map(|duration| duration.whole_milliseconds());
+        write!(f, "{avg}ms [{min}..{max}] median {median}ms total {total}ms n={count} {group}")
+    }
+}
+
+/// Compute the statistics of each group of spans, in the order of the groups' first appearance.
+pub fn group_stats(spans: &[Span]) -> Vec<GroupStats> {
+    let mut groups: Vec<(&str, Vec<Duration>)> = Vec::new();
+    for span in spans {
+        match groups.iter_mut().find(|(group, _)| *group == span.group) {
+            Some((_, durations)) => durations.push(span.duration()),
+            None => groups.push((&span.group, vec![span.duration()])),
+        }
+    }
+    let stats = groups.into_iter().map(|(group, durations)| {
+        let count = durations.len();
+        let total = durations.iter().copied().sum::<Duration>();
+        GroupStats {
+            group: group.to_string(),
+            count,
+            total,
+            min: durations.iter().copied().min().unwrap_or(Duration::ZERO),
+            avg: total / count as u32,
+            median: crate::median(durations.iter().copied()),
+            max: durations.iter().copied().max().unwrap_or(Duration::ZERO),
+        }
+    });
+    stats.collect()
+}
+
+
+
+// ===============
+// === Profile ===
+// ===============
+
+/// Render the spans as a profile in the `enso_profiler` event-log format.
+///
+/// The measurements are nested like the spans. Spans that overlap without one containing the other
+/// are logged one after another, as the format requires the active measurements to form a stack.
+pub fn profile(spans: &[Span]) -> String {
+    let mut log = format::Builder::new();
+    log.time_offset(format::Timestamp::from_ms(0.0));
+    log.process(PROFILE_PROCESS);
+    let mut ids: Vec<format::MeasurementId> = Vec::with_capacity(spans.len());
+    let mut active: Vec<usize> = Vec::new();
+    for (index, span) in spans.iter().enumerate() {
+        while let Some(&ancestor) = active.last() {
+            if Some(ancestor) == span.parent {
+                break;
+            }
+            log.end(timestamp(spans[ancestor].end), ids[ancestor]);
+            active.pop();
+        }
+        let parent = span.parent.map_or_else(format::Parent::root, |parent| ids[parent].into());
+        let start = timestamp(span.start);
+        let id = log.create(Some(start), parent, &span.group);
+        log.start(start, id);
+        ids.push(id);
+        active.push(index);
+    }
+    for index in active.into_iter().rev() {
+        log.end(timestamp(spans[index].end), ids[index]);
+    }
+    log.build_string()
+}
+
+fn timestamp(time: OffsetDateTime) -> format::Timestamp {
+    format::Timestamp::from_ms(time.unix_timestamp_nanos() as f64 / 1_000_000.0)
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, start: &str, end: &str, labels: &[&str]) -> Rule {
+        let labels = labels.iter().map(|l| l.to_string()).collect();
+        let definition =
+            RuleDefinition { name: name.into(), start: start.into(), end: end.into(), labels };
+        Rule::new(definition).unwrap()
+    }
+
+    #[test]
+    fn spans_are_paired_by_key_and_nested() {
+        let rules = [
+            rule("request", r"request (?P<key>\d+) (?P<method>\w+)", r"response (?P<key>\d+)", &[
+                "method",
+            ]),
+            rule("job", r"job started", r"job finished", &[]),
+        ];
+        let log = [
+            (0, "request 1 edit"),
+            (10, "request 2 open"),
+            (20, "job started"),
+            (30, "job finished"),
+            (40, "response 1"),
+            (60, "response 2"),
+            (70, "response 3"),
+            (80, "job started"),
+        ];
+        let entries = log.map(|(ms, message)| {
+            (OffsetDateTime::UNIX_EPOCH + Duration::milliseconds(ms), message)
+        });
+        let extraction = extract(&rules, entries);
+        let spans = &extraction.spans;
+        let groups = spans.iter().map(|s| s.group.as_str()).collect_vec();
+        assert_eq!(groups, ["request{method=edit}", "request{method=open}", "job"]);
+        let parents = spans.iter().map(|s| s.parent).collect_vec();
+        assert_eq!(parents, [None, None, Some(1)]);
+        assert_eq!((extraction.unmatched_starts, extraction.unmatched_ends), (1, 1));
+        let stats = group_stats(spans);
+        assert_eq!(stats[1].total, Duration::milliseconds(50));
+        let profile = profile(spans);
+        let profile: enso_profiler_data::Profile<enso_profiler_data::OpaqueMetadata> =
+            profile.parse().unwrap();
+        let root = profile.root_measurement();
+        assert_eq!(root.children.len(), 2);
+        let job = &profile.measurements[2];
+        assert_eq!(job.label.name, "job");
+        assert_eq!(profile.measurements[1].children.len(), 1);
+    }
+}
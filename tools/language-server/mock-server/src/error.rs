@@ -0,0 +1,99 @@
+//! Errors replied to the clients, mirroring the ones reported by the real Language Server.
+
+use enso_prelude::*;
+
+use serde::Serialize;
+
+
+
+// =============
+// === Codes ===
+// =============
+
+/// Error codes of the Language Server protocol, as defined in the `enso` repository.
+#[allow(missing_docs)]
+pub mod code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    pub const ACCESS_DENIED: i64 = 100;
+    pub const FILE_SYSTEM_ERROR: i64 = 1000;
+    pub const CONTENT_ROOT_NOT_FOUND: i64 = 1001;
+    pub const FILE_NOT_FOUND: i64 = 1003;
+    pub const FILE_EXISTS: i64 = 1004;
+    pub const NOT_DIRECTORY: i64 = 1006;
+    pub const CANNOT_OVERWRITE: i64 = 1008;
+    pub const READ_OUT_OF_BOUNDS: i64 = 1009;
+    pub const STACK_ITEM_NOT_FOUND: i64 = 2001;
+    pub const CONTEXT_NOT_FOUND: i64 = 2002;
+    pub const EMPTY_STACK: i64 = 2003;
+    pub const INVALID_STACK_ITEM: i64 = 2004;
+    pub const VISUALIZATION_NOT_FOUND: i64 = 2006;
+    pub const FILE_NOT_OPENED: i64 = 3001;
+    pub const TEXT_EDIT_VALIDATION_ERROR: i64 = 3002;
+    pub const INVALID_VERSION: i64 = 3003;
+    pub const WRITE_DENIED: i64 = 3004;
+    pub const SESSION_NOT_INITIALISED: i64 = 6001;
+}
+
+
+
+// ================
+// === RpcError ===
+// ================
+
+/// An error replied to a request.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RpcError {
+    /// One of the [`code`]s.
+    pub code:    i64,
+    /// Human-readable description.
+    pub message: String,
+    /// Additional information, specific to the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data:    Option<serde_json::Value>,
+}
+
+impl RpcError {
+    /// Constructor.
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+
+    /// The requested method is not supported.
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(code::METHOD_NOT_FOUND, format!("Method not found: {method}"))
+    }
+
+    /// The parameters could not be deserialized.
+    pub fn invalid_params(error: impl Display) -> Self {
+        Self::new(code::INVALID_PARAMS, format!("Invalid params: {error}"))
+    }
+
+    /// Convert an I/O error, using the code matching its kind.
+    pub fn io(error: std::io::Error) -> Self {
+        let code = match error.kind() {
+            std::io::ErrorKind::NotFound => code::FILE_NOT_FOUND,
+            std::io::ErrorKind::AlreadyExists => code::FILE_EXISTS,
+            std::io::ErrorKind::PermissionDenied => code::ACCESS_DENIED,
+            _ => code::FILE_SYSTEM_ERROR,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+impl From<std::io::Error> for RpcError {
+    fn from(error: std::io::Error) -> Self {
+        Self::io(error)
+    }
+}
+
+/// Result of a request handler.
+pub type Result<T = serde_json::Value> = std::result::Result<T, RpcError>;
@@ -0,0 +1,259 @@
+//! The file system exposed to the clients: a single project content root, backed by a directory.
+
+use enso_prelude::*;
+
+use crate::error::code;
+use crate::error::Result;
+use crate::error::RpcError;
+
+use engine_protocol::binary::message::FileSegment;
+use engine_protocol::language_server::ContentRoot;
+use engine_protocol::language_server::FileAttributes;
+use engine_protocol::language_server::FileSystemObject;
+use engine_protocol::language_server::Path as LsPath;
+use engine_protocol::types::Sha3_224;
+use engine_protocol::types::UTCDateTime;
+use std::fs;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+
+
+// =============
+// === Files ===
+// =============
+
+/// Maps the protocol's paths to the files in the project directory, and performs the file
+/// operations requested by the clients.
+#[derive(Clone, Debug)]
+pub struct Files {
+    root_id: Uuid,
+    root:    PathBuf,
+}
+
+impl Files {
+    /// Expose the `root` directory as the project content root.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root_id: Uuid::new_v4(), root: root.into() }
+    }
+
+    /// The id of the project content root.
+    pub fn root_id(&self) -> Uuid {
+        self.root_id
+    }
+
+    /// The project directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The content roots reported to the clients.
+    pub fn content_roots(&self) -> Vec<ContentRoot> {
+        vec![ContentRoot::Project { id: self.root_id }]
+    }
+
+    /// The protocol path of a file in the project directory.
+    pub fn path<S: Into<String>>(&self, segments: impl IntoIterator<Item = S>) -> LsPath {
+        let segments = segments.into_iter().map(Into::into).collect();
+        LsPath { root_id: self.root_id, segments }
+    }
+
+    /// The location of the file on the disk.
+    pub fn resolve(&self, path: &LsPath) -> Result<PathBuf> {
+        if path.root_id != self.root_id {
+            let message = format!("Content root not found: {}", path.root_id);
+            return Err(RpcError::new(code::CONTENT_ROOT_NOT_FOUND, message));
+        }
+        let escapes = |segment: &String| segment.is_empty() || segment == "." || segment == "..";
+        if path
+            .segments
+            .iter()
+            .any(|segment| escapes(segment) || segment.contains(|c| c == '/' || c == '\\'))
+        {
+            return Err(RpcError::new(code::ACCESS_DENIED, "Access denied"));
+        }
+        Ok(path.segments.iter().fold(self.root.clone(), |path, segment| path.join(segment)))
+    }
+
+
+    // === Whole Files ===
+
+    /// Read a text file.
+    pub fn read_text(&self, path: &LsPath) -> Result<String> {
+        Ok(fs::read_to_string(self.resolve(path)?)?)
+    }
+
+    /// Read a file.
+    pub fn read(&self, path: &LsPath) -> Result<Vec<u8>> {
+        Ok(fs::read(self.resolve(path)?)?)
+    }
+
+    /// Write a file, creating it and its parent directories if needed.
+    pub fn write(&self, path: &LsPath, contents: &[u8]) -> Result<()> {
+        let file = self.resolve(path)?;
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(fs::write(file, contents)?)
+    }
+
+    /// The checksum of a file.
+    pub fn checksum(&self, path: &LsPath) -> Result<Sha3_224> {
+        Ok(Sha3_224::new(&self.read(path)?))
+    }
+
+
+    // === File System Objects ===
+
+    /// Check if the file or directory exists.
+    pub fn exists(&self, path: &LsPath) -> Result<bool> {
+        Ok(self.resolve(path)?.exists())
+    }
+
+    /// List the directory.
+    pub fn list(&self, path: &LsPath) -> Result<Vec<FileSystemObject>> {
+        let dir = self.resolve(path)?;
+        if !dir.is_dir() {
+            let code = if dir.exists() { code::NOT_DIRECTORY } else { code::FILE_NOT_FOUND };
+            return Err(RpcError::new(code, format!("Not a directory: {}", dir.display())));
+        }
+        let mut names = fs::read_dir(&dir)?
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names
+            .into_iter()
+            .map(|name| Self::object(&dir.join(&name), name, path.clone()))
+            .collect())
+    }
+
+    /// Describe the file or directory.
+    pub fn info(&self, path: &LsPath) -> Result<FileAttributes> {
+        let file = self.resolve(path)?;
+        let metadata = fs::metadata(&file)?;
+        let time = |time: std::io::Result<SystemTime>| -> UTCDateTime {
+            let time = time.unwrap_or(SystemTime::UNIX_EPOCH);
+            chrono::DateTime::<chrono::Utc>::from(time).into()
+        };
+        let (parent, name) = match path.segments.split_last() {
+            Some((name, parent)) => (self.path(parent.iter().cloned()), name.clone()),
+            None => (path.clone(), String::new()),
+        };
+        Ok(FileAttributes {
+            creation_time:      time(metadata.created()),
+            last_access_time:   time(metadata.accessed()),
+            last_modified_time: time(metadata.modified()),
+            kind:               Self::object(&file, name, parent),
+            byte_size:          metadata.len(),
+        })
+    }
+
+    /// Create an empty file or a directory.
+    pub fn create(&self, object: &FileSystemObject) -> Result<()> {
+        let path = LsPath::from(object);
+        let file = self.resolve(&path)?;
+        match object {
+            FileSystemObject::Directory { .. } => fs::create_dir_all(file)?,
+            _ => {
+                if let Some(parent) = file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::File::create(file)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete the file or directory with all its contents.
+    pub fn delete(&self, path: &LsPath) -> Result<()> {
+        let file = self.resolve(path)?;
+        if file.is_dir() {
+            Ok(fs::remove_dir_all(file)?)
+        } else {
+            Ok(fs::remove_file(file)?)
+        }
+    }
+
+    /// Copy the file or directory with all its contents.
+    pub fn copy(&self, from: &LsPath, to: &LsPath) -> Result<()> {
+        Ok(copy_recursively(&self.resolve(from)?, &self.resolve(to)?)?)
+    }
+
+    /// Move the file or directory.
+    pub fn move_to(&self, from: &LsPath, to: &LsPath) -> Result<()> {
+        let target = self.resolve(to)?;
+        if target.exists() {
+            return Err(RpcError::new(code::FILE_EXISTS, "File already exists"));
+        }
+        Ok(fs::rename(self.resolve(from)?, target)?)
+    }
+
+    fn object(file: &Path, name: String, path: LsPath) -> FileSystemObject {
+        if file.is_dir() {
+            FileSystemObject::Directory { name, path }
+        } else if file.is_file() {
+            FileSystemObject::File { name, path }
+        } else {
+            FileSystemObject::Other { name, path }
+        }
+    }
+
+
+    // === Byte Ranges ===
+
+    /// Write the bytes at the given offset, returning the checksum of the written bytes.
+    ///
+    /// If `overwrite` is not set, only appending is allowed.
+    pub fn write_bytes(
+        &self,
+        path: &LsPath,
+        byte_offset: u64,
+        overwrite: bool,
+        bytes: &[u8],
+    ) -> Result<Sha3_224> {
+        let file = self.resolve(path)?;
+        let mut file = fs::OpenOptions::new().create(true).write(true).open(file)?;
+        let length = file.metadata()?.len();
+        if !overwrite && byte_offset < length {
+            return Err(RpcError::new(code::CANNOT_OVERWRITE, "Cannot overwrite the file"));
+        }
+        file.seek(SeekFrom::Start(byte_offset))?;
+        file.write_all(bytes)?;
+        Ok(Sha3_224::new(bytes))
+    }
+
+    /// Read the file segment.
+    pub fn read_bytes(&self, segment: &FileSegment) -> Result<Vec<u8>> {
+        let file = self.resolve(&segment.path)?;
+        let mut file = fs::File::open(file)?;
+        let file_length = file.metadata()?.len();
+        if segment.byte_offset + segment.length > file_length {
+            let mut error = RpcError::new(code::READ_OUT_OF_BOUNDS, "Read is out of bounds");
+            error.data = Some(serde_json::json!({ "fileLength": file_length }));
+            return Err(error);
+        }
+        let mut bytes = vec![0; segment.length as usize];
+        file.seek(SeekFrom::Start(segment.byte_offset))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
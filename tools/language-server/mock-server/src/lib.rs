@@ -0,0 +1,46 @@
+//! A local stand-in for the Language Server, for protocol-level integration tests of the IDE.
+//!
+//! The server listens on a text and a binary WebSocket endpoint, speaking the JSON-RPC and the
+//! FlatBuffers protocols defined in the `engine-protocol` crate. The `file/*` and `text/*` methods
+//! and the binary file operations work on a real directory (a temporary one by default), while the
+//! `executionContext/*` methods keep the execution contexts in memory and reply according to a
+//! [`Script`], which may also define the notifications and visualization updates sent to the
+//! clients.
+
+// === Standard Linter Configuration ===
+#![deny(non_ascii_idents)]
+#![warn(unsafe_code)]
+#![allow(clippy::bool_to_int_with_if)]
+#![allow(clippy::let_and_return)]
+// === Non-Standard Linter Configuration ===
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+
+
+
+// ==============
+// === Export ===
+// ==============
+
+pub mod error;
+pub mod files;
+pub mod script;
+pub mod server;
+pub mod state;
+pub mod text;
+
+pub use script::Notification;
+pub use script::Reply;
+pub use script::Script;
+pub use server::Config;
+pub use server::MockLanguageServer;
+
+
+
+/// Identifies a client's connection to either of the endpoints.
+pub type SessionId = u64;
@@ -0,0 +1,73 @@
+//! Runs the mock Language Server until interrupted, e.g. to connect the IDE to it manually.
+
+// === Standard Linter Configuration ===
+#![deny(non_ascii_idents)]
+#![warn(unsafe_code)]
+#![allow(clippy::bool_to_int_with_if)]
+#![allow(clippy::let_and_return)]
+// === Non-Standard Linter Configuration ===
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use clap::ValueHint;
+use mock_language_server::Config;
+use mock_language_server::MockLanguageServer;
+use mock_language_server::Script;
+
+
+
+// =====================
+// === CLI Arguments ===
+// =====================
+
+#[derive(Parser, Debug)]
+#[clap(version, about)]
+struct Args {
+    /// The project directory. A temporary directory is used if not given.
+    #[clap(long, value_hint = ValueHint::DirPath)]
+    root: Option<PathBuf>,
+
+    /// JSON file with the scripted replies, notifications and visualization data.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    script: Option<PathBuf>,
+
+    /// The host the endpoints are bound to.
+    #[clap(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// The port of the text endpoint. Any free port is used if 0.
+    #[clap(long, default_value = "30616")]
+    json_port: u16,
+
+    /// The port of the binary endpoint. Any free port is used if 0.
+    #[clap(long, default_value = "30617")]
+    binary_port: u16,
+}
+
+
+
+// ============
+// === Main ===
+// ============
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let script = args.script.map(Script::load).transpose()?.unwrap_or_default();
+    let Args { root, host, json_port, binary_port, .. } = args;
+    let config = Config { root, host, json_port, binary_port, script };
+    let server = MockLanguageServer::start(config).await?;
+    println!("Project root: {}", server.project_root().display());
+    println!("Text endpoint: {}", server.json_endpoint());
+    println!("Binary endpoint: {}", server.binary_endpoint());
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
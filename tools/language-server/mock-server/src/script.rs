@@ -0,0 +1,187 @@
+//! Scripted behavior of the server, overriding or extending the default replies.
+//!
+//! A script may be built in code, or loaded from a JSON file, e.g.:
+//! ```json
+//! {
+//!   "replies": {
+//!     "executionContext/push": [{ "error": { "code": 2004, "message": "Invalid stack item" } }, { "result": null }]
+//!   },
+//!   "notifications": {
+//!     "executionContext/push": [{
+//!       "method": "executionContext/expressionUpdates",
+//!       "params": { "contextId": "$contextId", "updates": [] }
+//!     }]
+//!   },
+//!   "visualizationData": { "8b2d1d6f-5c5e-4c3b-9d55-0c9e3a4e26b1": [1, 2, 3] }
+//! }
+//! ```
+
+use crate::error::RpcError;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+
+
+// ==============
+// === Script ===
+// ==============
+
+/// Scripted behavior of the server.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Script {
+    /// Replies to the method calls, by method name, overriding the default behavior. Subsequent
+    /// calls take subsequent replies; once they run out, the default behavior is restored.
+    pub replies:            HashMap<String, Vec<Reply>>,
+    /// Notifications sent after each successful call of the method, by method name.
+    pub notifications:      HashMap<String, Vec<Notification>>,
+    /// Data sent in a visualization update after a visualization of the expression is attached or
+    /// modified, by expression id. It is serialized to JSON, like the data computed by the engine.
+    pub visualization_data: HashMap<Uuid, serde_json::Value>,
+}
+
+impl Script {
+    /// Load the script from a JSON file.
+    pub fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Add a reply to the calls of the method.
+    pub fn reply(&mut self, method: impl Into<String>, reply: Reply) -> &mut Self {
+        self.replies.entry(method.into()).or_default().push(reply);
+        self
+    }
+
+    /// Add a notification sent after successful calls of the method.
+    pub fn notify_after(
+        &mut self,
+        method: impl Into<String>,
+        notification: Notification,
+    ) -> &mut Self {
+        self.notifications.entry(method.into()).or_default().push(notification);
+        self
+    }
+
+    /// Set the data of the updates of visualizations attached to the expression.
+    pub fn visualization_data(
+        &mut self,
+        expression_id: Uuid,
+        data: serde_json::Value,
+    ) -> &mut Self {
+        self.visualization_data.insert(expression_id, data);
+        self
+    }
+
+    /// The reply to the `index`-th call of the method, if scripted.
+    pub fn reply_to(&self, method: &str, index: usize) -> Option<&Reply> {
+        self.replies.get(method)?.get(index)
+    }
+}
+
+
+
+// =============
+// === Reply ===
+// =============
+
+/// A scripted reply to a method call, in the shape of a JSON-RPC response.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Reply {
+    /// The call fails with the error.
+    Error {
+        /// The error.
+        error: ScriptedError,
+    },
+    /// The call succeeds with the result.
+    Result {
+        /// The result.
+        result: serde_json::Value,
+    },
+}
+
+impl Reply {
+    /// A successful reply.
+    pub fn result(result: serde_json::Value) -> Self {
+        Self::Result { result }
+    }
+
+    /// A failure reply.
+    pub fn error(code: i64, message: impl Into<String>) -> Self {
+        Self::Error { error: ScriptedError { code, message: message.into(), data: None } }
+    }
+
+    /// The result of the call.
+    pub fn to_result(&self) -> crate::error::Result {
+        match self {
+            Reply::Result { result } => Ok(result.clone()),
+            Reply::Error { error } => {
+                let ScriptedError { code, message, data } = error.clone();
+                Err(RpcError { code, message, data })
+            }
+        }
+    }
+}
+
+/// An error in a [`Reply`].
+#[derive(Clone, Debug, Deserialize)]
+#[allow(missing_docs)]
+pub struct ScriptedError {
+    pub code:    i64,
+    pub message: String,
+    #[serde(default)]
+    pub data:    Option<serde_json::Value>,
+}
+
+
+
+// ====================
+// === Notification ===
+// ====================
+
+/// A notification sent after a method call.
+///
+/// String values of the form `$name` in `params` are replaced with the call's parameter `name`,
+/// e.g. `"$contextId"` with the id of the execution context the call refers to.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Notification {
+    /// The notification method.
+    pub method: String,
+    /// The notification parameters.
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+impl Notification {
+    /// Constructor.
+    pub fn new(method: impl Into<String>, params: serde_json::Value) -> Self {
+        Self { method: method.into(), params }
+    }
+
+    /// The parameters, with the placeholders replaced with the values of the call parameters.
+    pub fn params_for(&self, call_params: &serde_json::Value) -> serde_json::Value {
+        substitute(&self.params, call_params)
+    }
+}
+
+fn substitute(value: &serde_json::Value, call_params: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::String(string) =>
+            match string.strip_prefix('$').and_then(|name| call_params.get(name)) {
+                Some(param) => param.clone(),
+                None => value.clone(),
+            },
+        Value::Array(items) =>
+            Value::Array(items.iter().map(|item| substitute(item, call_params)).collect()),
+        Value::Object(fields) => {
+            let fields =
+                fields.iter().map(|(key, value)| (key.clone(), substitute(value, call_params)));
+            Value::Object(fields.collect())
+        }
+        _ => value.clone(),
+    }
+}
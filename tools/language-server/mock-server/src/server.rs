@@ -0,0 +1,255 @@
+//! The WebSocket endpoints of the server.
+
+use enso_prelude::*;
+
+use crate::files::Files;
+use crate::script::Script;
+use crate::state::Call;
+use crate::state::State;
+use crate::SessionId;
+
+use engine_protocol::binary::message::VisualizationContext;
+use engine_protocol::language_server::Path as LsPath;
+use futures::SinkExt;
+use futures::StreamExt;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::task::JoinSet;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// How long to wait before accepting connections again after a failure, e.g. when the process has
+/// run out of file descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+
+
+// ==============
+// === Config ===
+// ==============
+
+/// The configuration of the [`MockLanguageServer`].
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The project directory. If not set, a temporary directory is created and removed once the
+    /// server is dropped.
+    pub root:        Option<PathBuf>,
+    /// The host the endpoints are bound to.
+    pub host:        String,
+    /// The port of the text endpoint. If 0, any free port is used.
+    pub json_port:   u16,
+    /// The port of the binary endpoint. If 0, any free port is used.
+    pub binary_port: u16,
+    /// The scripted behavior of the server.
+    pub script:      Script,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let host = "127.0.0.1".into();
+        Self { root: None, host, json_port: 0, binary_port: 0, script: default() }
+    }
+}
+
+
+
+// ================
+// === Endpoint ===
+// ================
+
+#[derive(Clone, Copy, Debug)]
+enum Endpoint {
+    Json,
+    Binary,
+}
+
+
+
+// ==========================
+// === MockLanguageServer ===
+// ==========================
+
+/// A running mock of the Language Server. The server stops once dropped.
+#[derive(Debug)]
+pub struct MockLanguageServer {
+    state:          Arc<Mutex<State>>,
+    json_address:   SocketAddr,
+    binary_address: SocketAddr,
+    listeners:      Vec<JoinHandle<()>>,
+    _temp_dir:      Option<TempDir>,
+}
+
+impl MockLanguageServer {
+    /// Bind the endpoints and start serving the clients.
+    pub async fn start(config: Config) -> anyhow::Result<Self> {
+        let (root, temp_dir) = match config.root {
+            Some(root) => (root, None),
+            None => {
+                let temp_dir = tempfile::tempdir()?;
+                (temp_dir.path().to_owned(), Some(temp_dir))
+            }
+        };
+        let state = Arc::new(Mutex::new(State::new(Files::new(root), config.script)));
+        let json_listener = TcpListener::bind((config.host.as_str(), config.json_port)).await?;
+        let binary_listener = TcpListener::bind((config.host.as_str(), config.binary_port)).await?;
+        let json_address = json_listener.local_addr()?;
+        let binary_address = binary_listener.local_addr()?;
+        let listeners = vec![
+            tokio::spawn(listen(json_listener, state.clone(), Endpoint::Json)),
+            tokio::spawn(listen(binary_listener, state.clone(), Endpoint::Binary)),
+        ];
+        Ok(Self { state, json_address, binary_address, listeners, _temp_dir: temp_dir })
+    }
+
+    /// The URL of the text (JSON-RPC) endpoint.
+    pub fn json_endpoint(&self) -> String {
+        format!("ws://{}", self.json_address)
+    }
+
+    /// The URL of the binary endpoint.
+    pub fn binary_endpoint(&self) -> String {
+        format!("ws://{}", self.binary_address)
+    }
+
+    /// The project directory.
+    pub fn project_root(&self) -> PathBuf {
+        self.with_state(|state| state.files().root().to_owned())
+    }
+
+    /// The id of the project content root.
+    pub fn project_root_id(&self) -> Uuid {
+        self.with_state(|state| state.files().root_id())
+    }
+
+    /// The protocol path of a file in the project directory.
+    pub fn path<S: Into<String>>(&self, segments: impl IntoIterator<Item = S>) -> LsPath {
+        self.with_state(|state| state.files().path(segments))
+    }
+
+    /// Modify the scripted behavior. The changes affect the calls received afterwards.
+    pub fn script(&self, f: impl FnOnce(&mut Script)) {
+        self.with_state(|state| f(state.script_mut()))
+    }
+
+    /// Send a notification to all connected text clients.
+    pub fn send_notification(&self, method: &str, params: Value) {
+        self.with_state(|state| state.broadcast_notification(method, params))
+    }
+
+    /// Send a visualization update to all connected binary clients.
+    pub fn send_visualization_update(&self, context: VisualizationContext, data: Vec<u8>) {
+        self.with_state(|state| state.broadcast_visualization_update(context, data))
+    }
+
+    /// All method calls received so far, in order.
+    pub fn calls(&self) -> Vec<Call> {
+        self.with_state(|state| state.calls().to_vec())
+    }
+
+    /// The methods called so far, in order.
+    pub fn called_methods(&self) -> Vec<String> {
+        self.with_state(|state| state.calls().iter().map(|call| call.method.clone()).collect())
+    }
+
+    /// The visualizations currently attached by the clients.
+    pub fn visualizations(&self) -> Vec<VisualizationContext> {
+        self.with_state(|state| state.visualizations())
+    }
+
+    /// The contents of a text file opened by the clients, including the unsaved edits.
+    pub fn open_file_content(&self, path: &LsPath) -> Option<String> {
+        self.with_state(|state| state.open_file_content(path).map(ToOwned::to_owned))
+    }
+
+    /// Read a file from the project directory.
+    pub fn read_file(&self, path: impl AsRef<Path>) -> std::io::Result<String> {
+        std::fs::read_to_string(self.project_root().join(path))
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        f(&mut self.state.lock().unwrap())
+    }
+}
+
+impl Drop for MockLanguageServer {
+    fn drop(&mut self) {
+        for listener in &self.listeners {
+            listener.abort();
+        }
+    }
+}
+
+
+
+// ===================
+// === Connections ===
+// ===================
+
+/// Accept the connections to the endpoint. The connections are closed once the task is aborted.
+async fn listen(listener: TcpListener, state: Arc<Mutex<State>>, endpoint: Endpoint) {
+    let mut connections = JoinSet::new();
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                connections.spawn(serve(stream, state.clone(), endpoint));
+            }
+            Err(error) => {
+                warn!("Failed to accept a connection to {endpoint:?} endpoint: {error}");
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Handle the messages of a single client.
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>, endpoint: Endpoint) {
+    let socket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(error) => {
+            warn!("WebSocket handshake on {endpoint:?} endpoint failed: {error}");
+            return;
+        }
+    };
+    let (mut sink, mut source) = socket.split();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let session: SessionId = {
+        let mut state = state.lock().unwrap();
+        match endpoint {
+            Endpoint::Json => state.open_json_session(sender),
+            Endpoint::Binary => state.open_binary_session(sender),
+        }
+    };
+    let outgoing = tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+    while let Some(message) = source.next().await {
+        match message {
+            Ok(Message::Text(text)) => state.lock().unwrap().handle_text(session, &text),
+            Ok(Message::Binary(data)) => state.lock().unwrap().handle_binary(session, &data),
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+    state.lock().unwrap().close_session(session);
+    outgoing.abort();
+}
@@ -0,0 +1,780 @@
+//! The state of the server shared by all connections, and the handling of the clients' messages.
+
+use enso_prelude::*;
+
+use crate::error::code;
+use crate::error::Result;
+use crate::error::RpcError;
+use crate::files::Files;
+use crate::script::Script;
+use crate::text;
+use crate::text::OpenFile;
+use crate::SessionId;
+
+use engine_protocol::binary::message::ErrorPayload;
+use engine_protocol::binary::message::FromServerPayloadOwned;
+use engine_protocol::binary::message::MessageFromServer;
+use engine_protocol::binary::message::MessageToServerOwned;
+use engine_protocol::binary::message::ToServerPayloadOwned;
+use engine_protocol::binary::message::VisualizationContext;
+use engine_protocol::binary::serialization::DeserializableRoot;
+use engine_protocol::binary::serialization::SerializableRoot;
+use engine_protocol::language_server::response;
+use engine_protocol::language_server::CapabilityRegistration;
+use engine_protocol::language_server::ContextId;
+use engine_protocol::language_server::FileEdit;
+use engine_protocol::language_server::FileSystemObject;
+use engine_protocol::language_server::Path as LsPath;
+use engine_protocol::language_server::StackItem;
+use engine_protocol::language_server::VisualizationConfiguration;
+use engine_protocol::types::Sha3_224;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+
+
+// ===============
+// === Session ===
+// ===============
+
+/// A connected client's socket.
+#[derive(Debug)]
+struct Session {
+    /// The id the client introduced itself with; used to pair its text and binary connections.
+    client_id: Option<Uuid>,
+    sender:    UnboundedSender<Message>,
+}
+
+/// A method call received from a client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    /// The session the call was received from.
+    pub session: SessionId,
+    /// The method name.
+    pub method:  String,
+    /// The method parameters.
+    pub params:  Value,
+}
+
+
+
+// ========================
+// === ExecutionContext ===
+// ========================
+
+#[derive(Clone, Debug)]
+struct ExecutionContext {
+    /// The session which created the context, receiving its updates.
+    owner:          SessionId,
+    stack:          Vec<StackItem>,
+    /// The expressions of the attached visualizations, by visualization id.
+    visualizations: HashMap<Uuid, Uuid>,
+}
+
+/// A message to send once the reply to the current request is sent.
+#[derive(Debug)]
+enum Deferred {
+    Notification { session: SessionId, method: String, params: Value },
+    VisualizationUpdate { session: SessionId, context: VisualizationContext, data: Vec<u8> },
+}
+
+
+
+// =============
+// === State ===
+// =============
+
+/// The state of the server shared by all connections.
+#[derive(Debug)]
+pub struct State {
+    files:           Files,
+    script:          Script,
+    calls:           Vec<Call>,
+    call_counts:     HashMap<String, usize>,
+    next_session_id: SessionId,
+    json_sessions:   BTreeMap<SessionId, Session>,
+    binary_sessions: BTreeMap<SessionId, Session>,
+    open_files:      HashMap<LsPath, OpenFile>,
+    contexts:        HashMap<ContextId, ExecutionContext>,
+    deferred:        Vec<Deferred>,
+}
+
+impl State {
+    /// Constructor.
+    pub fn new(files: Files, script: Script) -> Self {
+        Self {
+            files,
+            script,
+            calls: default(),
+            call_counts: default(),
+            next_session_id: 0,
+            json_sessions: default(),
+            binary_sessions: default(),
+            open_files: default(),
+            contexts: default(),
+            deferred: default(),
+        }
+    }
+
+    /// The file system exposed to the clients.
+    pub fn files(&self) -> &Files {
+        &self.files
+    }
+
+    /// The script of the server's behavior.
+    pub fn script_mut(&mut self) -> &mut Script {
+        &mut self.script
+    }
+
+    /// All method calls received so far, in order.
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    /// The contents of a text file opened by the clients, including the unsaved edits.
+    pub fn open_file_content(&self, path: &LsPath) -> Option<&str> {
+        self.open_files.get(path).map(|file| file.content.as_str())
+    }
+
+    /// The visualizations attached by the clients.
+    pub fn visualizations(&self) -> Vec<VisualizationContext> {
+        let contexts = self.contexts.iter();
+        let visualizations = contexts.flat_map(|(context_id, context)| {
+            context.visualizations.iter().map(|(visualization_id, expression_id)| {
+                VisualizationContext {
+                    visualization_id: *visualization_id,
+                    context_id:       *context_id,
+                    expression_id:    *expression_id,
+                }
+            })
+        });
+        visualizations.collect()
+    }
+
+
+    // === Sessions ===
+
+    /// Register a new text connection.
+    pub fn open_json_session(&mut self, sender: UnboundedSender<Message>) -> SessionId {
+        let id = self.new_session_id();
+        self.json_sessions.insert(id, Session { client_id: None, sender });
+        id
+    }
+
+    /// Register a new binary connection.
+    pub fn open_binary_session(&mut self, sender: UnboundedSender<Message>) -> SessionId {
+        let id = self.new_session_id();
+        self.binary_sessions.insert(id, Session { client_id: None, sender });
+        id
+    }
+
+    /// Forget the closed connection, releasing its capabilities.
+    pub fn close_session(&mut self, session: SessionId) {
+        self.json_sessions.remove(&session);
+        self.binary_sessions.remove(&session);
+        self.open_files.retain(|_, file| {
+            file.sessions.remove(&session);
+            if file.editor == Some(session) {
+                file.editor = None;
+            }
+            !file.sessions.is_empty()
+        });
+        self.contexts.retain(|_, context| context.owner != session);
+    }
+
+    fn new_session_id(&mut self) -> SessionId {
+        let id = self.next_session_id;
+        self.next_session_id += 1;
+        id
+    }
+
+    /// Send a notification to all text connections.
+    pub fn broadcast_notification(&self, method: &str, params: Value) {
+        for session in self.json_sessions.keys() {
+            self.send_notification(*session, method, params.clone());
+        }
+    }
+
+    /// Send a visualization update to all binary connections.
+    pub fn broadcast_visualization_update(&self, context: VisualizationContext, data: Vec<u8>) {
+        let update = visualization_update(context, data);
+        for session in self.binary_sessions.values() {
+            let _ = session.sender.send(update.clone());
+        }
+    }
+
+    fn send_notification(&self, session: SessionId, method: &str, params: Value) {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.send_json(session, message);
+    }
+
+    fn send_json(&self, session: SessionId, message: Value) {
+        if let Some(session) = self.json_sessions.get(&session) {
+            let _ = session.sender.send(Message::Text(message.to_string()));
+        }
+    }
+
+    /// Send the messages which were waiting for the reply to the current request.
+    fn flush_deferred(&mut self) {
+        for deferred in mem::take(&mut self.deferred) {
+            match deferred {
+                Deferred::Notification { session, method, params } =>
+                    self.send_notification(session, &method, params),
+                Deferred::VisualizationUpdate { session, context, data } => {
+                    let client_id = self.json_sessions.get(&session).and_then(|s| s.client_id);
+                    let update = visualization_update(context, data);
+                    let binary_sessions = self.binary_sessions.values();
+                    let paired = |s: &&Session| client_id.is_some() && s.client_id == client_id;
+                    for binary in binary_sessions.filter(paired) {
+                        let _ = binary.sender.send(update.clone());
+                    }
+                }
+            }
+        }
+    }
+
+
+    // === Text Protocol ===
+
+    /// Handle a text message: a single request or a batch of them.
+    pub fn handle_text(&mut self, session: SessionId, text: &str) {
+        let reply = match serde_json::from_str::<Value>(text) {
+            Ok(Value::Array(messages)) => {
+                let replies = messages.into_iter().filter_map(|m| self.handle_json(session, m));
+                let replies = replies.collect_vec();
+                // A batch of notifications is not replied to at all, as JSON-RPC requires.
+                (!replies.is_empty()).then_some(Value::Array(replies))
+            }
+            Ok(message) => self.handle_json(session, message),
+            Err(error) => {
+                let error = RpcError::new(code::PARSE_ERROR, format!("Parse error: {error}"));
+                Some(json!({ "jsonrpc": "2.0", "id": null, "error": error }))
+            }
+        };
+        if let Some(reply) = reply {
+            self.send_json(session, reply);
+        }
+        self.flush_deferred();
+    }
+
+    /// Handle a single JSON-RPC message, returning the response if it was a request.
+    fn handle_json(&mut self, session: SessionId, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str).map(ToOwned::to_owned);
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        match (id, method) {
+            (Some(id), Some(method)) => Some(match self.call(session, &method, params) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            }),
+            // Neither notifications from the client nor responses to server's requests are used.
+            _ => None,
+        }
+    }
+
+    /// Handle a method call. Scripted replies take precedence over the default behavior.
+    fn call(&mut self, session: SessionId, method: &str, params: Value) -> Result {
+        self.calls.push(Call { session, method: method.to_owned(), params: params.clone() });
+        let count = self.call_counts.entry(method.to_owned()).or_default();
+        let index = *count;
+        *count += 1;
+        let deferred_before = self.deferred.len();
+        let result = match self.script.reply_to(method, index) {
+            Some(reply) => reply.to_result(),
+            None => self.default_call(session, method, &params),
+        };
+        if result.is_ok() {
+            // Scripted notifications, like expression updates, precede the default ones, like
+            // `executionComplete`.
+            let scripted = self.script.notifications.get(method).into_iter().flatten();
+            let scripted = scripted.map(|notification| Deferred::Notification {
+                session,
+                method: notification.method.clone(),
+                params: notification.params_for(&params),
+            });
+            let scripted = scripted.collect_vec();
+            let default = self.deferred.split_off(deferred_before);
+            self.deferred.extend(scripted);
+            self.deferred.extend(default);
+        }
+        result
+    }
+
+    fn default_call(&mut self, session: SessionId, method: &str, params: &Value) -> Result {
+        match method {
+            "session/initProtocolConnection" => {
+                let InitProtocol { client_id } = parse(params)?;
+                if let Some(session) = self.json_sessions.get_mut(&session) {
+                    session.client_id = Some(client_id);
+                }
+                let content_roots = self.files.content_roots();
+                to_value(response::InitProtocolConnection { content_roots })
+            }
+            "capability/acquire" | "capability/release" => Ok(Value::Null),
+            "file/read" => {
+                let PathParams { path } = parse(params)?;
+                to_value(response::Read { contents: self.files.read_text(&path)? })
+            }
+            "file/write" => {
+                let Write { path, contents } = parse(params)?;
+                self.files.write(&path, contents.as_bytes())?;
+                Ok(Value::Null)
+            }
+            "file/exists" => {
+                let PathParams { path } = parse(params)?;
+                to_value(response::FileExists { exists: self.files.exists(&path)? })
+            }
+            "file/list" => {
+                let PathParams { path } = parse(params)?;
+                to_value(response::FileList { paths: self.files.list(&path)? })
+            }
+            "file/info" => {
+                let PathParams { path } = parse(params)?;
+                to_value(response::FileInfo { attributes: self.files.info(&path)? })
+            }
+            "file/checksum" => {
+                let PathParams { path } = parse(params)?;
+                to_value(response::FileChecksum { checksum: self.files.checksum(&path)? })
+            }
+            "file/create" => {
+                let Create { object } = parse(params)?;
+                self.files.create(&object)?;
+                Ok(Value::Null)
+            }
+            "file/delete" => {
+                let PathParams { path } = parse(params)?;
+                self.files.delete(&path)?;
+                Ok(Value::Null)
+            }
+            "file/copy" => {
+                let FromTo { from, to } = parse(params)?;
+                self.files.copy(&from, &to)?;
+                Ok(Value::Null)
+            }
+            "file/move" => {
+                let FromTo { from, to } = parse(params)?;
+                self.files.move_to(&from, &to)?;
+                Ok(Value::Null)
+            }
+            "text/openFile" => self.open_text_file(session, parse(params)?),
+            "text/closeFile" => self.close_text_file(session, parse(params)?),
+            "text/save" => self.save_text_file(session, parse(params)?),
+            "text/applyEdit" => self.apply_text_edit(session, parse(params)?),
+            "executionContext/create" => self.create_context(session, parse(params)?),
+            "executionContext/destroy" => {
+                let ContextParams { context_id } = parse(params)?;
+                self.contexts.remove(&context_id).ok_or_else(|| context_not_found(context_id))?;
+                Ok(Value::Null)
+            }
+            "executionContext/push" => self.push(session, parse(params)?),
+            "executionContext/pop" => self.pop(session, parse(params)?),
+            "executionContext/recompute" => {
+                let ContextParams { context_id } = parse(params)?;
+                self.context(context_id)?;
+                self.defer_execution_complete(session, context_id);
+                Ok(Value::Null)
+            }
+            "executionContext/interrupt" | "executionContext/setExecutionEnvironment" => {
+                let ContextParams { context_id } = parse(params)?;
+                self.context(context_id)?;
+                Ok(Value::Null)
+            }
+            "executionContext/attachVisualization" =>
+                self.attach_visualization(session, parse(params)?),
+            "executionContext/detachVisualization" => self.detach_visualization(parse(params)?),
+            "executionContext/modifyVisualization" =>
+                self.modify_visualization(session, parse(params)?),
+            "executionContext/getComponentGroups" => Ok(json!({ "componentGroups": [] })),
+            "search/getSuggestionsDatabase" => Ok(json!({ "entries": [], "currentVersion": 0 })),
+            "search/getSuggestionsDatabaseVersion" => Ok(json!({ "currentVersion": 0 })),
+            "search/completion" => Ok(json!({ "results": [], "currentVersion": 0 })),
+            _ => Err(RpcError::method_not_found(method)),
+        }
+    }
+
+
+    // === Text Files ===
+
+    fn open_text_file(&mut self, session: SessionId, PathParams { path }: PathParams) -> Result {
+        let file = match self.open_files.entry(path.clone()) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) =>
+                entry.insert(OpenFile::new(self.files.read_text(&path)?)),
+        };
+        file.sessions.insert(session);
+        let editor = *file.editor.get_or_insert(session);
+        let write_capability =
+            (editor == session).then(|| CapabilityRegistration::create_can_edit_text_file(path));
+        let content = file.content.clone();
+        let current_version = file.version();
+        to_value(response::OpenTextFile { write_capability, content, current_version })
+    }
+
+    fn close_text_file(&mut self, session: SessionId, PathParams { path }: PathParams) -> Result {
+        let file = self.open_file(session, &path)?;
+        file.sessions.remove(&session);
+        if file.editor == Some(session) {
+            file.editor = None;
+        }
+        if file.sessions.is_empty() {
+            self.open_files.remove(&path);
+        }
+        Ok(Value::Null)
+    }
+
+    fn save_text_file(
+        &mut self,
+        session: SessionId,
+        Save { path, current_version }: Save,
+    ) -> Result {
+        let file = self.editable_file(session, &path)?;
+        if file.version() != current_version {
+            return Err(invalid_version(&file.version(), &current_version));
+        }
+        let content = file.content.clone();
+        self.files.write(&path, content.as_bytes())?;
+        Ok(Value::Null)
+    }
+
+    fn apply_text_edit(&mut self, session: SessionId, ApplyEdit { edit }: ApplyEdit) -> Result {
+        let file = self.editable_file(session, &edit.path)?;
+        if file.version() != edit.old_version {
+            return Err(invalid_version(&file.version(), &edit.old_version));
+        }
+        let content = text::apply_edits(&file.content, &edit.edits)?;
+        let new_version = Sha3_224::new(content.as_bytes());
+        if new_version != edit.new_version {
+            return Err(invalid_version(&new_version, &edit.new_version));
+        }
+        file.content = content;
+        let others = file.sessions.iter().filter(|other| **other != session).copied().collect_vec();
+        let params = json!({ "edits": [edit] });
+        for other in others {
+            let method = "text/didChange".to_owned();
+            self.deferred.push(Deferred::Notification {
+                session: other,
+                method,
+                params: params.clone(),
+            });
+        }
+        Ok(Value::Null)
+    }
+
+    fn open_file(&mut self, session: SessionId, path: &LsPath) -> Result<&mut OpenFile> {
+        match self.open_files.get_mut(path) {
+            Some(file) if file.sessions.contains(&session) => Ok(file),
+            _ => Err(RpcError::new(code::FILE_NOT_OPENED, "File not opened")),
+        }
+    }
+
+    fn editable_file(&mut self, session: SessionId, path: &LsPath) -> Result<&mut OpenFile> {
+        let file = self.open_file(session, path)?;
+        if file.editor == Some(session) {
+            Ok(file)
+        } else {
+            Err(RpcError::new(code::WRITE_DENIED, "Write denied"))
+        }
+    }
+
+
+    // === Execution Contexts ===
+
+    fn context(&mut self, context_id: ContextId) -> Result<&mut ExecutionContext> {
+        self.contexts.get_mut(&context_id).ok_or_else(|| context_not_found(context_id))
+    }
+
+    fn create_context(&mut self, session: SessionId, params: CreateContext) -> Result {
+        let context_id = params.context_id.unwrap_or_else(Uuid::new_v4);
+        let context = ExecutionContext {
+            owner:          session,
+            stack:          default(),
+            visualizations: default(),
+        };
+        self.contexts.insert(context_id, context);
+        to_value(response::CreateExecutionContext {
+            context_id,
+            can_modify: CapabilityRegistration::create_can_modify_execution_context(context_id),
+            receives_updates: CapabilityRegistration::create_receives_execution_context_updates(
+                context_id,
+            ),
+        })
+    }
+
+    fn push(&mut self, session: SessionId, Push { context_id, stack_item }: Push) -> Result {
+        let context = self.context(context_id)?;
+        let valid = match stack_item {
+            StackItem::ExplicitCall(_) => context.stack.is_empty(),
+            StackItem::LocalCall(_) => !context.stack.is_empty(),
+        };
+        if !valid {
+            return Err(RpcError::new(code::INVALID_STACK_ITEM, "Invalid stack item"));
+        }
+        context.stack.push(stack_item);
+        self.defer_execution_complete(session, context_id);
+        Ok(Value::Null)
+    }
+
+    fn pop(&mut self, session: SessionId, ContextParams { context_id }: ContextParams) -> Result {
+        let context = self.context(context_id)?;
+        context.stack.pop().ok_or_else(|| RpcError::new(code::EMPTY_STACK, "Stack is empty"))?;
+        if !context.stack.is_empty() {
+            self.defer_execution_complete(session, context_id);
+        }
+        Ok(Value::Null)
+    }
+
+    fn defer_execution_complete(&mut self, session: SessionId, context_id: ContextId) {
+        let method = "executionContext/executionComplete".to_owned();
+        let params = json!({ "contextId": context_id });
+        self.deferred.push(Deferred::Notification { session, method, params });
+    }
+
+    fn attach_visualization(&mut self, session: SessionId, params: AttachVisualization) -> Result {
+        let AttachVisualization { visualization_id, expression_id, visualization_config } = params;
+        let context_id = visualization_config.execution_context_id;
+        let context = self.context(context_id)?;
+        context.visualizations.insert(visualization_id, expression_id);
+        self.defer_visualization_update(session, VisualizationContext {
+            visualization_id,
+            context_id,
+            expression_id,
+        });
+        Ok(Value::Null)
+    }
+
+    fn detach_visualization(&mut self, params: DetachVisualization) -> Result {
+        let DetachVisualization { context_id, visualization_id, .. } = params;
+        let context = self.context(context_id)?;
+        let removed = context.visualizations.remove(&visualization_id);
+        removed.ok_or_else(|| visualization_not_found(visualization_id))?;
+        Ok(Value::Null)
+    }
+
+    fn modify_visualization(&mut self, session: SessionId, params: ModifyVisualization) -> Result {
+        let ModifyVisualization { visualization_id, visualization_config } = params;
+        let new_context_id = visualization_config.execution_context_id;
+        self.context(new_context_id)?;
+        let contexts = self.contexts.values_mut();
+        let removed = contexts.find_map(|context| context.visualizations.remove(&visualization_id));
+        let expression_id = removed.ok_or_else(|| visualization_not_found(visualization_id))?;
+        let context = self.context(new_context_id)?;
+        context.visualizations.insert(visualization_id, expression_id);
+        self.defer_visualization_update(session, VisualizationContext {
+            visualization_id,
+            context_id: new_context_id,
+            expression_id,
+        });
+        Ok(Value::Null)
+    }
+
+    /// Schedule sending the scripted data of the visualization, if any.
+    fn defer_visualization_update(&mut self, session: SessionId, context: VisualizationContext) {
+        if let Some(data) = self.script.visualization_data.get(&context.expression_id) {
+            let data = data.to_string().into_bytes();
+            self.deferred.push(Deferred::VisualizationUpdate { session, context, data });
+        }
+    }
+
+
+    // === Binary Protocol ===
+
+    /// Handle a binary message.
+    pub fn handle_binary(&mut self, session: SessionId, data: &[u8]) {
+        let request = match MessageToServerOwned::deserialize(data) {
+            Ok(request) => request,
+            Err(error) => {
+                warn!("Failed to deserialize binary message: {error:?}");
+                return;
+            }
+        };
+        let payload = match self.binary_call(session, request.payload.clone()) {
+            Ok(payload) => payload,
+            Err(error) => {
+                let file_length = error.data.as_ref().and_then(|d| d.get("fileLength"));
+                let file_length = file_length.and_then(Value::as_u64);
+                let data = file_length.map(|file_length| ErrorPayload::ReadOOB { file_length });
+                FromServerPayloadOwned::Error {
+                    code: error.code as i32,
+                    message: error.message,
+                    data,
+                }
+            }
+        };
+        let mut reply = MessageFromServer::new(payload);
+        reply.correlation_id = Some(request.message_id);
+        let reply = Message::Binary(reply.with_serialized(|data| data.to_vec()));
+        if let Some(session) = self.binary_sessions.get(&session) {
+            let _ = session.sender.send(reply);
+        }
+    }
+
+    fn binary_call(
+        &mut self,
+        session: SessionId,
+        payload: ToServerPayloadOwned,
+    ) -> Result<FromServerPayloadOwned> {
+        match payload {
+            ToServerPayloadOwned::InitSession { client_id } => {
+                if let Some(session) = self.binary_sessions.get_mut(&session) {
+                    session.client_id = Some(client_id);
+                }
+                Ok(FromServerPayloadOwned::Success {})
+            }
+            ToServerPayloadOwned::WriteFile { path, contents } => {
+                self.files.write(&path, &contents)?;
+                Ok(FromServerPayloadOwned::Success {})
+            }
+            ToServerPayloadOwned::ReadFile { path } =>
+                Ok(FromServerPayloadOwned::FileContentsReply { contents: self.files.read(&path)? }),
+            ToServerPayloadOwned::WriteBytes { path, byte_offset, overwrite, bytes } => {
+                let checksum = self.files.write_bytes(&path, byte_offset, overwrite, &bytes)?;
+                Ok(FromServerPayloadOwned::WriteBytesReply { checksum: digest(&checksum) })
+            }
+            ToServerPayloadOwned::ReadBytes { segment } => {
+                let bytes = self.files.read_bytes(&segment)?;
+                let checksum = digest(&Sha3_224::new(&bytes));
+                Ok(FromServerPayloadOwned::ReadBytesReply { checksum, bytes })
+            }
+            ToServerPayloadOwned::ChecksumBytes { segment } => {
+                let bytes = self.files.read_bytes(&segment)?;
+                let checksum = digest(&Sha3_224::new(&bytes));
+                Ok(FromServerPayloadOwned::ChecksumBytesReply { checksum })
+            }
+        }
+    }
+}
+
+
+
+// ===============
+// === Helpers ===
+// ===============
+
+fn parse<T: DeserializeOwned>(params: &Value) -> Result<T> {
+    T::deserialize(params).map_err(RpcError::invalid_params)
+}
+
+fn to_value(value: impl Serialize) -> Result {
+    serde_json::to_value(value)
+        .map_err(|error| RpcError::new(code::INTERNAL_ERROR, error.to_string()))
+}
+
+fn digest(checksum: &Sha3_224) -> engine_protocol::binary::message::EnsoDigest {
+    let bytes = hex::decode(checksum.as_str()).unwrap_or_default();
+    engine_protocol::binary::message::EnsoDigest { bytes }
+}
+
+fn visualization_update(context: VisualizationContext, data: Vec<u8>) -> Message {
+    let update =
+        MessageFromServer::new(FromServerPayloadOwned::VisualizationUpdate { context, data });
+    Message::Binary(update.with_serialized(|data| data.to_vec()))
+}
+
+fn invalid_version(actual: &Sha3_224, expected: &Sha3_224) -> RpcError {
+    let message = format!("Invalid version: expected {expected}, actual {actual}");
+    RpcError::new(code::INVALID_VERSION, message)
+}
+
+fn context_not_found(context_id: ContextId) -> RpcError {
+    RpcError::new(code::CONTEXT_NOT_FOUND, format!("Context not found: {context_id}"))
+}
+
+fn visualization_not_found(visualization_id: Uuid) -> RpcError {
+    let message = format!("Visualization not found: {visualization_id}");
+    RpcError::new(code::VISUALIZATION_NOT_FOUND, message)
+}
+
+
+// === Parameters ===
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InitProtocol {
+    client_id: Uuid,
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    path: LsPath,
+}
+
+#[derive(Deserialize)]
+struct FromTo {
+    from: LsPath,
+    to:   LsPath,
+}
+
+#[derive(Deserialize)]
+struct Write {
+    path:     LsPath,
+    contents: String,
+}
+
+#[derive(Deserialize)]
+struct Create {
+    object: FileSystemObject,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Save {
+    path:            LsPath,
+    current_version: Sha3_224,
+}
+
+#[derive(Deserialize)]
+struct ApplyEdit {
+    edit: FileEdit,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContextParams {
+    context_id: ContextId,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateContext {
+    #[serde(default)]
+    context_id: Option<ContextId>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Push {
+    context_id: ContextId,
+    stack_item: StackItem,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachVisualization {
+    visualization_id:     Uuid,
+    expression_id:        Uuid,
+    visualization_config: VisualizationConfiguration,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct DetachVisualization {
+    context_id:       ContextId,
+    visualization_id: Uuid,
+    expression_id:    Uuid,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModifyVisualization {
+    visualization_id:     Uuid,
+    visualization_config: VisualizationConfiguration,
+}
@@ -0,0 +1,133 @@
+//! Text files opened by the clients with the `text/*` methods.
+
+use enso_prelude::*;
+
+use crate::error::code;
+use crate::error::Result;
+use crate::error::RpcError;
+use crate::SessionId;
+
+use engine_protocol::language_server::Position;
+use engine_protocol::language_server::TextEdit;
+use engine_protocol::types::Sha3_224;
+use std::collections::BTreeSet;
+
+
+
+// ================
+// === OpenFile ===
+// ================
+
+/// A text file opened by at least one client. Edits are applied to the buffer and written to the
+/// disk when the file is saved.
+#[derive(Clone, Debug)]
+pub struct OpenFile {
+    /// The current contents of the buffer.
+    pub content:  String,
+    /// The session holding the `text/canEdit` capability.
+    pub editor:   Option<SessionId>,
+    /// The sessions which opened the file.
+    pub sessions: BTreeSet<SessionId>,
+}
+
+impl OpenFile {
+    /// Open a file with the given content.
+    pub fn new(content: String) -> Self {
+        Self { content, editor: None, sessions: default() }
+    }
+
+    /// The version of the buffer, as reported in the protocol.
+    pub fn version(&self) -> Sha3_224 {
+        Sha3_224::new(self.content.as_bytes())
+    }
+}
+
+
+
+// =============
+// === Edits ===
+// =============
+
+/// Apply the edits, one after another, to the text.
+pub fn apply_edits(text: &str, edits: &[TextEdit]) -> Result<String> {
+    let mut text = text.to_owned();
+    for edit in edits {
+        let start = byte_offset(&text, edit.range.start)?;
+        let end = byte_offset(&text, edit.range.end)?;
+        if start > end {
+            let message = format!("Invalid range: {:?}", edit.range);
+            return Err(RpcError::new(code::TEXT_EDIT_VALIDATION_ERROR, message));
+        }
+        text.replace_range(start..end, &edit.text);
+    }
+    Ok(text)
+}
+
+/// The byte offset of the position, whose `character` is given in UTF-16 code units.
+fn byte_offset(text: &str, position: Position) -> Result<usize> {
+    let invalid = || {
+        let message = format!("Invalid position: {position:?}");
+        RpcError::new(code::TEXT_EDIT_VALIDATION_ERROR, message)
+    };
+    let line_start = if position.line == 0 {
+        0
+    } else {
+        let mut newlines = text.match_indices('\n').map(|(index, _)| index + 1);
+        newlines.nth(position.line - 1).ok_or_else(invalid)?
+    };
+    let line_end = text[line_start..].find('\n').map_or(text.len(), |end| line_start + end);
+    let mut utf16_offset = 0;
+    for (index, char) in text[line_start..line_end].char_indices() {
+        if utf16_offset == position.character {
+            return Ok(line_start + index);
+        }
+        utf16_offset += char.len_utf16();
+    }
+    if utf16_offset == position.character {
+        Ok(line_end)
+    } else {
+        Err(invalid())
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use engine_protocol::language_server::TextRange;
+
+    fn edit(start: (usize, usize), end: (usize, usize), text: &str) -> TextEdit {
+        let start = Position { line: start.0, character: start.1 };
+        let end = Position { line: end.0, character: end.1 };
+        TextEdit { range: TextRange { start, end }, text: text.to_owned() }
+    }
+
+    #[test]
+    fn applying_edits() {
+        let text = "main =\n    x = 🌊 + 1\n    x";
+        let edits = [
+            edit((1, 13), (1, 14), "2"),
+            edit((2, 4), (2, 5), "x + 1"),
+            edit((0, 0), (0, 0), "# Hi\n"),
+        ];
+        let expected = "# Hi\nmain =\n    x = 🌊 + 2\n    x + 1";
+        assert_eq!(apply_edits(text, &edits).unwrap(), expected);
+    }
+
+    #[test]
+    fn rejecting_invalid_positions() {
+        let text = "a\nbc";
+        for position in [(0, 2), (1, 3), (2, 0)] {
+            let result = apply_edits(text, &[edit(position, position, "x")]);
+            assert_eq!(result.unwrap_err().code, code::TEXT_EDIT_VALIDATION_ERROR);
+        }
+        let inverted = apply_edits(text, &[edit((1, 1), (0, 1), "x")]);
+        assert_eq!(inverted.unwrap_err().code, code::TEXT_EDIT_VALIDATION_ERROR);
+    }
+}
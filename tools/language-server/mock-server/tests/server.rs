@@ -0,0 +1,287 @@
+//! Tests of the mock Language Server, talking to it over real WebSocket connections.
+
+use engine_protocol::binary::message::FileSegment;
+use engine_protocol::binary::message::FromServerPayloadOwned;
+use engine_protocol::binary::message::MessageFromServerOwned;
+use engine_protocol::binary::message::MessageToServer;
+use engine_protocol::binary::message::ToServerPayloadOwned;
+use engine_protocol::binary::serialization::DeserializableRoot;
+use engine_protocol::binary::serialization::SerializableRoot;
+use engine_protocol::types::Sha3_224;
+use futures::SinkExt;
+use futures::StreamExt;
+use mock_language_server::error::code;
+use mock_language_server::MockLanguageServer;
+use mock_language_server::Notification;
+use mock_language_server::Reply;
+use serde_json::json;
+use serde_json::Value;
+use std::collections::VecDeque;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::MaybeTlsStream;
+use tokio_tungstenite::WebSocketStream;
+use uuid::Uuid;
+
+
+
+// ===============
+// === Clients ===
+// ===============
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// A client of the text endpoint.
+struct JsonClient {
+    socket:        Socket,
+    next_id:       u64,
+    notifications: VecDeque<Value>,
+}
+
+impl JsonClient {
+    async fn connect(server: &MockLanguageServer, client_id: Uuid) -> Self {
+        let (socket, _) = tokio_tungstenite::connect_async(server.json_endpoint()).await.unwrap();
+        let mut client = Self { socket, next_id: 0, notifications: default() };
+        let init =
+            client.request("session/initProtocolConnection", json!({ "clientId": client_id }));
+        let roots = init.await.unwrap();
+        assert_eq!(roots["contentRoots"][0]["id"], json!(server.project_root_id()));
+        client
+    }
+
+    /// Call the method, returning its result or error.
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        self.socket.send(Message::Text(request.to_string())).await.unwrap();
+        loop {
+            let message = self.receive().await;
+            if message["id"] == json!(id) {
+                return match message.get("error") {
+                    Some(error) => Err(error.clone()),
+                    None => Ok(message["result"].clone()),
+                };
+            }
+            self.notifications.push_back(message);
+        }
+    }
+
+    async fn expect_notification(&mut self, method: &str) -> Value {
+        let notification = match self.notifications.pop_front() {
+            Some(notification) => notification,
+            None => self.receive().await,
+        };
+        assert_eq!(notification["method"], method, "Unexpected notification: {notification}");
+        notification["params"].clone()
+    }
+
+    async fn receive(&mut self) -> Value {
+        match self.socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            message => panic!("Unexpected message: {message:?}"),
+        }
+    }
+}
+
+/// A client of the binary endpoint.
+struct BinaryClient {
+    socket: Socket,
+}
+
+impl BinaryClient {
+    async fn connect(server: &MockLanguageServer, client_id: Uuid) -> Self {
+        let (socket, _) = tokio_tungstenite::connect_async(server.binary_endpoint()).await.unwrap();
+        let mut client = Self { socket };
+        let reply = client.request(ToServerPayloadOwned::InitSession { client_id }).await;
+        assert!(matches!(reply, FromServerPayloadOwned::Success {}));
+        client
+    }
+
+    async fn request(&mut self, payload: ToServerPayloadOwned) -> FromServerPayloadOwned {
+        let request = MessageToServer::new(payload);
+        let data = request.with_serialized(|data| data.to_vec());
+        self.socket.send(Message::Binary(data)).await.unwrap();
+        let reply = self.receive().await;
+        assert_eq!(reply.correlation_id, Some(request.message_id));
+        reply.0.payload
+    }
+
+    async fn receive(&mut self) -> MessageFromServerOwned {
+        match self.socket.next().await.unwrap().unwrap() {
+            Message::Binary(data) => MessageFromServerOwned::deserialize(&data).unwrap(),
+            message => panic!("Unexpected message: {message:?}"),
+        }
+    }
+}
+
+fn default<T: Default>() -> T {
+    T::default()
+}
+
+fn main_call() -> Value {
+    json!({
+        "type": "ExplicitCall",
+        "methodPointer": {
+            "module": "local.Project.Main",
+            "definedOnType": "local.Project.Main",
+            "name": "main"
+        },
+        "thisArgumentExpression": null,
+        "positionalArgumentsExpressions": []
+    })
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[tokio::test]
+async fn editing_text_files() {
+    let server = MockLanguageServer::start(default()).await.unwrap();
+    let mut client = JsonClient::connect(&server, Uuid::new_v4()).await;
+    let mut other = JsonClient::connect(&server, Uuid::new_v4()).await;
+    let path = server.path(["src", "Main.enso"]);
+
+    let contents = "main =\n    42";
+    client.request("file/write", json!({ "path": path, "contents": contents })).await.unwrap();
+    let exists = client.request("file/exists", json!({ "path": path })).await.unwrap();
+    assert_eq!(exists, json!({ "exists": true }));
+
+    let opened = client.request("text/openFile", json!({ "path": path })).await.unwrap();
+    assert_eq!(opened["content"], contents);
+    assert_eq!(opened["writeCapability"]["method"], "text/canEdit");
+    let opened_by_other = other.request("text/openFile", json!({ "path": path })).await.unwrap();
+    assert_eq!(opened_by_other["writeCapability"], Value::Null);
+
+    let new_contents = "main =\n    43";
+    let edit = json!({
+        "path": path,
+        "edits": [{
+            "range": { "start": { "line": 1, "character": 5 }, "end": { "line": 1, "character": 6 } },
+            "text": "3"
+        }],
+        "oldVersion": Sha3_224::new(contents.as_bytes()),
+        "newVersion": Sha3_224::new(new_contents.as_bytes()),
+    });
+    let denied = other.request("text/applyEdit", json!({ "edit": edit })).await.unwrap_err();
+    assert_eq!(denied["code"], code::WRITE_DENIED);
+    client.request("text/applyEdit", json!({ "edit": edit })).await.unwrap();
+    let change = other.expect_notification("text/didChange").await;
+    assert_eq!(change["edits"][0], edit);
+    assert_eq!(server.read_file("src/Main.enso").unwrap(), contents);
+
+    let version = Sha3_224::new(new_contents.as_bytes());
+    let save = json!({ "path": path, "currentVersion": version });
+    client.request("text/save", save).await.unwrap();
+    assert_eq!(server.read_file("src/Main.enso").unwrap(), new_contents);
+}
+
+#[tokio::test]
+async fn scripting_execution_contexts() {
+    let server = MockLanguageServer::start(default()).await.unwrap();
+    let expression_id = Uuid::new_v4();
+    server.script(|script| {
+        let invalid = Reply::error(code::INVALID_STACK_ITEM, "Invalid stack item");
+        script.reply("executionContext/push", invalid);
+        let updates =
+            json!({ "contextId": "$contextId", "updates": [{ "expressionId": expression_id }] });
+        let updates = Notification::new("executionContext/expressionUpdates", updates);
+        script.notify_after("executionContext/push", updates);
+    });
+    let mut client = JsonClient::connect(&server, Uuid::new_v4()).await;
+
+    let created = client.request("executionContext/create", json!({})).await.unwrap();
+    let context_id = created["contextId"].clone();
+    let push = json!({ "contextId": context_id, "stackItem": main_call() });
+    let error = client.request("executionContext/push", push.clone()).await.unwrap_err();
+    assert_eq!(error["code"], code::INVALID_STACK_ITEM);
+    client.request("executionContext/push", push).await.unwrap();
+    let updates = client.expect_notification("executionContext/expressionUpdates").await;
+    assert_eq!(updates["contextId"], context_id);
+    assert_eq!(updates["updates"][0]["expressionId"], json!(expression_id));
+    let complete = client.expect_notification("executionContext/executionComplete").await;
+    assert_eq!(complete["contextId"], context_id);
+
+    client.request("executionContext/pop", json!({ "contextId": context_id })).await.unwrap();
+    let error = client.request("executionContext/pop", json!({ "contextId": context_id })).await;
+    assert_eq!(error.unwrap_err()["code"], code::EMPTY_STACK);
+    let error = client.request("executionContext/unknownMethod", json!({})).await.unwrap_err();
+    assert_eq!(error["code"], code::METHOD_NOT_FOUND);
+
+    let methods = server.called_methods();
+    let pushes = methods.iter().filter(|method| *method == "executionContext/push").count();
+    assert_eq!(pushes, 2);
+}
+
+#[tokio::test]
+async fn binary_files_and_visualizations() {
+    let server = MockLanguageServer::start(default()).await.unwrap();
+    let expression_id = Uuid::new_v4();
+    server.script(|script| {
+        script.visualization_data(expression_id, json!([1, 2, 3]));
+    });
+    let client_id = Uuid::new_v4();
+    let mut json_client = JsonClient::connect(&server, client_id).await;
+    let mut binary_client = BinaryClient::connect(&server, client_id).await;
+
+    let path = server.path(["data.bin"]);
+    let contents = b"Hello, World!".to_vec();
+    let write = ToServerPayloadOwned::WriteFile { path: path.clone(), contents };
+    let reply = binary_client.request(write).await;
+    assert!(matches!(reply, FromServerPayloadOwned::Success {}));
+    let segment = FileSegment { path: path.clone(), byte_offset: 7, length: 5 };
+    let reply = binary_client.request(ToServerPayloadOwned::ReadBytes { segment }).await;
+    match reply {
+        FromServerPayloadOwned::ReadBytesReply { checksum, bytes } => {
+            assert_eq!(bytes, b"World");
+            assert_eq!(Sha3_224::from(checksum), Sha3_224::new(b"World"));
+        }
+        other => panic!("Unexpected reply: {other:?}"),
+    }
+    let segment = FileSegment { path, byte_offset: 10, length: 10 };
+    let reply = binary_client.request(ToServerPayloadOwned::ReadBytes { segment }).await;
+    assert!(matches!(reply, FromServerPayloadOwned::Error { code: 1009, .. }));
+
+    let created = json_client.request("executionContext/create", json!({})).await.unwrap();
+    let context_id = created["contextId"].clone();
+    let visualization_id = Uuid::new_v4();
+    let config = json!({
+        "visualizationModule": "local.Project.Main",
+        "executionContextId": context_id,
+        "expression": { "module": "Standard.Visualization", "definedOnType": "Id", "name": "id" },
+        "positionalArgumentsExpressions": []
+    });
+    let attach = json!({
+        "visualizationId": visualization_id,
+        "expressionId": expression_id,
+        "visualizationConfig": config,
+    });
+    json_client.request("executionContext/attachVisualization", attach).await.unwrap();
+    let update = binary_client.receive().await;
+    match update.0.payload {
+        FromServerPayloadOwned::VisualizationUpdate { context, data } => {
+            assert_eq!(context.visualization_id, visualization_id);
+            assert_eq!(context.expression_id, expression_id);
+            assert_eq!(data, b"[1,2,3]");
+        }
+        other => panic!("Unexpected message: {other:?}"),
+    }
+    assert_eq!(server.visualizations().len(), 1);
+}
+
+#[tokio::test]
+async fn batch_of_notifications_is_not_replied() {
+    let server = MockLanguageServer::start(default()).await.unwrap();
+    let mut client = JsonClient::connect(&server, Uuid::new_v4()).await;
+    let notification = json!({ "jsonrpc": "2.0", "method": "heartbeat/reply", "params": {} });
+    let batch = json!([notification.clone(), notification]);
+    client.socket.send(Message::Text(batch.to_string())).await.unwrap();
+
+    let path = server.path(["Missing.enso"]);
+    let exists = client.request("file/exists", json!({ "path": path })).await.unwrap();
+    assert_eq!(exists, json!({ "exists": false }));
+    assert!(client.notifications.is_empty(), "Unexpected messages: {:?}", client.notifications);
+}
@@ -17,6 +17,8 @@
 
 
 mod format;
+mod report;
+mod scenario;
 
 use enso_prelude::*;
 
@@ -103,6 +105,27 @@ struct Args {
     /// Time in milliseconds to wait before sending the next request from the `input` file.
     #[clap(long, value_name = "MILLISECONDS", default_value = "0")]
     wait_after_response: u64,
+
+    /// Path to a scenario file to run instead of the `input` messages. See the `scenario` module
+    /// for the format description.
+    #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    scenario: Option<PathBuf>,
+
+    /// Number of clients running the scenario concurrently, each over its own text socket.
+    #[clap(long, default_value = "1")]
+    clients: usize,
+
+    /// Time in milliseconds to wait for a response in a scenario step.
+    #[clap(long, value_name = "MILLISECONDS", default_value = "30000")]
+    response_timeout: u64,
+
+    /// Path to write the JSON report with the scenario step latencies to.
+    #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    report_json: Option<PathBuf>,
+
+    /// Path to write the CSV report with the scenario step latencies to.
+    #[clap(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    report_csv: Option<PathBuf>,
 }
 
 
@@ -133,6 +156,37 @@ async fn read_lines(path_buf: PathBuf) -> std::io::Result<Vec<String>> {
 
 
 
+// ================
+// === Scenario ===
+// ================
+
+/// Run the scenario with the given number of concurrent clients and report the step latencies.
+async fn run_scenario(args: Args, path: PathBuf) -> Result<()> {
+    let scenario = scenario::Scenario::read(&path).await?;
+    let timeout = Duration::from_millis(args.response_timeout);
+    let clients = (0..args.clients).map(|index| {
+        let url = args.text_socket.clone();
+        scenario::run_client(url, &scenario, index, timeout)
+    });
+    let results = futures::future::join_all(clients).await;
+    let mut samples = Vec::new();
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(client_samples) => samples.extend(client_samples),
+            Err(error) => return Err(format!("Client {index} failed: {error}").into()),
+        }
+    }
+    let report = report::Report::new(args.clients, &samples);
+    report.print();
+    report.write(args.report_json.as_deref(), args.report_csv.as_deref()).await?;
+    match report.failures() {
+        0 => Ok(()),
+        failures => Err(format!("{failures} scenario steps failed the assertions.").into()),
+    }
+}
+
+
+
 // ============
 // === Main ===
 // ============
@@ -140,6 +194,9 @@ async fn read_lines(path_buf: PathBuf) -> std::io::Result<Vec<String>> {
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    if let Some(path) = args.scenario.clone() {
+        return run_scenario(args, path).await;
+    }
 
     // text socket connection
     let text_socket_client = ClientBuilder::from_url(args.text_socket).async_connect().await?;
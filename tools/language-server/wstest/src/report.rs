@@ -0,0 +1,184 @@
+//! Latency statistics of the scenario steps, written as JSON and CSV reports.
+
+use enso_prelude::*;
+
+use crate::scenario::Sample;
+
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use websocket_lite::Result;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Name of the row aggregating the samples of all the steps.
+const ALL_STEPS: &str = "<all>";
+
+/// Header of the CSV report, matching the [`StepStats`] fields.
+const CSV_HEADER: &str = "step,count,failures,min_ms,mean_ms,p50_ms,p90_ms,p99_ms,max_ms";
+
+
+
+// =================
+// === StepStats ===
+// =================
+
+/// Latency statistics of a single step, in milliseconds.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StepStats {
+    pub step:     String,
+    pub count:    usize,
+    /// Number of the samples that failed the assertions.
+    pub failures: usize,
+    pub min_ms:   f64,
+    pub mean_ms:  f64,
+    pub p50_ms:   f64,
+    pub p90_ms:   f64,
+    pub p99_ms:   f64,
+    pub max_ms:   f64,
+}
+
+impl StepStats {
+    /// Compute the statistics of the given samples. There must be at least one sample.
+    fn new(step: impl Into<String>, samples: &[&Sample]) -> Self {
+        let mut latencies = samples.iter().map(|s| to_ms(s.latency)).collect_vec();
+        latencies.sort_by(f64::total_cmp);
+        let count = latencies.len();
+        let failures = samples.iter().filter(|s| !s.passed).count();
+        let mean_ms = latencies.iter().sum::<f64>() / count as f64;
+        Self {
+            step: step.into(),
+            count,
+            failures,
+            min_ms: latencies[0],
+            mean_ms,
+            p50_ms: percentile(&latencies, 50.0),
+            p90_ms: percentile(&latencies, 90.0),
+            p99_ms: percentile(&latencies, 99.0),
+            max_ms: latencies[count - 1],
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let Self { step, count, failures, min_ms, mean_ms, p50_ms, p90_ms, p99_ms, max_ms } = self;
+        let step = step.replace('"', "\"\"");
+        format!(
+            "\"{step}\",{count},{failures},{min_ms:.3},{mean_ms:.3},{p50_ms:.3},{p90_ms:.3},\
+            {p99_ms:.3},{max_ms:.3}"
+        )
+    }
+}
+
+fn to_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// The nearest-rank percentile of the sorted values.
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+
+
+// ==============
+// === Report ===
+// ==============
+
+/// Statistics of all the steps, in the order of their first appearance, followed by the
+/// aggregate over all the steps.
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub clients: usize,
+    pub steps:   Vec<StepStats>,
+}
+
+impl Report {
+    /// Compute the report from the samples collected by all the clients.
+    pub fn new(clients: usize, samples: &[Sample]) -> Self {
+        let mut by_step: Vec<(&str, Vec<&Sample>)> = Vec::new();
+        for sample in samples {
+            match by_step.iter_mut().find(|(step, _)| *step == sample.step) {
+                Some((_, step_samples)) => step_samples.push(sample),
+                None => by_step.push((&sample.step, vec![sample])),
+            }
+        }
+        let stats = by_step.iter().map(|(step, samples)| StepStats::new(*step, samples));
+        let mut steps = stats.collect_vec();
+        if !samples.is_empty() {
+            steps.push(StepStats::new(ALL_STEPS, &samples.iter().collect_vec()));
+        }
+        Self { clients, steps }
+    }
+
+    /// Total number of the samples that failed the assertions.
+    pub fn failures(&self) -> usize {
+        self.steps.iter().filter(|s| s.step != ALL_STEPS).map(|s| s.failures).sum()
+    }
+
+    /// Render the report as a CSV table.
+    pub fn to_csv(&self) -> String {
+        let rows = self.steps.iter().map(|stats| stats.csv_row());
+        std::iter::once(CSV_HEADER.to_string()).chain(rows).map(|row| row + "\n").collect()
+    }
+
+    /// Print a human-readable summary.
+    pub fn print(&self) {
+        println!("Latencies of {} clients, in milliseconds:", self.clients);
+        for stats in &self.steps {
+            println!(
+                "{:<30} n={:<6} p50={:<10.3} p90={:<10.3} p99={:<10.3} max={:.3}",
+                stats.step, stats.count, stats.p50_ms, stats.p90_ms, stats.p99_ms, stats.max_ms
+            );
+        }
+    }
+
+    /// Write the report to the given JSON and CSV files.
+    pub async fn write(&self, json: Option<&Path>, csv: Option<&Path>) -> Result<()> {
+        if let Some(path) = json {
+            tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        }
+        if let Some(path) = csv {
+            tokio::fs::write(path, self.to_csv()).await?;
+        }
+        Ok(())
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(step: &str, ms: u64, passed: bool) -> Sample {
+        Sample { step: step.into(), latency: Duration::from_millis(ms), passed }
+    }
+
+    #[test]
+    fn percentiles_are_computed_per_step() {
+        let mut samples = (1..=100).map(|ms| sample("edit", ms, true)).collect_vec();
+        samples.push(sample("open", 7, false));
+        let report = Report::new(2, &samples);
+        let names = report.steps.iter().map(|s| s.step.as_str()).collect_vec();
+        assert_eq!(names, vec!["edit", "open", ALL_STEPS]);
+        let edit = &report.steps[0];
+        assert_eq!((edit.p50_ms, edit.p90_ms, edit.p99_ms), (50.0, 90.0, 99.0));
+        assert_eq!((edit.min_ms, edit.max_ms, edit.mean_ms), (1.0, 100.0, 50.5));
+        assert_eq!(report.steps[1].p99_ms, 7.0);
+        assert_eq!(report.steps[2].count, 101);
+        assert_eq!(report.failures(), 1);
+        let csv = report.to_csv();
+        let lines = csv.lines().collect_vec();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[2], "\"open\",1,1,7.000,7.000,7.000,7.000,7.000,7.000");
+    }
+}
@@ -0,0 +1,362 @@
+//! Scenarios describing the sequence of requests sent by each simulated client.
+//!
+//! A scenario is a YAML file like the following:
+//!
+//! ```yaml
+//! variables:
+//!   root_id: 6f7d58dd-8ee8-44cf-9ab7-9f0454033641
+//! setup:
+//!   - name: init
+//!     send: >-
+//!       {"jsonrpc": "2.0", "id": 0, "method": "session/initProtocolConnection",
+//!       "params": {"clientId": "${uuid}"}}
+//!   - name: create context
+//!     send: '{"jsonrpc": "2.0", "id": 1, "method": "executionContext/create", "params": {}}'
+//!     capture:
+//!       context_id: /result/contextId
+//! steps:
+//!   - name: list files
+//!     send: >-
+//!       {"jsonrpc": "2.0", "id": 2, "method": "file/list",
+//!       "params": {"path": {"rootId": "${root_id}", "segments": ["src"]}}}
+//!     assert:
+//!       - pointer: /result/paths
+//!   - name: recompute
+//!     send: >-
+//!       {"jsonrpc": "2.0", "id": 3, "method": "executionContext/recompute",
+//!       "params": {"contextId": "${context_id}"}}
+//!     assert:
+//!       - pointer: /result
+//!         equals: null
+//! warmup_iterations: 5
+//! iterations: 20
+//! ```
+//!
+//! The `setup` steps are run once by each client, and the `steps` are then repeated. Only the
+//! repetitions following the warmup ones are measured. The `${name}` placeholders are replaced
+//! with the variables defined in the scenario, captured from the earlier responses, or with the
+//! built-in ones: `client` (the client index) and `uuid` (a fresh random UUID).
+
+use enso_prelude::*;
+
+use crate::format;
+
+use futures::SinkExt;
+use futures::StreamExt;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+use std::time::Instant;
+use url::Url;
+use websocket_lite::ClientBuilder;
+use websocket_lite::Message;
+use websocket_lite::Opcode;
+use websocket_lite::Result;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Name of the built-in variable with the client index.
+const CLIENT_VARIABLE: &str = "client";
+
+/// Name of the built-in variable that expands to a fresh random UUID.
+const UUID_VARIABLE: &str = "uuid";
+
+
+
+// ================
+// === Scenario ===
+// ================
+
+/// The scenario run by each simulated client.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scenario {
+    /// Initial values of the variables.
+    #[serde(default)]
+    pub variables:         BTreeMap<String, String>,
+    /// Steps run once, before the measured ones.
+    #[serde(default)]
+    pub setup:             Vec<Step>,
+    /// Steps that are repeated and measured.
+    pub steps:             Vec<Step>,
+    /// Number of the unmeasured repetitions of the steps.
+    #[serde(default)]
+    pub warmup_iterations: usize,
+    /// Number of the measured repetitions of the steps.
+    #[serde(default = "default_iterations")]
+    pub iterations:        usize,
+}
+
+fn default_iterations() -> usize {
+    1
+}
+
+/// A single request and the expectations about its response.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Step {
+    /// Name identifying the step in the reports.
+    pub name:     String,
+    /// The text message to send. May contain `${name}` placeholders.
+    pub send:     String,
+    /// Regex matching the response to wait for. By default, the step waits for the JSON-RPC
+    /// response with the same `id` as the request, or does not wait at all for notifications.
+    #[serde(default)]
+    pub wait_for: Option<String>,
+    /// Variables to capture, mapped to JSON pointers into the response.
+    #[serde(default)]
+    pub capture:  BTreeMap<String, String>,
+    /// Assertions the response must satisfy.
+    #[serde(default)]
+    pub assert:   Vec<Assertion>,
+    /// Time in milliseconds to wait after the response is received.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// An assertion about the value at the given JSON pointer in the response.
+///
+/// If neither `equals` nor `matches` is given, the value only needs to be present.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Assertion {
+    pub pointer: String,
+    /// The expected value. An explicit `null` is expected like any other value.
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub equals:  Option<Value>,
+    /// Regex that the value, rendered as a string, must match.
+    #[serde(default)]
+    pub matches: Option<String>,
+}
+
+impl Scenario {
+    /// Read the scenario from a YAML file.
+    pub async fn read(path: &Path) -> Result<Self> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        let scenario: Self = serde_yaml::from_str(&contents)?;
+        if scenario.steps.is_empty() {
+            return Err(format!("Scenario {} has no steps.", path.display()).into());
+        }
+        Ok(scenario)
+    }
+}
+
+/// Deserialize a field that is present in the input, even if it is `null`. Together with
+/// `#[serde(default)]`, this distinguishes an explicit `null` from a missing field.
+fn deserialize_present<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+impl Assertion {
+    /// Check the assertion, returning the description of the failure.
+    fn check(&self, response: &Value) -> std::result::Result<(), String> {
+        let pointer = &self.pointer;
+        let value = response.pointer(pointer).ok_or_else(|| format!("{pointer} is missing"))?;
+        if let Some(expected) = &self.equals {
+            if value != expected {
+                return Err(format!("{pointer} is {value} instead of {expected}"));
+            }
+        }
+        if let Some(pattern) = &self.matches {
+            let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+            let text = value.as_str().map_or_else(|| value.to_string(), |s| s.to_string());
+            if !regex.is_match(&text) {
+                return Err(format!("{pointer} is {value}, which does not match {pattern}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replace the `${name}` placeholders with the variable values. Undefined variables are an error.
+pub fn substitute(template: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    lazy_static! {
+        static ref PLACEHOLDER: Regex = Regex::new(r"\$\{(\w+)\}").unwrap();
+    }
+    let mut undefined = None;
+    let result = PLACEHOLDER.replace_all(template, |captures: &regex::Captures| {
+        let name = &captures[1];
+        if name == UUID_VARIABLE {
+            return uuid::Uuid::new_v4().to_string();
+        }
+        variables.get(name).cloned().unwrap_or_else(|| {
+            undefined = Some(name.to_string());
+            default()
+        })
+    });
+    match undefined {
+        Some(name) => Err(format!("Variable '{name}' is not defined.").into()),
+        None => Ok(result.into_owned()),
+    }
+}
+
+
+
+// ==============
+// === Client ===
+// ==============
+
+/// The latency of a single measured step.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub step:    String,
+    pub latency: Duration,
+    /// Whether the response satisfied all the assertions.
+    pub passed:  bool,
+}
+
+/// Run the scenario as a single client, returning the samples of the measured steps.
+pub async fn run_client(
+    url: Url,
+    scenario: &Scenario,
+    index: usize,
+    timeout: Duration,
+) -> Result<Vec<Sample>> {
+    let mut socket = ClientBuilder::from_url(url).async_connect().await?;
+    let mut variables = scenario.variables.clone();
+    variables.insert(CLIENT_VARIABLE.to_string(), index.to_string());
+    for step in &scenario.setup {
+        let sample = run_step(&mut socket, step, &mut variables, timeout).await?;
+        if !sample.passed {
+            return Err(format!("Client {index} failed the setup step '{}'.", step.name).into());
+        }
+    }
+    let iterations = scenario.warmup_iterations + scenario.iterations;
+    let mut samples = Vec::with_capacity(scenario.iterations * scenario.steps.len());
+    for iteration in 0..iterations {
+        for step in &scenario.steps {
+            let sample = run_step(&mut socket, step, &mut variables, timeout).await?;
+            if iteration >= scenario.warmup_iterations {
+                samples.push(sample);
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Send the step's request and wait for its response.
+async fn run_step<S>(
+    socket: &mut S,
+    step: &Step,
+    variables: &mut BTreeMap<String, String>,
+    timeout: Duration,
+) -> Result<Sample>
+where
+    S: futures::Sink<Message, Error = websocket_lite::Error>
+        + futures::Stream<Item = Result<Message>>
+        + Unpin,
+{
+    let request = substitute(&step.send, variables)?;
+    let id = serde_json::from_str::<Value>(&request).ok().and_then(|r| r.get("id").cloned());
+    let wait_for = step.wait_for.as_deref().map(Regex::new).transpose()?;
+    let start = Instant::now();
+    socket.send(Message::text(request.as_str())).await?;
+    println!("{}", format::bench_request(&request));
+    let response = if wait_for.is_some() || id.is_some() {
+        let is_response = |text: &str| match &wait_for {
+            Some(regex) => regex.is_match(text),
+            None => is_response_to(text, id.as_ref()),
+        };
+        let receive = receive_matching(socket, is_response);
+        match tokio::time::timeout(timeout, receive).await {
+            Ok(response) => Some(response?),
+            Err(_) => return Err(format!("Timeout in step '{}'.", step.name).into()),
+        }
+    } else {
+        None
+    };
+    let latency = start.elapsed();
+    let mut passed = true;
+    if let Some(response) = &response {
+        println!("{}", format::response_text(response));
+        let response = serde_json::from_str(response).unwrap_or(Value::Null);
+        for assertion in &step.assert {
+            if let Err(failure) = assertion.check(&response) {
+                eprintln!("Step '{}' assertion failed: {failure}.", step.name);
+                passed = false;
+            }
+        }
+        for (name, pointer) in &step.capture {
+            match response.pointer(pointer) {
+                Some(Value::String(value)) => variables.insert(name.clone(), value.clone()),
+                Some(value) => variables.insert(name.clone(), value.to_string()),
+                None => {
+                    eprintln!("Step '{}' could not capture {name} from {pointer}.", step.name);
+                    passed = false;
+                    None
+                }
+            };
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(step.delay_ms)).await;
+    Ok(Sample { step: step.name.clone(), latency, passed })
+}
+
+/// Check whether the text message is a JSON-RPC response to the request with the given id.
+fn is_response_to(text: &str, id: Option<&Value>) -> bool {
+    let Ok(message) = serde_json::from_str::<Value>(text) else { return false };
+    let is_response = message.get("result").is_some() || message.get("error").is_some();
+    is_response && message.get("id") == id
+}
+
+/// Receive messages until a text message satisfying the predicate arrives.
+async fn receive_matching<S>(socket: &mut S, predicate: impl Fn(&str) -> bool) -> Result<String>
+where S: futures::Stream<Item = Result<Message>> + Unpin {
+    while let Some(message) = socket.next().await {
+        let message = message?;
+        if let Opcode::Text = message.opcode() {
+            if let Some(text) = message.as_text().filter(|text| predicate(text)) {
+                return Ok(text.to_string());
+            }
+        }
+    }
+    Err("The connection was closed before the response arrived.".into())
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_substituted() {
+        let variables = BTreeMap::from([("context_id".to_string(), "1234".to_string())]);
+        let result = substitute(r#"{"contextId":"${context_id}"}"#, &variables).unwrap();
+        assert_eq!(result, r#"{"contextId":"1234"}"#);
+        let uuid = substitute("${uuid}", &variables).unwrap();
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+        assert!(substitute("${undefined}", &variables).is_err());
+    }
+
+    #[test]
+    fn null_can_be_asserted() {
+        let assertion = |yaml: &str| serde_yaml::from_str::<Assertion>(yaml).unwrap();
+        let is_null = assertion("pointer: /result\nequals: null");
+        assert_eq!(is_null.equals, Some(Value::Null));
+        assert!(is_null.check(&serde_json::json!({ "result": null })).is_ok());
+        assert!(is_null.check(&serde_json::json!({ "result": 1 })).is_err());
+        let is_present = assertion("pointer: /result");
+        assert_eq!(is_present.equals, None);
+        assert!(is_present.check(&serde_json::json!({ "result": 1 })).is_ok());
+        assert!(is_present.check(&serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn responses_are_matched_by_id() {
+        let id = Value::from(3);
+        assert!(is_response_to(r#"{"jsonrpc":"2.0","id":3,"result":null}"#, Some(&id)));
+        assert!(!is_response_to(r#"{"jsonrpc":"2.0","id":4,"result":null}"#, Some(&id)));
+        assert!(!is_response_to(r#"{"jsonrpc":"2.0","method":"file/event"}"#, Some(&id)));
+    }
+}